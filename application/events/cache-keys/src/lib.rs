@@ -1,10 +1,19 @@
-use events_responses::EventResponse;
+use std::time::Duration;
+
+use events_models::{EventId, EventTypeId};
+use events_responses::{EventPage, EventResponse};
 use redis_connection::cache_key;
 
-cache_key!(EventCacheKey::<EventResponse> => "event:{}"[id: i64]);
+/// How long an event stays in the per-id cache before jitter. Shorter than
+/// users' since events are updated more often.
+pub const EVENT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+cache_key!(EventCacheKey::<EventResponse> => "event:{}"[id: EventId]);
 cache_key!(EventListCacheKey::<Vec<EventResponse>> => "events:list:{}"[filter_hash: String]);
+cache_key!(EventPageCacheKey::<EventPage> => "events:page:{}"[filter_hash: String]);
 cache_key!(UserEventsCacheKey::<Vec<EventResponse>> => "events:user:{}"[user_id: i64]);
 cache_key!(UserEventsLimitCacheKey::<Vec<EventResponse>> => "events:user:{}:limit:{}"[user_id: i64, limit: u64]);
+cache_key!(EventCountCacheKey::<i64> => "events:count");
 
-cache_key!(EventTypeCacheKey::<String> => "event_type:{}"[id: i32]);
+cache_key!(EventTypeCacheKey::<String> => "event_type:{}"[id: EventTypeId]);
 cache_key!(EventTypeListCacheKey::<Vec<String>> => "event_types:list");
@@ -1,33 +1,155 @@
+use chrono::Utc;
 use database_traits::dao::GenericDao;
+use events_cache_keys::{EventCountCacheKey, UserEventsCacheKey};
 use events_commands::{
     BulkDeleteEventsCommand, CreateEventCommand, DeleteEventCommand,
-    UpdateEventCommand,
+    DeleteUserEventsCommand, UpdateEventCommand,
 };
 use events_dao::{EventDao, EventTypeDao};
-use events_errors::EventError;
-use events_responses::{BulkDeleteEventsResponse, EventResponse};
+use events_errors::{EventError, EventTypeError};
+use events_models::EventId;
+use events_responses::{
+    BulkDeleteEventsResponse, DeleteUserEventsResponse, EventResponse,
+};
+use futures::{StreamExt, stream};
+use redis_connection::{cache_provider::CacheProvider, core::CacheTypeBind};
+pub use schemas::{MetadataSchemas, SchemaError};
 use sql_connection::SqlConnect;
-use tracing::instrument;
+pub use timestamps::{DEFAULT_MAX_FUTURE_SKEW, TimestampPolicy};
+use tracing::{Instrument, field::Empty, info, info_span, instrument};
+pub use write_buffer::{
+    AsyncWriteBuffer, DEFAULT_MAX_BATCH, DEFAULT_MAX_DELAY,
+    DEFAULT_MAX_QUEUED, WriteBufferClosed, WriteBufferConfig,
+};
+
+mod schemas;
+mod timestamps;
+mod write_buffer;
 
 #[derive(Clone)]
 pub struct CreateEventHandler {
     event_dao: EventDao,
+    metadata_schemas: MetadataSchemas,
+    timestamp_policy: TimestampPolicy,
+    write_buffer: Option<
+        AsyncWriteBuffer<CreateEventCommand, EventResponse, EventError>,
+    >,
 }
 
 impl CreateEventHandler {
     pub fn new(db: SqlConnect) -> Self {
         Self {
             event_dao: EventDao::new(db),
+            metadata_schemas: MetadataSchemas::default(),
+            timestamp_policy: TimestampPolicy::default(),
+            write_buffer: None,
         }
     }
 
-    #[instrument(skip(self))]
+    /// Insert events in batches instead of one round trip each. An event
+    /// waits at most `config.max_delay` for others to share its insert,
+    /// trading that much latency for throughput under bursts. Must be
+    /// called within a Tokio runtime.
+    pub fn with_write_buffer(mut self, config: WriteBufferConfig) -> Self {
+        let event_dao = self.event_dao.clone();
+        self.write_buffer =
+            Some(AsyncWriteBuffer::new(config, move |commands| {
+                flush_events(event_dao.clone(), commands)
+            }));
+        self
+    }
+
+    /// Batched inserts made by the write buffer; 0 without one
+    pub fn write_batches(&self) -> u64 {
+        self.write_buffer
+            .as_ref()
+            .map_or(0, AsyncWriteBuffer::batches)
+    }
+
+    /// Bounds on how far in the future or past an event may be stamped
+    pub fn with_timestamp_policy(mut self, policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = policy;
+        self
+    }
+
+    /// Reject events whose metadata does not match the schema registered
+    /// for their type
+    pub fn with_metadata_schemas(mut self, schemas: MetadataSchemas) -> Self {
+        self.metadata_schemas = schemas;
+        self
+    }
+
     pub async fn execute(
         &self, command: CreateEventCommand,
     ) -> Result<EventResponse, EventError> {
-        // DAO now returns EventResponse directly with event type name
-        // included
-        self.event_dao.create(command).await
+        let span = info_span!(
+            "create_event",
+            user_id = command.user_id,
+            event_type = %command.event_type,
+            event_id = Empty
+        );
+
+        async {
+            self.timestamp_policy
+                .check(command.timestamp, Utc::now())
+                .map_err(|err| EventError::Validation(vec![err]))?;
+            self.metadata_schemas
+                .validate(&command.event_type, command.metadata.as_ref())
+                .map_err(EventError::Validation)?;
+
+            // DAO now returns EventResponse directly with event type name
+            // included
+            let event = match &self.write_buffer {
+                Some(buffer) => buffer.submit(command).await?,
+                None => self.event_dao.create(command).await?,
+            };
+            invalidate_event_count().await;
+            tracing::Span::current().record("event_id", event.id);
+            Ok(event)
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// Events inserted at once when a failed batch is retried one by one
+const RETRY_CONCURRENCY: usize = 16;
+
+/// Insert a buffered batch with one statement. If the statement fails,
+/// e.g. on one event's unknown user, the events are inserted one by one,
+/// a few at a time, so only the bad ones fail.
+async fn flush_events(
+    event_dao: EventDao, commands: Vec<CreateEventCommand>,
+) -> Vec<Result<EventResponse, EventError>> {
+    match event_dao.create_many(&commands).await {
+        Ok(events) => {
+            events
+                .into_iter()
+                .map(|event| {
+                    event.ok_or(EventError::EventType(
+                        EventTypeError::NotFound,
+                    ))
+                })
+                .collect()
+        }
+        Err(err) => {
+            tracing::warn!(
+                "Batched insert of {} events failed, retrying one by one: {}",
+                commands.len(),
+                err
+            );
+            stream::iter(commands)
+                .map(|command| event_dao.create(command))
+                .buffered(RETRY_CONCURRENCY)
+                .collect()
+                .await
+        }
+    }
+}
+
+impl From<WriteBufferClosed> for EventError {
+    fn from(err: WriteBufferClosed) -> Self {
+        EventError::InternalError(err.to_string())
     }
 }
 
@@ -35,6 +157,8 @@ impl CreateEventHandler {
 pub struct UpdateEventHandler {
     event_dao: EventDao,
     event_type_dao: EventTypeDao,
+    metadata_schemas: MetadataSchemas,
+    timestamp_policy: TimestampPolicy,
 }
 
 impl UpdateEventHandler {
@@ -42,15 +166,38 @@ impl UpdateEventHandler {
         Self {
             event_dao: EventDao::new(db.clone()),
             event_type_dao: EventTypeDao::new(db),
+            metadata_schemas: MetadataSchemas::default(),
+            timestamp_policy: TimestampPolicy::default(),
         }
     }
 
+    /// Bounds on the timestamp an update may set
+    pub fn with_timestamp_policy(mut self, policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = policy;
+        self
+    }
+
+    /// Reject updates leaving an event with metadata that does not match
+    /// the schema registered for its type
+    pub fn with_metadata_schemas(mut self, schemas: MetadataSchemas) -> Self {
+        self.metadata_schemas = schemas;
+        self
+    }
+
     #[instrument(skip(self))]
     pub async fn execute(
         &self, command: UpdateEventCommand,
     ) -> Result<EventResponse, EventError> {
-        let updated_event =
-            self.event_dao.update(command.event_id, command).await?;
+        command.validate().map_err(EventError::Validation)?;
+        self.timestamp_policy
+            .check(command.timestamp, Utc::now())
+            .map_err(|err| EventError::Validation(vec![err]))?;
+        self.check_metadata(&command).await?;
+
+        let updated_event = self
+            .event_dao
+            .update(EventId(command.event_id), command)
+            .await?;
         let event_type = self
             .event_type_dao
             .find_by_id(updated_event.event_type_id)
@@ -67,6 +214,46 @@ impl UpdateEventHandler {
             metadata: updated_event.metadata,
         })
     }
+
+    /// Check the metadata the event ends up with against the schema of the
+    /// type it ends up with. Whatever the command leaves unchanged is read
+    /// from the stored event, so changing only the type still checks the
+    /// old metadata against the new type's schema.
+    async fn check_metadata(
+        &self, command: &UpdateEventCommand,
+    ) -> Result<(), EventError> {
+        if self.metadata_schemas.is_empty()
+            || (command.event_type_id.is_none() && command.metadata.is_none())
+        {
+            return Ok(());
+        }
+
+        let (stored_type, stored_metadata) = if command
+            .event_type_id
+            .is_none()
+            || command.metadata.is_none()
+        {
+            let (event_type, metadata) = self
+                .event_dao
+                .find_type_and_metadata(EventId(command.event_id))
+                .await?;
+            (Some(event_type), metadata)
+        }
+        else {
+            (None, None)
+        };
+        let event_type = match command.event_type_id {
+            Some(id) => self.event_type_dao.find_by_id(id).await?.name,
+            None => stored_type.unwrap_or_default(),
+        };
+
+        self.metadata_schemas
+            .validate(
+                &event_type,
+                command.metadata.as_ref().or(stored_metadata.as_ref()),
+            )
+            .map_err(EventError::Validation)
+    }
 }
 
 #[derive(Clone)]
@@ -85,7 +272,8 @@ impl DeleteEventHandler {
     pub async fn execute(
         &self, command: DeleteEventCommand,
     ) -> Result<(), EventError> {
-        self.event_dao.delete(command.event_id).await?;
+        self.event_dao.delete(EventId(command.event_id)).await?;
+        invalidate_event_count().await;
         Ok(())
     }
 }
@@ -110,6 +298,9 @@ impl BulkDeleteEventsHandler {
             .event_dao
             .delete_before_timestamp(command.before)
             .await?;
+        if deleted_count > 0 {
+            invalidate_event_count().await;
+        }
 
         Ok(BulkDeleteEventsResponse {
             deleted_count,
@@ -118,6 +309,79 @@ impl BulkDeleteEventsHandler {
     }
 }
 
+/// Erases a user's whole event history, e.g. for a GDPR request
+#[derive(Clone)]
+pub struct DeleteUserEventsHandler {
+    event_dao: EventDao,
+}
+
+impl DeleteUserEventsHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            event_dao: EventDao::new(db),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn execute(
+        &self, command: DeleteUserEventsCommand,
+    ) -> Result<DeleteUserEventsResponse, EventError> {
+        let deleted_count =
+            self.event_dao.delete_by_user(command.user_id).await?;
+        if deleted_count > 0 {
+            invalidate_event_count().await;
+            invalidate_user_events(command.user_id).await;
+            invalidate_erased_events().await;
+        }
+
+        // Analytics consumers pick erasures up from this target
+        info!(
+            target: "analytics",
+            user_id = command.user_id,
+            deleted_count,
+            "user_events_erased"
+        );
+
+        Ok(DeleteUserEventsResponse {
+            user_id: command.user_id,
+            deleted_count,
+        })
+    }
+}
+
+/// Drop every cached listing of one user's events, limited or not
+async fn invalidate_user_events(user_id: i64) {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = UserEventsCacheKey
+            .bind_with(backend.clone(), &user_id)
+            .remove::<()>()
+            .await;
+        let pattern = CacheProvider::namespaced(
+            format!("events:user:{user_id}:limit:*").into(),
+        );
+        let _ = backend.invalidate_pattern(&pattern).await;
+    }
+}
+
+/// Drop every cached event and every cached listing or page, any of which
+/// may hold an erased event. The erased ids aren't collected, since a huge
+/// history would mean millions of them, so the per-id cache goes by
+/// pattern too.
+async fn invalidate_erased_events() {
+    if CacheProvider::try_get_backend().is_some() {
+        for pattern in ["event:*", "events:list:*", "events:page:*"] {
+            let _ = CacheProvider::invalidate_pattern(pattern).await;
+        }
+    }
+}
+
+/// Drop the cached event count after a write that changes it
+async fn invalidate_event_count() {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = EventCountCacheKey.bind(backend).remove::<()>().await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::{Duration, Utc};
@@ -175,6 +439,155 @@ mod tests {
         assert!(result.metadata.is_some());
     }
 
+    #[tokio::test]
+    async fn test_create_event_checks_registered_metadata_schema() {
+        let (container, create_handler, ..) =
+            setup_test_handlers().await.unwrap();
+        create_test_event_type(&container).await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let create_handler = create_handler.with_metadata_schemas(
+            MetadataSchemas::from_value(&json!({
+                "test_event": {
+                    "type": "object",
+                    "required": ["amount"],
+                    "properties": {"amount": {"type": "number"}}
+                }
+            }))
+            .unwrap(),
+        );
+        let command = |metadata| {
+            CreateEventCommand {
+                user_id,
+                event_type: "test_event".to_string(),
+                timestamp: Some(Utc::now()),
+                metadata: Some(metadata),
+            }
+        };
+
+        let created = create_handler
+            .execute(command(json!({"amount": 10})))
+            .await
+            .unwrap();
+        assert_eq!(created.event_type, "test_event");
+
+        let err = create_handler
+            .execute(command(json!({"amount": "ten"})))
+            .await
+            .unwrap_err();
+        let EventError::Validation(fields) = err
+        else {
+            panic!("expected a validation error, got {err:?}");
+        };
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].field, "metadata.amount");
+        assert_eq!(fields[0].code, "type");
+    }
+
+    #[tokio::test]
+    async fn test_create_event_rejects_out_of_range_timestamps() {
+        let (container, create_handler, ..) =
+            setup_test_handlers().await.unwrap();
+        create_test_event_type(&container).await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let create_handler = create_handler.with_timestamp_policy(
+            TimestampPolicy::default().with_max_age(Duration::days(365)),
+        );
+        let command = |timestamp| {
+            CreateEventCommand {
+                user_id,
+                event_type: "test_event".to_string(),
+                timestamp: Some(timestamp),
+                metadata: None,
+            }
+        };
+
+        for (timestamp, code) in [
+            (Utc::now() + Duration::hours(1), "in_future"),
+            (Utc::now() - Duration::days(3650), "too_old"),
+        ] {
+            let err = create_handler
+                .execute(command(timestamp))
+                .await
+                .unwrap_err();
+            let EventError::Validation(fields) = err
+            else {
+                panic!("expected a validation error, got {err:?}");
+            };
+            assert_eq!(fields[0].field, "timestamp");
+            assert_eq!(fields[0].code, code);
+        }
+
+        let created = create_handler
+            .execute(command(Utc::now() - Duration::days(1)))
+            .await
+            .unwrap();
+        assert_eq!(created.user_id, user_id);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_write_buffer_coalesces_concurrent_creates() {
+        let (container, create_handler, ..) =
+            setup_test_handlers().await.unwrap();
+        create_test_event_type(&container).await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let create_handler =
+            create_handler.with_write_buffer(WriteBufferConfig {
+                max_batch: 200,
+                max_delay: std::time::Duration::from_millis(20),
+                ..WriteBufferConfig::default()
+            });
+
+        let tasks: Vec<_> = (0..500)
+            .map(|n| {
+                let handler = create_handler.clone();
+                tokio::spawn(async move {
+                    handler
+                        .execute(CreateEventCommand {
+                            user_id,
+                            event_type: if n == 7 {
+                                "no_such_type".to_string()
+                            }
+                            else {
+                                "test_event".to_string()
+                            },
+                            timestamp: None,
+                            metadata: Some(
+                                json!({ "session_id": format!("s{n}") }),
+                            ),
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        let mut ids = std::collections::HashSet::new();
+        for (n, task) in tasks.into_iter().enumerate() {
+            match task.await.unwrap() {
+                Ok(event) => {
+                    assert_eq!(
+                        event.metadata.unwrap().session_id,
+                        Some(format!("s{n}"))
+                    );
+                    assert!(ids.insert(event.id));
+                }
+                Err(err) => {
+                    assert_eq!(n, 7);
+                    assert!(matches!(err, EventError::EventType(_)));
+                }
+            }
+        }
+        assert_eq!(ids.len(), 499);
+
+        let batches = create_handler.write_batches();
+        assert!((3..50).contains(&batches), "{batches} batches");
+        let client = container.pool.get().await.unwrap();
+        let row = client
+            .query_one("SELECT COUNT(*) FROM events", &[])
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), 499);
+    }
+
     #[tokio::test]
     async fn test_create_event_handler_invalid_event_type() {
         let (container, create_handler, ..) =
@@ -335,6 +748,128 @@ mod tests {
         assert!(result.metadata.is_some());
     }
 
+    #[tokio::test]
+    async fn test_update_event_rejects_out_of_range_timestamps() {
+        let (container, _, update_handler, ..) =
+            setup_test_handlers().await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_id =
+            create_test_event(&container, user_id, event_type_id, None)
+                .await
+                .unwrap();
+        let update_handler = update_handler.with_timestamp_policy(
+            TimestampPolicy::default().with_max_age(Duration::days(365)),
+        );
+
+        for (timestamp, code) in [
+            (Utc::now() + Duration::hours(1), "in_future"),
+            (Utc::now() - Duration::days(3650), "too_old"),
+        ] {
+            let err = update_handler
+                .execute(UpdateEventCommand {
+                    event_id,
+                    event_type_id: None,
+                    timestamp: Some(timestamp),
+                    metadata: None,
+                })
+                .await
+                .unwrap_err();
+            let EventError::Validation(fields) = err
+            else {
+                panic!("expected a validation error, got {err:?}");
+            };
+            assert_eq!(fields[0].field, "timestamp");
+            assert_eq!(fields[0].code, code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_event_checks_registered_metadata_schema() {
+        let (container, _, update_handler, ..) =
+            setup_test_handlers().await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        let paid_type_id =
+            create_test_event_type_with_name(&container, "order.paid")
+                .await
+                .unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_id = create_test_event(
+            &container,
+            user_id,
+            event_type_id,
+            Some(r#"{"page": "/cart"}"#),
+        )
+        .await
+        .unwrap();
+        let update_handler = update_handler.with_metadata_schemas(
+            MetadataSchemas::from_value(&json!({
+                "order.paid": {
+                    "type": "object",
+                    "required": ["amount"],
+                    "properties": {"amount": {"type": "number"}}
+                }
+            }))
+            .unwrap(),
+        );
+        let update = |event_type_id, metadata| {
+            UpdateEventCommand {
+                event_id,
+                event_type_id,
+                timestamp: None,
+                metadata,
+            }
+        };
+        let validation_fields = |err: EventError| {
+            let EventError::Validation(fields) = err
+            else {
+                panic!("expected a validation error, got {err:?}");
+            };
+            fields
+                .into_iter()
+                .map(|field| (field.field, field.code))
+                .collect::<Vec<_>>()
+        };
+
+        // The stored metadata has no amount
+        let err = update_handler
+            .execute(update(Some(paid_type_id), None))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            validation_fields(err),
+            [("metadata".to_string(), "required".to_string())]
+        );
+
+        let err = update_handler
+            .execute(update(
+                Some(paid_type_id),
+                Some(json!({"amount": "ten"})),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            validation_fields(err),
+            [("metadata.amount".to_string(), "type".to_string())]
+        );
+
+        let updated = update_handler
+            .execute(update(Some(paid_type_id), Some(json!({"amount": 10}))))
+            .await
+            .unwrap();
+        assert_eq!(updated.event_type, "order.paid");
+
+        // Now stored as order.paid, so a metadata-only change is checked too
+        let err = update_handler
+            .execute(update(None, Some(json!({"currency": "EUR"}))))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            validation_fields(err),
+            [("metadata".to_string(), "required".to_string())]
+        );
+    }
+
     #[tokio::test]
     async fn test_delete_event_handler() {
         let (container, create_handler, _, delete_handler, _) =
@@ -364,7 +899,8 @@ mod tests {
 
         // Verify it's actually deleted by trying to find it
         let event_dao = EventDao::new(create_sql_connect(&container));
-        let find_result = event_dao.find_by_id(created_event.id).await;
+        let find_result =
+            event_dao.find_by_id(EventId(created_event.id)).await;
         assert!(find_result.is_err());
     }
 
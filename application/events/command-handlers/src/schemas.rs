@@ -0,0 +1,170 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use common_errors::FieldError;
+use jsonschema::Validator;
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    #[error("Failed to read metadata schemas: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Metadata schemas are not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Metadata schemas must be an object keyed by event type")]
+    NotAnObject,
+    #[error("Invalid metadata schema for '{event_type}': {message}")]
+    InvalidSchema { event_type: String, message: String },
+}
+
+/// JSON Schemas that event metadata must satisfy, keyed by event type.
+/// Event types without a schema are not checked.
+#[derive(Clone, Default)]
+pub struct MetadataSchemas {
+    validators: Arc<HashMap<String, Validator>>,
+}
+
+impl MetadataSchemas {
+    /// Compile every schema of `{"<event type>": <schema>, ...}`
+    pub fn from_value(schemas: &Value) -> Result<Self, SchemaError> {
+        let schemas = schemas.as_object().ok_or(SchemaError::NotAnObject)?;
+        let validators = schemas
+            .iter()
+            .map(|(event_type, schema)| {
+                jsonschema::validator_for(schema)
+                    .map(|validator| (event_type.clone(), validator))
+                    .map_err(|err| {
+                        SchemaError::InvalidSchema {
+                            event_type: event_type.clone(),
+                            message: err.to_string(),
+                        }
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            validators: Arc::new(validators),
+        })
+    }
+
+    /// Load a JSON file in the [`from_value`](Self::from_value) format
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SchemaError> {
+        let raw = std::fs::read_to_string(path)?;
+        Self::from_value(&serde_json::from_str(&raw)?)
+    }
+
+    pub fn is_empty(&self) -> bool { self.validators.is_empty() }
+
+    pub fn len(&self) -> usize { self.validators.len() }
+
+    /// Check `metadata` against the schema of `event_type`, reporting one
+    /// field error per violation. Missing metadata is checked as `null`.
+    pub fn validate(
+        &self, event_type: &str, metadata: Option<&Value>,
+    ) -> Result<(), Vec<FieldError>> {
+        let Some(validator) = self.validators.get(event_type)
+        else {
+            return Ok(());
+        };
+
+        let errors: Vec<FieldError> = validator
+            .iter_errors(metadata.unwrap_or(&Value::Null))
+            .map(|err| {
+                let path = err.instance_path.as_str().replace('/', ".");
+                let keyword =
+                    err.schema_path.as_str().rsplit('/').next().unwrap_or("");
+                FieldError::new(
+                    &format!("metadata{path}"),
+                    keyword,
+                    &err.to_string(),
+                )
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn order_paid() -> MetadataSchemas {
+        MetadataSchemas::from_value(&json!({
+            "order.paid": {
+                "type": "object",
+                "required": ["amount"],
+                "properties": {
+                    "amount": {"type": "number", "minimum": 0},
+                    "currency": {"type": "string"}
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_conforming_metadata_passes() {
+        let schemas = order_paid();
+
+        assert_eq!(
+            schemas.validate(
+                "order.paid",
+                Some(&json!({"amount": 12.5, "currency": "EUR"}))
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_non_conforming_metadata_reports_fields() {
+        let schemas = order_paid();
+
+        let errors = schemas
+            .validate("order.paid", Some(&json!({"currency": 978})))
+            .unwrap_err();
+        let fields: Vec<_> = errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.code.as_str()))
+            .collect();
+        assert_eq!(fields.len(), 2);
+        assert!(fields.contains(&("metadata", "required")));
+        assert!(fields.contains(&("metadata.currency", "type")));
+
+        let errors = schemas.validate("order.paid", None).unwrap_err();
+        assert_eq!(errors[0].field, "metadata");
+    }
+
+    #[test]
+    fn test_unknown_event_type_is_not_checked() {
+        let schemas = order_paid();
+
+        assert_eq!(schemas.validate("page.view", Some(&json!(42))), Ok(()));
+        assert_eq!(schemas.validate("page.view", None), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_schema_is_rejected() {
+        let err = MetadataSchemas::from_value(
+            &json!({"order.paid": {"type": "nope"}}),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            SchemaError::InvalidSchema { ref event_type, .. }
+                if event_type == "order.paid"
+        ));
+        assert!(matches!(
+            MetadataSchemas::from_value(&json!([])),
+            Err(SchemaError::NotAnObject)
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Duration, Utc};
+use common_errors::FieldError;
+
+/// How far ahead of the server clock an event may be stamped by default
+pub const DEFAULT_MAX_FUTURE_SKEW: Duration = Duration::minutes(5);
+
+/// Bounds on client-supplied event timestamps. Events outside them would
+/// land in time buckets the analytics never expect to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampPolicy {
+    /// Allowed clock skew of timestamps ahead of now
+    pub max_future_skew: Duration,
+    /// Oldest accepted timestamp, relative to now; any age when `None`
+    pub max_age: Option<Duration>,
+}
+
+impl Default for TimestampPolicy {
+    fn default() -> Self {
+        Self {
+            max_future_skew: DEFAULT_MAX_FUTURE_SKEW,
+            max_age: None,
+        }
+    }
+}
+
+impl TimestampPolicy {
+    /// Also reject timestamps older than `max_age`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Check `timestamp` against `now`. A missing timestamp defaults to now
+    /// and always passes.
+    pub fn check(
+        &self, timestamp: Option<DateTime<Utc>>, now: DateTime<Utc>,
+    ) -> Result<(), FieldError> {
+        let Some(timestamp) = timestamp
+        else {
+            return Ok(());
+        };
+
+        // A bound past the representable range bounds nothing
+        if now
+            .checked_add_signed(self.max_future_skew)
+            .is_some_and(|latest| timestamp > latest)
+        {
+            return Err(FieldError::new(
+                "timestamp",
+                "in_future",
+                &format!(
+                    "Timestamp may be at most {}s ahead of server time",
+                    self.max_future_skew.num_seconds()
+                ),
+            ));
+        }
+
+        if let Some(max_age) = self.max_age
+            && now
+                .checked_sub_signed(max_age)
+                .is_some_and(|oldest| timestamp < oldest)
+        {
+            return Err(FieldError::new(
+                "timestamp",
+                "too_old",
+                &format!(
+                    "Timestamp may be at most {} days old",
+                    max_age.num_days()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_skew_and_missing_timestamps_pass() {
+        let now = Utc::now();
+        let policy = TimestampPolicy::default();
+
+        assert!(policy.check(None, now).is_ok());
+        assert!(policy.check(Some(now), now).is_ok());
+        assert!(policy.check(Some(now + Duration::minutes(4)), now).is_ok());
+    }
+
+    #[test]
+    fn test_future_timestamps_are_rejected() {
+        let now = Utc::now();
+        let err = TimestampPolicy::default()
+            .check(Some(now + Duration::minutes(6)), now)
+            .unwrap_err();
+
+        assert_eq!(err.field, "timestamp");
+        assert_eq!(err.code, "in_future");
+    }
+
+    #[test]
+    fn test_ancient_timestamps_are_rejected_only_when_configured() {
+        let now = Utc::now();
+        let ancient = Some(now - Duration::days(3650));
+
+        assert!(TimestampPolicy::default().check(ancient, now).is_ok());
+
+        let policy =
+            TimestampPolicy::default().with_max_age(Duration::days(30));
+        assert_eq!(policy.check(ancient, now).unwrap_err().code, "too_old");
+        assert!(policy.check(Some(now - Duration::days(29)), now).is_ok());
+    }
+
+    #[test]
+    fn test_huge_bounds_do_not_overflow() {
+        let now = Utc::now();
+        let policy = TimestampPolicy {
+            max_future_skew: Duration::MAX,
+            max_age: Some(Duration::MAX),
+        };
+
+        assert!(policy.check(Some(DateTime::<Utc>::MIN_UTC), now).is_ok());
+        assert!(policy.check(Some(DateTime::<Utc>::MAX_UTC), now).is_ok());
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+
+/// Most items written by one flush by default
+pub const DEFAULT_MAX_BATCH: usize = 500;
+
+/// How long the first item of a batch waits for company by default
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_millis(5);
+
+/// Most items waiting for a flush by default
+pub const DEFAULT_MAX_QUEUED: usize = 10 * DEFAULT_MAX_BATCH;
+
+/// When [`AsyncWriteBuffer`] flushes: as soon as `max_batch` items are
+/// waiting, or `max_delay` after the first of them arrived. Once
+/// `max_queued` items are waiting, further submits wait for room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteBufferConfig {
+    pub max_batch: usize,
+    pub max_delay: Duration,
+    pub max_queued: usize,
+}
+
+impl Default for WriteBufferConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: DEFAULT_MAX_BATCH,
+            max_delay: DEFAULT_MAX_DELAY,
+            max_queued: DEFAULT_MAX_QUEUED,
+        }
+    }
+}
+
+/// The buffer's flush task is gone, so the item was never written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Write buffer closed before the item was flushed")]
+pub struct WriteBufferClosed;
+
+type Pending<T, R, E> = (T, oneshot::Sender<Result<R, E>>);
+
+/// Coalesces single writes into batches. Each [`submit`] waits until the
+/// batch holding its item has been flushed and gets that item's result,
+/// so callers keep one-at-a-time semantics while the store sees a
+/// fraction of the round trips.
+///
+/// Flushes run one at a time on a background task; items arriving during
+/// a flush make up the next batch. The queue is bounded, so a store that
+/// falls behind slows submitters down instead of growing memory.
+///
+/// [`submit`]: AsyncWriteBuffer::submit
+pub struct AsyncWriteBuffer<T, R, E> {
+    tx: mpsc::Sender<Pending<T, R, E>>,
+    batches: Arc<AtomicU64>,
+}
+
+impl<T, R, E> Clone for AsyncWriteBuffer<T, R, E> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            batches: self.batches.clone(),
+        }
+    }
+}
+
+impl<T, R, E> AsyncWriteBuffer<T, R, E>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    E: From<WriteBufferClosed> + Send + 'static,
+{
+    /// Spawn the flush task. `flush` gets each batch in arrival order and
+    /// must return one result per item, in the same order. The task stops
+    /// once every clone of the buffer is dropped. Must be called within a
+    /// Tokio runtime.
+    pub fn new<F, Fut>(config: WriteBufferConfig, flush: F) -> Self
+    where
+        F: Fn(Vec<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = Vec<Result<R, E>>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(config.max_queued.max(1));
+        let batches = Arc::new(AtomicU64::new(0));
+        tokio::spawn(run(rx, config, flush, batches.clone()));
+        Self { tx, batches }
+    }
+
+    /// Queue `item`, waiting for room if the queue is full, and wait for
+    /// the result of its flush
+    pub async fn submit(&self, item: T) -> Result<R, E> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx
+            .send((item, done_tx))
+            .await
+            .map_err(|_| E::from(WriteBufferClosed))?;
+        done_rx.await.unwrap_or(Err(E::from(WriteBufferClosed)))
+    }
+
+    /// Batches flushed so far, shared across clones
+    pub fn batches(&self) -> u64 { self.batches.load(Ordering::Relaxed) }
+}
+
+async fn run<T, R, E, F, Fut>(
+    mut rx: mpsc::Receiver<Pending<T, R, E>>, config: WriteBufferConfig,
+    flush: F, batches: Arc<AtomicU64>,
+) where
+    F: Fn(Vec<T>) -> Fut,
+    Fut: Future<Output = Vec<Result<R, E>>>,
+{
+    let max_batch = config.max_batch.max(1);
+    while let Some(first) = rx.recv().await {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + config.max_delay;
+        while batch.len() < max_batch {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let (items, waiters): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        let results = flush(items).await;
+        batches.fetch_add(1, Ordering::Relaxed);
+        // A waiter without a result sees the buffer as closed
+        for (waiter, result) in waiters.into_iter().zip(results) {
+            let _ = waiter.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::sync::Semaphore;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Odd(u32),
+        Closed,
+    }
+
+    impl From<WriteBufferClosed> for TestError {
+        fn from(_: WriteBufferClosed) -> Self { Self::Closed }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_submits_share_batches() {
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = sizes.clone();
+        let buffer = AsyncWriteBuffer::new(
+            WriteBufferConfig {
+                max_batch: 40,
+                max_delay: Duration::from_millis(20),
+                ..WriteBufferConfig::default()
+            },
+            move |items: Vec<u32>| {
+                recorded.lock().unwrap().push(items.len());
+                async move {
+                    items
+                        .into_iter()
+                        .map(|n| {
+                            if n % 2 == 0 {
+                                Ok(n * 10)
+                            }
+                            else {
+                                Err(TestError::Odd(n))
+                            }
+                        })
+                        .collect()
+                }
+            },
+        );
+
+        let tasks: Vec<_> = (0..100)
+            .map(|n| {
+                let buffer = buffer.clone();
+                tokio::spawn(async move { (n, buffer.submit(n).await) })
+            })
+            .collect();
+        for task in tasks {
+            let (n, result) = task.await.unwrap();
+            if n % 2 == 0 {
+                assert_eq!(result, Ok(n * 10));
+            }
+            else {
+                assert_eq!(result, Err(TestError::Odd(n)));
+            }
+        }
+
+        let sizes = sizes.lock().unwrap();
+        assert_eq!(sizes.iter().sum::<usize>(), 100);
+        assert!(sizes.iter().all(|&size| size <= 40), "{sizes:?}");
+        assert_eq!(buffer.batches(), sizes.len() as u64);
+        assert!(sizes.len() < 10, "{sizes:?}");
+    }
+
+    #[tokio::test]
+    async fn test_lone_item_is_flushed_after_the_delay() {
+        let buffer = AsyncWriteBuffer::new(
+            WriteBufferConfig {
+                max_batch: 100,
+                max_delay: Duration::from_millis(10),
+                ..WriteBufferConfig::default()
+            },
+            |items: Vec<u32>| {
+                let results = items.into_iter().map(Ok::<_, TestError>);
+                async move { results.collect() }
+            },
+        );
+
+        let result =
+            tokio::time::timeout(Duration::from_secs(1), buffer.submit(7))
+                .await
+                .expect("a lone item should not wait for a full batch");
+        assert_eq!(result, Ok(7));
+        assert_eq!(buffer.batches(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_holds_back_submitters() {
+        // Flushes wait here until the test lets them through
+        let gate = Arc::new(Semaphore::new(0));
+        let flush_gate = gate.clone();
+        let buffer = AsyncWriteBuffer::new(
+            WriteBufferConfig {
+                max_batch: 1,
+                max_delay: Duration::ZERO,
+                max_queued: 4,
+            },
+            move |items: Vec<u32>| {
+                let gate = flush_gate.clone();
+                async move {
+                    let _permit = gate.acquire().await;
+                    items.into_iter().map(Ok::<_, TestError>).collect()
+                }
+            },
+        );
+
+        let tasks: Vec<_> = (0..20)
+            .map(|n| {
+                let buffer = buffer.clone();
+                tokio::spawn(async move { buffer.submit(n).await })
+            })
+            .collect();
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+
+        // One item is being flushed and four are queued; the other fifteen
+        // submitters are still waiting to send
+        assert_eq!(buffer.tx.capacity(), 0);
+        assert!(tasks.iter().all(|task| !task.is_finished()));
+
+        gate.add_permits(1);
+        for (n, task) in (0..).zip(tasks) {
+            assert_eq!(task.await.unwrap(), Ok(n));
+        }
+        assert_eq!(buffer.batches(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_missing_result_reports_closed() {
+        let buffer = AsyncWriteBuffer::new(
+            WriteBufferConfig::default(),
+            |_: Vec<u32>| async { Vec::<Result<u32, TestError>>::new() },
+        );
+
+        assert_eq!(buffer.submit(1).await, Err(TestError::Closed));
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use database_traits::dao::GenericDao;
+use events_cache_keys::{EventCacheKey, EventPageCacheKey};
+use events_command_handlers::DeleteUserEventsHandler;
+use events_commands::DeleteUserEventsCommand;
+use events_dao::EventDao;
+use events_models::EventId;
+use events_responses::EventPage;
+use redis_connection::{cache_provider::CacheProvider, core::CacheTypeBind};
+use test_utils::*;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_erasing_a_users_events_drops_their_cached_copies() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let sql_connect = create_sql_connect(&container);
+    let user_id = create_test_user(&container).await.unwrap();
+    let event_type_id = create_test_event_type(&container).await.unwrap();
+    let event_id = EventId(
+        create_test_event(&container, user_id, event_type_id, None)
+            .await
+            .unwrap(),
+    );
+    let event = EventDao::new(sql_connect.clone())
+        .find_by_id(event_id)
+        .await
+        .unwrap();
+
+    let backend = CacheProvider::get_backend();
+    let ttl = Duration::from_secs(60);
+    let mut cached_event =
+        EventCacheKey.bind_with(backend.clone(), &event_id);
+    cached_event
+        .set_with_expire::<()>(event.clone(), ttl)
+        .await
+        .unwrap();
+    let mut cached_page =
+        EventPageCacheKey.bind_with(backend, &"first".to_string());
+    cached_page
+        .set_with_expire::<()>(
+            EventPage {
+                events: vec![event],
+                next_cursor: None,
+            },
+            ttl,
+        )
+        .await
+        .unwrap();
+
+    let erased = DeleteUserEventsHandler::new(sql_connect)
+        .execute(DeleteUserEventsCommand { user_id })
+        .await
+        .unwrap();
+    assert_eq!(erased.deleted_count, 1);
+
+    assert!(cached_event.try_get().await.unwrap().is_none());
+    assert!(cached_page.try_get().await.unwrap().is_none());
+}
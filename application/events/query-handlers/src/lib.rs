@@ -1,20 +1,27 @@
 use std::{
+    collections::HashMap,
     hash::{DefaultHasher, Hash, Hasher},
     time::Duration,
 };
 
+use chrono::{DateTime, Utc};
+use common_query::DEFAULT_COUNT_TTL;
 use database_traits::dao::GenericDao;
 use events_cache_keys::{
-    EventCacheKey, EventListCacheKey, UserEventsCacheKey,
-    UserEventsLimitCacheKey,
+    EVENT_CACHE_TTL, EventCacheKey, EventCountCacheKey, EventListCacheKey,
+    EventPageCacheKey, UserEventsCacheKey, UserEventsLimitCacheKey,
 };
 use events_dao::EventDao;
 use events_errors::EventError;
-use events_queries::{GetEventQuery, GetUserEventsQuery, ListEventsQuery};
-use events_responses::EventResponse;
+use events_queries::{
+    GetEventQuery, GetSessionEventsQuery, GetUserEventsQuery,
+    ListEventsPageQuery, ListEventsQuery,
+};
+use events_responses::{EventPage, EventResponse};
 use redis_connection::{
     cache_provider::CacheProvider,
     core::{CacheTypeBind, Json},
+    ttl::jittered,
 };
 use sql_connection::SqlConnect;
 use tracing::instrument;
@@ -35,39 +42,20 @@ impl GetEventQueryHandler {
     pub async fn execute(
         &self, query: GetEventQuery,
     ) -> Result<EventResponse, EventError> {
+        let event_id = query.event_id;
         let backend = CacheProvider::get_backend();
-
-        // Try to get from cache first
-        let cache_key = EventCacheKey;
-        let mut cache = cache_key.bind_with(backend.clone(), &query.event_id);
-
-        if let Ok(Some(event)) = cache.try_get().await {
-            tracing::debug!("Cache hit for event {}", query.event_id);
-            return Ok(event);
-        }
-
-        tracing::debug!(
-            "Cache miss for event {}, fetching from DB",
-            query.event_id
-        );
-
-        let event = self.event_dao.find_by_id(query.event_id).await.map_err(
-            |_| {
-                EventError::NotFound {
-                    event_id: query.event_id,
-                }
-            },
-        )?;
-
-        // Cache for only 30 seconds - events are updated frequently
-        let _ = cache
-            .set_with_expire::<()>(
-                Json(event.clone()),
-                Duration::from_secs(30),
-            )
-            .await;
-
-        Ok(event)
+        let mut cache = EventCacheKey.bind_with(backend, &event_id);
+
+        CacheProvider::get_or_set(&mut cache, EVENT_CACHE_TTL, || {
+            async {
+                self.event_dao.find_by_id(event_id).await.map_err(|_| {
+                    EventError::NotFound {
+                        event_id: event_id.get(),
+                    }
+                })
+            }
+        })
+        .await
     }
 }
 
@@ -128,7 +116,7 @@ impl ListEventsQueryHandler {
         let _ = cache
             .set_with_expire::<()>(
                 Json(events.clone()),
-                Duration::from_secs(15),
+                jittered(Duration::from_secs(15)),
             )
             .await;
 
@@ -136,6 +124,65 @@ impl ListEventsQueryHandler {
     }
 }
 
+#[derive(Clone)]
+pub struct ListEventsPageQueryHandler {
+    event_dao: EventDao,
+}
+
+impl ListEventsPageQueryHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            event_dao: EventDao::new(db),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn execute(
+        &self, query: ListEventsPageQuery,
+    ) -> Result<EventPage, EventError> {
+        let mut hasher = DefaultHasher::new();
+        query.user_id.hash(&mut hasher);
+        query.event_type_id.hash(&mut hasher);
+        query.cursor.hash(&mut hasher);
+        query.limit.hash(&mut hasher);
+        let filter_hash = hasher.finish().to_string();
+
+        let backend = CacheProvider::get_backend();
+        let mut cache = EventPageCacheKey.bind_with(backend, &filter_hash);
+
+        if let Ok(Some(page)) = cache.try_get().await {
+            tracing::debug!("Cache hit for events page {}", filter_hash);
+            return Ok(page);
+        }
+
+        let result = self
+            .event_dao
+            .find_with_keyset(
+                query.user_id,
+                query.event_type_id,
+                query.cursor.map(|c| (c.timestamp, c.id)),
+                query.limit,
+            )
+            .await?;
+        let page = EventPage {
+            events: result.items,
+            next_cursor: result.next_cursor.map(|(timestamp, id)| {
+                EventDao::cursor_at(timestamp, id).encode()
+            }),
+        };
+
+        // Same short lifetime as the offset listing
+        let _ = cache
+            .set_with_expire::<()>(
+                Json(page.clone()),
+                jittered(Duration::from_secs(15)),
+            )
+            .await;
+
+        Ok(page)
+    }
+}
+
 #[derive(Clone)]
 pub struct GetUserEventsQueryHandler {
     event_dao: EventDao,
@@ -148,6 +195,15 @@ impl GetUserEventsQueryHandler {
         }
     }
 
+    /// When each of `user_ids` was last active. Not cached, since it moves
+    /// with every new event.
+    #[instrument(skip_all, fields(users = user_ids.len()))]
+    pub async fn last_active(
+        &self, user_ids: &[i64],
+    ) -> Result<HashMap<i64, DateTime<Utc>>, EventError> {
+        self.event_dao.latest_event_per_user(user_ids).await
+    }
+
     #[instrument(skip(self))]
     pub async fn execute(
         &self, query: GetUserEventsQuery,
@@ -185,7 +241,7 @@ impl GetUserEventsQueryHandler {
             let _ = cache
                 .set_with_expire::<()>(
                     Json(events.clone()),
-                    Duration::from_secs(30),
+                    jittered(Duration::from_secs(30)),
                 )
                 .await;
 
@@ -218,7 +274,7 @@ impl GetUserEventsQueryHandler {
             let _ = cache
                 .set_with_expire::<()>(
                     Json(events.clone()),
-                    Duration::from_secs(30),
+                    jittered(Duration::from_secs(30)),
                 )
                 .await;
 
@@ -227,6 +283,76 @@ impl GetUserEventsQueryHandler {
     }
 }
 
+/// Events returned for a session when the query sets no limit
+pub const DEFAULT_SESSION_EVENTS_LIMIT: u64 = 1000;
+
+/// All events of one session, oldest first. Not cached: sessions are read
+/// while they are still receiving events.
+#[derive(Clone)]
+pub struct GetSessionEventsQueryHandler {
+    event_dao: EventDao,
+}
+
+impl GetSessionEventsQueryHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            event_dao: EventDao::new(db),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn execute(
+        &self, query: GetSessionEventsQuery,
+    ) -> Result<Vec<EventResponse>, EventError> {
+        self.event_dao
+            .find_by_session_id(
+                &query.session_id,
+                query.limit.unwrap_or(DEFAULT_SESSION_EVENTS_LIMIT),
+            )
+            .await
+    }
+}
+
+/// Total number of events, cached briefly so paginated listings don't run
+/// `COUNT(*)` over the whole table on every request
+#[derive(Clone)]
+pub struct CountEventsQueryHandler {
+    event_dao: EventDao,
+    ttl: Duration,
+}
+
+impl CountEventsQueryHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            event_dao: EventDao::new(db),
+            ttl: DEFAULT_COUNT_TTL,
+        }
+    }
+
+    /// Override how long a count is cached, at least one second
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl.max(Duration::from_secs(1));
+        self
+    }
+
+    #[instrument(skip(self))]
+    pub async fn execute(&self) -> Result<i64, EventError> {
+        let backend = CacheProvider::get_backend();
+        let mut cache = EventCountCacheKey.bind(backend);
+
+        if let Ok(Some(count)) = cache.try_get().await {
+            tracing::debug!("Cache hit for event count");
+            return Ok(count);
+        }
+
+        let count = self.event_dao.count().await?;
+
+        let _ = cache.set_with_expire::<()>(count, self.ttl).await;
+
+        Ok(count)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use redis_connection::cache_provider::CacheProvider;
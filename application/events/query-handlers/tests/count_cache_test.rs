@@ -0,0 +1,45 @@
+use events_command_handlers::CreateEventHandler;
+use events_commands::CreateEventCommand;
+use events_query_handlers::CountEventsQueryHandler;
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_event_count_is_cached_until_an_event_is_created() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let sql_connect = create_sql_connect(&container);
+    let handler = CountEventsQueryHandler::new(sql_connect.clone());
+    let create_handler = CreateEventHandler::new(sql_connect);
+
+    let user_id = create_test_user(&container).await.unwrap();
+    let event_type_id = create_test_event_type(&container).await.unwrap();
+    create_test_event(&container, user_id, event_type_id, None)
+        .await
+        .unwrap();
+    let queries = QueryCounter::new();
+    let count = || queries.scope(handler.execute());
+
+    assert_eq!(count().await.unwrap(), 1);
+    assert_eq!(count().await.unwrap(), 1);
+    assert_eq!(queries.count(), 1);
+
+    create_handler
+        .execute(CreateEventCommand {
+            user_id,
+            event_type: "test_event".to_string(),
+            timestamp: None,
+            metadata: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(count().await.unwrap(), 2);
+    assert_eq!(queries.count(), 2);
+}
@@ -1,5 +1,11 @@
+use std::time::Duration;
+
 use redis_connection::cache_key;
 
+/// How long a user stays in the per-id cache before jitter
+pub const USER_CACHE_TTL: Duration = Duration::from_secs(300);
+
 cache_key!(UserCacheKey::<user_models::User> => "user:{}"[id: i64]);
+cache_key!(UserNotFoundCacheKey::<bool> => "user:{}:missing"[id: i64]);
 cache_key!(UserByNameCacheKey::<user_models::User> => "user:name:{}"[name: String]);
-cache_key!(UserListCacheKey::<Vec<user_models::User>> => "users:list");
+cache_key!(UserCountCacheKey::<i64> => "users:count");
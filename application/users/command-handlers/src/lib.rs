@@ -1,12 +1,22 @@
+use std::collections::HashSet;
+
 use database_traits::dao::GenericDao;
+use redis_connection::{
+    cache_provider::CacheProvider, core::CacheTypeBind, ttl::jittered,
+};
 use sql_connection::SqlConnect;
 use tracing::instrument;
+use user_cache_keys::{
+    USER_CACHE_TTL, UserByNameCacheKey, UserCacheKey, UserCountCacheKey,
+    UserNotFoundCacheKey,
+};
 use user_commands::{
-    CreateUserCommand, DeleteUserCommand, UpdateUserCommand,
+    CreateUserCommand, DeleteUserCommand, EnsureUserCommand,
+    ImportUsersCommand, UpdateUserCommand,
 };
 use user_dao::UserDao;
 use user_errors::UserError;
-use user_responses::UserResponse;
+use user_responses::{ImportRowError, UserImportReport, UserResponse};
 
 #[derive(Clone)]
 pub struct CreateUserHandler {
@@ -24,16 +34,63 @@ impl CreateUserHandler {
     pub async fn execute(
         &self, command: CreateUserCommand,
     ) -> Result<UserResponse, UserError> {
+        command.validate().map_err(UserError::Validation)?;
+
         let saved_user = self.user_dao.create(command).await?;
 
+        cache_user(&saved_user).await;
+        invalidate_user_count().await;
+
         Ok(UserResponse {
             id: saved_user.id,
             name: saved_user.name,
             created_at: saved_user.created_at,
+            event_count: None,
+            last_active: None,
         })
     }
 }
 
+#[derive(Clone)]
+pub struct EnsureUserHandler {
+    user_dao: UserDao,
+}
+
+impl EnsureUserHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            user_dao: UserDao::new(db),
+        }
+    }
+
+    /// The user named in `command` and whether it had to be created
+    #[instrument(skip(self))]
+    pub async fn execute(
+        &self, command: EnsureUserCommand,
+    ) -> Result<(UserResponse, bool), UserError> {
+        command.validate().map_err(UserError::Validation)?;
+
+        let (user, created) =
+            self.user_dao.find_or_create_by_name(&command.name).await?;
+
+        if created {
+            cache_user(&user).await;
+            invalidate_user_count().await;
+        }
+
+        Ok((
+            UserResponse {
+                id: user.id,
+                name: user.name,
+                created_at: user.created_at,
+                event_count: None,
+                last_active: None,
+            },
+            created,
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct UpdateUserHandler {
     user_dao: UserDao,
@@ -50,13 +107,31 @@ impl UpdateUserHandler {
     pub async fn execute(
         &self, command: UpdateUserCommand,
     ) -> Result<UserResponse, UserError> {
+        command.validate().map_err(UserError::Validation)?;
+
+        // A rename leaves the old name cached, so remember it to evict
+        let previous_name = match command.name {
+            Some(_) => {
+                Some(self.user_dao.find_by_id(command.user_id).await?.name)
+            }
+            None => None,
+        };
+
         let updated_user =
             self.user_dao.update(command.user_id, command).await?;
 
+        cache_user(&updated_user).await;
+        if let Some(name) = previous_name.filter(|n| *n != updated_user.name)
+        {
+            evict_user_name(name).await;
+        }
+
         Ok(UserResponse {
             id: updated_user.id,
             name: updated_user.name,
             created_at: updated_user.created_at,
+            event_count: None,
+            last_active: None,
         })
     }
 }
@@ -77,21 +152,116 @@ impl DeleteUserHandler {
     pub async fn execute(
         &self, command: DeleteUserCommand,
     ) -> Result<(), UserError> {
-        let _existing_user =
-            self.user_dao.find_by_id(command.user_id).await.map_err(
-                |_| {
-                    UserError::NotFound {
-                        user_id: command.user_id,
-                    }
-                },
-            )?;
+        // Only a missing row is NotFound; anything else, like the database
+        // being unreachable, must not pass for an already-deleted user
+        let existing_user = self.user_dao.find_by_id(command.user_id).await?;
 
         self.user_dao.delete(command.user_id).await?;
+        evict_user(&existing_user).await;
+        invalidate_user_count().await;
 
         Ok(())
     }
 }
 
+#[derive(Clone)]
+pub struct ImportUsersHandler {
+    user_dao: UserDao,
+}
+
+impl ImportUsersHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            user_dao: UserDao::new(db),
+        }
+    }
+
+    /// Create every valid row in one batch insert. A name repeated in the
+    /// file or already taken counts as a skipped duplicate, not an error.
+    #[instrument(skip_all, fields(rows = command.rows.len()))]
+    pub async fn execute(
+        &self, command: ImportUsersCommand,
+    ) -> Result<UserImportReport, UserError> {
+        let total = command.rows.len();
+        let mut seen = HashSet::new();
+        let names: Vec<String> = command
+            .rows
+            .into_iter()
+            .filter_map(|row| {
+                seen.insert(row.name.clone()).then_some(row.name)
+            })
+            .collect();
+
+        let created = self.user_dao.create_many(&names).await?;
+
+        if !created.is_empty() {
+            if let Some(backend) = CacheProvider::try_get_backend() {
+                for user in &created {
+                    let _ = UserNotFoundCacheKey
+                        .bind_with(backend.clone(), &user.id)
+                        .remove::<()>()
+                        .await;
+                }
+            }
+            invalidate_user_count().await;
+        }
+
+        Ok(UserImportReport {
+            created: created.len() as u64,
+            skipped_duplicates: (total - created.len()) as u64,
+            errors: command
+                .rejected
+                .into_iter()
+                .map(|(line, reason)| ImportRowError { line, reason })
+                .collect(),
+        })
+    }
+}
+
+/// Write `user` through to the per-id cache and drop any not-found
+/// tombstone an earlier lookup left, so a read right after the write sees
+/// the new row without going to the database
+async fn cache_user(user: &user_models::User) {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = UserNotFoundCacheKey
+            .bind_with(backend.clone(), &user.id)
+            .remove::<()>()
+            .await;
+        let _ = UserCacheKey
+            .bind_with(backend, &user.id)
+            .set_with_expire::<()>(user.clone(), jittered(USER_CACHE_TTL))
+            .await;
+    }
+}
+
+/// Drop every cached copy of a deleted user, so reads by id or name go to
+/// the database and find it gone
+async fn evict_user(user: &user_models::User) {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = UserCacheKey
+            .bind_with(backend, &user.id)
+            .remove::<()>()
+            .await;
+    }
+    evict_user_name(user.name.clone()).await;
+}
+
+async fn evict_user_name(name: String) {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = UserByNameCacheKey
+            .bind_with(backend, &name)
+            .remove::<()>()
+            .await;
+    }
+}
+
+/// Drop the cached user count after a write that changes it
+async fn invalidate_user_count() {
+    if let Some(backend) = CacheProvider::try_get_backend() {
+        let _ = UserCountCacheKey.bind(backend).remove::<()>().await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use test_utils::*;
@@ -1,60 +1,158 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
+use common_query::{CollectionVersion, DEFAULT_COUNT_TTL};
 use database_traits::dao::GenericDao;
-use redis_connection::{cache_provider::CacheProvider, core::CacheTypeBind};
+use redis_connection::{
+    cache_provider::CacheProvider,
+    core::{CacheKey, CacheTypeBind},
+    ttl::jittered,
+};
 use sql_connection::SqlConnect;
 use tracing::instrument;
-use user_cache_keys::{UserByNameCacheKey, UserCacheKey, UserListCacheKey};
+use user_cache_keys::{
+    USER_CACHE_TTL, UserByNameCacheKey, UserCacheKey, UserCountCacheKey,
+    UserNotFoundCacheKey,
+};
 use user_dao::UserDao;
 use user_errors::UserError;
 use user_queries::{GetUserByNameQuery, GetUserQuery, ListUsersQuery};
 use user_responses::UserResponse;
+pub use warmer::{CacheWarmer, HotUsers};
+
+mod warmer;
+
+/// How long a lookup of a missing user id is remembered by default
+pub const DEFAULT_NOT_FOUND_TTL: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub struct GetUserQueryHandler {
     user_dao: UserDao,
+    not_found_ttl: Duration,
 }
 
 impl GetUserQueryHandler {
     pub fn new(db: SqlConnect) -> Self {
         Self {
             user_dao: UserDao::new(db),
+            not_found_ttl: DEFAULT_NOT_FOUND_TTL,
         }
     }
 
+    /// Override how long not-found tombstones live. Redis expiry has second
+    /// granularity, so anything shorter is raised to one second.
+    pub fn with_not_found_ttl(mut self, ttl: Duration) -> Self {
+        self.not_found_ttl = ttl.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Number of events recorded for `user_id`. Not cached, since it moves
+    /// with every new event.
+    #[instrument(skip(self))]
+    pub async fn event_count(&self, user_id: i64) -> Result<i64, UserError> {
+        self.user_dao.count_events(user_id).await
+    }
+
     #[instrument(skip(self))]
     pub async fn execute(
         &self, query: GetUserQuery,
     ) -> Result<user_models::User, UserError> {
+        let user_id = query.user_id;
         let backend = CacheProvider::get_backend();
+        let mut cache = UserCacheKey.bind_with(backend, &user_id);
+
+        // User data doesn't change often
+        CacheProvider::get_or_set(&mut cache, USER_CACHE_TTL, || {
+            async {
+                self.load(user_id)
+                    .await?
+                    .ok_or(UserError::NotFound { user_id })
+            }
+        })
+        .await
+    }
 
-        // Try to get from cache first
-        let cache_key = UserCacheKey;
-        let mut cache = cache_key.bind_with(backend.clone(), &query.user_id);
+    /// The users among `user_ids`, in the order asked for; unknown ids are
+    /// skipped. Cached users come from one multi-get, and only the misses
+    /// are loaded, in one query, and cached for the next call.
+    #[instrument(skip_all, fields(ids = user_ids.len()))]
+    pub async fn execute_many(
+        &self, user_ids: &[i64],
+    ) -> Result<Vec<user_models::User>, UserError> {
+        let keys: Vec<String> = user_ids
+            .iter()
+            .map(|id| UserCacheKey.get_key_with_args((id,)).into_owned())
+            .collect();
+        let mut users: Vec<Option<user_models::User>> =
+            CacheProvider::multi_get(&keys).await;
+
+        let missing: Vec<i64> = user_ids
+            .iter()
+            .zip(&users)
+            .filter(|(_, user)| user.is_none())
+            .map(|(&id, _)| id)
+            .collect();
+        if !missing.is_empty() {
+            let loaded: HashMap<i64, user_models::User> = self
+                .user_dao
+                .find_by_ids(&missing)
+                .await?
+                .into_iter()
+                .map(|user| (user.id, user))
+                .collect();
+
+            let entries: Vec<_> = loaded
+                .values()
+                .map(|user| {
+                    let key = UserCacheKey.get_key_with_args((&user.id,));
+                    (key.into_owned(), user.clone(), jittered(USER_CACHE_TTL))
+                })
+                .collect();
+            if let Err(e) = CacheProvider::multi_set(&entries).await {
+                tracing::warn!("Failed to cache loaded users: {}", e);
+            }
 
-        if let Ok(Some(user)) = cache.try_get().await {
-            tracing::debug!("Cache hit for user {}", query.user_id);
-            return Ok(user);
+            for (user, id) in users.iter_mut().zip(user_ids) {
+                if user.is_none() {
+                    *user = loaded.get(id).cloned();
+                }
+            }
         }
 
-        tracing::debug!(
-            "Cache miss for user {}, fetching from DB",
-            query.user_id
-        );
+        Ok(users.into_iter().flatten().collect())
+    }
 
-        let user =
-            self.user_dao.find_by_id(query.user_id).await.map_err(|_| {
-                UserError::NotFound {
-                    user_id: query.user_id,
-                }
-            })?;
+    /// The user, or `None` when it doesn't exist. Database failures are
+    /// returned rather than reported as a missing user.
+    async fn load(
+        &self, user_id: i64,
+    ) -> Result<Option<user_models::User>, UserError> {
+        let backend = CacheProvider::get_backend();
+        let mut cache = UserCacheKey.bind_with(backend.clone(), &user_id);
 
-        // Cache for 5 minutes - user data doesn't change often
-        let _ = cache
-            .set_with_expire::<()>(user.clone(), Duration::from_secs(300))
-            .await;
+        // A load that just finished may have filled the cache after our miss
+        if let Ok(Some(user)) = cache.try_get().await {
+            return Ok(Some(user));
+        }
+
+        let mut tombstone =
+            UserNotFoundCacheKey.bind_with(backend.clone(), &user_id);
+        if let Ok(Some(true)) = tombstone.try_get().await {
+            tracing::debug!("Negative cache hit for user {}", user_id);
+            return Ok(None);
+        }
 
-        Ok(user)
+        match self.user_dao.find_by_id(user_id).await {
+            Ok(user) => Ok(Some(user)),
+            Err(UserError::NotFound { .. }) => {
+                // Remember the miss briefly so repeated lookups of a missing
+                // id don't reach the database
+                let _ = tombstone
+                    .set_with_expire::<()>(true, self.not_found_ttl)
+                    .await;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
     }
 }
 
@@ -111,6 +209,22 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_user_database_failure_is_not_not_found() {
+        let (container, handler) = setup_test_db().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+
+        // With no connections to be had, the lookup fails outright instead
+        // of reporting (and remembering) a missing user
+        container.pool.close();
+        let result = handler.execute(GetUserQuery { user_id }).await;
+
+        assert!(
+            matches!(result, Err(UserError::DatabasePool(_))),
+            "{result:?}"
+        );
+    }
+
     async fn setup_test_db_for_name_queries()
     -> anyhow::Result<(TestPostgresContainer, GetUserByNameQueryHandler)>
     {
@@ -194,6 +308,7 @@ mod tests {
         let query = ListUsersQuery {
             limit: Some(1),
             offset: None,
+            ..Default::default()
         };
         let result = handler.execute(query).await.unwrap();
 
@@ -209,6 +324,7 @@ mod tests {
         let query = ListUsersQuery {
             limit: None,
             offset: Some(1),
+            ..Default::default()
         };
         let result = handler.execute(query).await.unwrap();
         assert!(!result.is_empty());
@@ -222,6 +338,7 @@ mod tests {
         let query = ListUsersQuery {
             limit: None,
             offset: None,
+            ..Default::default()
         };
         let result = handler.execute(query).await.unwrap();
 
@@ -273,7 +390,10 @@ impl GetUserByNameQueryHandler {
 
         // Cache for 5 minutes - user data doesn't change often
         let _ = cache
-            .set_with_expire::<()>(user.clone(), Duration::from_secs(300))
+            .set_with_expire::<()>(
+                user.clone(),
+                jittered(Duration::from_secs(300)),
+            )
             .await;
 
         Ok(user.into())
@@ -296,42 +416,68 @@ impl ListUsersQueryHandler {
     pub async fn execute(
         &self, query: ListUsersQuery,
     ) -> Result<Vec<user_models::User>, UserError> {
-        // For paginated queries, we'll cache with a specific key including
-        // limit/offset For now, we'll only cache the default list (no
-        // pagination)
-        if query.limit.is_none() && query.offset.is_none() {
-            let backend = CacheProvider::get_backend();
-
-            let cache_key = UserListCacheKey;
-            let mut cache = cache_key.bind(backend);
-
-            if let Ok(Some(users)) = cache.try_get().await {
-                tracing::debug!("Cache hit for user list");
-                return Ok(users);
-            }
-
-            tracing::debug!("Cache miss for user list, fetching from DB");
+        // Every listing is paged, so it goes straight to the database
+        let users = self
+            .user_dao
+            .find_filtered(
+                query.created_after,
+                query.created_before,
+                query.sort.unwrap_or_default().order_by(),
+                query.limit,
+                query.offset,
+            )
+            .await?;
+        Ok(users)
+    }
 
-            let users =
-                self.user_dao.find_with_pagination(None, None).await?;
+    /// Version of the collection `query` lists from; always hits the
+    /// database so it never lags behind the cached list
+    #[instrument(skip(self))]
+    pub async fn collection_version(
+        &self, query: &ListUsersQuery,
+    ) -> Result<CollectionVersion, UserError> {
+        self.user_dao
+            .collection_version(query.created_after, query.created_before)
+            .await
+    }
+}
 
-            // Cache for 2 minutes - list might change more often than
-            // individual users
-            let _ = cache
-                .set_with_expire::<()>(
-                    users.clone(),
-                    Duration::from_secs(120),
-                )
-                .await;
+/// Total number of users, cached briefly so paginated listings don't run
+/// `COUNT(*)` on every request
+#[derive(Clone)]
+pub struct CountUsersQueryHandler {
+    user_dao: UserDao,
+    ttl: Duration,
+}
 
-            Ok(users)
+impl CountUsersQueryHandler {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            user_dao: UserDao::new(db),
+            ttl: DEFAULT_COUNT_TTL,
         }
-        else {
-            let users = self
-                .user_dao
-                .find_with_pagination(query.limit, query.offset)
-                .await?;
-            Ok(users)
+    }
+
+    /// Override how long a count is cached, at least one second
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl.max(Duration::from_secs(1));
+        self
+    }
+
+    #[instrument(skip(self))]
+    pub async fn execute(&self) -> Result<i64, UserError> {
+        let backend = CacheProvider::get_backend();
+        let mut cache = UserCountCacheKey.bind(backend);
+
+        if let Ok(Some(count)) = cache.try_get().await {
+            tracing::debug!("Cache hit for user count");
+            return Ok(count);
         }
+
+        let count = self.user_dao.count().await?;
+
+        let _ = cache.set_with_expire::<()>(count, self.ttl).await;
+
+        Ok(count)
     }
 }
@@ -0,0 +1,73 @@
+use chrono::{Duration, Utc};
+use redis_connection::{
+    cache_provider::CacheProvider, core::CacheKey, ttl::jittered,
+};
+use sql_connection::SqlConnect;
+use tracing::instrument;
+use user_cache_keys::{USER_CACHE_TTL, UserCacheKey};
+use user_dao::UserDao;
+use user_errors::UserError;
+
+/// Which users [`CacheWarmer`] preloads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotUsers {
+    /// The `limit` users with the most events in the trailing `window`
+    MostActive { limit: i64, window: Duration },
+    /// A fixed list of user ids
+    Ids(Vec<i64>),
+}
+
+/// Fills the per-id user cache before traffic arrives, so the first reads
+/// of the busiest users don't all reach the database at once.
+pub struct CacheWarmer {
+    user_dao: UserDao,
+    source: HotUsers,
+}
+
+impl CacheWarmer {
+    pub fn new(db: SqlConnect, source: HotUsers) -> Self {
+        Self {
+            user_dao: UserDao::new(db),
+            source,
+        }
+    }
+
+    /// Load the hot users in one query and cache them in one round trip,
+    /// each with its own jittered TTL so they don't all expire together.
+    /// Returns how many users were cached. A failed cache write is logged
+    /// and counts as nothing warmed, since the cache fills on demand anyway.
+    #[instrument(skip(self))]
+    pub async fn warm(&self) -> Result<usize, UserError> {
+        let ids = match &self.source {
+            HotUsers::MostActive { limit, window } => {
+                self.user_dao
+                    .most_active_ids(*limit, Utc::now() - *window)
+                    .await?
+            }
+            HotUsers::Ids(ids) => ids.clone(),
+        };
+        let users = self.user_dao.find_by_ids(&ids).await?;
+
+        let entries: Vec<_> = users
+            .into_iter()
+            .map(|user| {
+                let key = UserCacheKey.get_key_with_args((&user.id,));
+                (key.into_owned(), user, jittered(USER_CACHE_TTL))
+            })
+            .collect();
+        if let Err(e) = CacheProvider::multi_set(&entries).await {
+            tracing::warn!("Failed to warm the user cache: {}", e);
+            return Ok(0);
+        }
+
+        for (key, user, _) in &entries {
+            tracing::debug!(
+                cache.event = "warm",
+                cache.key = %key,
+                user_id = user.id,
+                "Warmed cache entry"
+            );
+        }
+        Ok(entries.len())
+    }
+}
@@ -0,0 +1,38 @@
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_query_handlers::GetUserQueryHandler;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_batch_lookup_loads_only_the_misses() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let handler = GetUserQueryHandler::new(create_sql_connect(&container));
+    let (first, second) = create_test_users(&container).await.unwrap();
+    let queries = QueryCounter::new();
+
+    // Warm only the first user
+    queries.scope(handler.execute_many(&[first])).await.unwrap();
+    assert_eq!(queries.count(), 1);
+
+    let users = queries
+        .scope(handler.execute_many(&[second, i64::MAX, first]))
+        .await
+        .unwrap();
+    let ids: Vec<i64> = users.iter().map(|user| user.id).collect();
+    assert_eq!(ids, [second, first]);
+    assert_eq!(queries.count(), 2);
+
+    // Both are cached now; the unknown id is looked up again
+    let users = queries
+        .scope(handler.execute_many(&[first, second]))
+        .await
+        .unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(queries.count(), 2);
+}
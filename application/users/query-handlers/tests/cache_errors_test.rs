@@ -0,0 +1,49 @@
+use redis_connection::{
+    cache_provider::CacheProvider, config::RedisDbConfig, connect_redis_db,
+    metrics::CacheMetrics,
+};
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_queries::GetUserQuery;
+use user_query_handlers::GetUserQueryHandler;
+
+// Lives in its own test binary because the cache backend and the cache
+// counters are process-wide, and here the backend is a Redis that never
+// answers
+#[tokio::test]
+async fn test_unreachable_redis_counts_errors_and_serves_from_db() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    // Nothing listens on port 1, so every cache call fails to connect
+    let unreachable = connect_redis_db(&RedisDbConfig {
+        host: "127.0.0.1".to_string(),
+        port: 1,
+        db: 0,
+    })
+    .await
+    .unwrap();
+    CacheProvider::init_redis_static(unreachable);
+
+    let user_id = create_test_user(&container).await.unwrap();
+    let handler = GetUserQueryHandler::new(create_sql_connect(&container));
+
+    let queries = QueryCounter::new();
+    let before = CacheMetrics::snapshot();
+    let user = queries
+        .scope(handler.execute(GetUserQuery { user_id }))
+        .await
+        .unwrap();
+    let after = CacheMetrics::snapshot();
+
+    assert_eq!(user.id, user_id);
+    assert_eq!(queries.count(), 1);
+    assert!(after.errors > before.errors, "{before:?} -> {after:?}");
+    assert_eq!(after.hits, before.hits);
+
+    // Failed invalidations and batch reads are counted too
+    assert!(CacheProvider::invalidate_pattern("user:*").await.is_err());
+    let keys = [format!("user:{user_id}")];
+    let cached = CacheProvider::multi_get::<user_models::User>(&keys).await;
+    assert_eq!(cached, [None]);
+    let last = CacheMetrics::snapshot();
+    assert_eq!(last.errors, after.errors + 2, "{after:?} -> {last:?}");
+}
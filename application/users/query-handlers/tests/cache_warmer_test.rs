@@ -0,0 +1,55 @@
+use chrono::Duration;
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_queries::GetUserQuery;
+use user_query_handlers::{CacheWarmer, GetUserQueryHandler, HotUsers};
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_warmed_users_are_served_from_cache() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let db = create_sql_connect(&container);
+    let active = create_test_user_with_name(&container, "active")
+        .await
+        .unwrap();
+    let inactive = create_test_user_with_name(&container, "inactive")
+        .await
+        .unwrap();
+    let type_id = create_test_event_type(&container).await.unwrap();
+    create_test_event(&container, active, type_id, None)
+        .await
+        .unwrap();
+
+    let warmer = CacheWarmer::new(
+        db.clone(),
+        HotUsers::MostActive {
+            limit: 10,
+            window: Duration::hours(1),
+        },
+    );
+    assert_eq!(warmer.warm().await.unwrap(), 1);
+
+    let handler = GetUserQueryHandler::new(db.clone());
+    let queries = QueryCounter::new();
+    let user = queries
+        .scope(handler.execute(GetUserQuery { user_id: active }))
+        .await
+        .unwrap();
+    assert_eq!(user.name, "active");
+    assert_eq!(queries.count(), 0);
+
+    queries
+        .scope(handler.execute(GetUserQuery { user_id: inactive }))
+        .await
+        .unwrap();
+    assert_eq!(queries.count(), 1);
+
+    let warmer = CacheWarmer::new(db, HotUsers::Ids(vec![inactive, 999999]));
+    assert_eq!(warmer.warm().await.unwrap(), 1);
+}
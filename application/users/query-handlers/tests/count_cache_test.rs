@@ -0,0 +1,46 @@
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_command_handlers::CreateUserHandler;
+use user_commands::CreateUserCommand;
+use user_query_handlers::CountUsersQueryHandler;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_user_count_is_cached_until_a_user_is_created() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let sql_connect = create_sql_connect(&container);
+    let handler = CountUsersQueryHandler::new(sql_connect.clone());
+    let create_handler = CreateUserHandler::new(sql_connect);
+
+    create_test_user(&container).await.unwrap();
+    let queries = QueryCounter::new();
+    let count = || queries.scope(handler.execute());
+
+    assert_eq!(count().await.unwrap(), 1);
+    assert_eq!(count().await.unwrap(), 1);
+    assert_eq!(queries.count(), 1);
+
+    // A row written behind the handlers' back stays invisible until the
+    // entry expires; that staleness is accepted
+    create_test_user_with_name(&container, "unnoticed")
+        .await
+        .unwrap();
+    assert_eq!(count().await.unwrap(), 1);
+    assert_eq!(queries.count(), 1);
+
+    create_handler
+        .execute(CreateUserCommand {
+            name: "counted".to_string(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(count().await.unwrap(), 3);
+    assert_eq!(queries.count(), 2);
+}
@@ -0,0 +1,70 @@
+use redis_connection::cache_provider::CacheProvider;
+use test_utils::*;
+use user_command_handlers::CreateUserHandler;
+use user_commands::CreateUserCommand;
+use user_errors::UserError;
+use user_queries::GetUserQuery;
+use user_query_handlers::GetUserQueryHandler;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_missing_user_is_negatively_cached_until_created() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let sql_connect = create_sql_connect(&container);
+    let handler = GetUserQueryHandler::new(sql_connect.clone());
+    let create_handler = CreateUserHandler::new(sql_connect);
+
+    let existing_id = create_test_user(&container).await.unwrap();
+    let missing_id = existing_id + 1;
+
+    let first = handler
+        .execute(GetUserQuery {
+            user_id: missing_id,
+        })
+        .await;
+    assert!(matches!(first, Err(UserError::NotFound { .. })));
+
+    // Sneak a row in behind the DAO's back; a second lookup that reached
+    // the database would now find it
+    container
+        .execute_sql(&format!(
+            "INSERT INTO users (id, name, created_at) VALUES ({missing_id}, \
+             'ghost', NOW())"
+        ))
+        .await
+        .unwrap();
+
+    let second = handler
+        .execute(GetUserQuery {
+            user_id: missing_id,
+        })
+        .await;
+    assert!(matches!(second, Err(UserError::NotFound { .. })));
+
+    container
+        .execute_sql(&format!("DELETE FROM users WHERE id = {missing_id}"))
+        .await
+        .unwrap();
+
+    // Creating the user clears the tombstone for its id
+    let created = create_handler
+        .execute(CreateUserCommand {
+            name: "late_arrival".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.id, missing_id);
+
+    let found = handler
+        .execute(GetUserQuery {
+            user_id: missing_id,
+        })
+        .await
+        .unwrap();
+    assert_eq!(found.name, "late_arrival");
+}
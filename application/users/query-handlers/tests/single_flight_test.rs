@@ -0,0 +1,37 @@
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_queries::GetUserQuery;
+use user_query_handlers::GetUserQueryHandler;
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_concurrent_misses_hit_database_once() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    let handler = GetUserQueryHandler::new(create_sql_connect(&container));
+    let user_id = create_test_user(&container).await.unwrap();
+
+    let queries = QueryCounter::new();
+    let tasks: Vec<_> = (0..50)
+        .map(|_| {
+            let (handler, queries) = (handler.clone(), queries.clone());
+            tokio::spawn(async move {
+                queries
+                    .scope(handler.execute(GetUserQuery { user_id }))
+                    .await
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        let user = task.await.unwrap().unwrap();
+        assert_eq!(user.id, user_id);
+    }
+
+    assert_eq!(queries.count(), 1);
+}
@@ -0,0 +1,117 @@
+use redis_connection::cache_provider::CacheProvider;
+use sql_connection::QueryCounter;
+use test_utils::*;
+use user_command_handlers::{
+    CreateUserHandler, DeleteUserHandler, UpdateUserHandler,
+};
+use user_commands::{
+    CreateUserCommand, DeleteUserCommand, UpdateUserCommand,
+};
+use user_errors::UserError;
+use user_queries::{GetUserByNameQuery, GetUserQuery};
+use user_query_handlers::{GetUserByNameQueryHandler, GetUserQueryHandler};
+
+// Lives in its own test binary so the global cache backend points at a
+// Redis container that stays up for the whole test
+#[tokio::test]
+async fn test_reads_after_writes_are_served_from_the_written_user() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let redis_container = TestRedisContainer::new().await.unwrap();
+    redis_container.flush_db().await.unwrap();
+    CacheProvider::init_redis_static(redis_container.pool.clone());
+
+    reads_follow_create_and_update(&container).await;
+    reads_miss_after_rename_and_delete(&container).await;
+}
+
+async fn reads_follow_create_and_update(container: &TestPostgresContainer) {
+    let sql_connect = create_sql_connect(container);
+    let handler = GetUserQueryHandler::new(sql_connect.clone());
+    let create_handler = CreateUserHandler::new(sql_connect.clone());
+    let update_handler = UpdateUserHandler::new(sql_connect);
+    let queries = QueryCounter::new();
+
+    let created = create_handler
+        .execute(CreateUserCommand {
+            name: "fresh".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let found = queries
+        .scope(handler.execute(GetUserQuery {
+            user_id: created.id,
+        }))
+        .await
+        .unwrap();
+    assert_eq!(found.id, created.id);
+    assert_eq!(found.name, "fresh");
+    assert_eq!(found.created_at, created.created_at);
+    assert_eq!(queries.count(), 0);
+
+    update_handler
+        .execute(UpdateUserCommand {
+            user_id: created.id,
+            name: Some("renamed".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let found = queries
+        .scope(handler.execute(GetUserQuery {
+            user_id: created.id,
+        }))
+        .await
+        .unwrap();
+    assert_eq!(found.name, "renamed");
+    assert_eq!(queries.count(), 0);
+}
+
+async fn reads_miss_after_rename_and_delete(
+    container: &TestPostgresContainer,
+) {
+    let sql_connect = create_sql_connect(container);
+    let handler = GetUserQueryHandler::new(sql_connect.clone());
+    let by_name = GetUserByNameQueryHandler::new(sql_connect.clone());
+    let update_handler = UpdateUserHandler::new(sql_connect.clone());
+    let delete_handler = DeleteUserHandler::new(sql_connect.clone());
+    let user_id = create_test_user_with_name(container, "before")
+        .await
+        .unwrap();
+
+    // Warm both caches
+    handler.execute(GetUserQuery { user_id }).await.unwrap();
+    by_name
+        .execute(GetUserByNameQuery {
+            name: "before".to_string(),
+        })
+        .await
+        .unwrap();
+
+    update_handler
+        .execute(UpdateUserCommand {
+            user_id,
+            name: Some("after".to_string()),
+        })
+        .await
+        .unwrap();
+    let old_name = by_name
+        .execute(GetUserByNameQuery {
+            name: "before".to_string(),
+        })
+        .await;
+    assert!(matches!(old_name, Err(UserError::NameNotFound { .. })));
+
+    delete_handler
+        .execute(DeleteUserCommand { user_id })
+        .await
+        .unwrap();
+    let deleted = handler.execute(GetUserQuery { user_id }).await;
+    assert!(matches!(deleted, Err(UserError::NotFound { .. })));
+    let new_name = by_name
+        .execute(GetUserByNameQuery {
+            name: "after".to_string(),
+        })
+        .await;
+    assert!(matches!(new_name, Err(UserError::NameNotFound { .. })));
+}
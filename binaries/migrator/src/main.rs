@@ -3,9 +3,8 @@ use std::env;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use sql_connection::{
-    config::PostgresDbConfig, connect_postgres_db, get_sql_pool,
+    SqlMigrator, config::PostgresDbConfig, connect_postgres_db, get_sql_pool,
 };
-use test_utils::SqlMigrator;
 use tracing::{Level, info};
 
 #[derive(Parser)]
@@ -51,7 +50,8 @@ async fn main() -> Result<()> {
         max_conn: Some(10),
         min_conn: Some(2),
         logger: false,
-        // Read replica fields removed for BRRRRR mode
+        // Migrations may run long; never bound them
+        statement_timeouts: Default::default(),
     };
 
     connect_postgres_db(&config).await?;
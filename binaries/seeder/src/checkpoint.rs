@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use deadpool_postgres::{Client, Pool};
+
+use crate::{
+    Event, EventType, create_event_types, journey::with_journey_event_types,
+    timestamp, write_events,
+};
+
+/// Whether a run may pick up where an interrupted one stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedMode {
+    /// Ignore any checkpoint, truncate and seed from scratch
+    Fresh,
+    /// Continue a checkpointed run with the same parameters, otherwise
+    /// behave like `Fresh`
+    Resume,
+}
+
+/// How generated events relate to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventMode {
+    /// Independent events with random types and sessions
+    Random,
+    /// Whole sessions walking an ordered purchase funnel
+    Journey,
+}
+
+impl EventMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventMode::Random => "random",
+            EventMode::Journey => "journey",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(EventMode::Random),
+            "journey" => Some(EventMode::Journey),
+            _ => None,
+        }
+    }
+}
+
+/// Run parameters a checkpoint is only valid for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedParams {
+    pub users_count: usize,
+    pub event_types_count: usize,
+    pub events_count: usize,
+    pub batch_size: usize,
+    pub mode: EventMode,
+}
+
+impl SeedParams {
+    /// Event types a run with these parameters stores; in journey mode the
+    /// funnel steps are added when missing
+    pub fn event_types(&self) -> Vec<EventType> {
+        let event_types = create_event_types(self.event_types_count);
+        match self.mode {
+            EventMode::Random => event_types,
+            EventMode::Journey => with_journey_event_types(event_types),
+        }
+    }
+
+    pub fn total_batches(&self) -> usize {
+        self.events_count.div_ceil(self.batch_size)
+    }
+
+    /// Number of events in batch `index`; only the last one may be short
+    pub fn batch_len(&self, index: usize) -> usize {
+        self.batch_size
+            .min(self.events_count.saturating_sub(index * self.batch_size))
+    }
+
+    /// Batch indexes still to write, in order
+    pub fn pending_batches(&self, completed: &HashSet<usize>) -> Vec<usize> {
+        (0..self.total_batches())
+            .filter(|index| !completed.contains(index))
+            .collect()
+    }
+}
+
+const CREATE_CHECKPOINT_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS seeder_checkpoint (
+        id SMALLINT PRIMARY KEY CHECK (id = 1),
+        users_count BIGINT NOT NULL,
+        event_types_count BIGINT NOT NULL,
+        events_count BIGINT NOT NULL,
+        batch_size BIGINT NOT NULL,
+        started_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+    -- Checkpoints from before modes were recorded have none and are never
+    -- resumed
+    ALTER TABLE seeder_checkpoint ADD COLUMN IF NOT EXISTS event_mode TEXT;
+    CREATE TABLE IF NOT EXISTS seeder_completed_batches (
+        batch_index BIGINT PRIMARY KEY
+    );";
+
+/// Batches already written by an earlier run with the same `params`, or
+/// `None` when there is nothing to resume
+pub async fn load_checkpoint(
+    pool: &Pool, params: &SeedParams,
+) -> anyhow::Result<Option<HashSet<usize>>> {
+    let client = pool.get().await?;
+    client.batch_execute(CREATE_CHECKPOINT_TABLES).await?;
+
+    let Some(row) = client
+        .query_opt(
+            "SELECT users_count, event_types_count, events_count, \
+             batch_size, event_mode FROM seeder_checkpoint WHERE id = 1",
+            &[],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let Some(mode) = row.get::<_, Option<&str>>(4).and_then(EventMode::parse)
+    else {
+        println!(
+            "{} ⚠️  Checkpoint does not record its --mode; starting fresh",
+            timestamp()
+        );
+        return Ok(None);
+    };
+    let stored = SeedParams {
+        users_count: row.get::<_, i64>(0) as usize,
+        event_types_count: row.get::<_, i64>(1) as usize,
+        events_count: row.get::<_, i64>(2) as usize,
+        batch_size: row.get::<_, i64>(3) as usize,
+        mode,
+    };
+    if stored != *params {
+        println!(
+            "{} ⚠️  Checkpoint was taken with different parameters \
+             ({stored:?}); starting fresh",
+            timestamp()
+        );
+        return Ok(None);
+    }
+
+    // The checkpoint is written after the lookups, but they may have been
+    // changed or truncated since
+    let lookups = client
+        .query_one(
+            "SELECT (SELECT COUNT(*) FROM users), (SELECT COUNT(*) FROM \
+             event_types)",
+            &[],
+        )
+        .await?;
+    let (users, event_types) = (
+        lookups.get::<_, i64>(0) as usize,
+        lookups.get::<_, i64>(1) as usize,
+    );
+    if users != params.users_count
+        || event_types != params.event_types().len()
+    {
+        println!(
+            "{} ⚠️  Checkpoint expects {} users and {} event types but {} \
+             and {} are stored; starting fresh",
+            timestamp(),
+            params.users_count,
+            params.event_types().len(),
+            users,
+            event_types
+        );
+        return Ok(None);
+    }
+
+    let completed = client
+        .query("SELECT batch_index FROM seeder_completed_batches", &[])
+        .await?
+        .iter()
+        .map(|row| row.get::<_, i64>(0) as usize)
+        .collect();
+    Ok(Some(completed))
+}
+
+/// Empty the seeded tables and drop any checkpoint. A new checkpoint is
+/// only recorded by [`record_checkpoint`] once the lookups are stored, so a
+/// run killed before then is never resumed.
+pub async fn start_fresh(pool: &Pool) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+    client.batch_execute(CREATE_CHECKPOINT_TABLES).await?;
+    client
+        .batch_execute(
+            "TRUNCATE events, users, event_types, seeder_checkpoint, \
+             seeder_completed_batches RESTART IDENTITY CASCADE",
+        )
+        .await?;
+    Ok(())
+}
+
+/// Checkpoint a run with `params` whose users and event types are stored,
+/// so its event batches can be resumed
+pub async fn record_checkpoint(
+    pool: &Pool, params: &SeedParams,
+) -> anyhow::Result<()> {
+    let client = pool.get().await?;
+    client
+        .execute(
+            "INSERT INTO seeder_checkpoint (id, users_count, \
+             event_types_count, events_count, batch_size, event_mode) \
+             VALUES (1, $1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE
+             SET users_count = EXCLUDED.users_count,
+                 event_types_count = EXCLUDED.event_types_count,
+                 events_count = EXCLUDED.events_count,
+                 batch_size = EXCLUDED.batch_size,
+                 event_mode = EXCLUDED.event_mode,
+                 started_at = NOW()",
+            &[
+                &(params.users_count as i64),
+                &(params.event_types_count as i64),
+                &(params.events_count as i64),
+                &(params.batch_size as i64),
+                &params.mode.as_str(),
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Users and event types written by the run being resumed
+pub async fn load_seeded_lookups(
+    pool: &Pool,
+) -> anyhow::Result<(Vec<i64>, Vec<EventType>, HashMap<String, i32>)> {
+    let client = pool.get().await?;
+
+    let user_ids = client
+        .query("SELECT id FROM users ORDER BY id", &[])
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    let rows = client
+        .query("SELECT id, name FROM event_types ORDER BY id", &[])
+        .await?;
+    let event_types = rows
+        .iter()
+        .map(|row| EventType { name: row.get(1) })
+        .collect();
+    let event_type_map =
+        rows.iter().map(|row| (row.get(1), row.get(0))).collect();
+
+    Ok((user_ids, event_types, event_type_map))
+}
+
+/// Write one batch and mark it written in the same transaction, so an
+/// interrupted run never leaves a batch both stored and pending
+pub async fn insert_batch_checkpointed(
+    client: &mut Client, batch_index: usize, batch: &[Event],
+    event_type_map: &HashMap<String, i32>,
+) -> anyhow::Result<()> {
+    let tx = client.transaction().await?;
+    write_events(&tx, batch, event_type_map).await?;
+    tx.execute(
+        "INSERT INTO seeder_completed_batches (batch_index) VALUES ($1)",
+        &[&(batch_index as i64)],
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::*;
+
+    use super::*;
+    use crate::create_events_for_batch;
+
+    async fn write_batches(
+        pool: &Pool, params: &SeedParams, user_ids: &[i64],
+        event_types: &[EventType], event_type_map: &HashMap<String, i32>,
+        batches: &[usize],
+    ) {
+        let mut client = pool.get().await.unwrap();
+        for &index in batches {
+            let batch = create_events_for_batch(
+                params.batch_len(index),
+                user_ids,
+                event_types,
+                index * params.batch_size,
+            );
+            insert_batch_checkpointed(
+                &mut client,
+                index,
+                &batch,
+                event_type_map,
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pending_batches_skip_completed() {
+        let params = SeedParams {
+            users_count: 1,
+            event_types_count: 1,
+            events_count: 25,
+            batch_size: 10,
+            mode: EventMode::Random,
+        };
+
+        assert_eq!(params.total_batches(), 3);
+        assert_eq!(params.batch_len(2), 5);
+        assert_eq!(params.pending_batches(&HashSet::from([0, 2])), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_resume_completes_without_duplicates() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let pool = &container.pool;
+        let params = SeedParams {
+            users_count: 1,
+            event_types_count: 1,
+            events_count: 45,
+            batch_size: 10,
+            mode: EventMode::Random,
+        };
+
+        start_fresh(pool).await.unwrap();
+        create_test_user(&container).await.unwrap();
+        create_test_event_type(&container).await.unwrap();
+        record_checkpoint(pool, &params).await.unwrap();
+        let (user_ids, event_types, event_type_map) =
+            load_seeded_lookups(pool).await.unwrap();
+
+        // Workers finish out of order, then the run dies
+        write_batches(
+            pool,
+            &params,
+            &user_ids,
+            &event_types,
+            &event_type_map,
+            &[0, 1, 3],
+        )
+        .await;
+
+        let other = SeedParams {
+            events_count: 90,
+            ..params
+        };
+        assert!(load_checkpoint(pool, &other).await.unwrap().is_none());
+        let other_mode = SeedParams {
+            mode: EventMode::Journey,
+            ..params
+        };
+        assert!(load_checkpoint(pool, &other_mode).await.unwrap().is_none());
+
+        let completed =
+            load_checkpoint(pool, &params).await.unwrap().unwrap();
+        let pending = params.pending_batches(&completed);
+        assert_eq!(pending, vec![2, 4]);
+
+        write_batches(
+            pool,
+            &params,
+            &user_ids,
+            &event_types,
+            &event_type_map,
+            &pending,
+        )
+        .await;
+
+        let client = pool.get().await.unwrap();
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM events", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 45);
+        assert!(
+            params
+                .pending_batches(
+                    &load_checkpoint(pool, &params).await.unwrap().unwrap()
+                )
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_killed_before_lookups_are_stored_is_not_resumed() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let pool = &container.pool;
+        let params = SeedParams {
+            users_count: 2,
+            event_types_count: 1,
+            events_count: 20,
+            batch_size: 10,
+            mode: EventMode::Random,
+        };
+
+        // Killed right after truncating, before any lookup is inserted
+        start_fresh(pool).await.unwrap();
+        assert!(load_checkpoint(pool, &params).await.unwrap().is_none());
+
+        // Checkpointed, but only some of the users are still there
+        create_test_user(&container).await.unwrap();
+        create_test_event_type(&container).await.unwrap();
+        record_checkpoint(pool, &params).await.unwrap();
+        assert!(load_checkpoint(pool, &params).await.unwrap().is_none());
+
+        create_test_user(&container).await.unwrap();
+        let completed =
+            load_checkpoint(pool, &params).await.unwrap().unwrap();
+        assert!(completed.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use crate::{EVENT_TYPES, Event, EventType, REFERRERS};
+
+/// Purchase funnel every journey walks through in order: view, cart,
+/// checkout, payment, fulfilment. Sessions drop off after any step, so later
+/// steps are progressively rarer.
+pub static JOURNEY_STEPS: &[&str] = &[
+    "product.viewed",
+    "product.added_to_cart",
+    "order.created",
+    "payment.processed",
+    "order.paid",
+    "order.shipped",
+    "order.delivered",
+];
+
+/// Chance a session moves on to the next funnel step
+const CONTINUE_PROBABILITY: f64 = 0.6;
+
+/// Seconds between consecutive events of a session
+const MIN_GAP_SECS: i64 = 5;
+const MAX_GAP_SECS: i64 = 300;
+
+/// Add any funnel step missing from `event_types`, so journeys can always
+/// be stored
+pub fn with_journey_event_types(
+    mut event_types: Vec<EventType>,
+) -> Vec<EventType> {
+    for step in JOURNEY_STEPS {
+        if !event_types.iter().any(|et| et.name == *step) {
+            event_types.push(EventType {
+                name: step.to_string(),
+            });
+        }
+    }
+    event_types
+}
+
+/// Generate `count` events as whole sessions: each session belongs to one
+/// user, shares one `session_id`, `referrer` and `product_id`, and walks a
+/// prefix of [`JOURNEY_STEPS`] with a few seconds to minutes between steps.
+/// The last session is cut short when the batch is full.
+pub fn create_journey_events_for_batch(
+    count: usize, user_ids: &[i64], offset: usize,
+) -> Vec<Event> {
+    let mut rng = SmallRng::from_entropy();
+    let now = Utc::now();
+    let longest_session = MAX_GAP_SECS * JOURNEY_STEPS.len() as i64;
+    let start_range = Duration::days(30).num_seconds() - longest_session;
+
+    let mut events = Vec::with_capacity(count);
+    while events.len() < count {
+        let user_id = user_ids[(offset + events.len()) % user_ids.len()];
+        let session_id =
+            rng.gen_range(100_000_000..999_999_999u64).to_string();
+        let referrer = REFERRERS[rng.gen_range(0..REFERRERS.len())];
+        let product_id = rng.gen_range(1..=5000);
+        let mut timestamp = now - Duration::days(30)
+            + Duration::seconds(rng.gen_range(0..start_range));
+
+        for (step, event_type) in JOURNEY_STEPS.iter().enumerate() {
+            if events.len() == count
+                || (step > 0 && !rng.gen_bool(CONTINUE_PROBABILITY))
+            {
+                break;
+            }
+            if step > 0 {
+                timestamp += Duration::seconds(
+                    rng.gen_range(MIN_GAP_SECS..=MAX_GAP_SECS),
+                );
+            }
+
+            events.push(Event {
+                user_id,
+                event_type: event_type.to_string(),
+                timestamp,
+                metadata: serde_json::json!({
+                    "page": EVENT_TYPES
+                        .get(event_type)
+                        .and_then(|m| m.get("page"))
+                        .map_or("/unknown", |v| *v),
+                    "product_id": product_id,
+                    "referrer": referrer,
+                    "session_id": session_id,
+                }),
+            });
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_sessions_are_ordered_funnel_prefixes() {
+        let events = create_journey_events_for_batch(5000, &[1, 2, 3], 0);
+        assert_eq!(events.len(), 5000);
+
+        let mut sessions: HashMap<String, Vec<&Event>> = HashMap::new();
+        for event in &events {
+            sessions
+                .entry(event.metadata["session_id"].to_string())
+                .or_default()
+                .push(event);
+        }
+        assert!(sessions.len() < events.len());
+        assert!(sessions.values().any(|session| session.len() > 1));
+
+        for session in sessions.values() {
+            let steps: Vec<&str> =
+                session.iter().map(|e| e.event_type.as_str()).collect();
+            assert_eq!(steps, JOURNEY_STEPS[..steps.len()]);
+
+            assert!(session.iter().all(|e| e.user_id == session[0].user_id));
+            assert!(session.iter().all(|e| {
+                e.metadata["product_id"] == session[0].metadata["product_id"]
+            }));
+            assert!(
+                session.windows(2).all(|w| w[0].timestamp < w[1].timestamp)
+            );
+            assert!(session.iter().all(|e| e.timestamp <= Utc::now()));
+        }
+    }
+
+    #[test]
+    fn test_missing_funnel_event_types_are_added() {
+        let event_types = with_journey_event_types(vec![EventType {
+            name: "product.viewed".to_string(),
+        }]);
+
+        assert_eq!(event_types.len(), JOURNEY_STEPS.len());
+        for step in JOURNEY_STEPS {
+            assert!(event_types.iter().any(|et| et.name == *step));
+        }
+    }
+}
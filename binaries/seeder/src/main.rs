@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     time::{Duration, Instant},
 };
 
@@ -10,13 +10,27 @@ use deadpool_postgres::Pool;
 use flume::{Receiver, Sender, bounded};
 use futures::future::try_join_all;
 use seeder::{
-    Event, EventType, User, create_event_types, create_events_for_batch,
-    create_pool, create_users, prepare_database, restore_database,
+    Event, EventType, User,
+    checkpoint::{
+        EventMode, SeedMode, SeedParams, insert_batch_checkpointed,
+        load_checkpoint, load_seeded_lookups, record_checkpoint, start_fresh,
+    },
+    create_events_for_batch, create_pool, create_users,
+    journey::create_journey_events_for_batch,
+    prepare_database,
+    progress::{
+        ProgressEvent, ProgressTracker, SeedPhase, insert_in_batches,
+    },
+    restore_database,
 };
 use tokio_postgres::types::ToSql;
 
 fn timestamp() -> String { Utc::now().format("[%H:%M:%S]").to_string() }
 
+/// Rows per insert for users and event types, well under the 65535 bind
+/// parameter limit
+const LOOKUP_BATCH_SIZE: usize = 10_000;
+
 #[derive(Parser)]
 #[command(name = "seeder")]
 #[command(about = "Database seeding tool for performance testing")]
@@ -44,6 +58,42 @@ struct Cli {
         help = "Batch size for bulk inserts"
     )]
     batch_size: usize,
+
+    #[arg(
+        long,
+        help = "Ignore any checkpoint from an interrupted run and start over"
+    )]
+    fresh: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "random",
+        help = "Generate independent random events or ordered session \
+                journeys"
+    )]
+    mode: EventMode,
+}
+
+impl Cli {
+    fn mode(&self) -> SeedMode {
+        if self.fresh {
+            SeedMode::Fresh
+        }
+        else {
+            SeedMode::Resume
+        }
+    }
+
+    fn params(&self) -> SeedParams {
+        SeedParams {
+            users_count: self.users_count,
+            event_types_count: self.event_types_count,
+            events_count: self.events_count,
+            batch_size: self.batch_size.max(1),
+            mode: self.mode,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -51,7 +101,6 @@ struct WorkerStats {
     batches_processed: u32,
     events_processed: u64,
     total_wait_time: Duration,
-    total_build_time: Duration,
     total_db_time: Duration,
 }
 
@@ -67,47 +116,45 @@ async fn main() -> Result<()> {
     let overall_start = Instant::now();
 
     let gen_start = Instant::now();
+    let params = cli.params();
     let users = create_users(cli.users_count);
-    let event_types = create_event_types(cli.event_types_count);
+    let event_types = params.event_types();
     let generation_duration = gen_start.elapsed();
 
     let load_start = Instant::now();
     let pool = create_pool().await?;
+    let checkpoint = match cli.mode() {
+        SeedMode::Fresh => None,
+        SeedMode::Resume => load_checkpoint(&pool, &params).await?,
+    };
     prepare_database(&pool).await?;
 
-    // Insert users
-    println!("{} 👥 Inserting {} users...", timestamp(), cli.users_count);
-    let user_insert_start = Instant::now();
-    let user_ids = insert_users(&pool, &users).await?;
-    let user_insert_duration = user_insert_start.elapsed();
-    println!(
-        "{} ✅ Inserted {} users in {:?}",
-        timestamp(),
-        user_ids.len(),
-        user_insert_duration
-    );
-
-    // Insert event types
-    println!(
-        "{} 📋 Inserting {} event types...",
-        timestamp(),
-        cli.event_types_count
-    );
-    let event_type_insert_start = Instant::now();
-    insert_event_types(&pool, &event_types).await?;
-    let event_type_insert_duration = event_type_insert_start.elapsed();
-    println!(
-        "{} ✅ Inserted {} event types in {:?}",
-        timestamp(),
-        cli.event_types_count,
-        event_type_insert_duration
-    );
-
-    let event_type_map: HashMap<String, i32> = event_types
-        .iter()
-        .enumerate()
-        .map(|(i, et)| (et.name.clone(), (i + 1) as i32))
-        .collect();
+    let (tracker, progress_rx) = ProgressTracker::new();
+    let reporter = tokio::spawn(report_progress(progress_rx));
+
+    let (user_ids, event_types, event_type_map, completed) = match checkpoint
+    {
+        Some(completed) => {
+            println!(
+                "{} ♻️  Resuming from checkpoint: {}/{} batches already \
+                 written",
+                timestamp(),
+                completed.len(),
+                params.total_batches()
+            );
+            let (user_ids, event_types, event_type_map) =
+                load_seeded_lookups(&pool).await?;
+            (user_ids, event_types, event_type_map, completed)
+        }
+        None => {
+            start_fresh(&pool).await?;
+            let (user_ids, event_type_map) =
+                seed_lookups(&pool, &users, &event_types, &tracker).await?;
+            record_checkpoint(&pool, &params).await?;
+            (user_ids, event_types, event_type_map, HashSet::new())
+        }
+    };
+    let pending = params.pending_batches(&completed);
 
     // Start bulk event insertion
     println!(
@@ -121,6 +168,12 @@ async fn main() -> Result<()> {
     let mut worker_tasks = Vec::new();
     let worker_count = num_cpus::get();
 
+    tracker.start(SeedPhase::Events, cli.events_count as u64);
+    tracker.advance(
+        SeedPhase::Events,
+        completed.iter().map(|&i| params.batch_len(i) as u64).sum(),
+    );
+
     let buffer_size = worker_count * 3;
     let (tx, rx) = bounded(buffer_size);
 
@@ -128,20 +181,29 @@ async fn main() -> Result<()> {
         tx,
         user_ids.clone(),
         event_types.clone(),
-        cli.events_count,
-        cli.batch_size,
+        params,
+        pending,
     ));
     for _ in 0..worker_count {
         let pool_clone = pool.clone();
         let rx_clone = rx.clone();
         let event_type_map_clone = event_type_map.clone();
+        let tracker_clone = tracker.clone();
         worker_tasks.push(tokio::spawn(async move {
-            worker_task(pool_clone, rx_clone, event_type_map_clone).await
+            worker_task(
+                pool_clone,
+                rx_clone,
+                event_type_map_clone,
+                tracker_clone,
+            )
+            .await
         }));
     }
 
     producer_handle.await??;
     let worker_results = try_join_all(worker_tasks).await?;
+    drop(tracker);
+    reporter.await?;
     let bulk_insert_duration = bulk_insert_start.elapsed();
     println!(
         "{} ✅ Completed bulk insertion of {} events in {:?}",
@@ -177,27 +239,27 @@ async fn main() -> Result<()> {
 }
 
 async fn produce_batches(
-    tx: Sender<Vec<Event>>, user_ids: Vec<i64>, event_types: Vec<EventType>,
-    events_count: usize, batch_size: usize,
+    tx: Sender<(usize, Vec<Event>)>, user_ids: Vec<i64>,
+    event_types: Vec<EventType>, params: SeedParams, pending: Vec<usize>,
 ) -> Result<()> {
-    let total_batches = events_count.div_ceil(batch_size);
-
-    for i in 0..total_batches {
-        let current_batch_size = if i == total_batches - 1 {
-            events_count % batch_size
-        }
-        else {
-            batch_size
+    for index in pending {
+        let (count, offset) =
+            (params.batch_len(index), index * params.batch_size);
+        let batch = match params.mode {
+            EventMode::Random => {
+                create_events_for_batch(
+                    count,
+                    &user_ids,
+                    &event_types,
+                    offset,
+                )
+            }
+            EventMode::Journey => {
+                create_journey_events_for_batch(count, &user_ids, offset)
+            }
         };
 
-        let batch = create_events_for_batch(
-            current_batch_size,
-            &user_ids,
-            &event_types,
-            i * batch_size,
-        );
-
-        if tx.send_async(batch).await.is_err() {
+        if tx.send_async((index, batch)).await.is_err() {
             break;
         }
     }
@@ -206,23 +268,22 @@ async fn produce_batches(
 }
 
 async fn worker_task(
-    pool: Pool, rx: Receiver<Vec<Event>>,
-    event_type_map: HashMap<String, i32>,
+    pool: Pool, rx: Receiver<(usize, Vec<Event>)>,
+    event_type_map: HashMap<String, i32>, tracker: ProgressTracker,
 ) -> Result<WorkerStats> {
     let mut stats = WorkerStats {
         batches_processed: 0,
         events_processed: 0,
         total_wait_time: Duration::ZERO,
-        total_build_time: Duration::ZERO,
         total_db_time: Duration::ZERO,
     };
 
-    let client = pool.get().await?;
+    let mut client = pool.get().await?;
 
     loop {
         let wait_start = Instant::now();
-        let batch = match rx.recv_async().await {
-            Ok(batch) => batch,
+        let (index, batch) = match rx.recv_async().await {
+            Ok(received) => received,
             Err(_) => break,
         };
         stats.total_wait_time += wait_start.elapsed();
@@ -231,57 +292,24 @@ async fn worker_task(
             continue;
         }
 
-        let build_start = Instant::now();
-        let (sql, params) = build_insert_query(&batch, &event_type_map);
-        let param_refs: Vec<&(dyn ToSql + Sync)> = params
-            .iter()
-            .map(|p| p.as_ref() as &(dyn ToSql + Sync))
-            .collect();
-        stats.total_build_time += build_start.elapsed();
-
         let db_start = Instant::now();
-        client.execute(sql.as_str(), &param_refs).await?;
+        insert_batch_checkpointed(
+            &mut client,
+            index,
+            &batch,
+            &event_type_map,
+        )
+        .await?;
         stats.total_db_time += db_start.elapsed();
 
         stats.batches_processed += 1;
         stats.events_processed += batch.len() as u64;
+        tracker.advance(SeedPhase::Events, batch.len() as u64);
     }
 
     Ok(stats)
 }
 
-fn build_insert_query(
-    batch: &[Event], event_type_map: &HashMap<String, i32>,
-) -> (String, Vec<Box<dyn ToSql + Sync + Send>>) {
-    let mut sql = "INSERT INTO events (user_id, event_type_id, timestamp, \
-                   metadata) VALUES "
-        .to_string();
-    let mut params: Vec<Box<dyn ToSql + Sync + Send>> =
-        Vec::with_capacity(batch.len() * 4);
-
-    for (i, event) in batch.iter().enumerate() {
-        let p_base = i * 4;
-        sql.push_str(&format!(
-            "(${}, ${}, ${}, ${})",
-            p_base + 1,
-            p_base + 2,
-            p_base + 3,
-            p_base + 4
-        ));
-        if i < batch.len() - 1 {
-            sql.push(',');
-        }
-
-        params.push(Box::new(event.user_id));
-        params
-            .push(Box::new(*event_type_map.get(&event.event_type).unwrap()));
-        params.push(Box::new(event.timestamp));
-        params.push(Box::new(event.metadata.clone()));
-    }
-
-    (sql, params)
-}
-
 fn print_summary_report(
     all_stats: Vec<WorkerStats>, timings: RunTimings, events_count: usize,
 ) {
@@ -308,16 +336,14 @@ fn print_summary_report(
 
     let mut total_batches = 0;
     let mut total_wait = Duration::ZERO;
-    let mut total_build = Duration::ZERO;
     let mut total_db = Duration::ZERO;
 
     for stats in &all_stats {
         total_batches += stats.batches_processed;
         total_wait += stats.total_wait_time;
-        total_build += stats.total_build_time;
         total_db += stats.total_db_time;
     }
-    let total_worker_time = total_wait + total_build + total_db;
+    let total_worker_time = total_wait + total_db;
 
     let worker_count = all_stats.len() as u32;
 
@@ -344,11 +370,6 @@ fn print_summary_report(
         total_wait / worker_count,
         (total_wait.as_secs_f64() / total_worker_time.as_secs_f64()) * 100.0
     );
-    println!(
-        "  -Building Queries:  {:.2?} ({:.1}%)",
-        total_build / worker_count,
-        (total_build.as_secs_f64() / total_worker_time.as_secs_f64()) * 100.0
-    );
     println!(
         "  -Database Calls:   {:.2?} ({:.1}%)",
         total_db / worker_count,
@@ -370,60 +391,148 @@ fn print_summary_report(
     println!("CPU Cores Utilized: {}", num_cpus::get());
 }
 
-async fn insert_users(pool: &Pool, users: &[User]) -> Result<Vec<i64>> {
-    let client = pool.get().await?;
-    let count = users.len();
-
-    let mut sql = "INSERT INTO users (name, created_at) VALUES ".to_string();
-    let mut params: Vec<Box<dyn ToSql + Sync + Send>> =
-        Vec::with_capacity(count * 2);
-    let mut param_idx = 1;
-
-    for user in users {
-        sql.push_str(&format!("(${}, ${}),", param_idx, param_idx + 1));
-        params.push(Box::new(user.name.as_str()));
-        params.push(Box::new(user.created_at));
-        param_idx += 2;
-    }
-    sql.pop();
-    sql.push_str(" RETURNING id");
+/// Insert users and event types, returning the user ids and the event type
+/// name to id mapping
+async fn seed_lookups(
+    pool: &Pool, users: &[User], event_types: &[EventType],
+    tracker: &ProgressTracker,
+) -> Result<(Vec<i64>, HashMap<String, i32>)> {
+    println!("{} 👥 Inserting {} users...", timestamp(), users.len());
+    let user_insert_start = Instant::now();
+    let user_ids = insert_users(pool, users, tracker).await?;
+    println!(
+        "{} ✅ Inserted {} users in {:?}",
+        timestamp(),
+        user_ids.len(),
+        user_insert_start.elapsed()
+    );
 
-    let params_slice: Vec<&(dyn ToSql + Sync)> = params
+    println!(
+        "{} 📋 Inserting {} event types...",
+        timestamp(),
+        event_types.len()
+    );
+    let event_type_insert_start = Instant::now();
+    let event_type_ids =
+        insert_event_types(pool, event_types, tracker).await?;
+    println!(
+        "{} ✅ Inserted {} event types in {:?}",
+        timestamp(),
+        event_type_ids.len(),
+        event_type_insert_start.elapsed()
+    );
+
+    let event_type_map = event_types
         .iter()
-        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .zip(event_type_ids)
+        .map(|(et, id)| (et.name.clone(), id))
         .collect();
-    let rows = client.query(sql.as_str(), &params_slice).await?;
 
-    let user_ids: Vec<i64> = rows.iter().map(|row| row.get(0)).collect();
-    Ok(user_ids)
+    Ok((user_ids, event_type_map))
 }
 
-async fn insert_event_types(
-    pool: &Pool, event_types: &[EventType],
-) -> Result<Vec<i32>> {
-    let client = pool.get().await?;
+/// Print each phase's progress every time it crosses another 10%
+async fn report_progress(rx: Receiver<ProgressEvent>) {
+    let mut last_step: HashMap<SeedPhase, u64> = HashMap::new();
 
-    let mut sql = "INSERT INTO event_types (name) VALUES ".to_string();
-    let mut params: Vec<Box<dyn ToSql + Sync + Send>> =
-        Vec::with_capacity(event_types.len());
-    for (i, event_type) in event_types.iter().enumerate() {
-        sql.push_str(&format!("(${})", i + 1));
-        if i < event_types.len() - 1 {
-            sql.push(',');
+    while let Ok(event) = rx.recv_async().await {
+        let step = (event.percent() / 10.0) as u64;
+        if last_step.insert(event.phase, step) == Some(step) {
+            continue;
         }
-        params.push(Box::new(event_type.name.as_str()));
+        println!(
+            "{} ⏳ {:?}: {}/{} ({:.0}%)",
+            timestamp(),
+            event.phase,
+            event.done,
+            event.total,
+            event.percent()
+        );
     }
+}
 
-    let params_slice: Vec<&(dyn ToSql + Sync)> = params
-        .iter()
-        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
-        .collect();
-    client.execute(sql.as_str(), &params_slice).await?;
+async fn insert_users(
+    pool: &Pool, users: &[User], tracker: &ProgressTracker,
+) -> Result<Vec<i64>> {
+    let client = pool.get().await?;
+    let client = &client;
+
+    insert_in_batches(
+        users,
+        LOOKUP_BATCH_SIZE,
+        SeedPhase::Users,
+        tracker,
+        |chunk| {
+            async move {
+                let mut sql = "INSERT INTO users (name, created_at) VALUES "
+                    .to_string();
+                let mut params: Vec<Box<dyn ToSql + Sync + Send>> =
+                    Vec::with_capacity(chunk.len() * 2);
+                let mut param_idx = 1;
+
+                for user in chunk {
+                    sql.push_str(&format!(
+                        "(${}, ${}),",
+                        param_idx,
+                        param_idx + 1
+                    ));
+                    params.push(Box::new(user.name.as_str()));
+                    params.push(Box::new(user.created_at));
+                    param_idx += 2;
+                }
+                sql.pop();
+                sql.push_str(" RETURNING id");
 
-    let rows = client
-        .query("SELECT id FROM event_types ORDER BY id", &[])
-        .await?;
-    Ok(rows.iter().map(|row| row.get("id")).collect())
+                let params_slice: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+                let rows = client.query(sql.as_str(), &params_slice).await?;
+
+                Ok(rows.iter().map(|row| row.get(0)).collect())
+            }
+        },
+    )
+    .await
+}
+
+async fn insert_event_types(
+    pool: &Pool, event_types: &[EventType], tracker: &ProgressTracker,
+) -> Result<Vec<i32>> {
+    let client = pool.get().await?;
+    let client = &client;
+
+    insert_in_batches(
+        event_types,
+        LOOKUP_BATCH_SIZE,
+        SeedPhase::EventTypes,
+        tracker,
+        |chunk| {
+            async move {
+                let mut sql =
+                    "INSERT INTO event_types (name) VALUES ".to_string();
+                let mut params: Vec<Box<dyn ToSql + Sync + Send>> =
+                    Vec::with_capacity(chunk.len());
+                for (i, event_type) in chunk.iter().enumerate() {
+                    sql.push_str(&format!("(${})", i + 1));
+                    if i < chunk.len() - 1 {
+                        sql.push(',');
+                    }
+                    params.push(Box::new(event_type.name.as_str()));
+                }
+                sql.push_str(" RETURNING id");
+
+                let params_slice: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+                let rows = client.query(sql.as_str(), &params_slice).await?;
+
+                Ok(rows.iter().map(|row| row.get("id")).collect())
+            }
+        },
+    )
+    .await
 }
 
 #[cfg(target_os = "macos")]
@@ -449,12 +558,12 @@ fn get_memory_usage() -> Option<u64> {
 
     if let Ok(contents) = fs::read_to_string("/proc/self/status") {
         for line in contents.lines() {
-            if line.starts_with("VmHWM:") {
-                if let Some(value) = line.split_whitespace().nth(1) {
-                    if let Ok(kb) = value.parse::<u64>() {
-                        return Some(kb * 1024);
-                    }
-                }
+            if let Some(kb) = line
+                .strip_prefix("VmHWM:")
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|value| value.parse::<u64>().ok())
+            {
+                return Some(kb * 1024);
             }
         }
     }
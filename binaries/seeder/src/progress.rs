@@ -0,0 +1,205 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use flume::{Receiver, Sender, unbounded};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeedPhase {
+    Users,
+    EventTypes,
+    Events,
+}
+
+impl SeedPhase {
+    fn index(self) -> usize {
+        match self {
+            Self::Users => 0,
+            Self::EventTypes => 1,
+            Self::Events => 2,
+        }
+    }
+}
+
+/// Rows written so far in one phase, out of a total known up front
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    pub phase: SeedPhase,
+    pub done: u64,
+    pub total: u64,
+}
+
+impl ProgressEvent {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        }
+        else {
+            self.done as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PhaseState {
+    done: u64,
+    total: u64,
+}
+
+/// Shared by every task writing rows. Updates for a phase are counted and
+/// sent under one lock, so a listener always sees `done` increase even when
+/// several workers report at once.
+#[derive(Clone)]
+pub struct ProgressTracker {
+    tx: Option<Sender<ProgressEvent>>,
+    phases: Arc<Mutex<[PhaseState; 3]>>,
+}
+
+impl ProgressTracker {
+    pub fn new() -> (Self, Receiver<ProgressEvent>) {
+        let (tx, rx) = unbounded();
+        let tracker = Self {
+            tx: Some(tx),
+            phases: Arc::default(),
+        };
+        (tracker, rx)
+    }
+
+    /// Tracker that counts but reports to nobody
+    pub fn disabled() -> Self {
+        Self {
+            tx: None,
+            phases: Arc::default(),
+        }
+    }
+
+    /// Begin `phase` with `total` rows to write and report 0%
+    pub fn start(&self, phase: SeedPhase, total: u64) {
+        self.update(phase, |state| {
+            *state = PhaseState { done: 0, total };
+        });
+    }
+
+    /// Record `rows` more rows written in `phase`
+    pub fn advance(&self, phase: SeedPhase, rows: u64) {
+        self.update(phase, |state| {
+            state.done = (state.done + rows).min(state.total);
+        });
+    }
+
+    fn update(&self, phase: SeedPhase, apply: impl FnOnce(&mut PhaseState)) {
+        let mut phases = self.phases.lock().unwrap();
+        let state = &mut phases[phase.index()];
+        apply(state);
+
+        if let Some(tx) = &self.tx {
+            // Nobody listening is fine; progress is informational
+            let _ = tx.send(ProgressEvent {
+                phase,
+                done: state.done,
+                total: state.total,
+            });
+        }
+    }
+}
+
+/// Write `items` in chunks of `batch_size`, reporting each chunk to
+/// `tracker` once `insert` succeeds. Returns the concatenated results.
+pub async fn insert_in_batches<'a, T, R, F, Fut>(
+    items: &'a [T], batch_size: usize, phase: SeedPhase,
+    tracker: &ProgressTracker, mut insert: F,
+) -> anyhow::Result<Vec<R>>
+where
+    F: FnMut(&'a [T]) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<R>>>,
+{
+    tracker.start(phase, items.len() as u64);
+
+    let mut results = Vec::with_capacity(items.len());
+    for chunk in items.chunks(batch_size.max(1)) {
+        results.extend(insert(chunk).await?);
+        tracker.advance(phase, chunk.len() as u64);
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_reaches_full(events: &[ProgressEvent], phase: SeedPhase) {
+        let events: Vec<_> =
+            events.iter().filter(|e| e.phase == phase).collect();
+        assert!(!events.is_empty());
+        assert!(events.windows(2).all(|w| w[0].done <= w[1].done));
+        assert_eq!(events.first().unwrap().done, 0);
+        assert_eq!(events.last().unwrap().percent(), 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_batched_inserts_report_up_to_full() {
+        let (tracker, rx) = ProgressTracker::new();
+        let users: Vec<u32> = (0..25).collect();
+        let event_types: Vec<u32> = (0..7).collect();
+
+        let ids = insert_in_batches(
+            &users,
+            10,
+            SeedPhase::Users,
+            &tracker,
+            |chunk| async move { Ok(chunk.to_vec()) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids, users);
+
+        insert_in_batches(
+            &event_types,
+            3,
+            SeedPhase::EventTypes,
+            &tracker,
+            |chunk| async move { Ok(chunk.to_vec()) },
+        )
+        .await
+        .unwrap();
+
+        let events: Vec<_> = rx.drain().collect();
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.phase == SeedPhase::Users)
+                .map(|e| e.done)
+                .collect::<Vec<_>>(),
+            vec![0, 10, 20, 25]
+        );
+        assert_reaches_full(&events, SeedPhase::Users);
+        assert_reaches_full(&events, SeedPhase::EventTypes);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_workers_report_monotonic_progress() {
+        let (tracker, rx) = ProgressTracker::new();
+        tracker.start(SeedPhase::Events, 800);
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let tracker = tracker.clone();
+                tokio::spawn(async move {
+                    for _ in 0..10 {
+                        tracker.advance(SeedPhase::Events, 10);
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.await.unwrap();
+        }
+
+        let events: Vec<_> = rx.drain().collect();
+        assert_eq!(events.len(), 81);
+        assert_reaches_full(&events, SeedPhase::Events);
+    }
+}
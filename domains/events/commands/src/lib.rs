@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
+use common_errors::FieldError;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+/// Deepest nesting accepted in event metadata
+pub const MAX_METADATA_DEPTH: usize = 5;
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BulkDeleteEventsCommand {
     pub before: DateTime<Utc>,
@@ -20,6 +24,12 @@ pub struct DeleteEventCommand {
     pub event_id: i64,
 }
 
+/// Erase every event recorded for one user
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeleteUserEventsCommand {
+    pub user_id: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateEventCommand {
     #[serde(skip)]
@@ -28,3 +38,111 @@ pub struct UpdateEventCommand {
     pub timestamp: Option<DateTime<Utc>>,
     pub metadata: Option<serde_json::Value>,
 }
+
+impl UpdateEventCommand {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if self.event_type_id.is_some_and(|id| id <= 0) {
+            errors.push(FieldError::new(
+                "event_type_id",
+                "invalid",
+                "Event type id must be positive",
+            ));
+        }
+
+        if let Some(metadata) = &self.metadata {
+            if !metadata.is_object() {
+                errors.push(FieldError::new(
+                    "metadata",
+                    "not_an_object",
+                    "Metadata must be a JSON object",
+                ));
+            }
+            else if json_depth(metadata) > MAX_METADATA_DEPTH {
+                errors.push(FieldError::new(
+                    "metadata",
+                    "too_deep",
+                    &format!(
+                        "Metadata must be nested at most \
+                         {MAX_METADATA_DEPTH} levels"
+                    ),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+fn json_depth(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Object(map) => {
+            1 + map.values().map(json_depth).max().unwrap_or(0)
+        }
+        serde_json::Value::Array(items) => {
+            1 + items.iter().map(json_depth).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn update_with_metadata(
+        metadata: serde_json::Value,
+    ) -> UpdateEventCommand {
+        UpdateEventCommand {
+            event_id: 1,
+            event_type_id: None,
+            timestamp: None,
+            metadata: Some(metadata),
+        }
+    }
+
+    #[test]
+    fn test_update_event_command_accepts_shallow_metadata() {
+        let command = update_with_metadata(json!({"page": "/home"}));
+        assert!(command.validate().is_ok());
+    }
+
+    #[test]
+    fn test_update_event_command_rejects_deep_metadata() {
+        let command = update_with_metadata(
+            json!({"a": {"b": {"c": {"d": {"e": {"f": 1}}}}}}),
+        );
+        let errors = command.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "metadata");
+        assert_eq!(errors[0].code, "too_deep");
+    }
+
+    #[test]
+    fn test_update_event_command_collects_all_field_errors() {
+        let command = UpdateEventCommand {
+            event_id: 1,
+            event_type_id: Some(0),
+            timestamp: None,
+            metadata: Some(json!([1, 2, 3])),
+        };
+        let fields: Vec<String> = command
+            .validate()
+            .unwrap_err()
+            .into_iter()
+            .map(|e| format!("{}: {}", e.field, e.code))
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["event_type_id: invalid", "metadata: not_an_object"]
+        );
+    }
+}
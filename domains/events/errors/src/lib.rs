@@ -1,4 +1,4 @@
-use common_errors::AppError;
+use common_errors::{AppError, FieldError, impl_app_error};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,6 +15,8 @@ pub enum EventError {
     Redis(#[from] redis_connection::RedisError),
     #[error("Redis pool error: {0}")]
     Pool(#[from] redis_connection::PoolError),
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<FieldError>),
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -33,6 +35,25 @@ pub enum EventTypeError {
     InternalError(String),
 }
 
+impl_app_error! {
+    EventTypeError => {
+        NotFound => not_found("EVENT_TYPE_NOT_FOUND", "Event type not found"),
+        AlreadyExists => unprocessable_entity(
+            "EVENT_TYPE_EXISTS",
+            "An event type with this name already exists"
+        ),
+        Database(db_err) => {
+            sql_connection::pg_error_to_app_error(&db_err)
+        },
+        Connection(conn_err) => {
+            sql_connection::pool_error_to_app_error(&conn_err)
+        },
+        InternalError(msg) => internal_server_error(
+            &format!("Internal error: {msg}")
+        ),
+    }
+}
+
 impl From<EventError> for AppError {
     fn from(err: EventError) -> Self {
         match err {
@@ -42,46 +63,13 @@ impl From<EventError> for AppError {
                     &format!("Event with ID {event_id} not found"),
                 )
             }
-            EventError::EventType(event_type_err) => {
-                match event_type_err {
-                    EventTypeError::NotFound => {
-                        AppError::not_found(
-                            "EVENT_TYPE_NOT_FOUND",
-                            "Event type not found",
-                        )
-                    }
-                    EventTypeError::AlreadyExists => {
-                        AppError::unprocessable_entity(
-                            "EVENT_TYPE_EXISTS",
-                            "An event type with this name already exists",
-                        )
-                    }
-                    EventTypeError::Database(db_err) => {
-                        AppError::internal_server_error(&format!(
-                            "Database error: {db_err}"
-                        ))
-                    }
-                    EventTypeError::Connection(conn_err) => {
-                        AppError::internal_server_error(&format!(
-                            "Database connection error: {conn_err}"
-                        ))
-                    }
-                    EventTypeError::InternalError(msg) => {
-                        AppError::internal_server_error(&format!(
-                            "Internal error: {msg}"
-                        ))
-                    }
-                }
-            }
+            EventError::EventType(event_type_err) => event_type_err.into(),
+            EventError::Validation(fields) => AppError::validation(fields),
             EventError::Database(db_err) => {
-                AppError::internal_server_error(&format!(
-                    "Database error: {db_err}"
-                ))
+                sql_connection::pg_error_to_app_error(&db_err)
             }
             EventError::Connection(conn_err) => {
-                AppError::internal_server_error(&format!(
-                    "Database connection error: {conn_err}"
-                ))
+                sql_connection::pool_error_to_app_error(&conn_err)
             }
             EventError::Redis(redis_err) => {
                 AppError::internal_server_error(&format!(
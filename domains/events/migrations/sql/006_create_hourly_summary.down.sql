@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS analytics_refresh_state;
+
+DROP TABLE IF EXISTS event_hourly_summary;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS event_hourly_summary
+(
+    event_type   TEXT        NOT NULL,
+    hour_bucket  TIMESTAMPTZ NOT NULL,
+    total_count  BIGINT      NOT NULL,
+    unique_users BIGINT      NOT NULL,
+    PRIMARY KEY (hour_bucket, event_type)
+);
+
+CREATE TABLE IF NOT EXISTS analytics_refresh_state
+(
+    view_name     TEXT PRIMARY KEY,
+    last_refresh  TIMESTAMPTZ NOT NULL,
+    last_event_id BIGINT      NOT NULL
+);
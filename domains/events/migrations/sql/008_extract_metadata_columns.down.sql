@@ -0,0 +1,10 @@
+DROP INDEX IF EXISTS idx_events_page;
+DROP INDEX IF EXISTS idx_events_referrer;
+DROP INDEX IF EXISTS idx_events_session_id;
+DROP INDEX IF EXISTS idx_events_product_id;
+
+ALTER TABLE events
+    DROP COLUMN IF EXISTS page,
+    DROP COLUMN IF EXISTS referrer,
+    DROP COLUMN IF EXISTS session_id,
+    DROP COLUMN IF EXISTS product_id;
\ No newline at end of file
@@ -0,0 +1,26 @@
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS page TEXT GENERATED ALWAYS AS (metadata->>'page') STORED;
+
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS referrer TEXT GENERATED ALWAYS AS (metadata->>'referrer') STORED;
+
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS session_id TEXT GENERATED ALWAYS AS (metadata->>'session_id') STORED;
+
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS product_id INTEGER GENERATED ALWAYS AS (
+        CASE
+            WHEN metadata->>'product_id' ~ '^-?[0-9]{1,9}$'
+                THEN (metadata->>'product_id')::integer
+        END
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_events_page ON events (page) WHERE page IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_events_referrer ON events (referrer) WHERE referrer IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_events_session_id ON events (session_id) WHERE session_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_events_product_id ON events (product_id) WHERE product_id IS NOT NULL;
+
+ANALYZE events;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE analytics_refresh_state
+    DROP COLUMN IF EXISTS previous_event_id;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE analytics_refresh_state
+    ADD COLUMN IF NOT EXISTS previous_event_id BIGINT;
\ No newline at end of file
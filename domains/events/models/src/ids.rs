@@ -0,0 +1,119 @@
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::{FromSql, IsNull, ToSql, Type};
+use utoipa::ToSchema;
+
+/// Wraps a bare integer key so ids of different tables can't be swapped
+/// for one another. Serializes, binds and reads back exactly like the
+/// integer it wraps.
+macro_rules! id_newtype {
+    ($(#[$meta:meta])* $name:ident($inner:ty)) => {
+        $(#[$meta])*
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            Hash,
+            PartialOrd,
+            Ord,
+            Serialize,
+            Deserialize,
+            ToSchema,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub $inner);
+
+        impl $name {
+            pub fn get(self) -> $inner { self.0 }
+        }
+
+        impl From<$inner> for $name {
+            fn from(id: $inner) -> Self { Self(id) }
+        }
+
+        impl From<$name> for $inner {
+            fn from(id: $name) -> Self { id.0 }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map(Self)
+            }
+        }
+
+        impl ToSql for $name {
+            tokio_postgres::types::to_sql_checked!();
+
+            fn to_sql(
+                &self, ty: &Type, out: &mut BytesMut,
+            ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+                self.0.to_sql(ty, out)
+            }
+
+            fn accepts(ty: &Type) -> bool { <$inner as ToSql>::accepts(ty) }
+        }
+
+        impl<'a> FromSql<'a> for $name {
+            fn from_sql(
+                ty: &Type, raw: &'a [u8],
+            ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+                <$inner>::from_sql(ty, raw).map(Self)
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                <$inner as FromSql>::accepts(ty)
+            }
+        }
+    };
+}
+
+id_newtype!(
+    /// Primary key of `events`
+    EventId(i64)
+);
+
+id_newtype!(
+    /// Primary key of `event_types`
+    EventTypeId(i32)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_look_like_plain_integers() {
+        let id = EventTypeId(7);
+        assert_eq!(serde_json::to_string(&id).unwrap(), "7");
+        assert_eq!(serde_json::from_str::<EventTypeId>("7").unwrap(), id);
+        assert_eq!(id.to_string(), "7");
+        assert_eq!("7".parse(), Ok(id));
+        assert!("seven".parse::<EventId>().is_err());
+    }
+
+    #[test]
+    fn test_ids_bind_as_their_integer_type() {
+        assert!(<EventId as ToSql>::accepts(&Type::INT8));
+        assert!(!<EventId as ToSql>::accepts(&Type::INT4));
+        assert!(<EventTypeId as FromSql>::accepts(&Type::INT4));
+
+        let mut out = BytesMut::new();
+        EventId(42).to_sql(&Type::INT8, &mut out).unwrap();
+        assert_eq!(
+            EventId::from_sql(&Type::INT8, &out).unwrap(),
+            EventId(42)
+        );
+    }
+}
@@ -1,5 +1,6 @@
 pub mod event_types;
 pub mod events;
+pub mod ids;
 pub mod metadata;
 
 pub use event_types::{
@@ -7,4 +8,5 @@ pub use event_types::{
     UpdateEventTypeRequest,
 };
 pub use events::Event;
+pub use ids::{EventId, EventTypeId};
 pub use metadata::{Metadata, MetadataValidationError};
@@ -1,10 +1,22 @@
 use bytes::BytesMut;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio_postgres::types::{FromSql, IsNull, ToSql, Type};
+use typed_builder::TypedBuilder;
 use utoipa::ToSchema;
 #[derive(
-    Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq, Default,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    TypedBuilder,
 )]
+#[builder(field_defaults(default, setter(strip_option, into)))]
 pub struct Metadata {
     /// The page or URL where the event occurred (used by page_analytics)
     pub page: Option<String>,
@@ -34,9 +46,8 @@ impl<'a> FromSql<'a> for Metadata {
     fn from_sql(
         _ty: &Type, raw: &'a [u8],
     ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
-        let json = serde_json::Value::from_sql(&Type::JSONB, raw)?;
-        let metadata = serde_json::from_value(json)?;
-        Ok(metadata)
+        let json = Value::from_sql(&Type::JSONB, raw)?;
+        Ok(Self::from_json(&json))
     }
 
     fn accepts(ty: &Type) -> bool { ty == &Type::JSONB || ty == &Type::JSON }
@@ -55,6 +66,35 @@ impl Metadata {
     /// Create metadata for analytics events
     pub fn analytics() -> Self { Self::default() }
 
+    /// Read the well-known keys out of arbitrary event metadata, the way
+    /// the generated columns of `008_extract_metadata_columns` do: scalars
+    /// are read as text, and a `product_id` that is not an `i32` (or a
+    /// string holding one) is dropped. Other keys are ignored.
+    pub fn from_json(json: &Value) -> Self {
+        Self {
+            page: text_field(json, "page"),
+            referrer: text_field(json, "referrer"),
+            session_id: text_field(json, "session_id"),
+            product_id: json.get("product_id").and_then(|v| {
+                match v {
+                    Value::Number(n) => {
+                        n.as_i64().and_then(|n| i32::try_from(n).ok())
+                    }
+                    Value::String(s) => s.parse().ok(),
+                    _ => None,
+                }
+            }),
+        }
+    }
+
+    pub fn page(&self) -> Option<&str> { self.page.as_deref() }
+
+    pub fn referrer(&self) -> Option<&str> { self.referrer.as_deref() }
+
+    pub fn session_id(&self) -> Option<&str> { self.session_id.as_deref() }
+
+    pub fn product_id(&self) -> Option<i32> { self.product_id }
+
     /// Validate the metadata structure
     pub fn validate(&self) -> Result<(), MetadataValidationError> {
         if let Some(ref referrer) = self.referrer {
@@ -77,6 +117,15 @@ impl Metadata {
     }
 }
 
+fn text_field(json: &Value, key: &str) -> Option<String> {
+    match json.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum MetadataValidationError {
     InvalidUrl(String),
@@ -88,8 +137,72 @@ pub enum MetadataValidationError {
 
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
+
     use super::*;
 
+    #[test]
+    fn test_accessors_read_present_fields() {
+        let metadata = Metadata::from_json(&json!({
+            "page": "/product/view",
+            "referrer": "https://google.com",
+            "session_id": "abc123",
+            "product_id": 42,
+            "campaign": "spring"
+        }));
+
+        assert_eq!(metadata.page(), Some("/product/view"));
+        assert_eq!(metadata.referrer(), Some("https://google.com"));
+        assert_eq!(metadata.session_id(), Some("abc123"));
+        assert_eq!(metadata.product_id(), Some(42));
+    }
+
+    #[test]
+    fn test_accessors_absent_fields_are_none() {
+        for json in [json!({}), json!(null), json!({"page": null})] {
+            assert_eq!(Metadata::from_json(&json), Metadata::default());
+        }
+    }
+
+    #[test]
+    fn test_accessors_wrong_types() {
+        let metadata = Metadata::from_json(&json!({
+            "page": ["/a", "/b"],
+            "referrer": {"url": "https://example.com"},
+            "session_id": 12345,
+            "product_id": "not a number"
+        }));
+
+        assert_eq!(metadata.page(), None);
+        assert_eq!(metadata.referrer(), None);
+        assert_eq!(metadata.session_id(), Some("12345"));
+        assert_eq!(metadata.product_id(), None);
+
+        for product_id in [json!(1.5), json!(3_000_000_000_i64), json!(true)]
+        {
+            let metadata =
+                Metadata::from_json(&json!({ "product_id": product_id }));
+            assert_eq!(metadata.product_id(), None);
+        }
+        let metadata = Metadata::from_json(&json!({"product_id": "7"}));
+        assert_eq!(metadata.product_id(), Some(7));
+    }
+
+    #[test]
+    fn test_builder() {
+        let metadata = Metadata::builder()
+            .page("/checkout")
+            .session_id("s-1")
+            .product_id(9)
+            .build();
+
+        assert_eq!(metadata.page(), Some("/checkout"));
+        assert_eq!(metadata.referrer(), None);
+        assert_eq!(metadata.session_id(), Some("s-1"));
+        assert_eq!(metadata.product_id(), Some(9));
+        assert_eq!(Metadata::builder().build(), Metadata::default());
+    }
+
     #[test]
     fn test_metadata_validation() {
         let metadata = Metadata {
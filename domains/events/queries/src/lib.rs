@@ -1,8 +1,10 @@
+use chrono::{DateTime, Utc};
+use events_models::{EventId, EventTypeId};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct GetEventQuery {
-    pub event_id: i64,
+    pub event_id: EventId,
 }
 
 #[derive(Debug, Deserialize)]
@@ -14,7 +16,32 @@ pub struct GetUserEventsQuery {
 #[derive(Debug, Deserialize)]
 pub struct ListEventsQuery {
     pub user_id: Option<i64>,
-    pub event_type_id: Option<i32>,
+    pub event_type_id: Option<EventTypeId>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct GetSessionEventsQuery {
+    pub session_id: String,
+    pub limit: Option<u64>,
+}
+
+/// Position after the last event of a keyset page. Events are listed by
+/// `(timestamp, id)` descending, so the id breaks timestamp ties and no
+/// event is skipped or repeated between pages. Clients see it encoded as
+/// an opaque `dao_utils` cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventCursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: i64,
+}
+
+/// One keyset page of the events listing, starting after `cursor`
+#[derive(Debug)]
+pub struct ListEventsPageQuery {
+    pub user_id: Option<i64>,
+    pub event_type_id: Option<EventTypeId>,
+    pub cursor: Option<EventCursor>,
+    pub limit: u64,
+}
@@ -0,0 +1,6 @@
+// Ids of different tables are distinct types, so swapping them is a
+// compile error rather than a query that silently matches nothing
+#[test]
+fn test_transposed_ids_do_not_compile() {
+    trybuild::TestCases::new().compile_fail("tests/compile_fail/*.rs");
+}
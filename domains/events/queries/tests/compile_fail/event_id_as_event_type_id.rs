@@ -0,0 +1,12 @@
+use events_models::EventId;
+use events_queries::ListEventsQuery;
+
+fn main() {
+    let event_id = EventId(42);
+    let _ = ListEventsQuery {
+        user_id: None,
+        event_type_id: Some(event_id),
+        limit: None,
+        offset: None,
+    };
+}
@@ -8,12 +8,31 @@ pub struct BulkDeleteEventsResponse {
     pub deleted_before: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeleteUserEventsResponse {
+    pub user_id: i64,
+    pub deleted_count: u64,
+}
+
+/// One row of the `stats_summary` materialized view. Event type rows carry
+/// `total_count` and `unique_users`, page rows carry `page_count`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatsSummary {
+    pub stat_type: String,
+    pub key_name: String,
+    pub hour_bucket: DateTime<Utc>,
+    pub total_count: Option<i64>,
+    pub unique_users: Option<i64>,
+    pub page_count: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EventResponse {
     pub id: i64,
-    #[serde(rename = "userId")]
+    // Aliases keep entries cached before the switch to snake_case readable
+    #[serde(alias = "userId")]
     pub user_id: i64,
-    #[serde(rename = "eventType")]
+    #[serde(alias = "eventType")]
     pub event_type: String,
     pub event_type_id: i32,
     pub timestamp: DateTime<Utc>,
@@ -32,3 +51,126 @@ impl From<events_models::Event> for EventResponse {
         }
     }
 }
+
+/// Distinct users with at least one event in the trailing day, week and
+/// 30 days before `as_of`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ActiveUserCounts {
+    pub as_of: DateTime<Utc>,
+    pub dau: i64,
+    pub wau: i64,
+    pub mau: i64,
+    /// `dau / mau`, or 0.0 without monthly active users
+    pub stickiness: f64,
+}
+
+impl ActiveUserCounts {
+    pub fn new(as_of: DateTime<Utc>, dau: i64, wau: i64, mau: i64) -> Self {
+        let stickiness = if mau > 0 {
+            dau as f64 / mau as f64
+        }
+        else {
+            0.0
+        };
+
+        Self {
+            as_of,
+            dau,
+            wau,
+            mau,
+            stickiness,
+        }
+    }
+}
+
+/// Sessions that arrived from a referrer and how many of them went on to a
+/// conversion event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReferrerConversion {
+    pub referrer: String,
+    pub sessions: i64,
+    pub conversions: i64,
+    /// `conversions / sessions`
+    pub conversion_rate: f64,
+}
+
+/// Events counted in the bucket starting at `bucket`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TimeSeriesPoint {
+    pub bucket: DateTime<Utc>,
+    pub count: i64,
+}
+
+/// Events counted in one hour of the week, in UTC. `dow` runs from 0
+/// (Sunday) to 6 and `hour` from 0 to 23.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct HeatmapCell {
+    pub dow: u8,
+    pub hour: u8,
+    pub count: i64,
+}
+
+/// A keyset page of events; `next_cursor` is absent on the last page
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventPage {
+    pub events: Vec<EventResponse>,
+    pub next_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stickiness_is_dau_over_mau() {
+        let counts = ActiveUserCounts::new(Utc::now(), 30, 80, 120);
+        assert_eq!(counts.stickiness, 0.25);
+
+        let empty = ActiveUserCounts::new(Utc::now(), 0, 0, 0);
+        assert_eq!(empty.stickiness, 0.0);
+    }
+
+    #[test]
+    fn test_event_response_serializes_snake_case() {
+        let response = EventResponse {
+            id: 1,
+            user_id: 2,
+            event_type: "page_view".to_string(),
+            event_type_id: 3,
+            timestamp: Utc::now(),
+            metadata: None,
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        let mut fields: Vec<_> =
+            json.as_object().unwrap().keys().cloned().collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            [
+                "event_type",
+                "event_type_id",
+                "id",
+                "metadata",
+                "timestamp",
+                "user_id"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_response_reads_legacy_camel_case() {
+        let json = serde_json::json!({
+            "id": 1,
+            "userId": 2,
+            "eventType": "page_view",
+            "event_type_id": 3,
+            "timestamp": "2024-01-15T10:00:00Z",
+            "metadata": null
+        });
+
+        let response: EventResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(response.user_id, 2);
+        assert_eq!(response.event_type, "page_view");
+    }
+}
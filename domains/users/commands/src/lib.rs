@@ -1,19 +1,415 @@
-use serde::{Deserialize, Serialize};
+use common_errors::FieldError;
+use serde::{Deserialize, Deserializer, Serialize, de};
 use utoipa::ToSchema;
 
+/// Matches the `VARCHAR(100)` of `users.name`
+pub const MAX_USER_NAME_LEN: usize = 100;
+
+/// Partial update: an omitted `name` leaves it unchanged, while a present
+/// one must be a valid name. `null` is rejected rather than read as
+/// "unchanged", since users cannot be nameless.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateUserCommand {
     #[serde(skip)]
     pub user_id: i64,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(nullable = false)]
+    pub name: Option<String>,
+}
+
+impl UpdateUserCommand {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if let Some(name) = &self.name {
+            validate_name(name, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+/// Fields of a user to overwrite; `None` leaves a field as it is
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserChangeSet {
     pub name: Option<String>,
 }
 
+impl UserChangeSet {
+    pub fn is_empty(&self) -> bool { self.name.is_none() }
+}
+
+impl From<UpdateUserCommand> for UserChangeSet {
+    fn from(command: UpdateUserCommand) -> Self {
+        Self { name: command.name }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateUserCommand {
     pub name: String,
 }
 
+impl CreateUserCommand {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        validate_name(&self.name, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+/// Look a user up by name, creating it when absent
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnsureUserCommand {
+    pub name: String,
+}
+
+impl EnsureUserCommand {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        validate_name(&self.name, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeleteUserCommand {
     pub user_id: i64,
 }
+
+/// Most data rows accepted by one CSV import
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// Longest line, in bytes, accepted by a CSV import
+pub const MAX_IMPORT_LINE_LEN: usize = 64 * 1024;
+
+/// A row of an import that passed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportUserRow {
+    /// 1-based line in the uploaded file
+    pub line: u64,
+    pub name: String,
+}
+
+/// Users to create from an uploaded CSV with a `name` column. Other
+/// columns are ignored, blank lines are skipped and names are trimmed.
+#[derive(Debug, Default)]
+pub struct ImportUsersCommand {
+    pub rows: Vec<ImportUserRow>,
+    /// Rows that failed parsing or validation, as `(line, reason)`
+    pub rejected: Vec<(u64, String)>,
+}
+
+impl ImportUsersCommand {
+    /// Parse a whole file at once; see [`ImportUsersParser`]
+    pub fn from_csv(csv: &str) -> Result<Self, Vec<FieldError>> {
+        let mut parser = ImportUsersParser::default();
+        for line in csv.lines() {
+            parser.push_line(line)?;
+        }
+        parser.finish()
+    }
+}
+
+/// Builds an [`ImportUsersCommand`] a line at a time, so an upload can be
+/// parsed while it streams in. Only problems with the file as a whole (no
+/// header, no `name` column, too many rows, an overlong line) are errors;
+/// a leading UTF-8 byte order mark is ignored.
+#[derive(Debug, Default)]
+pub struct ImportUsersParser {
+    /// `None` until the header has been read
+    name_column: Option<usize>,
+    line: u64,
+    command: ImportUsersCommand,
+}
+
+impl ImportUsersParser {
+    /// Take the next line of the file, without its line terminator
+    pub fn push_line(&mut self, text: &str) -> Result<(), Vec<FieldError>> {
+        self.line += 1;
+        let text = match self.line {
+            1 => text.strip_prefix('\u{feff}').unwrap_or(text),
+            _ => text,
+        };
+        if text.len() > MAX_IMPORT_LINE_LEN {
+            return Err(csv_error(
+                "line_too_long",
+                &format!(
+                    "Line {} is longer than {MAX_IMPORT_LINE_LEN} bytes",
+                    self.line
+                ),
+            ));
+        }
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+
+        let Some(name_column) = self.name_column
+        else {
+            self.name_column = Some(header_name_column(text)?);
+            return Ok(());
+        };
+
+        let command = &mut self.command;
+        if command.rows.len() + command.rejected.len() == MAX_IMPORT_ROWS {
+            return Err(csv_error(
+                "too_many_rows",
+                &format!(
+                    "At most {MAX_IMPORT_ROWS} rows can be imported at once"
+                ),
+            ));
+        }
+
+        let line = self.line;
+        let name = match split_csv_line(text) {
+            Ok(fields) => fields.into_iter().nth(name_column),
+            Err(reason) => {
+                command.rejected.push((line, reason.to_string()));
+                return Ok(());
+            }
+        };
+        let Some(name) = name
+        else {
+            command.rejected.push((line, "Missing name".to_string()));
+            return Ok(());
+        };
+
+        let name = name.trim().to_string();
+        let mut errors = Vec::new();
+        validate_name(&name, &mut errors);
+        match errors.into_iter().next() {
+            Some(error) => command.rejected.push((line, error.message)),
+            None => command.rows.push(ImportUserRow { line, name }),
+        }
+        Ok(())
+    }
+
+    /// The command, once every line has been pushed
+    pub fn finish(self) -> Result<ImportUsersCommand, Vec<FieldError>> {
+        match self.name_column {
+            Some(_) => Ok(self.command),
+            None => Err(csv_error("empty", "CSV body is empty")),
+        }
+    }
+}
+
+fn header_name_column(header: &str) -> Result<usize, Vec<FieldError>> {
+    split_csv_line(header)
+        .ok()
+        .and_then(|columns| {
+            columns
+                .iter()
+                .position(|c| c.trim().eq_ignore_ascii_case("name"))
+        })
+        .ok_or_else(|| {
+            csv_error(
+                "missing_name_column",
+                "CSV header must have a `name` column",
+            )
+        })
+}
+
+fn csv_error(code: &str, message: &str) -> Vec<FieldError> {
+    vec![FieldError::new("csv", code, message)]
+}
+
+/// Fields of one CSV line; `"` quotes a field and `""` is a literal quote.
+/// Quoted fields cannot span lines.
+fn split_csv_line(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut field_start = true;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                }
+                else {
+                    quoted = false;
+                }
+            }
+            '"' if field_start => quoted = true,
+            ',' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                field_start = true;
+                continue;
+            }
+            c => field.push(c),
+        }
+        field_start = false;
+    }
+
+    if quoted {
+        return Err("Unterminated quoted field");
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn validate_name(name: &str, errors: &mut Vec<FieldError>) {
+    if name.trim().is_empty() {
+        errors.push(FieldError::new(
+            "name",
+            "required",
+            "Name must not be empty",
+        ));
+    }
+    else if name.chars().count() > MAX_USER_NAME_LEN {
+        errors.push(FieldError::new(
+            "name",
+            "too_long",
+            &format!("Name must be at most {MAX_USER_NAME_LEN} characters"),
+        ));
+    }
+}
+
+/// Used with `#[serde(default)]`, so only a field that is present reaches
+/// it; an explicit `null` is an error instead of an absent value
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer)?
+        .map(Some)
+        .ok_or_else(|| de::Error::custom("field may not be null"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_user_command_validation() {
+        let valid = CreateUserCommand {
+            name: "alice".to_string(),
+        };
+        assert!(valid.validate().is_ok());
+
+        let empty = CreateUserCommand {
+            name: "   ".to_string(),
+        };
+        let errors = empty.validate().unwrap_err();
+        assert_eq!(errors[0].field, "name");
+        assert_eq!(errors[0].code, "required");
+
+        let too_long = CreateUserCommand {
+            name: "a".repeat(MAX_USER_NAME_LEN + 1),
+        };
+        let errors = too_long.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "too_long");
+    }
+
+    #[test]
+    fn test_import_users_from_csv() {
+        let csv = "id,name\r\n1,alice\n\n2,\"Smith, Bob\"\n3,   \
+                   \n4\n5,\"open\n6, carol \n";
+
+        let command = ImportUsersCommand::from_csv(csv).unwrap();
+
+        let rows: Vec<_> = command
+            .rows
+            .iter()
+            .map(|row| (row.line, row.name.as_str()))
+            .collect();
+        assert_eq!(rows, [(2, "alice"), (4, "Smith, Bob"), (8, "carol")]);
+        let rejected: Vec<_> =
+            command.rejected.iter().map(|(line, _)| *line).collect();
+        assert_eq!(rejected, [5, 6, 7]);
+        assert_eq!(command.rejected[0].1, "Name must not be empty");
+    }
+
+    #[test]
+    fn test_import_users_rejects_bad_files() {
+        let code = |csv: &str| {
+            ImportUsersCommand::from_csv(csv).unwrap_err()[0]
+                .code
+                .clone()
+        };
+
+        assert_eq!(code(""), "empty");
+        assert_eq!(code("id,email\n1,a@b.c\n"), "missing_name_column");
+
+        let too_many = format!("name\n{}", "x\n".repeat(MAX_IMPORT_ROWS + 1));
+        assert_eq!(code(&too_many), "too_many_rows");
+        let at_limit = format!("name\n{}", "x\n".repeat(MAX_IMPORT_ROWS));
+        assert!(ImportUsersCommand::from_csv(&at_limit).is_ok());
+
+        let long_line =
+            format!("name\n{}\n", "x".repeat(MAX_IMPORT_LINE_LEN + 1));
+        assert_eq!(code(&long_line), "line_too_long");
+    }
+
+    #[test]
+    fn test_import_users_ignores_byte_order_mark() {
+        let command =
+            ImportUsersCommand::from_csv("\u{feff}name,id\nalice,1\n")
+                .unwrap();
+
+        assert_eq!(
+            command.rows,
+            [ImportUserRow {
+                line: 2,
+                name: "alice".to_string()
+            }]
+        );
+        assert!(command.rejected.is_empty());
+    }
+
+    fn parse_update(json: &str) -> Result<UpdateUserCommand, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_update_user_command_omitted_name_is_no_op() {
+        let command = parse_update("{}").unwrap();
+        assert_eq!(command.name, None);
+        assert!(command.validate().is_ok());
+    }
+
+    #[test]
+    fn test_update_user_command_valid_name() {
+        let command = parse_update(r#"{"name": "bob"}"#).unwrap();
+        assert_eq!(command.name.as_deref(), Some("bob"));
+        assert!(command.validate().is_ok());
+    }
+
+    #[test]
+    fn test_update_user_command_rejects_empty_and_null_name() {
+        for name in ["", "   "] {
+            let command =
+                parse_update(&format!(r#"{{"name": "{name}"}}"#)).unwrap();
+            let errors = command.validate().unwrap_err();
+            assert_eq!(errors[0].field, "name");
+            assert_eq!(errors[0].code, "required");
+        }
+
+        let err = parse_update(r#"{"name": null}"#).unwrap_err();
+        assert!(err.contains("may not be null"));
+    }
+}
@@ -1,6 +1,9 @@
-use common_errors::AppError;
+use common_errors::{AppError, FieldError};
 use redis_connection::{PoolError, RedisError};
-use sql_connection::{PgError, PoolError as DbPoolError};
+use sql_connection::{
+    PgError, PoolError as DbPoolError, pg_error_to_app_error,
+    pool_error_to_app_error,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,6 +22,8 @@ pub enum UserError {
     RedisPool(#[from] PoolError),
     #[error("Name already exists")]
     NameExists,
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<FieldError>),
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -44,15 +49,10 @@ impl From<UserError> for AppError {
                     "A user with this name already exists",
                 )
             }
-            UserError::Database(db_err) => {
-                AppError::internal_server_error(&format!(
-                    "Database error: {db_err}"
-                ))
-            }
+            UserError::Validation(fields) => AppError::validation(fields),
+            UserError::Database(db_err) => pg_error_to_app_error(&db_err),
             UserError::DatabasePool(pool_err) => {
-                AppError::internal_server_error(&format!(
-                    "Database connection error: {pool_err}"
-                ))
+                pool_error_to_app_error(&pool_err)
             }
             UserError::Redis(redis_err) => {
                 AppError::internal_server_error(&format!(
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_users_name;
+
+CREATE INDEX IF NOT EXISTS idx_users_name ON users (name);
\ No newline at end of file
@@ -0,0 +1,14 @@
+UPDATE users u
+-- Names already taken more than once would fail the unique index. The
+-- oldest user keeps the name and later ones get their id appended, e.g.
+-- `alice#42`, cut short to still fit the column.
+SET name = left(u.name, 100 - length('#' || u.id)) || '#' || u.id
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY name ORDER BY id) AS rank
+    FROM users
+) taken
+WHERE taken.id = u.id AND taken.rank > 1;
+
+DROP INDEX IF EXISTS idx_users_name;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_name ON users (name);
\ No newline at end of file
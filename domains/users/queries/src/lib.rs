@@ -1,10 +1,57 @@
+use chrono::{DateTime, Utc};
+use common_query::{OrderBy, SortColumns, SortDirection};
 use serde::Deserialize;
+use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct ListUsersQuery {
     pub limit: Option<u64>,
     pub offset: Option<u64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub sort: Option<UserSort>,
 }
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSort {
+    #[default]
+    NameAsc,
+    NameDesc,
+    CreatedAtAsc,
+    CreatedAtDesc,
+}
+
+/// Columns users may be listed by
+pub const USER_SORT_COLUMNS: SortColumns =
+    SortColumns::new(&["name", "created_at"]).with_tie_breaker("id");
+
+impl UserSort {
+    pub fn column(&self) -> &'static str {
+        match self {
+            UserSort::NameAsc | UserSort::NameDesc => "name",
+            UserSort::CreatedAtAsc | UserSort::CreatedAtDesc => "created_at",
+        }
+    }
+
+    pub fn direction(&self) -> SortDirection {
+        match self {
+            UserSort::NameAsc | UserSort::CreatedAtAsc => SortDirection::Asc,
+            UserSort::NameDesc | UserSort::CreatedAtDesc => {
+                SortDirection::Desc
+            }
+        }
+    }
+
+    pub fn order_by(&self) -> OrderBy {
+        USER_SORT_COLUMNS
+            .order_by(self.column(), self.direction())
+            .expect("every UserSort column is in USER_SORT_COLUMNS")
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetUserQuery {
     pub user_id: i64,
@@ -13,3 +60,20 @@ pub struct GetUserQuery {
 pub struct GetUserByNameQuery {
     pub name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_sort_renders_allowlisted_order() {
+        assert_eq!(
+            UserSort::NameAsc.order_by().to_sql(),
+            "ORDER BY name ASC, id ASC"
+        );
+        assert_eq!(
+            UserSort::CreatedAtDesc.order_by().to_sql(),
+            "ORDER BY created_at DESC, id DESC"
+        );
+    }
+}
@@ -6,6 +6,13 @@ pub struct UserResponse {
     pub id: i64,
     pub name: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Only present when requested with `include_event_count=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_count: Option<i64>,
+    /// Timestamp of the user's newest event; only present when requested
+    /// with `include_last_active=true` and the user has events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl From<user_models::User> for UserResponse {
@@ -14,6 +21,49 @@ impl From<user_models::User> for UserResponse {
             id: user.id,
             name: user.name,
             created_at: user.created_at,
+            event_count: None,
+            last_active: None,
         }
     }
 }
+
+/// Outcome of a CSV user import
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct UserImportReport {
+    pub created: u64,
+    /// Names that already existed, or repeated earlier in the file
+    pub skipped_duplicates: u64,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRowError {
+    /// 1-based line in the uploaded file
+    pub line: u64,
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_response_serializes_snake_case() {
+        let response = UserResponse {
+            id: 1,
+            name: "alice".to_string(),
+            created_at: chrono::Utc::now(),
+            event_count: Some(4),
+            last_active: Some(chrono::Utc::now()),
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        let mut fields: Vec<_> =
+            json.as_object().unwrap().keys().cloned().collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            ["created_at", "event_count", "id", "last_active", "name"]
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+};
+use common_errors::AppError;
+use job_scheduler::{BackgroundJobScheduler, JobResult, JobStatus};
+use redis_connection::cache_provider::CacheProvider;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+/// Header carrying the shared admin secret
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Key prefixes owned by the application. A purge without a pattern clears
+/// all of them, and custom patterns must stay inside one of them.
+pub const APP_KEY_PATTERNS: &[&str] = &[
+    "user:*",
+    "users:*",
+    "event:*",
+    "events:*",
+    "event_type:*",
+    "event_types:*",
+    "stats:*",
+];
+
+#[derive(Clone)]
+pub struct AdminServices {
+    token: Option<Arc<str>>,
+    jobs: Option<BackgroundJobScheduler>,
+}
+
+impl AdminServices {
+    /// Without a token every admin request is rejected
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.filter(|t| !t.is_empty()).map(Into::into),
+            jobs: None,
+        }
+    }
+
+    /// Expose `scheduler`'s jobs under `/admin/jobs`
+    pub fn with_jobs(mut self, scheduler: BackgroundJobScheduler) -> Self {
+        self.jobs = Some(scheduler);
+        self
+    }
+
+    /// Read the shared secret from `ADMIN_TOKEN`
+    pub fn from_env() -> Self { Self::new(std::env::var("ADMIN_TOKEN").ok()) }
+
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), AppError> {
+        let provided = headers
+            .get(ADMIN_TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok());
+
+        match (&self.token, provided) {
+            (Some(expected), Some(provided))
+                if constant_time_eq(
+                    expected.as_bytes(),
+                    provided.as_bytes(),
+                ) =>
+            {
+                Ok(())
+            }
+            _ => {
+                Err(AppError::unauthorized(
+                    "UNAUTHORIZED",
+                    "Missing or invalid admin token",
+                ))
+            }
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub struct AdminHandlers;
+
+impl AdminHandlers {
+    pub fn routes() -> Router<AdminServices> {
+        Router::new()
+            .route("/admin/cache/purge", post(purge_cache))
+            .route("/admin/jobs", get(list_jobs))
+            .route("/admin/jobs/{name}/run", post(run_job))
+    }
+}
+
+/// OpenAPI paths and schemas of the admin endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(purge_cache, list_jobs, run_job),
+    components(schemas(PurgeCacheResponse, JobStatus, JobResult))
+)]
+pub struct AdminApi;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PurgeCacheParams {
+    /// Redis glob pattern, e.g. `user:*`. Defaults to all app-owned keys.
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PurgeCacheResponse {
+    pub patterns: Vec<String>,
+    pub removed: u64,
+}
+
+fn resolve_patterns(
+    pattern: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let Some(pattern) = pattern
+    else {
+        return Ok(APP_KEY_PATTERNS.iter().map(|p| p.to_string()).collect());
+    };
+
+    let app_owned = APP_KEY_PATTERNS
+        .iter()
+        .any(|prefix| pattern.starts_with(prefix.trim_end_matches('*')));
+    if !app_owned {
+        return Err(AppError::bad_request_with_details(
+            "INVALID_PATTERN",
+            "Pattern must target an app-owned key prefix",
+            &APP_KEY_PATTERNS.join(", "),
+        ));
+    }
+
+    Ok(vec![pattern])
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/cache/purge",
+    params(
+        PurgeCacheParams,
+        ("x-admin-token" = String, Header, description = "Shared admin secret")
+    ),
+    responses(
+        (status = 200, description = "Matching cache keys removed", body = PurgeCacheResponse),
+        (status = 400, description = "Pattern outside app-owned keys", body = common_errors::ApiErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "admin"
+)]
+#[instrument(skip_all)]
+pub async fn purge_cache(
+    State(services): State<AdminServices>, headers: HeaderMap,
+    Query(params): Query<PurgeCacheParams>,
+) -> Result<Json<PurgeCacheResponse>, AppError> {
+    services.authorize(&headers)?;
+    let patterns = resolve_patterns(params.pattern)?;
+
+    let mut removed = 0;
+    for pattern in &patterns {
+        removed += CacheProvider::invalidate_pattern(pattern)
+            .await
+            .map_err(AppError::from_error)?;
+    }
+
+    info!(?patterns, removed, "Purged cache keys");
+    Ok(Json(PurgeCacheResponse { patterns, removed }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    params(
+        ("x-admin-token" = String, Header, description = "Shared admin secret")
+    ),
+    responses(
+        (status = 200, description = "Registered background jobs and their latest runs", body = Vec<JobStatus>),
+        (status = 401, description = "Missing or invalid admin token", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "admin"
+)]
+#[instrument(skip_all)]
+pub async fn list_jobs(
+    State(services): State<AdminServices>, headers: HeaderMap,
+) -> Result<Json<Vec<JobStatus>>, AppError> {
+    services.authorize(&headers)?;
+    let jobs = services
+        .jobs
+        .as_ref()
+        .map(BackgroundJobScheduler::status)
+        .unwrap_or_default();
+    Ok(Json(jobs))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/jobs/{name}/run",
+    params(
+        ("name" = String, Path, description = "Job name, as listed by GET /admin/jobs"),
+        ("x-admin-token" = String, Header, description = "Shared admin secret")
+    ),
+    responses(
+        (status = 200, description = "Job ran; its status tells whether it succeeded", body = JobStatus),
+        (status = 401, description = "Missing or invalid admin token", body = common_errors::ApiErrorResponse),
+        (status = 404, description = "No job with that name", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "admin"
+)]
+#[instrument(skip_all, fields(job = %name))]
+pub async fn run_job(
+    State(services): State<AdminServices>, headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<JobStatus>, AppError> {
+    services.authorize(&headers)?;
+    let not_found = || {
+        AppError::not_found(
+            "JOB_NOT_FOUND",
+            &format!("No background job named '{name}'"),
+        )
+    };
+
+    let scheduler = services.jobs.as_ref().ok_or_else(not_found)?;
+    let status = scheduler.trigger(&name).await.ok_or_else(not_found)?;
+    info!(job = %name, result = ?status.last_result, "Ran background job");
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+    };
+    use redis_connection::{cache_key, core::CacheTypeBind};
+    use test_utils::TestRedisContainer;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    cache_key!(SeedUserKey::<String> => "user:{}"[id: i64]);
+    cache_key!(SeedStatsKey::<String> => "stats:{}"[id: i64]);
+
+    fn purge_request(uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(Method::POST).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(ADMIN_TOKEN_HEADER, token);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_seeded_keys_and_reports_count() {
+        let redis_container = TestRedisContainer::new().await.unwrap();
+        redis_container.flush_db().await.unwrap();
+        CacheProvider::init_redis_static(redis_container.pool.clone());
+
+        let backend = CacheProvider::get_backend();
+        for id in 1..=3 {
+            SeedUserKey
+                .bind_with(backend.clone(), &id)
+                .set_with_expire::<()>(
+                    format!("user {id}"),
+                    Duration::from_secs(300),
+                )
+                .await
+                .unwrap();
+        }
+        SeedStatsKey
+            .bind_with(backend.clone(), &1)
+            .set_with_expire::<()>(
+                "stats".to_string(),
+                Duration::from_secs(300),
+            )
+            .await
+            .unwrap();
+
+        let app = AdminHandlers::routes()
+            .with_state(AdminServices::new(Some("secret".to_string())));
+
+        let response = app
+            .clone()
+            .oneshot(purge_request("/admin/cache/purge", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(purge_request("/admin/cache/purge", Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(purge_request(
+                "/admin/cache/purge?pattern=session:*",
+                Some("secret"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(purge_request(
+                "/admin/cache/purge?pattern=user:*",
+                Some("secret"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let purged: PurgeCacheResponse =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(purged.removed, 3);
+        assert!(
+            SeedUserKey
+                .bind_with(backend.clone(), &1)
+                .try_get()
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            SeedStatsKey
+                .bind_with(backend.clone(), &1)
+                .try_get()
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let response = app
+            .oneshot(purge_request("/admin/cache/purge", Some("secret")))
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let purged: PurgeCacheResponse =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(purged.removed, 1);
+        assert_eq!(purged.patterns.len(), APP_KEY_PATTERNS.len());
+        assert!(
+            SeedStatsKey
+                .bind_with(backend, &1)
+                .try_get()
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_triggered_job_reports_successful_run() {
+        let scheduler = BackgroundJobScheduler::new();
+        scheduler
+            .register("noop", Duration::from_secs(60), || async { Ok(()) })
+            .unwrap();
+
+        let app = AdminHandlers::routes().with_state(
+            AdminServices::new(Some("secret".to_string()))
+                .with_jobs(scheduler),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(purge_request("/admin/jobs/missing/run", Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(purge_request("/admin/jobs/noop/run", Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let ran: JobStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ran.last_result, Some(JobResult::Success));
+
+        let list = Request::builder()
+            .uri("/admin/jobs")
+            .header(ADMIN_TOKEN_HEADER, "secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(list).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let jobs: Vec<JobStatus> = serde_json::from_slice(&body).unwrap();
+        let noop = jobs.iter().find(|job| job.name == "noop").unwrap();
+        assert_eq!(noop, &ran);
+        assert!(noop.last_run.is_some() && noop.last_duration_ms.is_some());
+        assert_eq!(noop.every_secs, 60);
+    }
+}
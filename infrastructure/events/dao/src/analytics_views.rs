@@ -0,0 +1,658 @@
+use chrono::{DateTime, Utc};
+use events_errors::EventError;
+use events_responses::{
+    ActiveUserCounts, HeatmapCell, ReferrerConversion, TimeSeriesPoint,
+};
+use sql_connection::SqlConnect;
+use tracing::instrument;
+
+/// `analytics_refresh_state` row tracking `event_hourly_summary`
+const HOURLY_SUMMARY: &str = "event_hourly_summary";
+
+/// Aggregates events per event type and hour bucket, mirroring the
+/// `event_type` rows of `stats_summary`. `$1` restricts the buckets to
+/// recompute; `NULL` recomputes all of them.
+const UPSERT_HOURLY_SUMMARY: &str = "INSERT INTO event_hourly_summary \
+                                     (event_type, hour_bucket, total_count, \
+                                     unique_users)
+     SELECT et.name, DATE_TRUNC('hour', e.timestamp), COUNT(e.id),
+            COUNT(DISTINCT e.user_id)
+     FROM events e
+     INNER JOIN event_types et ON e.event_type_id = et.id
+     WHERE e.timestamp >= NOW() - INTERVAL '30 days'
+       AND ($1::timestamptz[] IS NULL
+            OR DATE_TRUNC('hour', e.timestamp) = ANY($1::timestamptz[]))
+     GROUP BY et.name, DATE_TRUNC('hour', e.timestamp)
+     ON CONFLICT (hour_bucket, event_type) DO UPDATE
+     SET total_count = EXCLUDED.total_count,
+         unique_users = EXCLUDED.unique_users";
+
+/// Event type that counts as a conversion unless the caller names another
+pub const DEFAULT_CONVERSION_EVENT: &str = "order.paid";
+
+/// Per session in `[$1, $2]`: the first referrer seen and whether a `$3`
+/// event happened at or after it. Sessions without a referrer are dropped.
+const REFERRER_CONVERSIONS: &str = "WITH sessions AS (
+         SELECT (ARRAY_AGG(e.referrer ORDER BY e.timestamp, e.id)
+                     FILTER (WHERE e.referrer IS NOT NULL))[1] AS referrer,
+                MIN(e.timestamp) FILTER (WHERE e.referrer IS NOT NULL)
+                    AS referred_at,
+                MAX(e.timestamp) FILTER (WHERE et.name = $3) AS converted_at
+         FROM events e
+         JOIN event_types et ON e.event_type_id = et.id
+         WHERE e.session_id IS NOT NULL
+           AND e.timestamp >= $1 AND e.timestamp <= $2
+         GROUP BY e.session_id
+     )
+     SELECT referrer, COUNT(*),
+            COUNT(*) FILTER (WHERE converted_at >= referred_at)
+     FROM sessions
+     WHERE referrer IS NOT NULL
+     GROUP BY referrer
+     ORDER BY COUNT(*) DESC, referrer
+     LIMIT $4";
+
+/// Events of type `$1` in `[$2, $3)` counted per `$4`-second bucket. The
+/// buckets are aligned to the epoch (so to UTC hours and days) and every
+/// one of them is returned, including the empty ones; the first and last
+/// only count the part inside the range.
+const EVENT_TYPE_TIMESERIES: &str = "WITH buckets AS (
+         SELECT generate_series(
+                    TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM $2::timestamptz)
+                                       / $4) * $4),
+                    $3::timestamptz - INTERVAL '1 microsecond',
+                    MAKE_INTERVAL(secs => $4)) AS bucket
+     )
+     SELECT b.bucket, COUNT(e.id)
+     FROM buckets b
+     LEFT JOIN events e
+       ON e.event_type_id = $1
+      AND e.timestamp >= GREATEST(b.bucket, $2)
+      AND e.timestamp < LEAST(b.bucket + MAKE_INTERVAL(secs => $4), $3)
+     GROUP BY b.bucket
+     ORDER BY b.bucket";
+
+/// Events in `[$1, $2)` counted per UTC day of week and hour of day, with
+/// all 168 cells returned in order and the empty ones as zero
+const ACTIVITY_HEATMAP: &str = "WITH counts AS (
+         SELECT EXTRACT(DOW FROM timestamp AT TIME ZONE 'UTC')::int AS dow,
+                EXTRACT(HOUR FROM timestamp AT TIME ZONE 'UTC')::int AS hour,
+                COUNT(*) AS count
+         FROM events
+         WHERE timestamp >= $1 AND timestamp < $2
+         GROUP BY 1, 2
+     )
+     SELECT d.dow, h.hour, COALESCE(c.count, 0)
+     FROM generate_series(0, 6) AS d(dow)
+     CROSS JOIN generate_series(0, 23) AS h(hour)
+     LEFT JOIN counts c ON c.dow = d.dow AND c.hour = h.hour
+     ORDER BY d.dow, h.hour";
+
+/// What a call to [`AnalyticsViewsDao::refresh_hourly_summary`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SummaryRefresh {
+    /// No usable refresh state, or a rebuild was asked for, so every
+    /// bucket was recomputed
+    Full,
+    /// Only the hour buckets that received events since the refresh before
+    /// last
+    Incremental { buckets: Vec<DateTime<Utc>> },
+}
+
+/// Maintains the regular tables that mirror the analytics materialized
+/// views so they can be refreshed incrementally.
+#[derive(Clone)]
+pub struct AnalyticsViewsDao {
+    db: SqlConnect,
+}
+
+impl AnalyticsViewsDao {
+    pub fn new(db: SqlConnect) -> Self { Self { db } }
+
+    /// Recompute the `event_hourly_summary` buckets touched by recently
+    /// inserted events.
+    ///
+    /// New events are detected by id, so an event inserted with an old
+    /// timestamp still updates its bucket. A transaction still open during
+    /// a refresh can commit ids below that refresh's high-water mark, so
+    /// each refresh re-scans from the mark of the refresh before it; a row
+    /// is only missed if its transaction stays open across two refreshes.
+    /// Deleted events are not tracked; schedule
+    /// [`rebuild_hourly_summary`] to catch up on those. Falls back to a
+    /// full refresh when there is no refresh state or event ids were
+    /// reset.
+    ///
+    /// [`rebuild_hourly_summary`]: Self::rebuild_hourly_summary
+    #[instrument(skip(self))]
+    pub async fn refresh_hourly_summary(
+        &self,
+    ) -> Result<SummaryRefresh, EventError> {
+        self.refresh_summary(false).await
+    }
+
+    /// Recompute every `event_hourly_summary` bucket, dropping the counts
+    /// of deleted events that incremental refreshes leave behind
+    #[instrument(skip(self))]
+    pub async fn rebuild_hourly_summary(
+        &self,
+    ) -> Result<SummaryRefresh, EventError> {
+        self.refresh_summary(true).await
+    }
+
+    async fn refresh_summary(
+        &self, rebuild: bool,
+    ) -> Result<SummaryRefresh, EventError> {
+        let mut client = self.db.get_client().await?;
+        let tx = client.transaction().await?;
+
+        let marks: Option<(i64, Option<i64>)> = tx
+            .query_opt(
+                "SELECT last_event_id, previous_event_id FROM \
+                 analytics_refresh_state WHERE view_name = $1 FOR UPDATE",
+                &[&HOURLY_SUMMARY],
+            )
+            .await?
+            .map(|row| (row.get(0), row.get(1)));
+        let max_event_id: i64 = tx
+            .query_one("SELECT COALESCE(MAX(id), 0) FROM events", &[])
+            .await?
+            .get(0);
+
+        let refresh = match marks {
+            Some((last, previous)) if !rebuild && last <= max_event_id => {
+                let since =
+                    previous.map_or(last, |previous| previous.min(last));
+                let buckets: Vec<DateTime<Utc>> = tx
+                    .query(
+                        "SELECT DISTINCT DATE_TRUNC('hour', timestamp) FROM \
+                         events WHERE id > $1 AND id <= $2 ORDER BY 1",
+                        &[&since, &max_event_id],
+                    )
+                    .await?
+                    .iter()
+                    .map(|row| row.get(0))
+                    .collect();
+
+                if !buckets.is_empty() {
+                    tx.execute(UPSERT_HOURLY_SUMMARY, &[&Some(&buckets)])
+                        .await?;
+                }
+                SummaryRefresh::Incremental { buckets }
+            }
+            _ => {
+                tx.execute("DELETE FROM event_hourly_summary", &[]).await?;
+                tx.execute(
+                    UPSERT_HOURLY_SUMMARY,
+                    &[&None::<Vec<DateTime<Utc>>>],
+                )
+                .await?;
+                SummaryRefresh::Full
+            }
+        };
+
+        tx.execute(
+            "DELETE FROM event_hourly_summary WHERE hour_bucket < \
+             DATE_TRUNC('hour', NOW() - INTERVAL '30 days')",
+            &[],
+        )
+        .await?;
+        let previous_event_id = marks.map(|(last, _)| last.min(max_event_id));
+        tx.execute(
+            "INSERT INTO analytics_refresh_state (view_name, last_refresh, \
+             last_event_id, previous_event_id) VALUES ($1, NOW(), $2, $3)
+             ON CONFLICT (view_name) DO UPDATE
+             SET last_refresh = EXCLUDED.last_refresh,
+                 last_event_id = EXCLUDED.last_event_id,
+                 previous_event_id = EXCLUDED.previous_event_id",
+            &[&HOURLY_SUMMARY, &max_event_id, &previous_event_id],
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(refresh)
+    }
+
+    /// Daily, weekly and monthly active users: distinct users with an event
+    /// in `(as_of - 1 day, as_of]`, `(as_of - 7 days, as_of]` and
+    /// `(as_of - 30 days, as_of]`, computed in a single scan
+    #[instrument(skip(self))]
+    pub async fn active_users(
+        &self, as_of: DateTime<Utc>,
+    ) -> Result<ActiveUserCounts, EventError> {
+        let client = self.db.get_analytics_client().await?;
+        let row = client
+            .query_one(
+                "SELECT
+                     COUNT(DISTINCT user_id) FILTER (
+                         WHERE timestamp > $1 - INTERVAL '1 day'),
+                     COUNT(DISTINCT user_id) FILTER (
+                         WHERE timestamp > $1 - INTERVAL '7 days'),
+                     COUNT(DISTINCT user_id)
+                 FROM events
+                 WHERE timestamp > $1 - INTERVAL '30 days'
+                   AND timestamp <= $1",
+                &[&as_of],
+            )
+            .await?;
+
+        Ok(ActiveUserCounts::new(
+            as_of,
+            row.get(0),
+            row.get(1),
+            row.get(2),
+        ))
+    }
+
+    /// Referrers ranked by the sessions they brought in `[from, to]`, with
+    /// how many of those sessions produced a `conversion_event` afterwards
+    #[instrument(skip(self))]
+    pub async fn referrer_conversions(
+        &self, from: DateTime<Utc>, to: DateTime<Utc>,
+        conversion_event: &str, limit: i64,
+    ) -> Result<Vec<ReferrerConversion>, EventError> {
+        let client = self.db.get_analytics_client().await?;
+        let rows = client
+            .query(
+                REFERRER_CONVERSIONS,
+                &[&from, &to, &conversion_event, &limit],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let sessions: i64 = row.get(1);
+                let conversions: i64 = row.get(2);
+                ReferrerConversion {
+                    referrer: row.get(0),
+                    sessions,
+                    conversions,
+                    conversion_rate: conversions as f64 / sessions as f64,
+                }
+            })
+            .collect())
+    }
+
+    /// Gap-filled counts of one event type in `[start, end)`, one point per
+    /// `step`
+    #[instrument(skip(self))]
+    pub async fn event_type_timeseries(
+        &self, event_type_id: i32, start: DateTime<Utc>, end: DateTime<Utc>,
+        step: chrono::Duration,
+    ) -> Result<Vec<TimeSeriesPoint>, EventError> {
+        let step_secs = step.num_seconds() as f64;
+        let client = self.db.get_analytics_client().await?;
+        let rows = client
+            .query(
+                EVENT_TYPE_TIMESERIES,
+                &[&event_type_id, &start, &end, &step_secs],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                TimeSeriesPoint {
+                    bucket: row.get(0),
+                    count: row.get(1),
+                }
+            })
+            .collect())
+    }
+
+    /// Day-of-week by hour-of-day event counts in `[start, end)`, one cell
+    /// per hour of the week starting with Sunday 00:00 UTC
+    #[instrument(skip(self))]
+    pub async fn activity_heatmap(
+        &self, start: DateTime<Utc>, end: DateTime<Utc>,
+    ) -> Result<Vec<HeatmapCell>, EventError> {
+        let client = self.db.get_analytics_client().await?;
+        let rows = client.query(ACTIVITY_HEATMAP, &[&start, &end]).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                HeatmapCell {
+                    dow: row.get::<_, i32>(0) as u8,
+                    hour: row.get::<_, i32>(1) as u8,
+                    count: row.get(2),
+                }
+            })
+            .collect())
+    }
+
+    /// Distinct `page` values of events in `[start, end)`, sorted, at most
+    /// `limit` of them. Events without a page are skipped.
+    #[instrument(skip(self))]
+    pub async fn distinct_pages(
+        &self, start: DateTime<Utc>, end: DateTime<Utc>, limit: i64,
+    ) -> Result<Vec<String>, EventError> {
+        let client = self.db.get_analytics_client().await?;
+        let rows = client
+            .query(
+                "SELECT DISTINCT page FROM events
+                 WHERE page IS NOT NULL
+                   AND timestamp >= $1 AND timestamp < $2
+                 ORDER BY page
+                 LIMIT $3",
+                &[&start, &end, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// When `event_hourly_summary` was last brought up to date
+    #[instrument(skip(self))]
+    pub async fn hourly_summary_last_refresh(
+        &self,
+    ) -> Result<Option<DateTime<Utc>>, EventError> {
+        let client = self.db.get_read_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT last_refresh FROM analytics_refresh_state WHERE \
+                 view_name = $1",
+                &[&HOURLY_SUMMARY],
+            )
+            .await?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::*;
+
+    use super::*;
+
+    async fn summary_count(
+        container: &TestPostgresContainer, hours_ago: i32,
+    ) -> i64 {
+        let client = container.pool.get().await.unwrap();
+        client
+            .query_one(
+                "SELECT total_count FROM event_hourly_summary WHERE \
+                 hour_bucket = DATE_TRUNC('hour', NOW() - \
+                 make_interval(hours => $1))",
+                &[&hours_ago],
+            )
+            .await
+            .unwrap()
+            .get(0)
+    }
+
+    async fn insert_event(
+        container: &TestPostgresContainer, user_id: i64, event_type_id: i32,
+        hours_ago: i32,
+    ) {
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id, timestamp) \
+                 VALUES ({user_id}, {event_type_id}, NOW() - INTERVAL \
+                 '{hours_ago} hours')"
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_event_only_updates_its_hour_bucket() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        insert_event(&container, user_id, event_type_id, 5).await;
+        insert_event(&container, user_id, event_type_id, 2).await;
+
+        assert_eq!(
+            dao.refresh_hourly_summary().await.unwrap(),
+            SummaryRefresh::Full
+        );
+        assert_eq!(summary_count(&container, 5).await, 1);
+        assert_eq!(summary_count(&container, 2).await, 1);
+        assert!(dao.hourly_summary_last_refresh().await.unwrap().is_some());
+
+        // Scribble over the untouched bucket; an incremental refresh must
+        // leave it alone
+        container
+            .execute_sql(
+                "UPDATE event_hourly_summary SET total_count = 999 WHERE \
+                 hour_bucket = DATE_TRUNC('hour', NOW() - INTERVAL '5 \
+                 hours')",
+            )
+            .await
+            .unwrap();
+
+        insert_event(&container, user_id, event_type_id, 2).await;
+
+        match dao.refresh_hourly_summary().await.unwrap() {
+            SummaryRefresh::Incremental { buckets } => {
+                assert_eq!(buckets.len(), 1)
+            }
+            other => panic!("Expected incremental refresh, got {other:?}"),
+        }
+        assert_eq!(summary_count(&container, 2).await, 2);
+        assert_eq!(summary_count(&container, 5).await, 999);
+    }
+
+    #[tokio::test]
+    async fn test_active_users_per_window() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // Hours since each user's events; 24 * 40 is outside every window
+        // and -1 is after `as_of`
+        let activity: [&[i32]; 5] =
+            [&[2, 24 * 20], &[24 * 3], &[24 * 20], &[24 * 40], &[-1]];
+        for (i, hours) in activity.iter().enumerate() {
+            let user_id =
+                create_test_user_with_name(&container, &format!("user_{i}"))
+                    .await
+                    .unwrap();
+            for hours_ago in hours.iter() {
+                insert_event(&container, user_id, event_type_id, *hours_ago)
+                    .await;
+            }
+        }
+
+        let as_of = Utc::now();
+        let counts = dao.active_users(as_of).await.unwrap();
+        assert_eq!(counts, ActiveUserCounts::new(as_of, 1, 2, 3));
+        assert_eq!(counts.stickiness, 1.0 / 3.0);
+
+        // Nobody was active a year ago
+        let empty = dao
+            .active_users(as_of - chrono::Duration::days(365))
+            .await
+            .unwrap();
+        assert_eq!(empty.mau, 0);
+        assert_eq!(empty.stickiness, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_referrer_conversions() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let user_id = create_test_user(&container).await.unwrap();
+        let page_view =
+            create_test_event_type_with_name(&container, "page_view")
+                .await
+                .unwrap();
+        let purchase = create_test_event_type_with_name(
+            &container,
+            DEFAULT_CONVERSION_EVENT,
+        )
+        .await
+        .unwrap();
+
+        // (session, event type, minutes ago, referrer)
+        let events = [
+            ("s1", page_view, 30, Some("https://search.example")),
+            ("s1", purchase, 10, None),
+            ("s2", page_view, 30, Some("https://search.example")),
+            ("s2", page_view, 20, None),
+            ("s3", page_view, 30, Some("https://social.example")),
+            // Bought before arriving through the referrer
+            ("s4", purchase, 40, None),
+            ("s4", page_view, 30, Some("https://social.example")),
+            // No referrer, so not attributed anywhere
+            ("s5", purchase, 10, None),
+        ];
+        for (session, event_type_id, minutes_ago, referrer) in events {
+            let referrer = referrer
+                .map(|r| format!(r#", "referrer": "{r}""#))
+                .unwrap_or_default();
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp, \
+                     metadata) VALUES ({user_id}, {event_type_id}, NOW() - \
+                     INTERVAL '{minutes_ago} minutes', '{{\"session_id\": \
+                     \"{session}\"{referrer}}}')"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let to = Utc::now();
+        let from = to - chrono::Duration::hours(1);
+        let conversions = dao
+            .referrer_conversions(from, to, DEFAULT_CONVERSION_EVENT, 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            conversions,
+            vec![
+                ReferrerConversion {
+                    referrer: "https://search.example".to_string(),
+                    sessions: 2,
+                    conversions: 1,
+                    conversion_rate: 0.5,
+                },
+                ReferrerConversion {
+                    referrer: "https://social.example".to_string(),
+                    sessions: 2,
+                    conversions: 0,
+                    conversion_rate: 0.0,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_distinct_pages() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // (page, hours ago); the last one is outside the range
+        let events = [
+            (Some("/home"), 1),
+            (Some("/pricing"), 2),
+            (Some("/home"), 3),
+            (None, 1),
+            (Some("/about"), 4),
+            (Some("/old"), 48),
+        ];
+        for (page, hours_ago) in events {
+            let metadata = page
+                .map(|p| format!(r#"'{{"page": "{p}"}}'"#))
+                .unwrap_or_else(|| "NULL".to_string());
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp, \
+                     metadata) VALUES ({user_id}, {event_type_id}, NOW() - \
+                     INTERVAL '{hours_ago} hours', {metadata})"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let end = Utc::now();
+        let start = end - chrono::Duration::days(1);
+        assert_eq!(
+            dao.distinct_pages(start, end, 10).await.unwrap(),
+            vec!["/about", "/home", "/pricing"]
+        );
+        assert_eq!(
+            dao.distinct_pages(start, end, 2).await.unwrap(),
+            vec!["/about", "/home"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_without_new_events_touches_nothing() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+
+        assert_eq!(
+            dao.refresh_hourly_summary().await.unwrap(),
+            SummaryRefresh::Full
+        );
+        assert_eq!(
+            dao.refresh_hourly_summary().await.unwrap(),
+            SummaryRefresh::Incremental {
+                buckets: Vec::new()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_late_commit_below_the_mark_is_picked_up() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        dao.refresh_hourly_summary().await.unwrap();
+
+        // Takes the lower id but commits after a refresh has already moved
+        // the mark past it
+        let mut slow = container.pool.get().await.unwrap();
+        let slow_tx = slow.transaction().await.unwrap();
+        slow_tx
+            .execute(
+                "INSERT INTO events (user_id, event_type_id, timestamp) \
+                 VALUES ($1, $2, NOW() - INTERVAL '7 hours')",
+                &[&user_id, &event_type_id],
+            )
+            .await
+            .unwrap();
+        insert_event(&container, user_id, event_type_id, 1).await;
+        dao.refresh_hourly_summary().await.unwrap();
+        slow_tx.commit().await.unwrap();
+
+        dao.refresh_hourly_summary().await.unwrap();
+        assert_eq!(summary_count(&container, 7).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_drops_deleted_events() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let dao = AnalyticsViewsDao::new(create_sql_connect(&container));
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        insert_event(&container, user_id, event_type_id, 3).await;
+        insert_event(&container, user_id, event_type_id, 3).await;
+        dao.refresh_hourly_summary().await.unwrap();
+
+        container
+            .execute_sql(&format!(
+                "DELETE FROM events WHERE id = (SELECT MIN(id) FROM events \
+                 WHERE user_id = {user_id})"
+            ))
+            .await
+            .unwrap();
+        dao.refresh_hourly_summary().await.unwrap();
+        assert_eq!(summary_count(&container, 3).await, 2);
+
+        assert_eq!(
+            dao.rebuild_hourly_summary().await.unwrap(),
+            SummaryRefresh::Full
+        );
+        assert_eq!(summary_count(&container, 3).await, 1);
+    }
+}
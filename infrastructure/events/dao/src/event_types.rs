@@ -21,7 +21,7 @@ impl EventTypeDao {
     ) -> Result<EventTypeResponse, EventTypeError> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare("SELECT id, name FROM event_types WHERE id = $1")
+            .prepare_cached("SELECT id, name FROM event_types WHERE id = $1")
             .await?;
         let rows = client.query(&stmt, &[&id]).await?;
 
@@ -44,7 +44,9 @@ impl EventTypeDao {
     ) -> Result<Vec<EventTypeResponse>, EventTypeError> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare("SELECT id, name FROM event_types ORDER BY name ASC")
+            .prepare_cached(
+                "SELECT id, name FROM event_types ORDER BY name ASC",
+            )
             .await?;
         let rows = client.query(&stmt, &[]).await?;
 
@@ -69,7 +71,7 @@ impl EventTypeDao {
 
         // Check if name already exists
         let check_stmt = client
-            .prepare("SELECT id FROM event_types WHERE name = $1")
+            .prepare_cached("SELECT id FROM event_types WHERE name = $1")
             .await?;
         let check_rows = client.query(&check_stmt, &[&req.name]).await?;
         if !check_rows.is_empty() {
@@ -77,7 +79,7 @@ impl EventTypeDao {
         }
 
         let stmt = client
-            .prepare(
+            .prepare_cached(
                 "INSERT INTO event_types (name) VALUES ($1) RETURNING id, \
                  name",
             )
@@ -109,7 +111,7 @@ impl EventTypeDao {
 
         // Check if event type exists
         let check_stmt = client
-            .prepare("SELECT id FROM event_types WHERE id = $1")
+            .prepare_cached("SELECT id FROM event_types WHERE id = $1")
             .await?;
         let check_rows = client.query(&check_stmt, &[&id]).await?;
         if check_rows.is_empty() {
@@ -119,7 +121,7 @@ impl EventTypeDao {
         if let Some(name) = req.name {
             // Check if new name already exists for a different event type
             let check_name_stmt = client
-                .prepare(
+                .prepare_cached(
                     "SELECT id FROM event_types WHERE name = $1 AND id != $2",
                 )
                 .await?;
@@ -130,7 +132,7 @@ impl EventTypeDao {
             }
 
             let stmt = client
-                .prepare(
+                .prepare_cached(
                     "UPDATE event_types SET name = $1 WHERE id = $2 \
                      RETURNING id, name",
                 )
@@ -152,7 +154,9 @@ impl EventTypeDao {
         else {
             // No update needed, just return the existing event type
             let stmt = client
-                .prepare("SELECT id, name FROM event_types WHERE id = $1")
+                .prepare_cached(
+                    "SELECT id, name FROM event_types WHERE id = $1",
+                )
                 .await?;
             let rows = client.query(&stmt, &[&id]).await?;
 
@@ -174,7 +178,7 @@ impl EventTypeDao {
     pub async fn delete(&self, id: i32) -> Result<(), EventTypeError> {
         let client = self.db.get_client().await?;
         let stmt = client
-            .prepare("DELETE FROM event_types WHERE id = $1")
+            .prepare_cached("DELETE FROM event_types WHERE id = $1")
             .await?;
         let affected = client.execute(&stmt, &[&id]).await?;
 
@@ -191,7 +195,9 @@ impl EventTypeDao {
     ) -> Result<EventTypeResponse, EventTypeError> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare("SELECT id, name FROM event_types WHERE name = $1")
+            .prepare_cached(
+                "SELECT id, name FROM event_types WHERE name = $1",
+            )
             .await?;
         let rows = client.query(&stmt, &[&name]).await?;
 
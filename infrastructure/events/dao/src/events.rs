@@ -1,14 +1,26 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use dao_utils::query_helpers::{PgParam, PgParamVec};
+use common_query::SortDirection;
+use dao_utils::{
+    cursor::{Cursor, CursorError},
+    filter::{FilterBuilder, Op},
+    pagination::CursorPagination,
+    query_helpers::{CursorResult, PgParam, PgParamVec},
+};
 use database_traits::dao::GenericDao;
 use events_commands::{CreateEventCommand, UpdateEventCommand};
 use events_errors::{EventError, EventTypeError};
-use events_models::Event;
-use events_responses::EventResponse;
-use sql_connection::SqlConnect;
+use events_models::{Event, EventId, EventTypeId};
+use events_responses::{EventResponse, StatsSummary};
+use futures::{Stream, StreamExt, stream};
+use sql_connection::{GenericClient, SqlConnect, Transaction};
 use tracing::instrument;
 
+/// Rows removed per statement by [`EventDao::delete_by_user`]
+pub const DELETE_CHUNK_SIZE: i64 = 10_000;
+
 #[derive(Clone)]
 pub struct EventDao {
     db: SqlConnect,
@@ -61,7 +73,7 @@ impl EventDao {
                 )
             };
 
-        let stmt = client.prepare(query).await?;
+        let stmt = client.prepare_cached(query).await?;
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
             params
                 .iter()
@@ -99,7 +111,7 @@ impl EventDao {
                 )
             };
 
-        let stmt = client.prepare(query).await?;
+        let stmt = client.prepare_cached(query).await?;
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
             params
                 .iter()
@@ -112,6 +124,41 @@ impl EventDao {
         Ok(row.get(0))
     }
 
+    /// Rows of the `stats_summary` materialized view with `hour_bucket` in
+    /// `[from, to]`, optionally limited to one `stat_type`
+    #[instrument(skip(self))]
+    pub async fn stats_summary(
+        &self, from: DateTime<Utc>, to: DateTime<Utc>,
+        stat_type: Option<String>,
+    ) -> Result<Vec<StatsSummary>, EventError> {
+        let client = self.db.get_analytics_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT stat_type, key_name, hour_bucket, total_count, \
+                 unique_users, page_count
+                 FROM stats_summary
+                 WHERE hour_bucket >= $1 AND hour_bucket <= $2
+                   AND ($3::text IS NULL OR stat_type = $3::text)
+                 ORDER BY hour_bucket DESC, stat_type, key_name",
+            )
+            .await?;
+        let rows = client.query(&stmt, &[&from, &to, &stat_type]).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                StatsSummary {
+                    stat_type: row.get(0),
+                    key_name: row.get(1),
+                    hour_bucket: row.get(2),
+                    total_count: row.get(3),
+                    unique_users: row.get(4),
+                    page_count: row.get(5),
+                }
+            })
+            .collect())
+    }
+
     #[instrument(skip(self))]
     pub async fn get_event_type_stats(
         &self, from: DateTime<Utc>, to: DateTime<Utc>,
@@ -140,7 +187,7 @@ impl EventDao {
                 )
             };
 
-        let stmt = client.prepare(query).await?;
+        let stmt = client.prepare_cached(query).await?;
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
             params
                 .iter()
@@ -158,6 +205,8 @@ impl EventDao {
         Ok(results)
     }
 
+    /// Most viewed pages in `[from, to]`, read from the `page` column
+    /// generated from `metadata->>'page'`
     #[instrument(skip(self))]
     pub async fn get_top_pages(
         &self, from: DateTime<Utc>, to: DateTime<Utc>,
@@ -168,13 +217,13 @@ impl EventDao {
         let (query, params): (&str, PgParamVec) =
             if let Some(event_type) = event_type {
                 (
-                    "SELECT metadata->>'page' as page, COUNT(*) as count 
+                    "SELECT e.page, COUNT(*) as count 
                  FROM events e 
                  JOIN event_types et ON e.event_type_id = et.id 
                  WHERE e.timestamp >= $1 AND e.timestamp <= $2 AND et.name = \
                      $3
-                   AND metadata ? 'page' 
-                 GROUP BY metadata->>'page' 
+                   AND e.page IS NOT NULL 
+                 GROUP BY e.page 
                  ORDER BY COUNT(*) DESC 
                  LIMIT $4",
                     vec![
@@ -187,18 +236,18 @@ impl EventDao {
             }
             else {
                 (
-                    "SELECT metadata->>'page' as page, COUNT(*) as count 
+                    "SELECT e.page, COUNT(*) as count 
                  FROM events e 
                  WHERE e.timestamp >= $1 AND e.timestamp <= $2
-                   AND metadata ? 'page' 
-                 GROUP BY metadata->>'page' 
+                   AND e.page IS NOT NULL 
+                 GROUP BY e.page 
                  ORDER BY COUNT(*) DESC 
                  LIMIT $3",
                     vec![Box::new(from), Box::new(to), Box::new(limit)],
                 )
             };
 
-        let stmt = client.prepare(query).await?;
+        let stmt = client.prepare_cached(query).await?;
         let param_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
             params
                 .iter()
@@ -210,13 +259,7 @@ impl EventDao {
         let rows = client.query(&stmt, &param_refs).await?;
         let results = rows
             .into_iter()
-            .map(|row| {
-                (
-                    row.get::<_, Option<String>>(0)
-                        .unwrap_or_else(|| "unknown".to_string()),
-                    row.get::<_, i64>(1),
-                )
-            })
+            .map(|row| (row.get::<_, String>(0), row.get::<_, i64>(1)))
             .collect();
 
         Ok(results)
@@ -227,7 +270,7 @@ impl EventDao {
 impl GenericDao for EventDao {
     type CreateRequest = CreateEventCommand;
     type Error = EventError;
-    type ID = i64;
+    type ID = EventId;
     type Model = Event;
     type Response = EventResponse;
     type UpdateRequest = UpdateEventCommand;
@@ -237,7 +280,7 @@ impl GenericDao for EventDao {
     ) -> Result<Self::Response, Self::Error> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare(
+            .prepare_cached(
                 "SELECT e.id, e.user_id, e.event_type_id, e.timestamp, \
                  e.metadata, et.name FROM events e JOIN event_types et ON \
                  e.event_type_id = et.id WHERE e.id = $1",
@@ -248,7 +291,7 @@ impl GenericDao for EventDao {
         let event_response = rows
             .first()
             .map(|row| self.map_row_to_response(row))
-            .ok_or(EventError::NotFound { event_id: id })?;
+            .ok_or(EventError::NotFound { event_id: id.get() })?;
 
         Ok(event_response)
     }
@@ -256,7 +299,7 @@ impl GenericDao for EventDao {
     async fn all(&self) -> Result<Vec<Self::Response>, Self::Error> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare(
+            .prepare_cached(
                 "SELECT e.id, e.user_id, e.event_type_id, e.timestamp, \
                  e.metadata, et.name FROM events e JOIN event_types et ON \
                  e.event_type_id = et.id ORDER BY e.timestamp DESC",
@@ -276,64 +319,7 @@ impl GenericDao for EventDao {
         &self, req: Self::CreateRequest,
     ) -> Result<Self::Response, Self::Error> {
         let client = self.db.get_client().await?;
-        let timestamp = req.timestamp.unwrap_or_else(Utc::now);
-
-        // Look up event type by name and create event in single query using
-        // CTE - now includes event type name in result
-        let stmt = client
-            .prepare(
-                "WITH event_type_lookup AS (
-                     SELECT id as event_type_id, name as event_type_name \
-                 FROM event_types WHERE name = $2
-                 ),
-                 event_insert AS (
-                     INSERT INTO events (user_id, event_type_id, timestamp, \
-                 metadata) 
-                     SELECT $1, etl.event_type_id, $3, $4
-                     FROM event_type_lookup etl
-                     RETURNING id, user_id, event_type_id, timestamp, \
-                 metadata
-                 )
-                 SELECT ei.id, ei.user_id, ei.event_type_id, ei.timestamp, \
-                 ei.metadata,
-                        CASE WHEN etl.event_type_id IS NULL THEN true ELSE \
-                 false END as type_not_found,
-                        COALESCE(etl.event_type_name, '') as event_type_name
-                 FROM event_type_lookup etl
-                 RIGHT JOIN event_insert ei ON true",
-            )
-            .await?;
-
-        let rows = client
-            .query(
-                &stmt,
-                &[&req.user_id, &req.event_type, &timestamp, &req.metadata],
-            )
-            .await?;
-
-        if let Some(row) = rows.first() {
-            let type_not_found: bool = row.get(5);
-            if type_not_found {
-                return Err(EventError::EventType(EventTypeError::NotFound));
-            }
-
-            let event_response = EventResponse {
-                id: row.get(0),
-                user_id: row.get(1),
-                event_type_id: row.get(2),
-                event_type: row.get(6), // event_type_name from query
-                timestamp: row.get(3),
-                metadata: row
-                    .get::<_, Option<serde_json::Value>>(4)
-                    .and_then(|json| serde_json::from_value(json).ok()),
-            };
-            Ok(event_response)
-        }
-        else {
-            Err(EventError::InternalError(
-                "Failed to create event".to_string(),
-            ))
-        }
+        insert_event(&*client, req).await
     }
 
     async fn update(
@@ -346,7 +332,7 @@ impl GenericDao for EventDao {
         match (&req.event_type_id, &req.metadata) {
             (Some(event_type_id), Some(metadata)) => {
                 let stmt = client
-                    .prepare(
+                    .prepare_cached(
                         "WITH validation AS (
                              SELECT CASE 
                                  WHEN NOT EXISTS(SELECT 1 FROM events WHERE \
@@ -377,7 +363,7 @@ impl GenericDao for EventDao {
                     let status: String = row.get(5);
                     match status.as_str() {
                         "not_found" => {
-                            Err(EventError::NotFound { event_id: id })
+                            Err(EventError::NotFound { event_id: id.get() })
                         }
                         "ok" => {
                             let metadata_json: Option<serde_json::Value> =
@@ -413,12 +399,12 @@ impl GenericDao for EventDao {
                     }
                 }
                 else {
-                    Err(EventError::NotFound { event_id: id })
+                    Err(EventError::NotFound { event_id: id.get() })
                 }
             }
             (Some(event_type_id), None) => {
                 let stmt = client
-                    .prepare(
+                    .prepare_cached(
                         "WITH updated AS (
                              UPDATE events SET event_type_id = $2 
                              WHERE id = $1 
@@ -437,13 +423,13 @@ impl GenericDao for EventDao {
                 let event_response = rows
                     .first()
                     .map(|row| self.map_row_to_response(row))
-                    .ok_or(EventError::NotFound { event_id: id })?;
+                    .ok_or(EventError::NotFound { event_id: id.get() })?;
 
                 Ok(event_response)
             }
             (None, Some(metadata)) => {
                 let stmt = client
-                    .prepare(
+                    .prepare_cached(
                         "WITH updated AS (
                              UPDATE events SET metadata = $2 
                              WHERE id = $1 
@@ -462,7 +448,7 @@ impl GenericDao for EventDao {
                 let event_response = rows
                     .first()
                     .map(|row| self.map_row_to_response(row))
-                    .ok_or(EventError::NotFound { event_id: id })?;
+                    .ok_or(EventError::NotFound { event_id: id.get() })?;
 
                 Ok(event_response)
             }
@@ -478,12 +464,12 @@ impl GenericDao for EventDao {
 
         // Use RETURNING clause to check if row existed
         let stmt = client
-            .prepare("DELETE FROM events WHERE id = $1 RETURNING id")
+            .prepare_cached("DELETE FROM events WHERE id = $1 RETURNING id")
             .await?;
         let affected = client.execute(&stmt, &[&id]).await?;
 
         if affected == 0 {
-            return Err(EventError::NotFound { event_id: id });
+            return Err(EventError::NotFound { event_id: id.get() });
         }
 
         Ok(())
@@ -505,7 +491,8 @@ impl GenericDao for EventDao {
 
     async fn count(&self) -> Result<i64, Self::Error> {
         let client = self.db.get_read_client().await?;
-        let stmt = client.prepare("SELECT COUNT(*) FROM events").await?;
+        let stmt =
+            client.prepare_cached("SELECT COUNT(*) FROM events").await?;
         let rows = client.query(&stmt, &[]).await?;
 
         let count: i64 = rows.first().map(|row| row.get(0)).unwrap_or(0);
@@ -517,68 +504,226 @@ impl GenericDao for EventDao {
 impl EventDao {
     #[instrument(skip_all)]
     pub async fn find_with_filters(
-        &self, user_id: Option<i64>, event_type_id: Option<i32>,
+        &self, user_id: Option<i64>, event_type_id: Option<EventTypeId>,
         limit: Option<u64>, offset: Option<u64>,
     ) -> Result<Vec<EventResponse>, EventError> {
         let client = self.db.get_read_client().await?;
 
-        // Build query dynamically based on provided filters
-        let mut query = String::from(
+        let mut filter = FilterBuilder::new();
+        filter
+            .eq_opt("e.user_id", user_id)
+            .eq_opt("e.event_type_id", event_type_id);
+
+        let mut query = format!(
             "SELECT e.id, e.user_id, e.event_type_id, e.timestamp, \
              e.metadata, et.name FROM events e JOIN event_types et ON \
-             e.event_type_id = et.id",
+             e.event_type_id = et.id{} ORDER BY e.timestamp DESC",
+            filter.where_clause()
         );
-        let mut where_clauses = Vec::new();
-        let mut params: PgParamVec = Vec::new();
-        let mut param_count = 0;
-
-        // Add WHERE filters
-        if let Some(uid) = user_id {
-            param_count += 1;
-            where_clauses.push(format!("e.user_id = ${param_count}"));
-            params.push(Box::new(uid));
-        }
 
-        if let Some(etid) = event_type_id {
-            param_count += 1;
-            where_clauses.push(format!("e.event_type_id = ${param_count}"));
-            params.push(Box::new(etid));
+        if let Some(l) = limit {
+            query.push_str(&format!(" LIMIT {}", filter.bind(l as i64)));
         }
 
-        if !where_clauses.is_empty() {
-            query.push_str(" WHERE ");
-            query.push_str(&where_clauses.join(" AND "));
+        if let Some(o) = offset {
+            query.push_str(&format!(" OFFSET {}", filter.bind(o as i64)));
         }
 
-        // Add ORDER BY
-        query.push_str(" ORDER BY e.timestamp DESC");
+        let stmt = client.prepare_cached(&query).await?;
+        let rows = client.query(&stmt, &filter.params()).await?;
 
-        // Add LIMIT and OFFSET
-        if let Some(l) = limit {
-            param_count += 1;
-            query.push_str(&format!(" LIMIT ${param_count}"));
-            params.push(Box::new(l as i64));
+        let events = rows
+            .iter()
+            .map(|row| self.map_row_to_response(row))
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Opaque cursor for the [`find_with_keyset`] position `(timestamp, id)`
+    ///
+    /// [`find_with_keyset`]: EventDao::find_with_keyset
+    pub fn cursor_at(timestamp: DateTime<Utc>, id: i64) -> Cursor {
+        Cursor::new(
+            vec![serde_json::json!(timestamp), serde_json::json!(id)],
+            SortDirection::Desc,
+        )
+    }
+
+    /// The `(timestamp, id)` position `cursor` was made for by
+    /// [`cursor_at`], rejecting cursors of other listings
+    ///
+    /// [`cursor_at`]: EventDao::cursor_at
+    pub fn cursor_position(
+        cursor: &Cursor,
+    ) -> Result<(DateTime<Utc>, i64), CursorError> {
+        cursor.sort_key(SortDirection::Desc)
+    }
+
+    /// Keyset page of events, newest first, starting after `after`.
+    ///
+    /// Seeks on `(timestamp, id)` rather than skipping rows, so deep pages
+    /// cost the same as the first. The returned cursor is the position of
+    /// the last event when more events follow.
+    #[instrument(skip_all)]
+    pub async fn find_with_keyset(
+        &self, user_id: Option<i64>, event_type_id: Option<EventTypeId>,
+        after: Option<(DateTime<Utc>, i64)>, limit: u64,
+    ) -> Result<CursorResult<EventResponse, (DateTime<Utc>, i64)>, EventError>
+    {
+        let client = self.db.get_read_client().await?;
+        let pagination = CursorPagination::new(after, limit);
+
+        let mut filter = FilterBuilder::new();
+        filter
+            .eq_opt("e.user_id", user_id)
+            .eq_opt("e.event_type_id", event_type_id);
+        if let Some(after) = pagination.cursor {
+            filter.cmp_pair(("e.timestamp", "e.id"), Op::Lt, after);
         }
+        let limit_plus_one = filter.bind(pagination.limit_plus_one());
 
-        if let Some(o) = offset {
-            param_count += 1;
-            query.push_str(&format!(" OFFSET ${param_count}"));
-            params.push(Box::new(o as i64));
+        let query = format!(
+            "SELECT e.id, e.user_id, e.event_type_id, e.timestamp, \
+             e.metadata, et.name FROM events e JOIN event_types et ON \
+             e.event_type_id = et.id{} ORDER BY e.timestamp DESC, e.id DESC \
+             LIMIT {limit_plus_one}",
+            filter.where_clause()
+        );
+
+        let stmt = client.prepare_cached(&query).await?;
+        let rows = client.query(&stmt, &filter.params()).await?;
+
+        let events: Vec<EventResponse> = rows
+            .iter()
+            .take(pagination.limit as usize)
+            .map(|row| self.map_row_to_response(row))
+            .collect();
+
+        let next_cursor = if rows.len() > pagination.limit as usize {
+            events.last().map(|e| (e.timestamp, e.id))
         }
+        else {
+            None
+        };
 
-        let stmt = client.prepare(&query).await?;
+        Ok(CursorResult::new(events, next_cursor))
+    }
 
-        let param_refs: Vec<&PgParam> =
-            params.iter().map(|p| p.as_ref() as &PgParam).collect();
+    /// Events with `timestamp` in `[from, to)` by ascending id, yielded as
+    /// Postgres sends the rows instead of being collected first. The pooled
+    /// connection stays checked out until the stream is dropped.
+    #[instrument(skip(self))]
+    pub async fn find_stream(
+        &self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>,
+    ) -> Result<
+        impl Stream<Item = Result<Event, EventError>> + Send + 'static,
+        EventError,
+    > {
+        let client = self.db.get_read_client().await?;
 
-        let rows = client.query(&stmt, &param_refs).await?;
+        let mut filter = FilterBuilder::new();
+        filter.cmp_opt("timestamp", Op::Ge, from).cmp_opt(
+            "timestamp",
+            Op::Lt,
+            to,
+        );
+        let query = format!(
+            "SELECT id, user_id, event_type_id, timestamp, metadata FROM \
+             events{} ORDER BY id",
+            filter.where_clause()
+        );
 
-        let events = rows
+        let stmt = client.prepare_cached(&query).await?;
+        let rows = client.query_raw(&stmt, filter.params()).await?;
+
+        Ok(stream::unfold(
+            (self.clone(), client, Box::pin(rows)),
+            |(dao, client, mut rows)| {
+                async move {
+                    let event = rows
+                        .next()
+                        .await?
+                        .map(|row| dao.map_row(&row))
+                        .map_err(EventError::from);
+                    Some((event, (dao, client, rows)))
+                }
+            },
+        ))
+    }
+
+    /// Events of one session in the order they happened, read through the
+    /// `session_id` column generated from `metadata->>'session_id'`
+    #[instrument(skip(self))]
+    pub async fn find_by_session_id(
+        &self, session_id: &str, limit: u64,
+    ) -> Result<Vec<EventResponse>, EventError> {
+        let client = self.db.get_read_client().await?;
+
+        let mut filter = FilterBuilder::new();
+        filter.eq("e.session_id", session_id.to_string());
+        let limit = filter.bind(limit as i64);
+
+        let query = format!(
+            "SELECT e.id, e.user_id, e.event_type_id, e.timestamp, \
+             e.metadata, et.name FROM events e JOIN event_types et ON \
+             e.event_type_id = et.id{} ORDER BY e.timestamp ASC, e.id ASC \
+             LIMIT {limit}",
+            filter.where_clause()
+        );
+
+        let stmt = client.prepare_cached(&query).await?;
+        let rows = client.query(&stmt, &filter.params()).await?;
+
+        Ok(rows
             .iter()
             .map(|row| self.map_row_to_response(row))
-            .collect();
+            .collect())
+    }
 
-        Ok(events)
+    /// Type name and stored metadata of one event. Unlike
+    /// [`EventResponse::metadata`] the metadata keeps every field, as
+    /// needed to check it against a schema.
+    #[instrument(skip(self))]
+    pub async fn find_type_and_metadata(
+        &self, id: EventId,
+    ) -> Result<(String, Option<serde_json::Value>), EventError> {
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT et.name, e.metadata FROM events e JOIN event_types \
+                 et ON e.event_type_id = et.id WHERE e.id = $1",
+            )
+            .await?;
+        let row = client
+            .query_opt(&stmt, &[&id])
+            .await?
+            .ok_or(EventError::NotFound { event_id: id.get() })?;
+
+        Ok((row.get(0), row.get(1)))
+    }
+
+    /// Timestamp of each user's newest event; users without events are
+    /// left out
+    #[instrument(skip_all, fields(users = user_ids.len()))]
+    pub async fn latest_event_per_user(
+        &self, user_ids: &[i64],
+    ) -> Result<HashMap<i64, DateTime<Utc>>, EventError> {
+        if user_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT DISTINCT ON (user_id) user_id, timestamp FROM \
+                 events WHERE user_id = ANY($1) ORDER BY user_id, timestamp \
+                 DESC",
+            )
+            .await?;
+        let rows = client.query(&stmt, &[&user_ids]).await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
     }
 
     #[instrument(skip_all)]
@@ -587,12 +732,54 @@ impl EventDao {
     ) -> Result<u64, EventError> {
         let client = self.db.get_client().await?;
         let stmt = client
-            .prepare("DELETE FROM events WHERE timestamp < $1")
+            .prepare_cached("DELETE FROM events WHERE timestamp < $1")
             .await?;
         let affected = client.execute(&stmt, &[&before]).await?;
         Ok(affected)
     }
 
+    /// Delete every event of `user_id` and return how many were removed.
+    ///
+    /// Runs in chunks of [`DELETE_CHUNK_SIZE`] rows, each its own statement,
+    /// so erasing a huge history never holds one long-running delete.
+    pub async fn delete_by_user(
+        &self, user_id: i64,
+    ) -> Result<u64, EventError> {
+        self.delete_by_user_in_chunks(user_id, DELETE_CHUNK_SIZE)
+            .await
+    }
+
+    /// [`delete_by_user`](Self::delete_by_user) with `chunk_size` rows per
+    /// statement, which must be positive
+    #[instrument(skip(self))]
+    pub async fn delete_by_user_in_chunks(
+        &self, user_id: i64, chunk_size: i64,
+    ) -> Result<u64, EventError> {
+        if chunk_size <= 0 {
+            return Err(EventError::InternalError(format!(
+                "Delete chunk size must be positive, got {chunk_size}"
+            )));
+        }
+
+        let client = self.db.get_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "DELETE FROM events WHERE id IN (SELECT id FROM events \
+                 WHERE user_id = $1 LIMIT $2)",
+            )
+            .await?;
+
+        let mut deleted = 0;
+        loop {
+            let affected =
+                client.execute(&stmt, &[&user_id, &chunk_size]).await?;
+            deleted += affected;
+            if affected < chunk_size as u64 {
+                return Ok(deleted);
+            }
+        }
+    }
+
     #[instrument(skip_all)]
     pub async fn find_by_user_id(
         &self, user_id: i64, limit: Option<u64>,
@@ -626,7 +813,7 @@ impl EventDao {
             }
         };
 
-        let stmt = client.prepare(&sql).await?;
+        let stmt = client.prepare_cached(&sql).await?;
 
         let param_refs: Vec<&PgParam> = std::iter::once(&user_id as &PgParam)
             .chain(params.iter().map(|p| p as &PgParam))
@@ -682,7 +869,7 @@ impl EventDao {
             }
         };
 
-        let stmt = client.prepare(&sql).await?;
+        let stmt = client.prepare_cached(&sql).await?;
 
         let param_refs: Vec<&PgParam> =
             params.iter().map(|p| p as &PgParam).collect();
@@ -692,105 +879,517 @@ impl EventDao {
 
         Ok(events)
     }
+}
+
+impl EventDao {
+    /// Insert every event in one statement. Results follow the order of
+    /// `reqs`; an event whose type does not exist is `None` and is not
+    /// inserted. Ids are drawn up front so each row can be matched to its
+    /// request. Any other failure rejects the whole batch.
+    #[instrument(skip_all, fields(events = reqs.len()))]
+    pub async fn create_many(
+        &self, reqs: &[CreateEventCommand],
+    ) -> Result<Vec<Option<EventResponse>>, EventError> {
+        if reqs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let now = Utc::now();
+        let mut user_ids = Vec::with_capacity(reqs.len());
+        let mut event_types = Vec::with_capacity(reqs.len());
+        let mut timestamps = Vec::with_capacity(reqs.len());
+        let mut metadata = Vec::with_capacity(reqs.len());
+        for req in reqs {
+            user_ids.push(req.user_id);
+            event_types.push(req.event_type.as_str());
+            timestamps.push(req.timestamp.unwrap_or(now));
+            metadata.push(req.metadata.clone());
+        }
+
+        let client = self.db.get_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "WITH input AS (
+                     SELECT * FROM unnest($1::bigint[], $2::text[], \
+                 $3::timestamptz[], $4::jsonb[])
+                     WITH ORDINALITY AS t(user_id, event_type, timestamp, \
+                 metadata, ord)
+                 ),
+                 typed AS (
+                     SELECT nextval(pg_get_serial_sequence('events', 'id')) \
+                 AS id, i.ord, i.user_id, et.id AS event_type_id, et.name \
+                 AS event_type, i.timestamp, i.metadata
+                     FROM input i JOIN event_types et ON et.name = \
+                 i.event_type
+                 ),
+                 inserted AS (
+                     INSERT INTO events (id, user_id, event_type_id, \
+                 timestamp, metadata)
+                     SELECT id, user_id, event_type_id, timestamp, metadata
+                     FROM typed
+                 )
+                 SELECT ord, id, user_id, event_type_id, timestamp, \
+                 metadata, event_type FROM typed",
+            )
+            .await?;
+        let rows = client
+            .query(&stmt, &[&user_ids, &event_types, &timestamps, &metadata])
+            .await?;
+
+        let mut events = vec![None; reqs.len()];
+        for row in rows {
+            let ord: i64 = row.get(0);
+            events[ord as usize - 1] = Some(EventResponse {
+                id: row.get(1),
+                user_id: row.get(2),
+                event_type_id: row.get(3),
+                event_type: row.get(6),
+                timestamp: row.get(4),
+                metadata: row
+                    .get::<_, Option<serde_json::Value>>(5)
+                    .and_then(|json| serde_json::from_value(json).ok()),
+            });
+        }
+        Ok(events)
+    }
+}
 
+/// Event writes that run inside a transaction opened with
+/// [`SqlConnect::with_transaction`]
+pub struct EventTxDao<'t> {
+    tx: &'t Transaction<'t>,
+}
+
+impl EventDao {
+    /// Bind event writes to `tx`, so they commit or roll back with it
+    pub fn in_transaction<'t>(tx: &'t Transaction<'t>) -> EventTxDao<'t> {
+        EventTxDao { tx }
+    }
+}
+
+impl EventTxDao<'_> {
     #[instrument(skip_all)]
-    pub async fn find_with_cursor(
-        &self, cursor: Option<DateTime<Utc>>, limit: u64,
-    ) -> Result<(Vec<Event>, Option<DateTime<Utc>>), EventError> {
-        let client = self.db.get_read_client().await?;
-        let limit = limit.min(1000);
-        let limit_plus_one = limit as i64 + 1;
-
-        let (_sql, rows) = match cursor {
-            Some(cursor_timestamp) => {
-                let sql = "SELECT id, user_id, event_type_id, timestamp, \
-                           metadata FROM events 
-                          WHERE timestamp < $1 
-                          ORDER BY timestamp DESC 
-                          LIMIT $2";
-                let stmt = client.prepare(sql).await?;
-                let rows = client
-                    .query(&stmt, &[&cursor_timestamp, &limit_plus_one])
-                    .await?;
-                (sql, rows)
-            }
-            None => {
-                let sql = "SELECT id, user_id, event_type_id, timestamp, \
-                           metadata FROM events 
-                          ORDER BY timestamp DESC 
-                          LIMIT $1";
-                let stmt = client.prepare(sql).await?;
-                let rows = client.query(&stmt, &[&limit_plus_one]).await?;
-                (sql, rows)
-            }
+    pub async fn create(
+        &self, req: CreateEventCommand,
+    ) -> Result<EventResponse, EventError> {
+        insert_event(self.tx, req).await
+    }
+}
+
+/// Insert an event of a type looked up by name, on a pooled connection or
+/// in a transaction
+async fn insert_event(
+    client: &impl GenericClient, req: CreateEventCommand,
+) -> Result<EventResponse, EventError> {
+    let timestamp = req.timestamp.unwrap_or_else(Utc::now);
+
+    // Look up event type by name and create event in single query using
+    // CTE - now includes event type name in result
+    let stmt = client
+        .prepare_cached(
+            "WITH event_type_lookup AS (
+                 SELECT id as event_type_id, name as event_type_name FROM \
+             event_types WHERE name = $2
+             ),
+             event_insert AS (
+                 INSERT INTO events (user_id, event_type_id, timestamp, \
+             metadata) 
+                 SELECT $1, etl.event_type_id, $3, $4
+                 FROM event_type_lookup etl
+                 RETURNING id, user_id, event_type_id, timestamp, metadata
+             )
+             SELECT ei.id, ei.user_id, ei.event_type_id, ei.timestamp, \
+             ei.metadata,
+                    CASE WHEN etl.event_type_id IS NULL THEN true ELSE false \
+             END as type_not_found,
+                    COALESCE(etl.event_type_name, '') as event_type_name
+             FROM event_type_lookup etl
+             RIGHT JOIN event_insert ei ON true",
+        )
+        .await?;
+
+    let rows = client
+        .query(
+            &stmt,
+            &[&req.user_id, &req.event_type, &timestamp, &req.metadata],
+        )
+        .await?;
+
+    if let Some(row) = rows.first() {
+        let type_not_found: bool = row.get(5);
+        if type_not_found {
+            return Err(EventError::EventType(EventTypeError::NotFound));
+        }
+
+        let event_response = EventResponse {
+            id: row.get(0),
+            user_id: row.get(1),
+            event_type_id: row.get(2),
+            event_type: row.get(6), // event_type_name from query
+            timestamp: row.get(3),
+            metadata: row
+                .get::<_, Option<serde_json::Value>>(4)
+                .and_then(|json| serde_json::from_value(json).ok()),
         };
+        Ok(event_response)
+    }
+    else {
+        Err(EventError::InternalError(
+            "Failed to create event".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trips_its_position() {
+        let timestamp =
+            DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let raw = EventDao::cursor_at(timestamp, 42).encode();
 
-        let events: Vec<Event> = rows
+        let cursor = Cursor::decode(&raw).unwrap();
+        assert_eq!(EventDao::cursor_position(&cursor), Ok((timestamp, 42)));
+
+        // A cursor of the users listing doesn't page events
+        let users = Cursor::new(
+            vec![serde_json::json!("ann"), serde_json::json!(7)],
+            SortDirection::Asc,
+        );
+        assert_eq!(
+            EventDao::cursor_position(&users),
+            Err(CursorError::Mismatch)
+        );
+    }
+
+    /// `get_top_pages` as it read `metadata` before the generated columns
+    async fn top_pages_from_metadata(
+        container: &TestPostgresContainer, from: DateTime<Utc>,
+        to: DateTime<Utc>, limit: i64,
+    ) -> Vec<(String, i64)> {
+        let client = container.pool.get().await.unwrap();
+        client
+            .query(
+                "SELECT metadata->>'page', COUNT(*) FROM events
+                 WHERE timestamp >= $1 AND timestamp <= $2
+                   AND metadata ? 'page'
+                 GROUP BY metadata->>'page'
+                 ORDER BY COUNT(*) DESC
+                 LIMIT $3",
+                &[&from, &to, &limit],
+            )
+            .await
+            .unwrap()
             .iter()
-            .take(limit as usize)
-            .map(|row| self.map_row(row))
-            .collect();
+            .map(|row| (row.get(0), row.get(1)))
+            .collect()
+    }
 
-        let next_cursor = if rows.len() > limit as usize {
-            events.last().map(|e| e.timestamp)
+    async fn insert_event(
+        container: &TestPostgresContainer, user_id: i64, event_type_id: i32,
+        metadata: &str,
+    ) {
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id, metadata) \
+                 VALUES ({user_id}, {event_type_id}, '{metadata}')"
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_top_pages_match_metadata_query() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let migrator = container.get_migrator().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // Rows written before the migration must be backfilled by it
+        migrator
+            .run_down_migrations(&["008_extract_metadata_columns"])
+            .await
+            .unwrap();
+        for (page, count) in [("/home", 3), ("/about", 2), ("/pricing", 1)] {
+            for _ in 0..count {
+                insert_event(
+                    &container,
+                    user_id,
+                    event_type_id,
+                    &format!(r#"{{"page": "{page}", "product_id": 7}}"#),
+                )
+                .await;
+            }
         }
-        else {
-            None
-        };
+        insert_event(
+            &container,
+            user_id,
+            event_type_id,
+            r#"{"referrer": "https://example.com"}"#,
+        )
+        .await;
+        migrator.run_all_migrations().await.unwrap();
+
+        insert_event(
+            &container,
+            user_id,
+            event_type_id,
+            r#"{"page": "/home"}"#,
+        )
+        .await;
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        let from = Utc::now() - Duration::hours(1);
+        let to = Utc::now() + Duration::hours(1);
+
+        let top_pages = dao.get_top_pages(from, to, None, 10).await.unwrap();
+        assert_eq!(
+            top_pages,
+            top_pages_from_metadata(&container, from, to, 10).await
+        );
+        assert_eq!(
+            top_pages,
+            vec![
+                ("/home".to_string(), 4),
+                ("/about".to_string(), 2),
+                ("/pricing".to_string(), 1),
+            ]
+        );
 
-        Ok((events, next_cursor))
+        let by_type = dao
+            .get_top_pages(from, to, Some("test_event".to_string()), 2)
+            .await
+            .unwrap();
+        assert_eq!(by_type, top_pages[..2]);
+
+        let client = container.pool.get().await.unwrap();
+        let backfilled: i64 = client
+            .query_one(
+                "SELECT COUNT(*) FROM events WHERE product_id = 7",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(backfilled, 6);
     }
 
-    #[instrument(skip_all)]
-    pub async fn find_by_user_with_cursor(
-        &self, user_id: i64, cursor: Option<DateTime<Utc>>, limit: u64,
-    ) -> Result<(Vec<Event>, Option<DateTime<Utc>>), EventError> {
-        let client = self.db.get_read_client().await?;
-        let limit = limit.min(1000);
-        let limit_plus_one = limit as i64 + 1;
-
-        let (_sql, rows) = match cursor {
-            Some(cursor_timestamp) => {
-                let sql = "SELECT id, user_id, event_type_id, timestamp, \
-                           metadata FROM events 
-                          WHERE user_id = $1 AND timestamp < $2 
-                          ORDER BY timestamp DESC 
-                          LIMIT $3";
-                let stmt = client.prepare(sql).await?;
-                let rows = client
-                    .query(
-                        &stmt,
-                        &[&user_id, &cursor_timestamp, &limit_plus_one],
-                    )
-                    .await?;
-                (sql, rows)
-            }
-            None => {
-                let sql = "SELECT id, user_id, event_type_id, timestamp, \
-                           metadata FROM events 
-                          WHERE user_id = $1 
-                          ORDER BY timestamp DESC 
-                          LIMIT $2";
-                let stmt = client.prepare(sql).await?;
-                let rows =
-                    client.query(&stmt, &[&user_id, &limit_plus_one]).await?;
-                (sql, rows)
+    #[tokio::test]
+    async fn test_keyset_pages_have_no_gaps_or_duplicates() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // Ten events share each timestamp, so pages must split ties by id
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id, timestamp) \
+                 SELECT {user_id}, {event_type_id}, DATE_TRUNC('second', \
+                 NOW()) - make_interval(secs => i / 10) FROM \
+                 generate_series(0, 499) AS i"
+            ))
+            .await
+            .unwrap();
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page =
+                dao.find_with_keyset(None, None, cursor, 37).await.unwrap();
+            assert!(page.items.len() <= 37);
+            seen.extend(page.items.iter().map(|e| (e.timestamp, e.id)));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
             }
-        };
+        }
 
-        let events: Vec<Event> = rows
-            .iter()
-            .take(limit as usize)
-            .map(|row| self.map_row(row))
-            .collect();
+        let mut expected = seen.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        expected.dedup();
+        assert_eq!(seen.len(), 500);
+        assert_eq!(seen, expected);
+
+        let other_user = dao
+            .find_with_keyset(Some(user_id + 1), None, None, 37)
+            .await
+            .unwrap();
+        assert!(other_user.items.is_empty());
+        assert!(other_user.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_stream_yields_every_row_once() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id) SELECT \
+                 {user_id}, {event_type_id} FROM generate_series(1, 2000)"
+            ))
+            .await
+            .unwrap();
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        let mut events = Box::pin(dao.find_stream(None, None).await.unwrap());
+
+        // Rows are handled one at a time as they arrive
+        let mut processed = 0;
+        let mut last_id = 0;
+        while let Some(event) = events.next().await {
+            let event = event.unwrap();
+            assert!(event.id > last_id);
+            last_id = event.id;
+            processed += 1;
+        }
+        assert_eq!(processed, 2000);
+
+        // Dropping the stream early is fine
+        let first: Vec<_> = dao
+            .find_stream(None, None)
+            .await
+            .unwrap()
+            .take(10)
+            .collect()
+            .await;
+        assert_eq!(first.len(), 10);
+
+        let none = dao
+            .find_stream(Some(Utc::now() + Duration::hours(1)), None)
+            .await
+            .unwrap()
+            .count()
+            .await;
+        assert_eq!(none, 0);
+    }
 
-        let next_cursor = if rows.len() > limit as usize {
-            events.last().map(|e| e.timestamp)
+    #[tokio::test]
+    async fn test_find_by_session_id_orders_by_timestamp() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // Inserted out of order, plus an event from another session
+        for (session, minutes_ago) in [
+            ("s-1", 5),
+            ("s-1", 20),
+            ("s-2", 10),
+            ("s-1", 1),
+            ("s-1", 12),
+        ] {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp, \
+                     metadata) VALUES ({user_id}, {event_type_id}, NOW() - \
+                     INTERVAL '{minutes_ago} minutes', '{{\"session_id\": \
+                     \"{session}\"}}')"
+                ))
+                .await
+                .unwrap();
         }
-        else {
-            None
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        let events = dao.find_by_session_id("s-1", 100).await.unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert!(events.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert!(events.iter().all(|event| {
+            event
+                .metadata
+                .as_ref()
+                .and_then(|m| m.session_id.as_deref())
+                == Some("s-1")
+        }));
+
+        let ids = |events: &[EventResponse]| {
+            events.iter().map(|event| event.id).collect::<Vec<_>>()
         };
+        let first_two = dao.find_by_session_id("s-1", 2).await.unwrap();
+        assert_eq!(ids(&first_two), ids(&events[..2]));
+        assert!(dao.find_by_session_id("s-3", 100).await.unwrap().is_empty());
+    }
 
-        Ok((events, next_cursor))
+    #[tokio::test]
+    async fn test_latest_event_per_user() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let (alice, bob) = create_test_users(&container).await.unwrap();
+        let idle = create_test_user_with_name(&container, "idle")
+            .await
+            .unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        for (user_id, minutes_ago) in
+            [(alice, 30), (alice, 5), (alice, 90), (bob, 60), (bob, 45)]
+        {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp) \
+                     VALUES ({user_id}, {event_type_id}, TIMESTAMPTZ \
+                     '2024-06-01 12:00:00+00' - INTERVAL '{minutes_ago} \
+                     minutes')"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        let latest = dao
+            .latest_event_per_user(&[alice, bob, idle])
+            .await
+            .unwrap();
+
+        let noon = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[&alice], noon - Duration::minutes(5));
+        assert_eq!(latest[&bob], noon - Duration::minutes(45));
+        assert!(!latest.contains_key(&idle));
+
+        let only_bob = dao.latest_event_per_user(&[bob]).await.unwrap();
+        assert_eq!(only_bob.len(), 1);
+        assert!(dao.latest_event_per_user(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_user_removes_only_that_users_events() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let (erased, kept) = create_test_users(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id) SELECT \
+                 {erased}, {event_type_id} FROM generate_series(1, 25)"
+            ))
+            .await
+            .unwrap();
+        insert_event(&container, kept, event_type_id, "{}").await;
+
+        let dao = EventDao::new(create_sql_connect(&container));
+        // 25 rows in chunks of 10 takes three statements
+        assert_eq!(
+            dao.delete_by_user_in_chunks(erased, 10).await.unwrap(),
+            25
+        );
+        assert!(matches!(
+            dao.delete_by_user_in_chunks(kept, 0).await,
+            Err(EventError::InternalError(_))
+        ));
+
+        let client = container.pool.get().await.unwrap();
+        let count = "SELECT COUNT(*) FROM events WHERE user_id = $1";
+        let remaining: i64 =
+            client.query_one(count, &[&erased]).await.unwrap().get(0);
+        assert_eq!(remaining, 0);
+        let remaining: i64 =
+            client.query_one(count, &[&kept]).await.unwrap().get(0);
+        assert_eq!(remaining, 1);
+        assert_eq!(dao.delete_by_user(erased).await.unwrap(), 0);
     }
 }
@@ -1,5 +1,9 @@
+mod analytics_views;
 mod event_types;
 mod events;
 
+pub use analytics_views::{
+    AnalyticsViewsDao, DEFAULT_CONVERSION_EVENT, SummaryRefresh,
+};
 pub use event_types::EventTypeDao;
-pub use events::EventDao;
+pub use events::{DELETE_CHUNK_SIZE, EventDao, EventTxDao};
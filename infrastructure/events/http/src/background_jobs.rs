@@ -1,49 +1,64 @@
 use std::time::Duration;
 
+use events_dao::{AnalyticsViewsDao, SummaryRefresh};
+use job_scheduler::BackgroundJobScheduler;
 use sql_connection::SqlConnect;
-use tokio::time::interval;
-use tracing::{error, info, warn};
+use tracing::{info, warn};
 
+/// Refreshes `stats_summary` and the hourly summary
+pub const STATS_REFRESH_JOB: &str = "stats_refresh";
+
+const STATS_REFRESH_EVERY: Duration = Duration::from_secs(3600);
+
+/// Recomputes the whole hourly summary, catching up on deleted events that
+/// the hourly incremental refresh doesn't see
+pub const SUMMARY_REBUILD_JOB: &str = "hourly_summary_rebuild";
+
+const SUMMARY_REBUILD_EVERY: Duration = Duration::from_secs(24 * 3600);
+
+/// Keeps the stats views current, on a schedule once [`register`]ed or on
+/// demand through [`run`]
+///
+/// [`register`]: StatsRefresh::register
+/// [`run`]: StatsRefresh::run
 #[derive(Clone)]
-pub struct BackgroundJobScheduler {
+pub struct StatsRefresh {
     db: SqlConnect,
+    analytics_views: AnalyticsViewsDao,
 }
 
-impl BackgroundJobScheduler {
-    pub fn new(db: SqlConnect) -> Self { Self { db } }
-
-    /// Start background job to refresh materialized views
-    pub fn start_stats_refresh_job(self) {
-        tokio::spawn(async move {
-            let mut refresh_interval = interval(Duration::from_secs(3600)); // 60 minutes
-            refresh_interval.tick().await; // Skip first immediate tick
-
-            info!(
-                "Starting stats materialized view refresh job (every 60 \
-                 minutes)"
-            );
-
-            loop {
-                refresh_interval.tick().await;
-
-                match self.refresh_stats_materialized_view().await {
-                    Ok(_) => {
-                        info!(
-                            "Successfully refreshed stats materialized view"
-                        );
-                    }
-                    Err(e) => {
-                        error!(
-                            "Failed to refresh stats materialized view: {}",
-                            e
-                        );
-                    }
+impl StatsRefresh {
+    pub fn new(db: SqlConnect) -> Self {
+        Self {
+            analytics_views: AnalyticsViewsDao::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Schedule the hourly stats refresh and the daily summary rebuild
+    pub fn register(&self, scheduler: &BackgroundJobScheduler) {
+        let refresh = self.clone();
+        scheduler
+            .register(STATS_REFRESH_JOB, STATS_REFRESH_EVERY, move || {
+                let refresh = refresh.clone();
+                async move { refresh.run().await }
+            })
+            .expect("stats refresh interval is non-zero");
+
+        let analytics_views = self.analytics_views.clone();
+        scheduler
+            .register(SUMMARY_REBUILD_JOB, SUMMARY_REBUILD_EVERY, move || {
+                let analytics_views = analytics_views.clone();
+                async move {
+                    analytics_views.rebuild_hourly_summary().await?;
+                    Ok(())
                 }
-            }
-        });
+            })
+            .expect("summary rebuild interval is non-zero");
     }
 
-    async fn refresh_stats_materialized_view(&self) -> anyhow::Result<()> {
+    /// Refresh the stats views now, outside the schedule
+    pub async fn run(&self) -> anyhow::Result<()> {
         let client = self.db.get_client().await.map_err(|e| {
             anyhow::anyhow!("Database connection error: {}", e)
         })?;
@@ -68,22 +83,42 @@ impl BackgroundJobScheduler {
         let duration = start.elapsed();
         info!("Materialized view refresh completed in {:?}", duration);
 
+        match self.analytics_views.refresh_hourly_summary().await {
+            Ok(SummaryRefresh::Full) => {
+                info!("Hourly summary fully recomputed");
+            }
+            Ok(SummaryRefresh::Incremental { buckets }) => {
+                info!(
+                    "Hourly summary refreshed incrementally ({} buckets)",
+                    buckets.len()
+                );
+            }
+            Err(e) => warn!("Failed to refresh hourly summary: {}", e),
+        }
+
         Ok(())
     }
+}
 
-    /// Manually trigger stats refresh (for testing or on-demand refresh)
-    pub async fn trigger_stats_refresh(&self) -> anyhow::Result<()> {
-        self.refresh_stats_materialized_view().await
-    }
+#[cfg(test)]
+mod tests {
+    use job_scheduler::JobResult;
+    use test_utils::{TestPostgresContainer, create_sql_connect};
 
-    /// Alias for trigger_stats_refresh for compatibility
-    pub async fn refresh_stats_now(&self) -> anyhow::Result<()> {
-        self.refresh_stats_materialized_view().await
-    }
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats_jobs_are_registered_and_run() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let scheduler = BackgroundJobScheduler::new();
+        StatsRefresh::new(create_sql_connect(&container))
+            .register(&scheduler);
+
+        let names: Vec<_> =
+            scheduler.status().into_iter().map(|job| job.name).collect();
+        assert_eq!(names, [STATS_REFRESH_JOB, SUMMARY_REBUILD_JOB]);
 
-    /// Start all background jobs
-    pub async fn start(&self) {
-        let scheduler = self.clone();
-        scheduler.start_stats_refresh_job();
+        let status = scheduler.trigger(STATS_REFRESH_JOB).await.unwrap();
+        assert_eq!(status.last_result, Some(JobResult::Success));
     }
 }
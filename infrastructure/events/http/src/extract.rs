@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+};
+use common_errors::AppError;
+
+/// Numeric id, e.g. an `EventId`, taken from the only path parameter of a
+/// route. Unlike axum's `Path<i64>`, a value that is not an integer is
+/// rejected with a JSON `400 INVALID_ID` instead of a plain-text body.
+pub struct PathId<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for PathId<T>
+where
+    S: Send + Sync,
+    T: FromStr,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts, state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(raw) = Path::<String>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| {
+                AppError::bad_request("INVALID_ID", &err.body_text())
+            })?;
+
+        raw.parse().map(Self).map_err(|_| {
+            AppError::bad_request_with_details(
+                "INVALID_ID",
+                "The id in the path must be an integer",
+                &format!("Got '{raw}'"),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use events_models::EventId;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn call(uri: &str) -> (StatusCode, Vec<u8>) {
+        let app = Router::new().route(
+            "/event/{id}",
+            get(|PathId(id): PathId<EventId>| async move { id.to_string() }),
+        );
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_numeric_id_is_extracted() {
+        assert_eq!(call("/event/42").await, (StatusCode::OK, b"42".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_non_numeric_id_is_a_json_bad_request() {
+        let (status, body) = call("/event/abc").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "INVALID_ID");
+        assert_eq!(
+            body["error"]["message"],
+            "The id in the path must be an integer"
+        );
+        assert_eq!(body["error"]["details"], "Got 'abc'");
+    }
+}
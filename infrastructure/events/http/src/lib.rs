@@ -1,31 +1,59 @@
 pub mod background_jobs;
+pub mod extract;
+pub mod rate_limit;
 pub mod stats;
+
+use std::time::Duration;
+
 use axum::{
     Router,
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
     routing::{delete, get, post, put},
 };
 use chrono::{DateTime, Utc};
-use common_errors::AppError;
+use common_errors::{AppError, FieldError};
+use common_query::{
+    CollectionEtag, CollectionVersion, DeleteParams, TOTAL_COUNT_HEADER,
+};
+use dao_utils::{
+    cursor::{Cursor, CursorError},
+    pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, PaginationParams},
+};
 use events_command_handlers::{
     BulkDeleteEventsHandler, CreateEventHandler, DeleteEventHandler,
-    UpdateEventHandler,
+    MetadataSchemas, TimestampPolicy, UpdateEventHandler, WriteBufferConfig,
 };
 use events_commands::{
     BulkDeleteEventsCommand, CreateEventCommand, UpdateEventCommand,
 };
-use events_queries::{GetEventQuery, ListEventsQuery};
-use events_query_handlers::{GetEventQueryHandler, ListEventsQueryHandler};
+use events_dao::EventDao;
+use events_errors::EventError;
+use events_models::{EventId, EventTypeId};
+use events_queries::{
+    EventCursor, GetEventQuery, GetSessionEventsQuery, ListEventsPageQuery,
+    ListEventsQuery,
+};
+use events_query_handlers::{
+    CountEventsQueryHandler, GetEventQueryHandler,
+    GetSessionEventsQueryHandler, ListEventsPageQueryHandler,
+    ListEventsQueryHandler,
+};
 use events_responses::{BulkDeleteEventsResponse, EventResponse};
+use futures::StreamExt;
+use job_scheduler::BackgroundJobScheduler;
+use redis_connection::connection::RedisConnectionManager;
 use serde::Deserialize;
 use sql_connection::SqlConnect;
 use tracing::instrument;
-use utoipa::{IntoParams, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 use crate::{
-    background_jobs::BackgroundJobScheduler,
+    background_jobs::StatsRefresh,
+    extract::PathId,
+    rate_limit::EventRateLimiter,
     stats::{StatsService, get_stats},
 };
 
@@ -38,12 +66,33 @@ pub struct EventServices {
 
     pub get_event: GetEventQueryHandler,
     pub list_events: ListEventsQueryHandler,
+    pub list_events_page: ListEventsPageQueryHandler,
+    pub get_session_events: GetSessionEventsQueryHandler,
+    /// Total sent with unfiltered `GET /events` pages
+    pub count_events: CountEventsQueryHandler,
+    /// Streams `GET /events/export`; bypasses the cached query handlers
+    pub export_events: EventDao,
+    /// Serves `GET /events/recent` uncached, so tailing sees new events at
+    /// once
+    pub recent_events: EventDao,
     pub stats: StatsService,
+    pub stats_refresh: StatsRefresh,
+    /// Runs [`stats_refresh`] on its schedule once started
+    ///
+    /// [`stats_refresh`]: EventServices::stats_refresh
     pub background_jobs: BackgroundJobScheduler,
+    /// Per-user cap on `POST /event`; unlimited when `None`
+    pub rate_limiter: Option<EventRateLimiter>,
+    /// Events per page of `GET /events` when the request sets no `limit`
+    pub default_page_size: u64,
 }
 
 impl EventServices {
     pub fn new(db: SqlConnect) -> Self {
+        let stats_refresh = StatsRefresh::new(db.clone());
+        let background_jobs = BackgroundJobScheduler::new();
+        stats_refresh.register(&background_jobs);
+
         Self {
             create_event: CreateEventHandler::new(db.clone()),
             update_event: UpdateEventHandler::new(db.clone()),
@@ -51,10 +100,55 @@ impl EventServices {
             bulk_delete_events: BulkDeleteEventsHandler::new(db.clone()),
             get_event: GetEventQueryHandler::new(db.clone()),
             list_events: ListEventsQueryHandler::new(db.clone()),
+            list_events_page: ListEventsPageQueryHandler::new(db.clone()),
+            get_session_events: GetSessionEventsQueryHandler::new(db.clone()),
+            count_events: CountEventsQueryHandler::new(db.clone()),
+            export_events: EventDao::new(db.clone()),
+            recent_events: EventDao::new(db.clone()),
             stats: StatsService::new(db.clone()),
-            background_jobs: BackgroundJobScheduler::new(db.clone()),
+            stats_refresh,
+            background_jobs,
+            rate_limiter: None,
+            default_page_size: DEFAULT_PAGE_SIZE,
         }
     }
+
+    /// Events per page of `GET /events` when the request sets no `limit`
+    pub fn with_default_page_size(mut self, size: u64) -> Self {
+        self.default_page_size = size;
+        self
+    }
+
+    /// Allow each user at most `limit` new events per `window`
+    pub fn with_rate_limit(
+        mut self, redis: RedisConnectionManager, limit: u64, window: Duration,
+    ) -> Self {
+        self.rate_limiter = Some(EventRateLimiter::new(redis, limit, window));
+        self
+    }
+
+    /// Validate created and updated events' metadata against per-type
+    /// JSON Schemas
+    pub fn with_metadata_schemas(mut self, schemas: MetadataSchemas) -> Self {
+        self.update_event =
+            self.update_event.with_metadata_schemas(schemas.clone());
+        self.create_event = self.create_event.with_metadata_schemas(schemas);
+        self
+    }
+
+    /// Bound how far in the future or past created and updated events may
+    /// be stamped
+    pub fn with_timestamp_policy(mut self, policy: TimestampPolicy) -> Self {
+        self.update_event = self.update_event.with_timestamp_policy(policy);
+        self.create_event = self.create_event.with_timestamp_policy(policy);
+        self
+    }
+
+    /// Coalesce created events into batched inserts
+    pub fn with_write_buffer(mut self, config: WriteBufferConfig) -> Self {
+        self.create_event = self.create_event.with_write_buffer(config);
+        self
+    }
 }
 
 pub struct EventHandlers;
@@ -66,12 +160,43 @@ impl EventHandlers {
             .route("/", post(create_event))
             .route("/", delete(bulk_delete_events))
             .route("/stats", get(get_stats))
+            .route("/session/{session_id}", get(get_session_events))
+            .route("/export", get(export_events))
+            .route("/recent", get(recent_events))
             .route("/{id}", get(get_event))
             .route("/{id}", put(update_event))
             .route("/{id}", delete(delete_event))
     }
 }
 
+/// OpenAPI paths and schemas of the event endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_event,
+        update_event,
+        delete_event,
+        get_event,
+        list_events,
+        bulk_delete_events,
+        get_session_events,
+        export_events,
+        recent_events
+    ),
+    components(schemas(
+        EventResponse,
+        EventsListParams,
+        EventsDeleteParams,
+        SessionEventsParams,
+        ExportEventsParams,
+        RecentEventsParams,
+        CreateEventCommand,
+        UpdateEventCommand,
+        BulkDeleteEventsResponse
+    ))
+)]
+pub struct EventsApi;
+
 #[utoipa::path(
     put,
     path = "/event/{id}",
@@ -82,18 +207,18 @@ impl EventHandlers {
     responses(
         (status = 200, description = "Event updated successfully", body = EventResponse),
         (status = 404, description = "Event not found", body = common_errors::ApiErrorResponse),
-        (status = 400, description = "Invalid request data", body = common_errors::ApiErrorResponse),
+        (status = 400, description = "Invalid request data or non-numeric event id", body = common_errors::ApiErrorResponse),
         (status = 422, description = "Validation error", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
     tag = "events"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(event_id = %id))]
 pub async fn update_event(
-    State(services): State<EventServices>, Path(id): Path<i64>,
+    State(services): State<EventServices>, PathId(id): PathId<EventId>,
     Json(mut command): Json<UpdateEventCommand>,
 ) -> Result<Json<EventResponse>, AppError> {
-    command.event_id = id;
+    command.event_id = id.get();
     let result = services.update_event.execute(command).await?;
     Ok(Json(result))
 }
@@ -102,21 +227,30 @@ pub async fn update_event(
     delete,
     path = "/event/{id}",
     params(
-        ("id" = i64, Path, description = "Event ID")
+        ("id" = i64, Path, description = "Event ID"),
+        DeleteParams
     ),
     responses(
-        (status = 204, description = "Event deleted successfully"),
+        (status = 204, description = "Event deleted, or already absent with `idempotent=true`"),
+        (status = 400, description = "Non-numeric event id", body = common_errors::ApiErrorResponse),
         (status = 404, description = "Event not found", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
     tag = "events"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(event_id = %id))]
 pub async fn delete_event(
-    State(services): State<EventServices>, Path(id): Path<i64>,
+    State(services): State<EventServices>, PathId(id): PathId<EventId>,
+    Query(params): Query<DeleteParams>,
 ) -> Result<StatusCode, AppError> {
-    let command = events_commands::DeleteEventCommand { event_id: id };
-    services.delete_event.execute(command).await?;
+    let command = events_commands::DeleteEventCommand { event_id: id.get() };
+    match services.delete_event.execute(command).await {
+        Ok(()) => {}
+        Err(EventError::NotFound { .. }) if params.idempotent => {
+            tracing::debug!("Event {} already absent", id);
+        }
+        Err(e) => return Err(e.into()),
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -128,14 +262,15 @@ pub async fn delete_event(
     ),
     responses(
         (status = 200, description = "Event found", body = EventResponse),
+        (status = 400, description = "Non-numeric event id", body = common_errors::ApiErrorResponse),
         (status = 404, description = "Event not found", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
     tag = "events"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(event_id = %id))]
 pub async fn get_event(
-    State(services): State<EventServices>, Path(id): Path<i64>,
+    State(services): State<EventServices>, PathId(id): PathId<EventId>,
 ) -> Result<Json<EventResponse>, AppError> {
     let query = GetEventQuery { event_id: id };
     let event = services.get_event.execute(query).await?;
@@ -153,8 +288,64 @@ pub struct ListEventsParams {
     pub user_id: Option<i64>,
     pub event_type_id: Option<i32>,
     pub limit: Option<u64>,
+    /// Offset pagination, kept for existing clients; prefer `cursor`
     pub offset: Option<u64>,
+    /// Offset pagination, kept for existing clients; prefer `cursor`
     pub page: Option<u64>,
+    /// `X-Next-Cursor` of the previous response
+    pub cursor: Option<String>,
+}
+
+/// Response header carrying the cursor of the next keyset page
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Events per page of `GET /events/recent` when the request sets no
+/// `limit`
+pub const DEFAULT_RECENT_EVENTS: u64 = 50;
+
+/// Largest page `GET /events/recent` serves
+pub const MAX_RECENT_EVENTS: u64 = 200;
+
+/// Cursor taken from the query parameter `field`, as a validation error
+/// naming that parameter when it is malformed
+fn parse_cursor(
+    field: &str, raw: Option<&str>,
+) -> Result<Option<EventCursor>, AppError> {
+    raw.map(|raw| {
+        let cursor = Cursor::decode(raw)?;
+        let (timestamp, id) = EventDao::cursor_position(&cursor)?;
+        Ok(EventCursor { timestamp, id })
+    })
+    .transpose()
+    .map_err(|err: CursorError| {
+        AppError::validation(vec![FieldError::new(
+            field,
+            "invalid_cursor",
+            &err.to_string(),
+        )])
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct RecentEventsParams {
+    /// Maximum number of events (max: 200, default: 50)
+    pub limit: Option<u64>,
+    /// `X-Next-Cursor` of the previous response; only older events follow
+    pub before: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SessionEventsParams {
+    /// Maximum number of events (max: 1000, default: 1000)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ExportEventsParams {
+    /// Earliest event timestamp to include
+    pub from: Option<DateTime<Utc>>,
+    /// Events at or after this timestamp are left out
+    pub to: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
@@ -175,15 +366,22 @@ pub struct BulkDeleteParams {
         (status = 201, description = "Event created successfully", body = EventResponse),
         (status = 400, description = "Invalid request data", body = common_errors::ApiErrorResponse),
         (status = 422, description = "Validation error", body = common_errors::ApiErrorResponse),
+        (status = 429, description = "Too many events for this user; see Retry-After", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
     tag = "events"
 )]
-#[instrument(skip_all)]
+// No #[instrument]: the handler emits the create_event span
 pub async fn create_event(
     State(services): State<EventServices>,
     Json(command): Json<CreateEventCommand>,
 ) -> Result<(StatusCode, Json<EventResponse>), AppError> {
+    // The user id only exists in the body, so limiting happens here rather
+    // than in a middleware
+    if let Some(limiter) = &services.rate_limiter {
+        limiter.check(command.user_id).await?;
+    }
+
     let result = services.create_event.execute(command).await?;
     Ok((StatusCode::CREATED, Json(result)))
 }
@@ -195,7 +393,14 @@ pub async fn create_event(
         ListEventsParams
     ),
     responses(
-        (status = 200, description = "List of events", body = Vec<EventResponse>),
+        (status = 200, description = "List of events, newest first", body = Vec<EventResponse>,
+            headers(
+                ("x-next-cursor" = String, description = "Cursor of the next page; absent on the last page and with offset pagination"),
+                ("x-total-count" = i64, description = "Number of events in total, up to 30 seconds stale; only without `user_id` and `event_type_id` filters"),
+                ("etag" = String, description = "Collection tag; send it back in `If-None-Match`")
+            )
+        ),
+        (status = 304, description = "The page is unchanged since the `If-None-Match` tag"),
         (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
@@ -203,27 +408,199 @@ pub async fn create_event(
 )]
 #[instrument(skip_all)]
 pub async fn list_events(
-    State(services): State<EventServices>,
+    State(services): State<EventServices>, request_headers: HeaderMap,
     Query(params): Query<ListEventsParams>,
+) -> Result<Response, AppError> {
+    let limit = params
+        .limit
+        .unwrap_or(services.default_page_size)
+        .min(MAX_PAGE_SIZE);
+    let event_type_id = params.event_type_id.map(EventTypeId);
+
+    // Keyset pagination unless the client asks for an offset
+    let (events, next_cursor) = if params.offset.is_none()
+        && params.page.is_none()
+    {
+        let cursor = parse_cursor("cursor", params.cursor.as_deref())?;
+        let query = ListEventsPageQuery {
+            user_id: params.user_id,
+            event_type_id,
+            cursor,
+            limit,
+        };
+        let page = services.list_events_page.execute(query).await?;
+        (page.events, page.next_cursor)
+    }
+    else {
+        // A page too deep to address saturates and is rejected below
+        let page_offset = |p: u64| p.saturating_sub(1).saturating_mul(limit);
+        let offset = params.offset.or_else(|| params.page.map(page_offset));
+        let pagination = PaginationParams::validated(
+            Some(limit),
+            Some(offset.unwrap_or(0)),
+            MAX_PAGE_SIZE,
+        )?;
+
+        let query = ListEventsQuery {
+            user_id: params.user_id,
+            event_type_id,
+            limit: pagination.limit,
+            offset: pagination.offset,
+        };
+        (services.list_events.execute(query).await?, None)
+    };
+    // The cached count covers the whole table, so filtered pages go without
+    let total = if params.user_id.is_none() && event_type_id.is_none() {
+        Some(services.count_events.execute().await?)
+    }
+    else {
+        None
+    };
+
+    // The tag is derived from what the response carries, so no extra query
+    // runs for it: the rows on the page, the next cursor and the total.
+    // Metadata is included since editing an event changes no other field.
+    let etag = events.iter().fold(
+        CollectionEtag::new(CollectionVersion::default())
+            .scope(total)
+            .scope(&next_cursor),
+        |tag, event| {
+            tag.scope((
+                event.id,
+                event.user_id,
+                event.event_type_id,
+                &event.event_type,
+                event.timestamp,
+                &event.metadata,
+            ))
+        },
+    );
+    let mut headers = etag.headers();
+    if etag.matches(&request_headers) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    if let Some(next_cursor) = next_cursor
+        .as_deref()
+        .and_then(|cursor| HeaderValue::from_str(cursor).ok())
+    {
+        headers.insert(NEXT_CURSOR_HEADER, next_cursor);
+    }
+    if let Some(total) = total {
+        headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    }
+    Ok((headers, Json(events)).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/recent",
+    params(RecentEventsParams),
+    responses(
+        (status = 200, description = "Latest events of all users, newest first", body = Vec<EventResponse>,
+            headers(
+                ("x-next-cursor" = String, description = "Pass as `before` for the next older page; absent on the last page")
+            )
+        ),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 422, description = "Malformed `before` cursor", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "events"
+)]
+#[instrument(skip_all)]
+pub async fn recent_events(
+    State(services): State<EventServices>,
+    Query(params): Query<RecentEventsParams>,
+) -> Result<Response, AppError> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_RECENT_EVENTS)
+        .clamp(1, MAX_RECENT_EVENTS);
+    let before = parse_cursor("before", params.before.as_deref())?;
+
+    let page = services
+        .recent_events
+        .find_with_keyset(
+            None,
+            None,
+            before.map(|c| (c.timestamp, c.id)),
+            limit,
+        )
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(next_cursor) = page.next_cursor.and_then(|(timestamp, id)| {
+        HeaderValue::from_str(&EventDao::cursor_at(timestamp, id).encode())
+            .ok()
+    }) {
+        headers.insert(NEXT_CURSOR_HEADER, next_cursor);
+    }
+    Ok((headers, Json(page.items)).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/session/{session_id}",
+    params(
+        ("session_id" = String, Path, description = "Session ID from event metadata"),
+        SessionEventsParams
+    ),
+    responses(
+        (status = 200, description = "Events of the session, oldest first", body = Vec<EventResponse>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "events"
+)]
+#[instrument(skip_all, fields(session_id = %session_id))]
+pub async fn get_session_events(
+    State(services): State<EventServices>, Path(session_id): Path<String>,
+    Query(params): Query<SessionEventsParams>,
 ) -> Result<Json<Vec<EventResponse>>, AppError> {
-    let limit = params.limit.unwrap_or(100).min(1000);
-    let offset = params
-        .offset
-        .or_else(|| {
-            params.page.map(|p| if p > 0 { (p - 1) * limit } else { 0 })
-        })
-        .unwrap_or(0);
-
-    let query = ListEventsQuery {
-        user_id: params.user_id,
-        event_type_id: params.event_type_id,
-        limit: Some(limit),
-        offset: Some(offset),
+    let query = GetSessionEventsQuery {
+        session_id,
+        limit: params.limit.map(|limit| limit.min(1000)),
     };
-    let events = services.list_events.execute(query).await?;
+    let events = services.get_session_events.execute(query).await?;
     Ok(Json(events))
 }
 
+#[utoipa::path(
+    get,
+    path = "/events/export",
+    params(ExportEventsParams),
+    responses(
+        (status = 200, description = "Events as newline-delimited JSON, by ascending id", body = String, content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "events"
+)]
+#[instrument(skip_all)]
+pub async fn export_events(
+    State(services): State<EventServices>,
+    Query(params): Query<ExportEventsParams>,
+) -> Result<Response, AppError> {
+    let events = services
+        .export_events
+        .find_stream(params.from, params.to)
+        .await?;
+
+    // One line per row as it is read; an error mid-stream aborts the body
+    let lines = events.map(|event| {
+        let mut line = serde_json::to_vec(&event?)?;
+        line.push(b'\n');
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(line)
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
 #[utoipa::path(
     delete,
     path = "/events",
@@ -248,3 +625,224 @@ pub async fn bulk_delete_events(
     let result = services.bulk_delete_events.execute(command).await?;
     Ok(Json(result))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use redis_connection::cache_provider::CacheProvider;
+    use test_utils::*;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn create_request(user_id: i64) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/event")
+            .header("content-type", "application/json")
+            .body(Body::from(format!(
+                r#"{{"user_id": {user_id}, "event_type": "click"}}"#
+            )))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_missing_event_is_404_unless_idempotent() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let services = EventServices::new(create_sql_connect(&container));
+        let app = Router::new()
+            .route("/event/{id}", delete(delete_event))
+            .with_state(services);
+
+        for (uri, status) in [
+            ("/event/999999", StatusCode::NOT_FOUND),
+            ("/event/999999?idempotent=false", StatusCode::NOT_FOUND),
+            ("/event/999999?idempotent=true", StatusCode::NO_CONTENT),
+        ] {
+            let request = Request::builder()
+                .method("DELETE")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), status, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_events_etag_and_not_modified() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let redis_container = TestRedisContainer::new().await.unwrap();
+        redis_container.flush_db().await.unwrap();
+        CacheProvider::init_redis_static(redis_container.pool.clone());
+
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        create_test_event(&container, user_id, event_type_id, None)
+            .await
+            .unwrap();
+
+        let services = EventServices::new(create_sql_connect(&container));
+        let app = Router::new()
+            .route("/events", get(list_events))
+            .with_state(services);
+        let list = |if_none_match: Option<HeaderValue>| {
+            let mut request =
+                Request::builder().uri(format!("/events?user_id={user_id}"));
+            if let Some(tag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, tag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = list(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].clone();
+        // A filtered page doesn't get the whole table's count
+        assert!(!response.headers().contains_key(TOTAL_COUNT_HEADER));
+
+        let response = list(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        create_test_event(&container, user_id, event_type_id, None)
+            .await
+            .unwrap();
+        // The tag follows the page served, so it changes once the cached
+        // page makes way for one with the new event
+        let response = list(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        CacheProvider::invalidate_pattern("events:page:*")
+            .await
+            .unwrap();
+        let response = list(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag);
+
+        let request = Request::builder().uri("/events").body(Body::empty());
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        assert_eq!(response.headers()[TOTAL_COUNT_HEADER], "2");
+
+        // Bad parameters are rejected before anything reaches the database
+        container.pool.close();
+        let request = Request::builder()
+            .uri("/events?offset=99999999999")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_recent_events_page_back_in_time() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+
+        // Two events share a timestamp, so the id has to break the tie
+        for minutes_ago in [30, 10, 20, 10, 40] {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp) \
+                     VALUES ({user_id}, {event_type_id}, \
+                     DATE_TRUNC('minute', NOW()) - INTERVAL '{minutes_ago} \
+                     minutes')"
+                ))
+                .await
+                .unwrap();
+        }
+        let expected: Vec<i64> = container
+            .pool
+            .get()
+            .await
+            .unwrap()
+            .query(
+                "SELECT id FROM events ORDER BY timestamp DESC, id DESC",
+                &[],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let services = EventServices::new(create_sql_connect(&container));
+        let app = Router::new()
+            .route("/events/recent", get(recent_events))
+            .with_state(services);
+
+        let mut seen = Vec::new();
+        let mut uri = "/events/recent?limit=2".to_string();
+        loop {
+            let request = Request::builder().uri(&uri).body(Body::empty());
+            let response =
+                app.clone().oneshot(request.unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let next = response
+                .headers()
+                .get(NEXT_CURSOR_HEADER)
+                .map(|v| v.to_str().unwrap().to_string());
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let events: Vec<serde_json::Value> =
+                serde_json::from_slice(&body).unwrap();
+            assert!(events.len() <= 2);
+            seen.extend(events.iter().map(|e| e["id"].as_i64().unwrap()));
+
+            match next {
+                Some(cursor) => {
+                    uri = format!("/events/recent?limit=2&before={cursor}")
+                }
+                None => break,
+            }
+        }
+        assert_eq!(seen, expected);
+
+        let request = Request::builder()
+            .uri("/events/recent?before=yesterday")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_rate_limited_per_user() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let redis_container = TestRedisContainer::new().await.unwrap();
+        redis_container.flush_db().await.unwrap();
+
+        let user_id = create_test_user(&container).await.unwrap();
+        create_test_event_type_with_name(&container, "click")
+            .await
+            .unwrap();
+
+        let services = EventServices::new(create_sql_connect(&container))
+            .with_rate_limit(
+                RedisConnectionManager::new(redis_container.pool.clone()),
+                3,
+                Duration::from_secs(60),
+            );
+        let app = Router::new()
+            .route("/event", post(create_event))
+            .with_state(services);
+
+        for _ in 0..3 {
+            let response =
+                app.clone().oneshot(create_request(user_id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let response = app.oneshot(create_request(user_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .headers()
+            .get("retry-after")
+            .expect("Retry-After header should be set")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use common_errors::AppError;
+use redis_connection::{
+    AsyncCommands, cache_provider::CacheProvider,
+    connection::RedisConnectionManager,
+};
+use tracing::warn;
+
+/// Caps how many events a single user may submit per window.
+///
+/// Counts live in Redis under the namespaced `ratelimit:events:{user_id}`:
+/// the first event of a window creates the counter and sets its expiry,
+/// later ones only increment it. Redis failures let the event through
+/// rather than blocking ingestion.
+#[derive(Clone)]
+pub struct EventRateLimiter {
+    redis: RedisConnectionManager,
+    limit: u64,
+    window: Duration,
+}
+
+/// The caller exceeded its allowance; retry once the window resets
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl EventRateLimiter {
+    /// Redis expiry has second granularity, so shorter windows are raised
+    /// to one second
+    pub fn new(
+        redis: RedisConnectionManager, limit: u64, window: Duration,
+    ) -> Self {
+        Self {
+            redis,
+            limit,
+            window: window.max(Duration::from_secs(1)),
+        }
+    }
+
+    pub fn limit(&self) -> u64 { self.limit }
+
+    pub fn window(&self) -> Duration { self.window }
+
+    /// Count one event for `user_id`, rejecting it once the limit is hit
+    pub async fn check(&self, user_id: i64) -> Result<(), RateLimited> {
+        match self.hit(user_id).await {
+            Ok(None) => Ok(()),
+            Ok(Some(retry_after)) => Err(RateLimited { retry_after }),
+            Err(e) => {
+                warn!("Rate limiter unavailable, allowing event: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    async fn hit(
+        &self, user_id: i64,
+    ) -> Result<Option<Duration>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let key = CacheProvider::namespaced(
+            format!("ratelimit:events:{user_id}").into(),
+        );
+        let window_secs = self.window.as_secs().max(1);
+        let mut conn = self.redis.get_connection().await?;
+
+        let count: u64 = conn.incr(&key, 1).await?;
+        if count == 1 {
+            let _: () = conn.expire(&key, window_secs as i64).await?;
+        }
+        if count <= self.limit {
+            return Ok(None);
+        }
+
+        let ttl: i64 = conn.ttl(&key).await?;
+        let retry_after = if ttl > 0 {
+            ttl as u64
+        }
+        else {
+            // The expiry never landed; set it so the key can't stick
+            let _: () = conn.expire(&key, window_secs as i64).await?;
+            window_secs
+        };
+
+        Ok(Some(Duration::from_secs(retry_after)))
+    }
+}
+
+impl From<RateLimited> for AppError {
+    fn from(limited: RateLimited) -> Self {
+        AppError::too_many_requests(
+            "Too many events submitted for this user",
+            Some(limited.retry_after),
+        )
+    }
+}
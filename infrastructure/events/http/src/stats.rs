@@ -1,33 +1,262 @@
 use std::time::Duration;
 
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{FromRequestParts, Path, State},
+    http::{StatusCode, request::Parts},
     response::Json,
 };
-use chrono::{DateTime, Timelike, Utc};
+use chrono::{DateTime, DurationRound, Timelike, Utc};
 use common_errors::AppError;
-use events_dao::EventDao;
+use common_query::date::optional_utc;
+use events_dao::{
+    AnalyticsViewsDao, DEFAULT_CONVERSION_EVENT, EventDao, EventTypeDao,
+};
+use events_errors::EventError;
+use events_responses::{
+    ActiveUserCounts, HeatmapCell, ReferrerConversion, StatsSummary,
+    TimeSeriesPoint,
+};
 use redis_connection::{
     cache_key, cache_provider::CacheProvider, core::CacheTypeBind,
 };
-use serde::{Deserialize, Serialize};
-use sql_connection::SqlConnect;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sql_connection::{SqlConnect, pg_error_to_app_error};
 use tracing::instrument;
-use utoipa::{IntoParams, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 use crate::EventServices;
 
 cache_key!(StatsCacheKey::<StatsResponse> => "stats:{}"[cache_key: String]);
+cache_key!(StatsSummaryCacheKey::<Vec<StatsSummary>> => "stats:summary:{}"[cache_key: String]);
+cache_key!(ActiveUsersCacheKey::<ActiveUserCounts> => "stats:active_users:{}"[as_of: i64]);
+cache_key!(PagesCacheKey::<Vec<String>> => "stats:pages:{}"[cache_key: String]);
+
+/// How long `/stats/summary` responses are cached. Kept short so a manual
+/// refresh shows up quickly.
+pub const SUMMARY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long `/analytics/active-users` responses are cached
+pub const ACTIVE_USERS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long `/analytics/pages` responses are cached
+pub const PAGES_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Widest window an analytics query may cover unless overridden through
+/// `STATS_MAX_RANGE_DAYS`.
+pub const DEFAULT_MAX_RANGE_DAYS: i64 = 365;
+
+pub fn max_range() -> chrono::Duration {
+    let days = std::env::var("STATS_MAX_RANGE_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_MAX_RANGE_DAYS);
+    chrono::Duration::days(days)
+}
+
+/// Rejects ranges where `start` is not strictly before `end` or that span
+/// more than [`max_range`].
+pub fn validate_range(
+    start: DateTime<Utc>, end: DateTime<Utc>,
+) -> Result<(), AppError> {
+    validate_range_with_max(start, end, max_range())
+}
+
+pub fn validate_range_with_max(
+    start: DateTime<Utc>, end: DateTime<Utc>, max_span: chrono::Duration,
+) -> Result<(), AppError> {
+    if start >= end {
+        return Err(AppError::bad_request(
+            "INVALID_RANGE",
+            "The start of the range must be before its end",
+        ));
+    }
+
+    if end - start > max_span {
+        return Err(AppError::bad_request_with_details(
+            "INVALID_RANGE",
+            "The requested range is too wide",
+            &format!("Maximum range is {} days", max_span.num_days()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Query string extractor for analytics endpoints. Unlike axum's `Query`,
+/// a rejection names the offending parameter so clients can tell which
+/// value to fix.
+pub struct AnalyticsQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for AnalyticsQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts, _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer = serde_urlencoded::Deserializer::new(
+            url::form_urlencoded::parse(query.as_bytes()),
+        );
+
+        serde_path_to_error::deserialize(deserializer)
+            .map(Self)
+            .map_err(|err| {
+                let field = err.path().to_string();
+                let message = if field == "." {
+                    "Invalid query parameters provided".to_string()
+                }
+                else {
+                    format!("Invalid value for query parameter '{field}'")
+                };
+                AppError::bad_request_with_details(
+                    "INVALID_QUERY_PARAMS",
+                    &message,
+                    &format!(
+                        "{}. Expected date format: YYYY-MM-DD or RFC3339 \
+                         (e.g., 2025-01-01T00:00:00Z)",
+                        err.inner()
+                    ),
+                )
+            })
+    }
+}
+
+/// OpenAPI paths and schemas of the stats and analytics endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_stats,
+        get_stats_summary,
+        refresh_stats,
+        get_active_users,
+        get_referrer_conversions,
+        get_event_type_timeseries,
+        get_activity_heatmap,
+        get_pages
+    ),
+    components(schemas(
+        StatsQuery,
+        StatsResponse,
+        StatsSummaryQuery,
+        StatsSummary,
+        ActiveUsersQuery,
+        ActiveUserCounts,
+        ReferrerConversionsQuery,
+        ReferrerConversion,
+        EventTypeTimeseriesQuery,
+        TimeSeriesInterval,
+        TimeSeriesPoint,
+        HeatmapQuery,
+        HeatmapCell,
+        PagesQuery
+    ))
+)]
+pub struct StatsApi;
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
 pub struct StatsQuery {
+    #[serde(default, deserialize_with = "optional_utc")]
     pub from: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "optional_utc")]
     pub to: Option<DateTime<Utc>>,
     #[serde(rename = "type")]
     pub event_type: Option<String>,
 }
 
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct StatsSummaryQuery {
+    /// Earliest hour bucket to include; defaults to 24 hours ago
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub from: Option<DateTime<Utc>>,
+    /// Latest hour bucket to include; defaults to now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub to: Option<DateTime<Utc>>,
+    /// `event_type` or `page`; both when omitted
+    pub stat_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ActiveUsersQuery {
+    /// End of the trailing windows, truncated to the minute; defaults to
+    /// now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ReferrerConversionsQuery {
+    /// Start of the range; defaults to 7 days ago
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range; defaults to now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub to: Option<DateTime<Utc>>,
+    /// Event type that counts as a conversion; defaults to `order.paid`
+    pub conversion_event: Option<String>,
+    /// Number of referrers to return (max: 100, default: 20)
+    pub limit: Option<i64>,
+}
+
+/// Width of a time series bucket; buckets start on UTC hours or days
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeSeriesInterval {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl TimeSeriesInterval {
+    pub fn step(self) -> chrono::Duration {
+        match self {
+            Self::Hour => chrono::Duration::hours(1),
+            Self::Day => chrono::Duration::days(1),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct EventTypeTimeseriesQuery {
+    /// Start of the range; defaults to 24 hours before `end`
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub start: Option<DateTime<Utc>>,
+    /// Exclusive end of the range; defaults to now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub end: Option<DateTime<Utc>>,
+    /// Bucket width (default: hour)
+    #[serde(default)]
+    pub interval: TimeSeriesInterval,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct HeatmapQuery {
+    /// Start of the range; defaults to 7 days before `end`
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub start: Option<DateTime<Utc>>,
+    /// Exclusive end of the range; defaults to now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct PagesQuery {
+    /// Start of the range; defaults to 7 days before `end`
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub start: Option<DateTime<Utc>>,
+    /// Exclusive end of the range; defaults to now
+    #[serde(default, deserialize_with = "optional_utc")]
+    pub end: Option<DateTime<Utc>>,
+    /// Number of pages to return (max: 1000, default: 100)
+    pub limit: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StatsResponse {
     pub total_events: i64,
@@ -58,12 +287,16 @@ pub struct TimeRange {
 #[derive(Clone)]
 pub struct StatsService {
     event_dao: EventDao,
+    event_types: EventTypeDao,
+    analytics_views: AnalyticsViewsDao,
 }
 
 impl StatsService {
     pub fn new(db: SqlConnect) -> Self {
         Self {
-            event_dao: EventDao::new(db),
+            event_dao: EventDao::new(db.clone()),
+            event_types: EventTypeDao::new(db.clone()),
+            analytics_views: AnalyticsViewsDao::new(db),
         }
     }
 
@@ -75,6 +308,7 @@ impl StatsService {
             .from
             .unwrap_or_else(|| now - chrono::Duration::days(30));
         let to = query.to.unwrap_or(now);
+        validate_range(from, to)?;
 
         let backend = CacheProvider::get_backend();
         let cache_key = StatsCacheKey;
@@ -205,11 +439,7 @@ impl StatsService {
                 &[&event_type_param, &from_rounded, &to_rounded],
             )
             .await
-            .map_err(|e| {
-                AppError::internal_server_error(&format!(
-                    "Database query error: {e}"
-                ))
-            })?;
+            .map_err(|e| pg_error_to_app_error(&e))?;
 
         let mut total_events = 0i64;
         let mut total_unique_users = 0i64;
@@ -273,6 +503,167 @@ impl StatsService {
 
         Ok(stats_response)
     }
+
+    pub async fn get_summary(
+        &self, query: StatsSummaryQuery,
+    ) -> Result<Vec<StatsSummary>, AppError> {
+        let now = Utc::now();
+        let from = query
+            .from
+            .unwrap_or_else(|| now - chrono::Duration::hours(24));
+        let to = query.to.unwrap_or(now);
+        validate_range(from, to)?;
+
+        // Buckets are hourly, so minute precision is plenty for the key
+        let composite_key = format!(
+            "{}:{}:{}",
+            from.format("%Y-%m-%dT%H:%M"),
+            to.format("%Y-%m-%dT%H:%M"),
+            query.stat_type.as_deref().unwrap_or("all")
+        );
+        let backend = CacheProvider::get_backend();
+        let mut cache =
+            StatsSummaryCacheKey.bind_with(backend.clone(), &composite_key);
+
+        if let Ok(Some(rows)) = cache.try_get().await {
+            return Ok(rows);
+        }
+
+        let rows = self
+            .event_dao
+            .stats_summary(from, to, query.stat_type)
+            .await?;
+
+        let _ = cache
+            .set_with_expire::<()>(rows.clone(), SUMMARY_CACHE_TTL)
+            .await;
+
+        Ok(rows)
+    }
+
+    pub async fn referrer_conversions(
+        &self, query: ReferrerConversionsQuery,
+    ) -> Result<Vec<ReferrerConversion>, AppError> {
+        let to = query.to.unwrap_or_else(Utc::now);
+        let from = query.from.unwrap_or(to - chrono::Duration::days(7));
+        validate_range(from, to)?;
+
+        let conversion_event = query
+            .conversion_event
+            .as_deref()
+            .unwrap_or(DEFAULT_CONVERSION_EVENT);
+        let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+        Ok(self
+            .analytics_views
+            .referrer_conversions(from, to, conversion_event, limit)
+            .await?)
+    }
+
+    /// Bucketed counts of the event type called `name`; an unknown name is
+    /// a not-found error
+    pub async fn event_type_timeseries(
+        &self, name: &str, query: EventTypeTimeseriesQuery,
+    ) -> Result<Vec<TimeSeriesPoint>, AppError> {
+        let end = query.end.unwrap_or_else(Utc::now);
+        let start = query.start.unwrap_or(end - chrono::Duration::days(1));
+        validate_range(start, end)?;
+
+        let event_type = self
+            .event_types
+            .find_by_name(name)
+            .await
+            .map_err(EventError::from)?;
+
+        Ok(self
+            .analytics_views
+            .event_type_timeseries(
+                event_type.id,
+                start,
+                end,
+                query.interval.step(),
+            )
+            .await?)
+    }
+
+    pub async fn activity_heatmap(
+        &self, query: HeatmapQuery,
+    ) -> Result<Vec<HeatmapCell>, AppError> {
+        let end = query.end.unwrap_or_else(Utc::now);
+        let start = query.start.unwrap_or(end - chrono::Duration::days(7));
+        validate_range(start, end)?;
+
+        Ok(self.analytics_views.activity_heatmap(start, end).await?)
+    }
+
+    /// Distinct pages seen in the range, sorted. The bounds are truncated
+    /// to the minute, so requests within the same minute share a cache
+    /// entry.
+    pub async fn pages(
+        &self, query: PagesQuery,
+    ) -> Result<Vec<String>, AppError> {
+        let truncate = |at: DateTime<Utc>| {
+            at.duration_trunc(chrono::Duration::minutes(1))
+                .map_err(|e| {
+                    AppError::bad_request("INVALID_RANGE", &e.to_string())
+                })
+        };
+        let end = truncate(query.end.unwrap_or_else(Utc::now))?;
+        let start =
+            truncate(query.start.unwrap_or(end - chrono::Duration::days(7)))?;
+        validate_range(start, end)?;
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+        let composite_key =
+            format!("{}:{}:{}", start.timestamp(), end.timestamp(), limit);
+        let backend = CacheProvider::get_backend();
+        let mut cache = PagesCacheKey.bind_with(backend, &composite_key);
+
+        if let Ok(Some(pages)) = cache.try_get().await {
+            return Ok(pages);
+        }
+
+        let pages = self
+            .analytics_views
+            .distinct_pages(start, end, limit)
+            .await?;
+
+        let _ = cache
+            .set_with_expire::<()>(pages.clone(), PAGES_CACHE_TTL)
+            .await;
+
+        Ok(pages)
+    }
+
+    pub async fn active_users(
+        &self, query: ActiveUsersQuery,
+    ) -> Result<ActiveUserCounts, AppError> {
+        // Whole minutes, so requests within the same minute share a cache
+        // entry that is exact for its `as_of`
+        let as_of = query
+            .as_of
+            .unwrap_or_else(Utc::now)
+            .duration_trunc(chrono::Duration::minutes(1))
+            .map_err(|e| {
+                AppError::bad_request("INVALID_AS_OF", &e.to_string())
+            })?;
+
+        let backend = CacheProvider::get_backend();
+        let mut cache =
+            ActiveUsersCacheKey.bind_with(backend, &as_of.timestamp());
+
+        if let Ok(Some(counts)) = cache.try_get().await {
+            return Ok(counts);
+        }
+
+        let counts = self.analytics_views.active_users(as_of).await?;
+
+        let _ = cache
+            .set_with_expire::<()>(counts.clone(), ACTIVE_USERS_CACHE_TTL)
+            .await;
+
+        Ok(counts)
+    }
 }
 
 #[utoipa::path(
@@ -289,38 +680,136 @@ impl StatsService {
 #[instrument(skip_all)]
 pub async fn get_stats(
     State(services): State<EventServices>,
-    query_result: Result<
-        Query<StatsQuery>,
-        axum::extract::rejection::QueryRejection,
-    >,
+    AnalyticsQuery(query): AnalyticsQuery<StatsQuery>,
 ) -> Result<Json<StatsResponse>, AppError> {
-    let Query(query) = query_result.map_err(|rejection| {
-        match rejection {
-            axum::extract::rejection::QueryRejection::FailedToDeserializeQueryString(err) => {
-                AppError::bad_request_with_details(
-                    "INVALID_QUERY_PARAMS",
-                    "Invalid query parameters provided",
-                    &format!("Query parameter error: {err}. Expected date format: RFC3339 (e.g., 2025-01-01T00:00:00Z)")
-                )
-            }
-            _ => AppError::bad_request("INVALID_QUERY_PARAMS", "Invalid query parameters provided")
-        }
-    })?;
-
-    // Validate date range if both dates are provided
-    if let (Some(from), Some(to)) = (query.from, query.to) {
-        if from >= to {
-            return Err(AppError::bad_request(
-                "INVALID_DATE_RANGE",
-                "The 'from' date must be before the 'to' date",
-            ));
-        }
-    }
-
     let stats = services.stats.get_stats(query).await?;
     Ok(Json(stats))
 }
 
+#[utoipa::path(
+    get,
+    path = "/stats/summary",
+    params(StatsSummaryQuery),
+    responses(
+        (status = 200, description = "Rows of the stats_summary materialized view", body = Vec<StatsSummary>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all)]
+pub async fn get_stats_summary(
+    State(services): State<EventServices>,
+    AnalyticsQuery(query): AnalyticsQuery<StatsSummaryQuery>,
+) -> Result<Json<Vec<StatsSummary>>, AppError> {
+    let rows = services.stats.get_summary(query).await?;
+    Ok(Json(rows))
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/active-users",
+    params(ActiveUsersQuery),
+    responses(
+        (status = 200, description = "Distinct active users over the trailing day, week and 30 days, and DAU/MAU stickiness", body = ActiveUserCounts),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all)]
+pub async fn get_active_users(
+    State(services): State<EventServices>,
+    AnalyticsQuery(query): AnalyticsQuery<ActiveUsersQuery>,
+) -> Result<Json<ActiveUserCounts>, AppError> {
+    let counts = services.stats.active_users(query).await?;
+    Ok(Json(counts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/referrers/conversions",
+    params(ReferrerConversionsQuery),
+    responses(
+        (status = 200, description = "Sessions per referrer and how many converted", body = Vec<ReferrerConversion>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all)]
+pub async fn get_referrer_conversions(
+    State(services): State<EventServices>,
+    AnalyticsQuery(query): AnalyticsQuery<ReferrerConversionsQuery>,
+) -> Result<Json<Vec<ReferrerConversion>>, AppError> {
+    let conversions = services.stats.referrer_conversions(query).await?;
+    Ok(Json(conversions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/event-types/{name}/timeseries",
+    params(
+        ("name" = String, Path, description = "Event type name"),
+        EventTypeTimeseriesQuery
+    ),
+    responses(
+        (status = 200, description = "One point per bucket in the range, empty buckets included", body = Vec<TimeSeriesPoint>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 404, description = "Unknown event type", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all, fields(event_type = %name))]
+pub async fn get_event_type_timeseries(
+    State(services): State<EventServices>, Path(name): Path<String>,
+    AnalyticsQuery(query): AnalyticsQuery<EventTypeTimeseriesQuery>,
+) -> Result<Json<Vec<TimeSeriesPoint>>, AppError> {
+    let points = services.stats.event_type_timeseries(&name, query).await?;
+    Ok(Json(points))
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/heatmap",
+    params(HeatmapQuery),
+    responses(
+        (status = 200, description = "Event counts for all 168 UTC day-of-week and hour-of-day cells", body = Vec<HeatmapCell>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all)]
+pub async fn get_activity_heatmap(
+    State(services): State<EventServices>,
+    AnalyticsQuery(query): AnalyticsQuery<HeatmapQuery>,
+) -> Result<Json<Vec<HeatmapCell>>, AppError> {
+    let cells = services.stats.activity_heatmap(query).await?;
+    Ok(Json(cells))
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/pages",
+    params(PagesQuery),
+    responses(
+        (status = 200, description = "Distinct pages seen in the range, sorted", body = Vec<String>),
+        (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "stats"
+)]
+#[instrument(skip_all)]
+pub async fn get_pages(
+    State(services): State<EventServices>,
+    AnalyticsQuery(query): AnalyticsQuery<PagesQuery>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let pages = services.stats.pages(query).await?;
+    Ok(Json(pages))
+}
+
 #[utoipa::path(
     post,
     path = "/stats/refresh",
@@ -334,6 +823,281 @@ pub async fn get_stats(
 pub async fn refresh_stats(
     State(services): State<EventServices>,
 ) -> Result<StatusCode, AppError> {
-    services.background_jobs.refresh_stats_now().await?;
+    services.stats_refresh.run().await?;
     Ok(StatusCode::OK)
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::Request,
+        routing::get,
+    };
+    use chrono::TimeZone;
+    use test_utils::*;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_validate_range_accepts_ordered_range() {
+        assert!(validate_range(at(2025, 1, 1), at(2025, 2, 1)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_range_rejects_swapped_range() {
+        let err = validate_range(at(2025, 2, 1), at(2025, 1, 1)).unwrap_err();
+        match err {
+            AppError::BadRequest { code, .. } => {
+                assert_eq!(code, "INVALID_RANGE")
+            }
+            other => panic!("Expected BadRequest, got {other:?}"),
+        }
+
+        let same = at(2025, 1, 1);
+        assert!(validate_range(same, same).is_err());
+    }
+
+    #[test]
+    fn test_validate_range_rejects_overlong_span() {
+        let err = validate_range_with_max(
+            at(2023, 1, 1),
+            at(2025, 1, 1),
+            chrono::Duration::days(DEFAULT_MAX_RANGE_DAYS),
+        )
+        .unwrap_err();
+        match err {
+            AppError::BadRequest { code, details, .. } => {
+                assert_eq!(code, "INVALID_RANGE");
+                assert!(details.unwrap().contains("365 days"));
+            }
+            other => panic!("Expected BadRequest, got {other:?}"),
+        }
+    }
+
+    async fn extract(uri: &str) -> Result<StatsQuery, AppError> {
+        let (mut parts, _) =
+            Request::builder().uri(uri).body(()).unwrap().into_parts();
+        AnalyticsQuery::<StatsQuery>::from_request_parts(&mut parts, &())
+            .await
+            .map(|AnalyticsQuery(query)| query)
+    }
+
+    #[tokio::test]
+    async fn test_analytics_query_parses_valid_params() {
+        let query = extract("/stats?from=2025-01-01T00:00:00Z&type=click")
+            .await
+            .unwrap();
+        assert_eq!(query.from, Some(at(2025, 1, 1)));
+        assert_eq!(query.event_type.as_deref(), Some("click"));
+    }
+
+    #[tokio::test]
+    async fn test_analytics_query_takes_date_only_as_utc_midnight() {
+        let query = extract("/stats?from=2025-01-01&to=2025-01-02T12:00:00Z")
+            .await
+            .unwrap();
+        assert_eq!(query.from, Some(at(2025, 1, 1)));
+        assert_eq!(
+            query.to,
+            Some(at(2025, 1, 2) + chrono::Duration::hours(12))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_analytics_query_names_malformed_field() {
+        let err = extract("/stats?from=not-a-date").await.unwrap_err();
+        match &err {
+            AppError::BadRequest { code, message, .. } => {
+                assert_eq!(code, "INVALID_QUERY_PARAMS");
+                assert!(message.contains("'from'"), "{message}");
+            }
+            other => panic!("Expected BadRequest, got {other:?}"),
+        }
+
+        let response = axum::response::IntoResponse::into_response(err);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_stats_summary_returns_refreshed_rows() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let redis_container = TestRedisContainer::new().await.unwrap();
+        redis_container.flush_db().await.unwrap();
+        CacheProvider::init_redis_static(redis_container.pool.clone());
+
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id =
+            create_test_event_type_with_name(&container, "page_view")
+                .await
+                .unwrap();
+        container
+            .execute_sql(&format!(
+                "INSERT INTO events (user_id, event_type_id, metadata) \
+                 VALUES ({user_id}, {event_type_id}, '{{\"page\": \
+                 \"/home\"}}')"
+            ))
+            .await
+            .unwrap();
+
+        let services =
+            crate::EventServices::new(create_sql_connect(&container));
+        services.stats_refresh.run().await.unwrap();
+
+        let app = Router::new()
+            .route("/stats/summary", get(get_stats_summary))
+            .with_state(services);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/stats/summary")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let rows: Vec<StatsSummary> = serde_json::from_slice(&body).unwrap();
+
+        let event_row = rows
+            .iter()
+            .find(|row| row.stat_type == "event_type")
+            .expect("event type row");
+        assert_eq!(event_row.key_name, "page_view");
+        assert_eq!(event_row.total_count, Some(1));
+        assert_eq!(event_row.unique_users, Some(1));
+        assert_eq!(event_row.page_count, None);
+
+        let page_row = rows
+            .iter()
+            .find(|row| row.stat_type == "page")
+            .expect("page row");
+        assert_eq!(page_row.key_name, "/home");
+        assert_eq!(page_row.page_count, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_event_type_timeseries() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id =
+            create_test_event_type_with_name(&container, "page_view")
+                .await
+                .unwrap();
+        for timestamp in [
+            "2025-01-01T10:15:00Z",
+            "2025-01-01T10:45:00Z",
+            "2025-01-01T12:59:59Z",
+            "2025-01-01T14:00:00Z",
+        ] {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp) \
+                     VALUES ({user_id}, {event_type_id}, '{timestamp}')"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let app = Router::new()
+            .route(
+                "/analytics/event-types/{name}/timeseries",
+                get(get_event_type_timeseries),
+            )
+            .with_state(crate::EventServices::new(create_sql_connect(
+                &container,
+            )));
+        let range = "start=2025-01-01T10:00:00Z&end=2025-01-01T14:00:00Z";
+        let call = |name: &str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri(format!(
+                        "/analytics/event-types/{name}/timeseries?{range}"
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let missing = call("no_such_type").await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let response = call("page_view").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let points: Vec<TimeSeriesPoint> =
+            serde_json::from_slice(&body).unwrap();
+        let hour = |h| Utc.with_ymd_and_hms(2025, 1, 1, h, 0, 0).unwrap();
+        assert_eq!(
+            points,
+            [(10, 2), (11, 0), (12, 1), (13, 0)].map(|(h, count)| {
+                TimeSeriesPoint {
+                    bucket: hour(h),
+                    count,
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_activity_heatmap_fills_every_cell() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        // 2025-01-01 is a Wednesday
+        for timestamp in [
+            "2025-01-01T10:15:00Z",
+            "2025-01-01T10:45:00Z",
+            "2025-01-04T23:30:00Z",
+            "2025-01-09T10:00:00Z",
+        ] {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO events (user_id, event_type_id, timestamp) \
+                     VALUES ({user_id}, {event_type_id}, '{timestamp}')"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/analytics/heatmap", get(get_activity_heatmap))
+            .with_state(crate::EventServices::new(create_sql_connect(
+                &container,
+            )));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(
+                        "/analytics/heatmap?start=2025-01-01T00:00:00Z&\
+                         end=2025-01-08T00:00:00Z",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let cells: Vec<HeatmapCell> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(cells.len(), 168);
+        let count = |dow: u8, hour: u8| {
+            let cell = &cells[usize::from(dow) * 24 + usize::from(hour)];
+            assert_eq!((cell.dow, cell.hour), (dow, hour));
+            cell.count
+        };
+        assert_eq!(count(3, 10), 2);
+        assert_eq!(count(6, 23), 1);
+        assert_eq!(count(4, 10), 0);
+        assert_eq!(cells.iter().map(|cell| cell.count).sum::<i64>(), 3);
+    }
+}
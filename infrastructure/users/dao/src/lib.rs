@@ -1,13 +1,18 @@
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use common_query::{CollectionVersion, OrderBy, SortDirection};
 use dao_utils::{
+    cursor::Cursor,
+    filter::{FilterBuilder, Op},
     pagination::{CursorPagination, PaginationParams, create_param_refs},
     query_helpers::{CursorResult, count_query},
+    update::UpdateBuilder,
 };
 use database_traits::dao::GenericDao;
-use sql_connection::SqlConnect;
+use sql_connection::{GenericClient, SqlConnect, Transaction};
+use tokio_postgres::error::SqlState;
 use tracing::instrument;
-use user_commands::{CreateUserCommand, UpdateUserCommand};
+use user_commands::{CreateUserCommand, UpdateUserCommand, UserChangeSet};
 use user_errors::UserError;
 use user_models::User;
 
@@ -27,7 +32,9 @@ impl UserDao {
     ) -> Result<Option<User>, UserError> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare("SELECT id, name, created_at FROM users WHERE name = $1")
+            .prepare_cached(
+                "SELECT id, name, created_at FROM users WHERE name = $1",
+            )
             .await?;
         let rows = client.query(&stmt, &[&name]).await?;
 
@@ -35,6 +42,181 @@ impl UserDao {
 
         Ok(user)
     }
+
+    /// The user called `name`, inserting it first if there is none, and
+    /// whether this call inserted it. The no-op update on conflict makes
+    /// `RETURNING` yield the existing row, so concurrent callers all get the
+    /// same user.
+    #[instrument(skip(self))]
+    pub async fn find_or_create_by_name(
+        &self, name: &str,
+    ) -> Result<(User, bool), UserError> {
+        let client = self.db.get_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "INSERT INTO users (name, created_at) VALUES ($1, $2)
+                 ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name
+                 RETURNING id, name, created_at, xmax = 0 AS inserted",
+            )
+            .await?;
+        let row = client.query_one(&stmt, &[&name, &Utc::now()]).await?;
+
+        Ok((self.map_row(&row), row.get(3)))
+    }
+
+    /// Write only the fields present in `changes`. An empty change set
+    /// writes nothing and returns the current row.
+    #[instrument(skip(self))]
+    pub async fn update_partial(
+        &self, id: i64, changes: UserChangeSet,
+    ) -> Result<User, UserError> {
+        let mut update = UpdateBuilder::new();
+        update.set_opt("name", changes.name);
+        if update.is_empty() {
+            return self.find_by_id(id).await;
+        }
+
+        let id_param = update.bind(id);
+        let sql = format!(
+            "UPDATE users{} WHERE id = {id_param} RETURNING id, name, \
+             created_at",
+            update.set_clause()
+        );
+        let client = self.db.get_client().await?;
+        let rows =
+            client.query(&sql, &update.params()).await.map_err(|e| {
+                if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                    UserError::NameExists
+                }
+                else {
+                    UserError::Database(e)
+                }
+            })?;
+
+        rows.first()
+            .map(|row| self.map_row(row))
+            .ok_or(UserError::NotFound { user_id: id })
+    }
+
+    /// Insert every name in one statement, skipping names that are already
+    /// taken. Only the inserted users are returned.
+    #[instrument(skip_all, fields(names = names.len()))]
+    pub async fn create_many(
+        &self, names: &[String],
+    ) -> Result<Vec<User>, UserError> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let client = self.db.get_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "INSERT INTO users (name, created_at)
+                 SELECT name, $2 FROM unnest($1::text[]) AS t(name)
+                 ON CONFLICT (name) DO NOTHING
+                 RETURNING id, name, created_at",
+            )
+            .await?;
+        let rows = client.query(&stmt, &[&names, &Utc::now()]).await?;
+
+        Ok(rows.iter().map(|row| self.map_row(row)).collect())
+    }
+
+    /// Number of events recorded for `user_id`
+    #[instrument(skip(self))]
+    pub async fn count_events(&self, user_id: i64) -> Result<i64, UserError> {
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached("SELECT COUNT(*) FROM events WHERE user_id = $1")
+            .await?;
+        let row = client.query_one(&stmt, &[&user_id]).await?;
+
+        Ok(row.get(0))
+    }
+
+    /// Number of users created in `[start, end)`, e.g. new users per week
+    #[instrument(skip(self))]
+    pub async fn count_created_between(
+        &self, start: DateTime<Utc>, end: DateTime<Utc>,
+    ) -> Result<i64, UserError> {
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT COUNT(*) FROM users
+                 WHERE created_at >= $1 AND created_at < $2",
+            )
+            .await?;
+        let row = client.query_one(&stmt, &[&start, &end]).await?;
+
+        Ok(row.get(0))
+    }
+
+    /// The users among `ids`, in no particular order. Unknown ids are
+    /// skipped.
+    #[instrument(skip_all, fields(ids = ids.len()))]
+    pub async fn find_by_ids(
+        &self, ids: &[i64],
+    ) -> Result<Vec<User>, UserError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT id, name, created_at FROM users WHERE id = ANY($1)",
+            )
+            .await?;
+        let rows = client.query(&stmt, &[&ids]).await?;
+
+        Ok(rows.iter().map(|row| self.map_row(row)).collect())
+    }
+
+    /// Ids of the `limit` users with the most events since `since`, most
+    /// active first
+    #[instrument(skip(self))]
+    pub async fn most_active_ids(
+        &self, limit: i64, since: DateTime<Utc>,
+    ) -> Result<Vec<i64>, UserError> {
+        let client = self.db.get_read_client().await?;
+        let stmt = client
+            .prepare_cached(
+                "SELECT user_id FROM events WHERE timestamp >= $1
+                 GROUP BY user_id ORDER BY COUNT(*) DESC, user_id
+                 LIMIT $2",
+            )
+            .await?;
+        let rows = client.query(&stmt, &[&since, &limit]).await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// Count and newest `created_at` of the users matching the list
+    /// filters, used to tag list responses
+    #[instrument(skip(self))]
+    pub async fn collection_version(
+        &self, created_after: Option<DateTime<Utc>>,
+        created_before: Option<DateTime<Utc>>,
+    ) -> Result<CollectionVersion, UserError> {
+        let client = self.db.get_read_client().await?;
+
+        let mut filter = FilterBuilder::new();
+        filter.cmp_opt("created_at", Op::Ge, created_after).cmp_opt(
+            "created_at",
+            Op::Lt,
+            created_before,
+        );
+        let sql = format!(
+            "SELECT COUNT(*), MAX(created_at) FROM users{}",
+            filter.where_clause()
+        );
+        let row = client.query_one(&sql, &filter.params()).await?;
+
+        Ok(CollectionVersion {
+            count: row.get(0),
+            last_modified: row.get(1),
+        })
+    }
 }
 
 #[async_trait]
@@ -50,8 +232,9 @@ impl GenericDao for UserDao {
         &self, id: Self::ID,
     ) -> Result<Self::Response, Self::Error> {
         let client = self.db.get_read_client().await?;
-        let stmt =
-            client.prepare("SELECT * FROM users WHERE id = $1").await?;
+        let stmt = client
+            .prepare_cached("SELECT * FROM users WHERE id = $1")
+            .await?;
         let rows = client.query(&stmt, &[&id]).await?;
 
         let user = rows
@@ -65,7 +248,7 @@ impl GenericDao for UserDao {
     async fn all(&self) -> Result<Vec<Self::Response>, Self::Error> {
         let client = self.db.get_read_client().await?;
         let stmt = client
-            .prepare("SELECT * FROM users ORDER BY name ASC")
+            .prepare_cached("SELECT * FROM users ORDER BY name ASC")
             .await?;
         let rows = client.query(&stmt, &[]).await?;
 
@@ -78,113 +261,20 @@ impl GenericDao for UserDao {
         &self, req: Self::CreateRequest,
     ) -> Result<Self::Response, Self::Error> {
         let client = self.db.get_client().await?;
-        let created_at = Utc::now();
-
-        let stmt = client
-            .prepare(
-                "WITH name_check AS (
-                     SELECT EXISTS(SELECT 1 FROM users WHERE name = $1) as \
-                 name_exists
-                 ),
-                 inserted AS (
-                     INSERT INTO users (name, created_at) 
-                     SELECT $1, $2
-                     WHERE NOT EXISTS(SELECT 1 FROM name_check WHERE \
-                 name_exists = true)
-                     RETURNING id, name, created_at
-                 )
-                 SELECT i.id, i.name, i.created_at, nc.name_exists
-                 FROM name_check nc
-                 LEFT JOIN inserted i ON nc.name_exists = false",
-            )
-            .await?;
-
-        let rows = client.query(&stmt, &[&req.name, &created_at]).await?;
-
-        if let Some(row) = rows.first() {
-            let name_exists: bool = row.get(3);
-            if name_exists {
-                return Err(UserError::NameExists);
-            }
-
-            let user = User {
-                id: row.get(0),
-                name: row.get(1),
-                created_at: row.get(2),
-            };
-            Ok(user)
-        }
-        else {
-            Err(UserError::InternalError(
-                "Failed to create user".to_string(),
-            ))
-        }
+        insert_user(&*client, req).await
     }
 
     async fn update(
         &self, id: Self::ID, req: Self::UpdateRequest,
     ) -> Result<Self::Response, Self::Error> {
-        let client = self.db.get_client().await?;
-
-        match &req.name {
-            Some(new_name) => {
-                let stmt = client
-                    .prepare(
-                        "WITH conflict_check AS (
-                             SELECT CASE 
-                                 WHEN NOT EXISTS(SELECT 1 FROM users WHERE \
-                         id = $2) THEN 'not_found'::text
-                                 WHEN EXISTS(SELECT 1 FROM users WHERE name \
-                         = $1 AND id != $2) THEN 'name_exists'::text
-                                 ELSE 'ok'::text
-                             END as status
-                         ),
-                         updated AS (
-                             UPDATE users 
-                             SET name = $1 
-                             WHERE id = $2 
-                             AND (SELECT status FROM conflict_check) = 'ok'
-                             RETURNING id, name, created_at
-                         )
-                         SELECT u.id, u.name, u.created_at, c.status
-                         FROM conflict_check c
-                         LEFT JOIN updated u ON c.status = 'ok'",
-                    )
-                    .await?;
-
-                let rows = client.query(&stmt, &[new_name, &id]).await?;
-
-                if let Some(row) = rows.first() {
-                    let status: String = row.get(3);
-                    match status.as_str() {
-                        "not_found" => {
-                            Err(UserError::NotFound { user_id: id })
-                        }
-                        "name_exists" => Err(UserError::NameExists),
-                        "ok" => {
-                            let user = self.map_row(row);
-                            Ok(user)
-                        }
-                        _ => {
-                            Err(UserError::InternalError(
-                                "Unexpected response structure".to_string(),
-                            ))
-                        }
-                    }
-                }
-                else {
-                    Err(UserError::NotFound { user_id: id })
-                }
-            }
-            None => self.find_by_id(id).await,
-        }
+        self.update_partial(id, req.into()).await
     }
 
     async fn delete(&self, id: Self::ID) -> Result<(), Self::Error> {
         let client = self.db.get_client().await?;
 
         let stmt = client
-            .prepare("DELETE FROM users WHERE id = $1 RETURNING id")
+            .prepare_cached("DELETE FROM users WHERE id = $1 RETURNING id")
             .await?;
         let rows = client.execute(&stmt, &[&id]).await?;
 
@@ -219,10 +309,10 @@ impl UserDao {
         let pagination = PaginationParams::new(limit, offset);
         let (sql, params) = pagination.build_query_parts(
             "SELECT id, name, created_at FROM users",
-            "ORDER BY name ASC",
+            "ORDER BY name ASC, id ASC",
         );
 
-        let stmt = client.prepare(&sql).await?;
+        let stmt = client.prepare_cached(&sql).await?;
         let param_refs = create_param_refs(&params);
         let rows = client.query(&stmt, &param_refs).await?;
         let users = rows.iter().map(|row| self.map_row(row)).collect();
@@ -230,30 +320,72 @@ impl UserDao {
         Ok(users)
     }
 
+    #[instrument(skip_all)]
+    pub async fn find_filtered(
+        &self, created_after: Option<DateTime<Utc>>,
+        created_before: Option<DateTime<Utc>>, order: OrderBy,
+        limit: Option<u64>, offset: Option<u64>,
+    ) -> Result<Vec<User>, UserError> {
+        let client = self.db.get_read_client().await?;
+
+        let mut filter = FilterBuilder::new();
+        filter.cmp_opt("created_at", Op::Ge, created_after).cmp_opt(
+            "created_at",
+            Op::Lt,
+            created_before,
+        );
+
+        let query = format!(
+            "SELECT id, name, created_at FROM users{}",
+            filter.where_clause()
+        );
+        let pagination = PaginationParams::new(limit, offset);
+        let (sql, page_params) = pagination.build_query_with_existing_params(
+            &query,
+            &order.to_sql(),
+            filter.param_count(),
+        );
+        for param in page_params {
+            filter.bind(param);
+        }
+
+        let stmt = client.prepare_cached(&sql).await?;
+        let rows = client.query(&stmt, &filter.params()).await?;
+
+        Ok(rows.iter().map(|row| self.map_row(row)).collect())
+    }
+
+    /// Page of users in `name, id` order starting after `cursor`. The id
+    /// breaks ties between equal names so no row is skipped or repeated at
+    /// a page boundary. The cursor's sort key is `[name, id]`, read
+    /// ascending.
     #[instrument(skip_all)]
     pub async fn find_with_cursor(
-        &self, cursor: Option<String>, limit: u64,
-    ) -> Result<CursorResult<User, String>, UserError> {
+        &self, cursor: Option<Cursor>, limit: u64,
+    ) -> Result<CursorResult<User, Cursor>, UserError> {
+        let after = cursor
+            .as_ref()
+            .map(|c| c.sort_key::<(String, i64)>(SortDirection::Asc))
+            .transpose()
+            .map_err(|e| UserError::Validation(vec![e.into()]))?;
         let client = self.db.get_read_client().await?;
-        let pagination = CursorPagination::new(cursor.clone(), limit);
+        let pagination = CursorPagination::new(cursor, limit);
         let limit_plus_one = pagination.limit_plus_one();
 
-        let rows = match cursor {
-            Some(cursor_name) => {
+        let rows = match after {
+            Some((name, id)) => {
                 let sql = "SELECT id, name, created_at FROM users 
-                          WHERE name > $1 
-                          ORDER BY name ASC 
-                          LIMIT $2";
-                let stmt = client.prepare(sql).await?;
-                client
-                    .query(&stmt, &[&cursor_name, &limit_plus_one])
-                    .await?
+                          WHERE (name, id) > ($1, $2) 
+                          ORDER BY name ASC, id ASC 
+                          LIMIT $3";
+                let stmt = client.prepare_cached(sql).await?;
+                client.query(&stmt, &[&name, &id, &limit_plus_one]).await?
             }
             None => {
                 let sql = "SELECT id, name, created_at FROM users 
-                          ORDER BY name ASC 
+                          ORDER BY name ASC, id ASC 
                           LIMIT $1";
-                let stmt = client.prepare(sql).await?;
+                let stmt = client.prepare_cached(sql).await?;
                 client.query(&stmt, &[&limit_plus_one]).await?
             }
         };
@@ -265,7 +397,12 @@ impl UserDao {
             .collect();
 
         let next_cursor = if rows.len() > pagination.limit as usize {
-            users.last().map(|u| u.name.clone())
+            users.last().map(|u| {
+                Cursor::new(
+                    vec![u.name.clone().into(), u.id.into()],
+                    SortDirection::Asc,
+                )
+            })
         }
         else {
             None
@@ -275,11 +412,98 @@ impl UserDao {
     }
 }
 
+/// User writes that run inside a transaction opened with
+/// [`SqlConnect::with_transaction`]
+pub struct UserTxDao<'t> {
+    tx: &'t Transaction<'t>,
+}
+
+impl UserDao {
+    /// Bind user writes to `tx`, so they commit or roll back with it
+    pub fn in_transaction<'t>(tx: &'t Transaction<'t>) -> UserTxDao<'t> {
+        UserTxDao { tx }
+    }
+}
+
+impl UserTxDao<'_> {
+    #[instrument(skip_all)]
+    pub async fn create(
+        &self, req: CreateUserCommand,
+    ) -> Result<User, UserError> {
+        insert_user(self.tx, req).await
+    }
+}
+
+/// Insert a user unless the name is taken, on a pooled connection or in a
+/// transaction
+async fn insert_user(
+    client: &impl GenericClient, req: CreateUserCommand,
+) -> Result<User, UserError> {
+    let created_at = Utc::now();
+
+    let stmt = client
+        .prepare_cached(
+            "WITH name_check AS (
+                 SELECT EXISTS(SELECT 1 FROM users WHERE name = $1) as \
+             name_exists
+             ),
+             inserted AS (
+                 INSERT INTO users (name, created_at) 
+                 SELECT $1, $2
+                 WHERE NOT EXISTS(SELECT 1 FROM name_check WHERE name_exists \
+             = true)
+                 RETURNING id, name, created_at
+             )
+             SELECT i.id, i.name, i.created_at, nc.name_exists
+             FROM name_check nc
+             LEFT JOIN inserted i ON nc.name_exists = false",
+        )
+        .await?;
+
+    // The name check and the insert aren't atomic: a concurrent insert of
+    // the same name only shows up as a unique index violation
+    let rows = client
+        .query(&stmt, &[&req.name, &created_at])
+        .await
+        .map_err(|e| {
+            if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                UserError::NameExists
+            }
+            else {
+                UserError::Database(e)
+            }
+        })?;
+
+    if let Some(row) = rows.first() {
+        let name_exists: bool = row.get(3);
+        if name_exists {
+            return Err(UserError::NameExists);
+        }
+
+        let user = User {
+            id: row.get(0),
+            name: row.get(1),
+            created_at: row.get(2),
+        };
+        Ok(user)
+    }
+    else {
+        Err(UserError::InternalError(
+            "Failed to create user".to_string(),
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use common_query::SortDirection;
+    use dao_utils::cursor::Cursor;
     use database_traits::dao::GenericDao;
-    use test_utils::*;
-    use user_commands::{CreateUserCommand, UpdateUserCommand};
+    use test_utils::{dao_harness::GenericDaoSuite, *};
+    use user_commands::{
+        CreateUserCommand, UpdateUserCommand, UserChangeSet,
+    };
+    use user_queries::UserSort;
 
     use crate::{UserDao, UserError};
 
@@ -293,6 +517,190 @@ mod tests {
         }
     }
 
+    test_generic_dao!(
+        test_generic_dao_suite,
+        |container| UserDao::new(create_sql_connect(container)),
+        GenericDaoSuite::<UserDao> {
+            create: create_test_user("suite_user"),
+            update: UpdateUserCommand {
+                user_id: 0,
+                name: Some("suite_user_renamed".to_string()),
+            },
+            id_of: |user| user.id,
+            assert_updated: |user| {
+                assert_eq!(user.name, "suite_user_renamed")
+            },
+            is_not_found: |err| matches!(err, UserError::NotFound { .. }),
+        }
+    );
+
+    #[tokio::test]
+    async fn test_create_many_skips_taken_names() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        dao.create(create_test_user("taken")).await.unwrap();
+
+        let names = ["fresh_a", "taken", "fresh_b", "fresh_a"]
+            .map(String::from)
+            .to_vec();
+        let created = dao.create_many(&names).await.unwrap();
+
+        let mut created: Vec<_> =
+            created.into_iter().map(|user| user.name).collect();
+        created.sort();
+        assert_eq!(created, ["fresh_a", "fresh_b"]);
+        assert_eq!(dao.count().await.unwrap(), 3);
+        assert!(dao.create_many(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rolls_back_every_write_on_error() {
+        let container = setup_test_db().await;
+        let db = create_sql_connect(&container);
+        let dao = UserDao::new(db.clone());
+
+        let err = db
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    let users = UserDao::in_transaction(tx);
+                    users.create(create_test_user("tx_first")).await?;
+                    users.create(create_test_user("tx_second")).await?;
+                    users.create(create_test_user("tx_first")).await
+                })
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UserError::NameExists));
+        assert_eq!(dao.count().await.unwrap(), 0);
+
+        let created = db
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    UserDao::in_transaction(tx)
+                        .create(create_test_user("tx_first"))
+                        .await
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            dao.find_by_id(created.id).await.unwrap().name,
+            "tx_first"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_insert_of_a_taken_name_is_name_exists() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+
+        // An uncommitted insert the other writer's name check can't see
+        let mut first = container.pool.get().await.unwrap();
+        let tx = first.transaction().await.unwrap();
+        tx.execute(
+            "INSERT INTO users (name, created_at) VALUES ('racer', NOW())",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let second = tokio::spawn({
+            let dao = dao.clone();
+            async move { dao.create(create_test_user("racer")).await }
+        });
+        // Commit only once the second insert waits on the unique index
+        let observer = container.pool.get().await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            loop {
+                let waiting: i64 = observer
+                    .query_one(
+                        "SELECT COUNT(*) FROM pg_locks WHERE NOT granted",
+                        &[],
+                    )
+                    .await
+                    .unwrap()
+                    .get(0);
+                if waiting > 0 {
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("second insert should wait for the first");
+        tx.commit().await.unwrap();
+
+        let err = second.await.unwrap().unwrap_err();
+        assert!(matches!(err, UserError::NameExists), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_count_created_between() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        for (name, days_ago) in [("new", 1), ("recent", 6), ("old", 10)] {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO users (name, created_at) VALUES ('{name}', \
+                     NOW() - INTERVAL '{days_ago} days')"
+                ))
+                .await
+                .unwrap();
+        }
+
+        let now = chrono::Utc::now();
+        let week_ago = now - chrono::Duration::days(7);
+        assert_eq!(
+            dao.count_created_between(week_ago, now).await.unwrap(),
+            2
+        );
+        assert_eq!(
+            dao.count_created_between(
+                now - chrono::Duration::days(14),
+                week_ago
+            )
+            .await
+            .unwrap(),
+            1
+        );
+        assert_eq!(dao.count_created_between(now, now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_ids_and_most_active_ids() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        let quiet = dao.create(create_test_user("quiet")).await.unwrap();
+        let busy = dao.create(create_test_user("busy")).await.unwrap();
+        let idle = dao.create(create_test_user("idle")).await.unwrap();
+        let type_id = create_test_event_type(&container).await.unwrap();
+        for (user_id, count) in [(quiet.id, 1), (busy.id, 3)] {
+            for _ in 0..count {
+                create_test_event(&container, user_id, type_id, None)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let since = chrono::Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(
+            dao.most_active_ids(10, since).await.unwrap(),
+            [busy.id, quiet.id]
+        );
+        assert_eq!(dao.most_active_ids(1, since).await.unwrap(), [busy.id]);
+
+        let mut found: Vec<_> = dao
+            .find_by_ids(&[idle.id, busy.id, i64::MAX])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.name)
+            .collect();
+        found.sort();
+        assert_eq!(found, ["busy", "idle"]);
+        assert!(dao.find_by_ids(&[]).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_user_dao_new() {
         let container = setup_test_db().await;
@@ -340,6 +748,52 @@ mod tests {
         );
     }
 
+    /// Statements cached across every connection of the pool, checked out
+    /// all at once so each is counted exactly once
+    async fn cached_statements(container: &TestPostgresContainer) -> usize {
+        let mut clients = Vec::new();
+        for _ in 0..container.pool.status().size {
+            clients.push(container.pool.get().await.unwrap());
+        }
+        clients.iter().map(|c| c.statement_cache.size()).sum()
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_reuses_prepared_statements() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        let ids = [
+            dao.create(create_test_user("alice")).await.unwrap().id,
+            dao.create(create_test_user("bob")).await.unwrap().id,
+        ];
+
+        // Pairs of concurrent lookups spread the calls over two connections
+        let lookups = || {
+            async {
+                for _ in 0..10 {
+                    let (a, b) = tokio::join!(
+                        dao.find_by_id(ids[0]),
+                        dao.find_by_id(ids[1])
+                    );
+                    assert_eq!(a.unwrap().name, "alice");
+                    assert_eq!(b.unwrap().name, "bob");
+                }
+            }
+        };
+
+        let before = cached_statements(&container).await;
+        lookups().await;
+        let warmed = cached_statements(&container).await;
+        assert!(warmed > before, "find_by_id should cache its statement");
+
+        lookups().await;
+        assert_eq!(
+            cached_statements(&container).await,
+            warmed,
+            "repeated lookups must not prepare again"
+        );
+    }
+
     #[tokio::test]
     async fn test_find_by_name() {
         let container = setup_test_db().await;
@@ -356,6 +810,40 @@ mod tests {
         assert_eq!(found_user.id, created_user.id);
     }
 
+    #[tokio::test]
+    async fn test_concurrent_find_or_create_yields_one_user() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let dao = dao.clone();
+                tokio::spawn(async move {
+                    dao.find_or_create_by_name("ensured").await.unwrap()
+                })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+
+        let id = results[0].0.id;
+        assert!(results.iter().all(|(user, _)| user.id == id));
+        assert_eq!(results.iter().filter(|(_, created)| *created).count(), 1);
+
+        let client = container.pool.get().await.unwrap();
+        let count: i64 = client
+            .query_one(
+                "SELECT COUNT(*) FROM users WHERE name = 'ensured'",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 1);
+    }
+
     #[tokio::test]
     async fn test_find_by_name_not_found() {
         let container = setup_test_db().await;
@@ -388,6 +876,95 @@ mod tests {
         assert_eq!(names, vec!["user_1", "user_2", "user_3"]);
     }
 
+    async fn insert_users_created_at(
+        container: &TestPostgresContainer, users: &[(&str, &str)],
+    ) {
+        for (name, created_at) in users {
+            container
+                .execute_sql(&format!(
+                    "INSERT INTO users (name, created_at) VALUES ('{name}', \
+                     '{created_at}')"
+                ))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_filtered_by_created_at_range() {
+        let container = setup_test_db().await;
+        insert_users_created_at(
+            &container,
+            &[
+                ("early", "2024-01-01T00:00:00Z"),
+                ("middle", "2024-06-01T00:00:00Z"),
+                ("late", "2024-12-01T00:00:00Z"),
+            ],
+        )
+        .await;
+        let dao = UserDao::new(create_sql_connect(&container));
+
+        let after = "2024-03-01T00:00:00Z".parse().unwrap();
+        let before = "2024-09-01T00:00:00Z".parse().unwrap();
+
+        let users = dao
+            .find_filtered(
+                Some(after),
+                None,
+                UserSort::NameAsc.order_by(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let names: Vec<&str> =
+            users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["late", "middle"]);
+
+        let users = dao
+            .find_filtered(
+                Some(after),
+                Some(before),
+                UserSort::NameAsc.order_by(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let names: Vec<&str> =
+            users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["middle"]);
+    }
+
+    #[tokio::test]
+    async fn test_find_filtered_created_at_desc() {
+        let container = setup_test_db().await;
+        insert_users_created_at(
+            &container,
+            &[
+                ("b_user", "2024-01-01T00:00:00Z"),
+                ("c_user", "2024-06-01T00:00:00Z"),
+                ("a_user", "2024-12-01T00:00:00Z"),
+            ],
+        )
+        .await;
+        let dao = UserDao::new(create_sql_connect(&container));
+
+        let users = dao
+            .find_filtered(
+                None,
+                None,
+                UserSort::CreatedAtDesc.order_by(),
+                Some(2),
+                None,
+            )
+            .await
+            .unwrap();
+        let names: Vec<&str> =
+            users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["a_user", "c_user"]);
+    }
+
     #[tokio::test]
     async fn test_update_user() {
         let container = setup_test_db().await;
@@ -425,6 +1002,44 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_update_partial_empty_change_set_is_no_op() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        let created =
+            dao.create(create_test_user("unchanged")).await.unwrap();
+
+        let user = dao
+            .update_partial(created.id, UserChangeSet::default())
+            .await
+            .unwrap();
+        assert_eq!(user.name, "unchanged");
+        assert_eq!(user.created_at, created.created_at);
+
+        let missing =
+            dao.update_partial(999999, UserChangeSet::default()).await;
+        assert!(matches!(missing, Err(UserError::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_update_partial_name_conflict() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+        dao.create(create_test_user("taken")).await.unwrap();
+        let user = dao.create(create_test_user("renamed")).await.unwrap();
+
+        let result = dao
+            .update_partial(
+                user.id,
+                UserChangeSet {
+                    name: Some("taken".to_string()),
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(UserError::NameExists)));
+        assert_eq!(dao.find_by_id(user.id).await.unwrap().name, "renamed");
+    }
+
     #[tokio::test]
     async fn test_delete_user() {
         let container = setup_test_db().await;
@@ -503,6 +1118,68 @@ mod tests {
         assert!(final_page_result.next_cursor.is_none());
     }
 
+    #[tokio::test]
+    async fn test_cursor_from_another_listing_is_rejected() {
+        let container = setup_test_db().await;
+        let dao = UserDao::new(create_sql_connect(&container));
+
+        let cursor = Cursor::new(vec![42_i64.into()], SortDirection::Desc);
+        match dao.find_with_cursor(Some(cursor), 2).await {
+            Err(UserError::Validation(fields)) => {
+                assert_eq!(fields[0].field, "cursor");
+            }
+            Err(e) => panic!("expected a validation error, got {e}"),
+            Ok(_) => panic!("a foreign cursor must not page"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_names_paginate_without_gaps_or_repeats() {
+        let container = setup_test_db().await;
+        let sql_connect = create_sql_connect(&container);
+        // Names are unique since migration 007; lift that to get ties
+        sql_connect
+            .get_client()
+            .await
+            .unwrap()
+            .batch_execute("DROP INDEX idx_users_name")
+            .await
+            .unwrap();
+        let dao = UserDao::new(sql_connect);
+
+        let mut expected = Vec::new();
+        for name in ["alice", "dup", "dup", "dup", "dup", "zoe"] {
+            expected
+                .push(dao.create(create_test_user(name)).await.unwrap().id);
+        }
+
+        // Pages of two put the boundaries between equal names
+        let mut by_cursor = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = dao.find_with_cursor(cursor, 2).await.unwrap();
+            by_cursor.extend(page.items.iter().map(|u| u.id));
+            match page.next_cursor {
+                // Go through the wire form, as a client would
+                Some(next) => {
+                    cursor = Some(Cursor::decode(&next.encode()).unwrap())
+                }
+                None => break,
+            }
+        }
+        assert_eq!(by_cursor, expected);
+
+        let mut by_offset = Vec::new();
+        for offset in (0..6).step_by(2) {
+            let page = dao
+                .find_with_pagination(Some(2), Some(offset))
+                .await
+                .unwrap();
+            by_offset.extend(page.iter().map(|u| u.id));
+        }
+        assert_eq!(by_offset, expected);
+    }
+
     #[tokio::test]
     async fn test_count() {
         let container = setup_test_db().await;
@@ -524,6 +1201,25 @@ mod tests {
         assert_eq!(final_count, 3);
     }
 
+    #[tokio::test]
+    async fn test_count_events() {
+        let container = setup_test_db().await;
+        let sql_connect = create_sql_connect(&container);
+        let dao = UserDao::new(sql_connect);
+
+        let user = dao.create(create_test_user("eventful")).await.unwrap();
+        let quiet = dao.create(create_test_user("quiet")).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        for _ in 0..3 {
+            create_test_event(&container, user.id, event_type_id, None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(dao.count_events(user.id).await.unwrap(), 3);
+        assert_eq!(dao.count_events(quiet.id).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_map_row() {
         let container = setup_test_db().await;
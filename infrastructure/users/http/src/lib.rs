@@ -1,36 +1,60 @@
 use axum::{
+    Router,
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post, put},
 };
-use common_errors::AppError;
+use chrono::{DateTime, Utc};
+use common_errors::{AppError, FieldError};
+use common_query::{CollectionEtag, DeleteParams, TOTAL_COUNT_HEADER};
+use dao_utils::pagination::{
+    DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, PaginationParams,
+};
+use events_command_handlers::DeleteUserEventsHandler;
+use events_commands::DeleteUserEventsCommand;
 use events_queries::GetUserEventsQuery;
 use events_query_handlers::GetUserEventsQueryHandler;
-use events_responses::EventResponse;
+use events_responses::{DeleteUserEventsResponse, EventResponse};
+use futures::StreamExt;
 use serde::Deserialize;
 use tracing::instrument;
 use user_command_handlers::{
-    CreateUserHandler, DeleteUserHandler, UpdateUserHandler,
+    CreateUserHandler, DeleteUserHandler, EnsureUserHandler,
+    ImportUsersHandler, UpdateUserHandler,
 };
 use user_commands::{
-    CreateUserCommand, DeleteUserCommand, UpdateUserCommand,
+    CreateUserCommand, DeleteUserCommand, EnsureUserCommand,
+    ImportUsersCommand, ImportUsersParser, MAX_IMPORT_LINE_LEN,
+    UpdateUserCommand,
 };
+use user_errors::UserError;
+use user_queries::UserSort;
 use user_query_handlers::{
-    GetUserByNameQueryHandler, GetUserQueryHandler, ListUsersQueryHandler,
+    CountUsersQueryHandler, GetUserByNameQueryHandler, GetUserQueryHandler,
+    ListUsersQueryHandler,
 };
-use user_responses::UserResponse;
-use utoipa::{IntoParams, ToSchema};
+use user_responses::{ImportRowError, UserImportReport, UserResponse};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 #[derive(Clone)]
 pub struct UserServices {
     pub create_user: CreateUserHandler,
     pub update_user: UpdateUserHandler,
     pub delete_user: DeleteUserHandler,
+    pub ensure_user: EnsureUserHandler,
+    pub import_users: ImportUsersHandler,
 
     pub get_user: GetUserQueryHandler,
     pub get_user_by_name: GetUserByNameQueryHandler,
     pub list_users: ListUsersQueryHandler,
+    /// Total sent with unfiltered `GET /users` pages
+    pub count_users: CountUsersQueryHandler,
     pub get_user_events: GetUserEventsQueryHandler,
+    pub delete_user_events: DeleteUserEventsHandler,
+    /// Users per page of `GET /users` when the request sets no `limit`
+    pub default_page_size: u64,
 }
 
 impl UserServices {
@@ -39,14 +63,74 @@ impl UserServices {
             create_user: CreateUserHandler::new(db.clone()),
             update_user: UpdateUserHandler::new(db.clone()),
             delete_user: DeleteUserHandler::new(db.clone()),
+            ensure_user: EnsureUserHandler::new(db.clone()),
+            import_users: ImportUsersHandler::new(db.clone()),
             get_user: GetUserQueryHandler::new(db.clone()),
             get_user_by_name: GetUserByNameQueryHandler::new(db.clone()),
             list_users: ListUsersQueryHandler::new(db.clone()),
-            get_user_events: GetUserEventsQueryHandler::new(db),
+            count_users: CountUsersQueryHandler::new(db.clone()),
+            get_user_events: GetUserEventsQueryHandler::new(db.clone()),
+            delete_user_events: DeleteUserEventsHandler::new(db),
+            default_page_size: DEFAULT_PAGE_SIZE,
         }
     }
+
+    /// Users per page of `GET /users` when the request sets no `limit`
+    pub fn with_default_page_size(mut self, size: u64) -> Self {
+        self.default_page_size = size;
+        self
+    }
+}
+
+pub struct UserHandlers;
+
+impl UserHandlers {
+    /// `GET` routes also answer `HEAD`: axum runs the same handler, keeps
+    /// the `Content-Length` and strips the body.
+    pub fn routes() -> Router<UserServices> {
+        Router::new()
+            .route("/user", post(create_user))
+            .route("/user/{id}", get(get_user))
+            .route("/user/{id}", put(update_user))
+            .route("/user/{id}", delete(delete_user))
+            .route("/user/{id}/events", get(get_user_events))
+            .route("/user/{id}/events", delete(delete_user_events))
+            .route("/users", get(list_users))
+            .route("/users/batch", get(get_users_batch))
+            .route("/users/ensure", post(ensure_user))
+            .route("/users/import", post(import_users))
+    }
 }
 
+/// OpenAPI paths and schemas of the user endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_user,
+        ensure_user,
+        import_users,
+        update_user,
+        delete_user,
+        get_user,
+        get_users_batch,
+        list_users,
+        get_user_events,
+        delete_user_events
+    ),
+    components(schemas(
+        UserResponse,
+        CreateUserCommand,
+        EnsureUserCommand,
+        UpdateUserCommand,
+        UserImportReport,
+        ImportRowError,
+        UserSort,
+        EventResponse,
+        DeleteUserEventsResponse
+    ))
+)]
+pub struct UsersApi;
+
 #[utoipa::path(
     post,
     path = "/user",
@@ -59,18 +143,128 @@ impl UserServices {
     ),
     tag = "users"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(user_id = tracing::field::Empty))]
 pub async fn create_user(
     State(services): State<UserServices>,
     Json(command): Json<CreateUserCommand>,
 ) -> Result<(StatusCode, Json<UserResponse>), AppError> {
     let result = services.create_user.execute(command).await?;
+    tracing::Span::current().record("user_id", result.id);
 
     tracing::info!("User created: {}", result.id);
 
     Ok((StatusCode::CREATED, Json(result)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/users/ensure",
+    request_body = EnsureUserCommand,
+    responses(
+        (status = 200, description = "User already existed", body = UserResponse),
+        (status = 201, description = "User created", body = UserResponse),
+        (status = 400, description = "Invalid request data", body = common_errors::ApiErrorResponse),
+        (status = 422, description = "Validation error", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "users"
+)]
+#[instrument(skip_all, fields(user_id = tracing::field::Empty))]
+pub async fn ensure_user(
+    State(services): State<UserServices>,
+    Json(command): Json<EnsureUserCommand>,
+) -> Result<(StatusCode, Json<UserResponse>), AppError> {
+    let (result, created) = services.ensure_user.execute(command).await?;
+    tracing::Span::current().record("user_id", result.id);
+
+    let status = if created {
+        tracing::info!("User created: {}", result.id);
+        StatusCode::CREATED
+    }
+    else {
+        StatusCode::OK
+    };
+
+    Ok((status, Json(result)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/import",
+    request_body(
+        content = String,
+        content_type = "text/csv",
+        description = "CSV with a header row containing a `name` column (max 10000 rows)"
+    ),
+    responses(
+        (status = 200, description = "Import report; rejected rows are listed in `errors`", body = UserImportReport),
+        (status = 422, description = "Empty file, no `name` column, too many rows, an overlong line or not UTF-8", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "users"
+)]
+#[instrument(skip_all)]
+pub async fn import_users(
+    State(services): State<UserServices>, body: Body,
+) -> Result<Json<UserImportReport>, AppError> {
+    let command = read_import(body).await?;
+    let report = services.import_users.execute(command).await?;
+
+    tracing::info!(
+        "Imported users: {} created, {} duplicates, {} errors",
+        report.created,
+        report.skipped_duplicates,
+        report.errors.len()
+    );
+
+    Ok(Json(report))
+}
+
+/// Parse an import as it arrives, a line at a time, so a file that is too
+/// long is turned away without being read to the end
+async fn read_import(body: Body) -> Result<ImportUsersCommand, AppError> {
+    let mut parser = ImportUsersParser::default();
+    let mut push = |line: &[u8]| {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let text = std::str::from_utf8(line).map_err(|_| {
+            AppError::validation(vec![FieldError::new(
+                "csv",
+                "invalid_utf8",
+                "CSV body must be UTF-8",
+            )])
+        })?;
+        parser.push_line(text).map_err(AppError::validation)
+    };
+
+    let mut pending = Vec::new();
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| {
+            AppError::bad_request("INVALID_BODY", &e.to_string())
+        })?;
+        let mut rest = &chunk[..];
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            pending.extend_from_slice(&rest[..end]);
+            push(&pending)?;
+            pending.clear();
+            rest = &rest[end + 1..];
+        }
+        pending.extend_from_slice(rest);
+        // Overlong before its end arrives; the parser reports it
+        if pending.len() > MAX_IMPORT_LINE_LEN {
+            let text = String::from_utf8_lossy(&pending);
+            return Err(AppError::validation(
+                parser.push_line(&text).unwrap_err(),
+            ));
+        }
+    }
+    if !pending.is_empty() {
+        push(&pending)?;
+    }
+
+    parser.finish().map_err(AppError::validation)
+}
+
 #[utoipa::path(
     put,
     path = "/user/{id}",
@@ -87,7 +281,7 @@ pub async fn create_user(
     ),
     tag = "users"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(user_id = %id))]
 pub async fn update_user(
     State(services): State<UserServices>, Path(id): Path<i64>,
     Json(mut command): Json<UpdateUserCommand>,
@@ -104,31 +298,53 @@ pub async fn update_user(
     delete,
     path = "/user/{id}",
     params(
-        ("id" = i64, Path, description = "User ID")
+        ("id" = i64, Path, description = "User ID"),
+        DeleteParams
     ),
     responses(
-        (status = 204, description = "User deleted successfully"),
+        (status = 204, description = "User deleted, or already absent with `idempotent=true`"),
         (status = 404, description = "User not found", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
     tag = "users"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(user_id = %id))]
 pub async fn delete_user(
     State(services): State<UserServices>, Path(id): Path<i64>,
+    Query(params): Query<DeleteParams>,
 ) -> Result<StatusCode, AppError> {
     let command = DeleteUserCommand { user_id: id };
-    services.delete_user.execute(command).await?;
-
-    tracing::info!("User deleted: {}", id);
+    match services.delete_user.execute(command).await {
+        Ok(()) => tracing::info!("User deleted: {}", id),
+        Err(UserError::NotFound { .. }) if params.idempotent => {
+            tracing::debug!("User {} already absent", id);
+        }
+        Err(e) => return Err(e.into()),
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
 pub struct UserQueryParams {
+    /// Include the user's total event count in the response
+    #[serde(default)]
+    include_event_count: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ListUsersParams {
     limit: Option<u64>,
     offset: Option<u64>,
+    /// Only users created at or after this instant (RFC3339)
+    created_after: Option<DateTime<Utc>>,
+    /// Only users created strictly before this instant (RFC3339)
+    created_before: Option<DateTime<Utc>>,
+    /// Sort order; defaults to `name_asc`
+    sort: Option<UserSort>,
+    /// Add each user's latest event timestamp as `last_active`
+    #[serde(default)]
+    include_last_active: bool,
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
@@ -151,25 +367,86 @@ pub struct UserEventsQueryParams {
     ),
     tag = "users"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(user_id = %id))]
 pub async fn get_user(
     State(services): State<UserServices>, Path(id): Path<i64>,
-    Query(_params): Query<UserQueryParams>,
+    Query(params): Query<UserQueryParams>,
 ) -> Result<Json<UserResponse>, AppError> {
     let query = user_queries::GetUserQuery { user_id: id };
     let user = services.get_user.execute(query).await?;
 
-    Ok(Json(user.into()))
+    let mut response = UserResponse::from(user);
+    if params.include_event_count {
+        response.event_count = Some(services.get_user.event_count(id).await?);
+    }
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct UserBatchParams {
+    /// Comma-separated user ids, at most 1000
+    ids: String,
+}
+
+impl UserBatchParams {
+    fn user_ids(&self) -> Result<Vec<i64>, AppError> {
+        let invalid = |message: &str| {
+            AppError::validation(vec![FieldError::new(
+                "ids", "invalid", message,
+            )])
+        };
+        let ids = self
+            .ids
+            .split(',')
+            .map(|id| id.trim().parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("ids must be comma-separated integers"))?;
+        if ids.len() as u64 > MAX_PAGE_SIZE {
+            return Err(invalid("at most 1000 ids can be resolved at once"));
+        }
+        Ok(ids)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/batch",
+    params(
+        UserBatchParams
+    ),
+    responses(
+        (status = 200, description = "The known users, in the order asked for", body = Vec<UserResponse>),
+        (status = 422, description = "Malformed or too many ids", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "users"
+)]
+#[instrument(skip_all)]
+pub async fn get_users_batch(
+    State(services): State<UserServices>,
+    Query(params): Query<UserBatchParams>,
+) -> Result<Json<Vec<UserResponse>>, AppError> {
+    let ids = params.user_ids()?;
+    let users = services.get_user.execute_many(&ids).await?;
+
+    Ok(Json(users.into_iter().map(Into::into).collect()))
 }
 
 #[utoipa::path(
     get,
     path = "/users",
     params(
-        UserQueryParams
+        ListUsersParams
     ),
     responses(
-        (status = 200, description = "List of users", body = Vec<UserResponse>),
+        (status = 200, description = "List of users", body = Vec<UserResponse>,
+            headers(
+                ("x-total-count" = i64, description = "Number of users in total, up to 30 seconds stale; only without `created_after` and `created_before`"),
+                ("etag" = String, description = "Collection tag; send it back in `If-None-Match`")
+            )
+        ),
+        (status = 304, description = "The list is unchanged since the `If-None-Match` tag"),
         (status = 400, description = "Invalid query parameters", body = common_errors::ApiErrorResponse),
         (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
     ),
@@ -177,16 +454,64 @@ pub async fn get_user(
 )]
 #[instrument(skip_all)]
 pub async fn list_users(
-    State(services): State<UserServices>,
-    Query(_params): Query<UserQueryParams>,
-) -> Result<Json<Vec<UserResponse>>, AppError> {
+    State(services): State<UserServices>, headers: HeaderMap,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Response, AppError> {
+    let pagination = PaginationParams::validated(
+        Some(params.limit.unwrap_or(services.default_page_size)),
+        params.offset,
+        MAX_PAGE_SIZE,
+    )?;
     let query = user_queries::ListUsersQuery {
-        limit: _params.limit,
-        offset: _params.offset,
+        limit: pagination.limit,
+        offset: pagination.offset,
+        created_after: params.created_after,
+        created_before: params.created_before,
+        sort: params.sort,
     };
-    let users = services.list_users.execute(query).await?;
+    let version = services.list_users.collection_version(&query).await?;
+    let mut users: Vec<UserResponse> = services
+        .list_users
+        .execute(query)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
 
-    Ok(Json(users.into_iter().map(Into::into).collect()))
+    if params.include_last_active {
+        let ids: Vec<i64> = users.iter().map(|user| user.id).collect();
+        let mut last_active =
+            services.get_user_events.last_active(&ids).await?;
+        for user in &mut users {
+            user.last_active = last_active.remove(&user.id);
+        }
+    }
+
+    // The listed rows are part of the tag so a list served from cache
+    // never shares a tag with a fresher one; `last_active` moves without
+    // the users changing, so it is part of it too
+    let etag =
+        users
+            .iter()
+            .fold(CollectionEtag::new(version), |tag, user| {
+                tag.scope((
+                    user.id,
+                    &user.name,
+                    user.created_at,
+                    user.last_active,
+                ))
+            });
+    if etag.matches(&headers) {
+        return Ok((StatusCode::NOT_MODIFIED, etag.headers()).into_response());
+    }
+
+    let mut headers = etag.headers();
+    // The cached count covers every user, so filtered lists go without
+    if params.created_after.is_none() && params.created_before.is_none() {
+        let total = services.count_users.execute().await?;
+        headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    }
+    Ok((headers, Json(users)).into_response())
 }
 
 #[utoipa::path(
@@ -204,7 +529,7 @@ pub async fn list_users(
     ),
     tag = "Event"
 )]
-#[instrument(skip_all)]
+#[instrument(skip_all, fields(user_id = %user_id))]
 pub async fn get_user_events(
     State(services): State<UserServices>, Path(user_id): Path<i64>,
     Query(params): Query<UserEventsQueryParams>,
@@ -218,3 +543,578 @@ pub async fn get_user_events(
 
     Ok(Json(events))
 }
+
+#[utoipa::path(
+    delete,
+    path = "/user/{user_id}/events",
+    params(
+        ("user_id" = i64, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Every event of the user was deleted", body = DeleteUserEventsResponse),
+        (status = 400, description = "Invalid ID format", body = common_errors::ApiErrorResponse),
+        (status = 500, description = "Internal server error", body = common_errors::ApiErrorResponse)
+    ),
+    tag = "Event"
+)]
+#[instrument(skip_all, fields(user_id = %user_id))]
+pub async fn delete_user_events(
+    State(services): State<UserServices>, Path(user_id): Path<i64>,
+) -> Result<Json<DeleteUserEventsResponse>, AppError> {
+    let command = DeleteUserEventsCommand { user_id };
+    let result = services.delete_user_events.execute(command).await?;
+
+    tracing::info!(
+        "Deleted {} events of user {}",
+        result.deleted_count,
+        user_id
+    );
+
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Method, Request, header},
+    };
+    use database_traits::dao::GenericDao;
+    use redis_connection::cache_provider::CacheProvider;
+    use test_utils::{TestRedisContainer, *};
+    use tower::ServiceExt;
+    use tracing::{
+        Subscriber,
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+    };
+    use tracing_subscriber::{
+        layer::{Context, Layer, SubscriberExt},
+        registry::LookupSpan,
+    };
+
+    use super::*;
+
+    async fn setup_test_app()
+    -> anyhow::Result<(TestPostgresContainer, TestRedisContainer, Router)>
+    {
+        let container = TestPostgresContainer::new().await?;
+        let redis_container = TestRedisContainer::new().await?;
+        redis_container.flush_db().await?;
+
+        CacheProvider::init_redis_static(redis_container.pool.clone());
+
+        let services = UserServices::new(create_sql_connect(&container));
+        Ok((
+            container,
+            redis_container,
+            UserHandlers::routes().with_state(services),
+        ))
+    }
+
+    fn request(method: Method, uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn content_length(response: &axum::response::Response) -> usize {
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .expect("Content-Length header should be set")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_head_users_returns_length_without_body() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        create_test_users(&container).await.unwrap();
+
+        let get_response = app
+            .clone()
+            .oneshot(request(Method::GET, "/users"))
+            .await
+            .unwrap();
+        let get_length = content_length(&get_response);
+        let get_body = to_bytes(get_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(get_length, get_body.len());
+
+        let head_response =
+            app.oneshot(request(Method::HEAD, "/users")).await.unwrap();
+        assert_eq!(head_response.status(), StatusCode::OK);
+        assert_eq!(content_length(&head_response), get_length);
+
+        let head_body = to_bytes(head_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(head_body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_head_user_detail() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::HEAD, &format!("/user/{user_id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(content_length(&response) > 0);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        let missing = app
+            .oneshot(request(Method::HEAD, "/user/999999"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn get_json(app: Router, uri: &str) -> serde_json::Value {
+        let response = app.oneshot(request(Method::GET, uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_user_omits_event_count_by_default() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+
+        let json = get_json(app, &format!("/user/{user_id}")).await;
+        assert_eq!(json["id"], user_id);
+        assert!(json.get("event_count").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_user_includes_event_count_when_requested() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        for _ in 0..2 {
+            create_test_event(&container, user_id, event_type_id, None)
+                .await
+                .unwrap();
+        }
+
+        let json = get_json(
+            app,
+            &format!("/user/{user_id}?include_event_count=true"),
+        )
+        .await;
+        assert_eq!(json["id"], user_id);
+        assert_eq!(json["event_count"], 2);
+    }
+
+    async fn ensure(app: Router, name: &str) -> (StatusCode, i64) {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/users/ensure")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(format!(r#"{{"name":"{name}"}}"#)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (status, json["id"].as_i64().unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_delete_missing_user_is_404_unless_idempotent() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+
+        for (uri, status) in [
+            (format!("/user/{user_id}"), StatusCode::NO_CONTENT),
+            (format!("/user/{user_id}"), StatusCode::NOT_FOUND),
+            (
+                format!("/user/{user_id}?idempotent=false"),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                format!("/user/{user_id}?idempotent=true"),
+                StatusCode::NO_CONTENT,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(Method::DELETE, &uri))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_delete_still_reports_database_errors() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        container.pool.close();
+
+        let response = app
+            .oneshot(request(
+                Method::DELETE,
+                &format!("/user/{user_id}?idempotent=true"),
+            ))
+            .await
+            .unwrap();
+        assert!(response.status().is_server_error(), "{}", response.status());
+    }
+
+    #[tokio::test]
+    async fn test_batch_lookup_keeps_the_requested_order() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let (first, second) = create_test_users(&container).await.unwrap();
+
+        let json = get_json(
+            app.clone(),
+            &format!("/users/batch?ids={second},999999,{first}"),
+        )
+        .await;
+        let ids: Vec<i64> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, [second, first]);
+
+        let response = app
+            .oneshot(request(Method::GET, "/users/batch?ids=1,two"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_user_creates_then_finds() {
+        let (_container, _redis, app) = setup_test_app().await.unwrap();
+
+        let (status, created_id) = ensure(app.clone(), "ensured").await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, found_id) = ensure(app.clone(), "ensured").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found_id, created_id);
+
+        let (status, _) = ensure(app, "  ").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_list_users_etag_and_not_modified() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let dao = user_dao::UserDao::new(create_sql_connect(&container));
+        dao.create(CreateUserCommand {
+            name: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+        let list_etag = |response: &axum::response::Response| {
+            response.headers()[header::ETAG]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/users?limit=10"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = list_etag(&response);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/users?limit=10")
+                    .header(header::IF_NONE_MATCH, &etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(list_etag(&response), etag);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        dao.create(CreateUserCommand {
+            name: "bob".to_string(),
+        })
+        .await
+        .unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/users?limit=10")
+                    .header(header::IF_NONE_MATCH, &etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(list_etag(&response), etag);
+    }
+
+    #[tokio::test]
+    async fn test_list_users_clamps_limit_and_rejects_absurd_offset() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        create_test_users(&container).await.unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/users?limit=99999999"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for uri in [
+            "/users?offset=99999999999",
+            "/users?offset=18446744073709551615",
+            "/users?limit=-1",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(Method::GET, uri))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_users_defaults_to_a_bounded_page() {
+        let (container, _redis, _) = setup_test_app().await.unwrap();
+        let app = UserHandlers::routes().with_state(
+            UserServices::new(create_sql_connect(&container))
+                .with_default_page_size(2),
+        );
+        for name in ["ann", "ben", "cid"] {
+            create_test_user_with_name(&container, name).await.unwrap();
+        }
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let users: Vec<serde_json::Value> =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(users.len(), 2);
+
+        let response = app
+            .oneshot(request(Method::GET, "/users?limit=3"))
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let users: Vec<serde_json::Value> =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(users.len(), 3);
+    }
+
+    /// `bytes` as a streamed body, `size` bytes per chunk
+    fn chunked(bytes: Vec<u8>, size: usize) -> Body {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+            bytes.chunks(size).map(|chunk| Ok(chunk.to_vec())).collect();
+        Body::from_stream(futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn test_import_is_parsed_across_chunks() {
+        let csv = "\u{feff}name,team\r\nalice,red\r\n\r\nbob,blue";
+
+        for size in [1, 3, csv.len()] {
+            let command =
+                read_import(chunked(csv.into(), size)).await.unwrap();
+            let rows: Vec<_> = command
+                .rows
+                .iter()
+                .map(|row| (row.line, row.name.as_str()))
+                .collect();
+            assert_eq!(rows, [(2, "alice"), (4, "bob")], "{size}");
+        }
+
+        let code = |result: Result<ImportUsersCommand, AppError>| {
+            match result {
+                Err(AppError::Validation { fields, .. }) => {
+                    fields[0].code.clone()
+                }
+                other => panic!("expected a validation error, got {other:?}"),
+            }
+        };
+        let unterminated =
+            [b"name\n".as_slice(), &[b'x'; MAX_IMPORT_LINE_LEN + 1]].concat();
+        assert_eq!(
+            code(read_import(chunked(unterminated, 4096)).await),
+            "line_too_long"
+        );
+        let latin1 = b"name\ncaf\xe9\n".to_vec();
+        assert_eq!(
+            code(read_import(chunked(latin1, 4)).await),
+            "invalid_utf8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_users_csv_report() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let sql_connect = create_sql_connect(&container);
+        user_dao::UserDao::new(sql_connect.clone())
+            .create(CreateUserCommand {
+                name: "existing".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let csv = concat!(
+            "name,team\n",
+            "alice,red\n",
+            "existing,blue\n",
+            ",green\n",
+            "bob,red\n",
+            "alice,blue\n",
+        );
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/users/import")
+                    .header(header::CONTENT_TYPE, "text/csv")
+                    .body(Body::from(csv))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["created"], 2);
+        assert_eq!(report["skipped_duplicates"], 2);
+        assert_eq!(
+            report["errors"],
+            serde_json::json!([
+                {"line": 4, "reason": "Name must not be empty"}
+            ])
+        );
+        let count =
+            user_dao::UserDao::new(sql_connect).count().await.unwrap();
+        assert_eq!(count, 3);
+    }
+
+    /// Records the fields of every span by span name
+    #[derive(Clone, Default)]
+    struct SpanFields(Arc<Mutex<HashMap<String, HashMap<String, String>>>>);
+
+    impl SpanFields {
+        fn get(&self, span: &str, field: &str) -> Option<String> {
+            self.0.lock().unwrap().get(span)?.get(field).cloned()
+        }
+
+        fn capture(
+            &self, span: &str, record: impl FnOnce(&mut FieldVisitor<'_>),
+        ) {
+            let mut spans = self.0.lock().unwrap();
+            record(&mut FieldVisitor(
+                spans.entry(span.to_string()).or_default(),
+            ));
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S> Layer<S> for SpanFields
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>,
+        ) {
+            self.capture(attrs.metadata().name(), |visitor| {
+                attrs.record(visitor)
+            });
+        }
+
+        fn on_record(
+            &self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>,
+        ) {
+            if let Some(span) = ctx.span(id) {
+                self.capture(span.name(), |visitor| values.record(visitor));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_spans_carry_user_id() {
+        let (container, _redis, app) = setup_test_app().await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+
+        let spans = SpanFields::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(spans.clone()),
+        );
+
+        get_json(app.clone(), &format!("/user/{user_id}")).await;
+        assert_eq!(
+            spans.get("get_user", "user_id"),
+            Some(user_id.to_string())
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/user")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"name":"span-user"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value =
+            serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            spans.get("create_user", "user_id"),
+            Some(created["id"].to_string())
+        );
+    }
+}
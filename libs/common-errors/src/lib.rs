@@ -1,12 +1,17 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use axum::{
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Json, Response},
 };
 use serde::Serialize;
 use utoipa::ToSchema;
 
+mod macros;
+mod redact;
+
+pub use redact::{REDACTED, redact};
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ApiErrorResponse {
     pub error: ApiErrorInfo,
@@ -17,6 +22,26 @@ pub struct ApiErrorInfo {
     pub code: String,
     pub message: String,
     pub details: Option<String>,
+    /// Per-field validation failures; omitted when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -26,21 +51,51 @@ pub enum AppError {
         message: String,
         details: Option<String>,
     },
+    Unauthorized {
+        code: String,
+        message: String,
+        details: Option<String>,
+    },
     NotFound {
         code: String,
         message: String,
         details: Option<String>,
     },
+    MethodNotAllowed {
+        code: String,
+        message: String,
+        details: Option<String>,
+    },
     UnprocessableEntity {
         code: String,
         message: String,
         details: Option<String>,
     },
+    TooManyRequests {
+        code: String,
+        message: String,
+        retry_after: Option<Duration>,
+    },
     InternalServerError {
         code: String,
         message: String,
         details: Option<String>,
     },
+    ServiceUnavailable {
+        code: String,
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    GatewayTimeout {
+        code: String,
+        message: String,
+        details: Option<String>,
+    },
+    Validation {
+        code: String,
+        message: String,
+        fields: Vec<FieldError>,
+    },
 }
 
 impl AppError {
@@ -62,6 +117,14 @@ impl AppError {
         }
     }
 
+    pub fn unauthorized(code: &str, message: &str) -> Self {
+        Self::Unauthorized {
+            code: code.to_string(),
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
     pub fn not_found(code: &str, message: &str) -> Self {
         Self::NotFound {
             code: code.to_string(),
@@ -70,6 +133,16 @@ impl AppError {
         }
     }
 
+    /// 405; the `Allow` header is left to the router, which knows the
+    /// methods of the matched path
+    pub fn method_not_allowed(message: &str) -> Self {
+        Self::MethodNotAllowed {
+            code: "METHOD_NOT_ALLOWED".to_string(),
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
     pub fn unprocessable_entity(code: &str, message: &str) -> Self {
         Self::UnprocessableEntity {
             code: code.to_string(),
@@ -78,6 +151,32 @@ impl AppError {
         }
     }
 
+    /// 422 carrying one entry per invalid field. `message` stays a flat
+    /// "field: code" summary for clients that only read the message.
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        let message = fields
+            .iter()
+            .map(|f| format!("{}: {}", f.field, f.code))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self::Validation {
+            code: "VALIDATION_ERROR".to_string(),
+            message,
+            fields,
+        }
+    }
+
+    /// 429; `retry_after` is sent back as a `Retry-After` header in seconds
+    pub fn too_many_requests(
+        message: &str, retry_after: Option<Duration>,
+    ) -> Self {
+        Self::TooManyRequests {
+            code: "RATE_LIMITED".to_string(),
+            message: message.to_string(),
+            retry_after,
+        }
+    }
+
     pub fn internal_server_error(message: &str) -> Self {
         Self::InternalServerError {
             code: "INTERNAL_ERROR".to_string(),
@@ -86,17 +185,39 @@ impl AppError {
         }
     }
 
+    /// 503 for a temporary lack of capacity, such as an exhausted
+    /// connection pool; `retry_after` is sent back as a `Retry-After` header
+    pub fn service_unavailable(
+        code: &str, message: &str, retry_after: Option<Duration>,
+    ) -> Self {
+        Self::ServiceUnavailable {
+            code: code.to_string(),
+            message: message.to_string(),
+            retry_after,
+        }
+    }
+
+    pub fn gateway_timeout(message: &str) -> Self {
+        Self::GatewayTimeout {
+            code: "REQUEST_TIMEOUT".to_string(),
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
     pub fn from_error<E>(err: E) -> Self
     where
         E: std::error::Error + Send + Sync + 'static,
     {
-        // Handle specific error types with appropriate HTTP status codes
-        let error_str = err.to_string();
+        // Handle specific error types with appropriate HTTP status codes.
+        // Connection errors can quote the connection string, so the text
+        // is redacted before it reaches the client.
+        let error_str = redact(&err.to_string());
 
         // Check for common database connection errors
         if error_str.contains("connection") || error_str.contains("pool") {
             return Self::internal_server_error(&format!(
-                "Database connection error: {err}"
+                "Database connection error: {error_str}"
             ));
         }
 
@@ -123,52 +244,95 @@ impl AppError {
 
         // Default to internal server error for unknown errors
         Self::internal_server_error(&format!(
-            "An unexpected error occurred: {err}"
+            "An unexpected error occurred: {error_str}"
         ))
     }
 
     fn status_code(&self) -> StatusCode {
         match self {
             Self::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
             Self::NotFound { .. } => StatusCode::NOT_FOUND,
-            Self::UnprocessableEntity { .. } => {
+            Self::MethodNotAllowed { .. } => StatusCode::METHOD_NOT_ALLOWED,
+            Self::UnprocessableEntity { .. } | Self::Validation { .. } => {
                 StatusCode::UNPROCESSABLE_ENTITY
             }
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
             Self::InternalServerError { .. } => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
+            Self::ServiceUnavailable { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::GatewayTimeout { .. } => StatusCode::GATEWAY_TIMEOUT,
         }
     }
 
     fn to_response_data(&self) -> ApiErrorResponse {
-        let (code, message, details) = match self {
+        let (code, message, details, fields) = match self {
             Self::BadRequest {
                 code,
                 message,
                 details,
-            } => (code, message, details),
-            Self::NotFound {
+            }
+            | Self::Unauthorized {
+                code,
+                message,
+                details,
+            }
+            | Self::NotFound {
+                code,
+                message,
+                details,
+            }
+            | Self::MethodNotAllowed {
+                code,
+                message,
+                details,
+            }
+            | Self::UnprocessableEntity {
                 code,
                 message,
                 details,
-            } => (code, message, details),
-            Self::UnprocessableEntity {
+            }
+            | Self::InternalServerError {
                 code,
                 message,
                 details,
-            } => (code, message, details),
-            Self::InternalServerError {
+            }
+            | Self::GatewayTimeout {
                 code,
                 message,
                 details,
-            } => (code, message, details),
+            } => (code, message, details.clone(), Vec::new()),
+            Self::TooManyRequests {
+                code,
+                message,
+                retry_after,
+            }
+            | Self::ServiceUnavailable {
+                code,
+                message,
+                retry_after,
+            } => {
+                let details = retry_after.map(|retry_after| {
+                    format!("Retry after {} seconds", retry_after.as_secs())
+                });
+                (code, message, details, Vec::new())
+            }
+            Self::Validation {
+                code,
+                message,
+                fields,
+            } => (code, message, None, fields.clone()),
         };
 
         ApiErrorResponse {
             error: ApiErrorInfo {
                 code: code.clone(),
                 message: message.clone(),
-                details: details.clone(),
+                details,
+                fields,
             },
         }
     }
@@ -178,13 +342,21 @@ impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::BadRequest { message, .. } => write!(f, "{message}"),
+            Self::Unauthorized { message, .. } => write!(f, "{message}"),
             Self::NotFound { message, .. } => write!(f, "{message}"),
+            Self::MethodNotAllowed { message, .. } => write!(f, "{message}"),
             Self::UnprocessableEntity { message, .. } => {
                 write!(f, "{message}")
             }
+            Self::TooManyRequests { message, .. } => write!(f, "{message}"),
             Self::InternalServerError { message, .. } => {
                 write!(f, "{message}")
             }
+            Self::ServiceUnavailable { message, .. } => {
+                write!(f, "{message}")
+            }
+            Self::GatewayTimeout { message, .. } => write!(f, "{message}"),
+            Self::Validation { message, .. } => write!(f, "{message}"),
         }
     }
 }
@@ -195,7 +367,24 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let status = self.status_code();
         let response_data = self.to_response_data();
-        (status, Json(response_data)).into_response()
+        let mut response = (status, Json(response_data)).into_response();
+
+        if let Self::TooManyRequests {
+            retry_after: Some(retry_after),
+            ..
+        }
+        | Self::ServiceUnavailable {
+            retry_after: Some(retry_after),
+            ..
+        } = self
+        {
+            response.headers_mut().insert(
+                RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs()),
+            );
+        }
+
+        response
     }
 }
 
@@ -216,3 +405,148 @@ impl From<anyhow::Error> for AppError {
 }
 
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validation_error_serializes_fields() {
+        let err = AppError::validation(vec![
+            FieldError::new("name", "too_long", "Name is too long"),
+            FieldError::new("metadata", "too_deep", "Metadata is too deep"),
+        ]);
+        assert_eq!(err.to_string(), "name: too_long, metadata: too_deep");
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "VALIDATION_ERROR");
+        assert_eq!(
+            json["error"]["fields"],
+            serde_json::json!([
+                {"field": "name", "code": "too_long", "message": "Name is too long"},
+                {"field": "metadata", "code": "too_deep", "message": "Metadata is too deep"}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_non_validation_errors_omit_fields() {
+        let response = AppError::bad_request("BAD", "Bad").into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert!(json["error"].get("fields").is_none());
+        assert!(json["error"]["details"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_too_many_requests_sets_retry_after() {
+        let response = AppError::too_many_requests(
+            "Slow down",
+            Some(Duration::from_secs(42)),
+        )
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "42");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "RATE_LIMITED");
+        assert_eq!(json["error"]["details"], "Retry after 42 seconds");
+
+        let response =
+            AppError::too_many_requests("Slow down", None).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().get(RETRY_AFTER).is_none());
+    }
+
+    #[derive(Debug)]
+    enum WidgetError {
+        NotFound,
+        Locked { owner: String },
+        Storage(String),
+    }
+
+    impl_app_error! {
+        WidgetError => {
+            NotFound => not_found("WIDGET_NOT_FOUND", "Widget not found"),
+            Locked { owner } => unprocessable_entity(
+                "WIDGET_LOCKED",
+                &format!("Widget is locked by {owner}")
+            ),
+            Storage(msg) => {
+                AppError::service_unavailable("WIDGET_STORAGE", &msg, None)
+            },
+        }
+    }
+
+    #[test]
+    fn test_impl_app_error_maps_each_variant() {
+        let cases = [
+            (
+                WidgetError::NotFound,
+                StatusCode::NOT_FOUND,
+                "WIDGET_NOT_FOUND",
+            ),
+            (
+                WidgetError::Locked {
+                    owner: "alice".into(),
+                },
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "WIDGET_LOCKED",
+            ),
+            (
+                WidgetError::Storage("disk full".into()),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "WIDGET_STORAGE",
+            ),
+        ];
+
+        for (err, status, code) in cases {
+            let app_err = AppError::from(err);
+            assert_eq!(app_err.status_code(), status);
+            assert_eq!(app_err.to_response_data().error.code, code);
+        }
+        assert_eq!(
+            AppError::from(WidgetError::Locked {
+                owner: "alice".into()
+            })
+            .to_string(),
+            "Widget is locked by alice"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_unavailable_sets_retry_after() {
+        let response = AppError::service_unavailable(
+            "DATABASE_BUSY",
+            "Busy",
+            Some(Duration::from_secs(1)),
+        )
+        .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "1");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "DATABASE_BUSY");
+    }
+
+    #[test]
+    fn test_from_error_masks_connection_passwords() {
+        let err = std::io::Error::other(
+            "connection refused: postgres://app:hunter2@db:5432/collider",
+        );
+
+        let message = AppError::from_error(err).to_string();
+        assert!(!message.contains("hunter2"));
+        assert!(message.contains("postgres://app:***@db:5432/collider"));
+    }
+}
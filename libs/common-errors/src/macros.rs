@@ -0,0 +1,73 @@
+/// Generate `From<$error> for AppError` from one arm per variant.
+///
+/// An arm either names an [`AppError`](crate::AppError) constructor and its
+/// arguments, or gives a block that builds the error itself. Tuple and
+/// struct variants bind their fields for use on the right-hand side.
+///
+/// ```
+/// use common_errors::{AppError, impl_app_error};
+///
+/// enum LookupError {
+///     Missing,
+///     Taken { name: String },
+///     Backend(String),
+/// }
+///
+/// impl_app_error! {
+///     LookupError => {
+///         Missing => not_found("MISSING", "Nothing here"),
+///         Taken { name } => unprocessable_entity(
+///             "TAKEN",
+///             &format!("{name} is taken")
+///         ),
+///         Backend(msg) => {
+///             AppError::internal_server_error(&format!("Backend: {msg}"))
+///         },
+///     }
+/// }
+///
+/// let err: AppError = LookupError::Missing.into();
+/// assert!(matches!(err, AppError::NotFound { .. }));
+/// ```
+#[macro_export]
+macro_rules! impl_app_error {
+    ($error:ident => { $($arms:tt)* }) => {
+        impl From<$error> for $crate::AppError {
+            fn from(err: $error) -> Self {
+                $crate::impl_app_error!(@arms err $error [] $($arms)*)
+            }
+        }
+    };
+    (@arms $err:ident $error:ident [$($out:tt)*]) => {
+        match $err { $($out)* }
+    };
+    (@arms $err:ident $error:ident [$($out:tt)*]
+        $variant:ident => $ctor:ident($($args:tt)*) $(, $($rest:tt)*)?
+    ) => {
+        $crate::impl_app_error!(@arms $err $error [
+            $($out)* $error::$variant => $crate::AppError::$ctor($($args)*),
+        ] $($($rest)*)?)
+    };
+    (@arms $err:ident $error:ident [$($out:tt)*]
+        $variant:ident => $body:block $(, $($rest:tt)*)?
+    ) => {
+        $crate::impl_app_error!(@arms $err $error [
+            $($out)* $error::$variant => $body,
+        ] $($($rest)*)?)
+    };
+    (@arms $err:ident $error:ident [$($out:tt)*]
+        $variant:ident $fields:tt => $ctor:ident($($args:tt)*)
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::impl_app_error!(@arms $err $error [
+            $($out)* $error::$variant $fields => $crate::AppError::$ctor($($args)*),
+        ] $($($rest)*)?)
+    };
+    (@arms $err:ident $error:ident [$($out:tt)*]
+        $variant:ident $fields:tt => $body:block $(, $($rest:tt)*)?
+    ) => {
+        $crate::impl_app_error!(@arms $err $error [
+            $($out)* $error::$variant $fields => $body,
+        ] $($($rest)*)?)
+    };
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// Parse an RFC3339 timestamp, or a bare `YYYY-MM-DD` date taken as
+/// midnight UTC. Timestamps with an offset are converted to UTC.
+pub fn parse_utc(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(raw) {
+        return Some(at.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|at| at.and_utc())
+}
+
+/// `deserialize_with` for optional date parameters, accepting whatever
+/// [`parse_utc`] does. Pair it with `#[serde(default)]` so an absent
+/// parameter stays `None`.
+pub fn optional_utc<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|raw| {
+            parse_utc(&raw).ok_or_else(|| {
+                D::Error::custom(format!(
+                    "invalid date '{raw}', expected YYYY-MM-DD or RFC3339"
+                ))
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Range {
+        #[serde(default, deserialize_with = "optional_utc")]
+        start_date: Option<DateTime<Utc>>,
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_date_only_is_utc_midnight() {
+        let range: Range =
+            serde_json::from_str(r#"{"start_date": "2025-03-09"}"#).unwrap();
+        assert_eq!(range.start_date, Some(midnight(2025, 3, 9)));
+    }
+
+    #[test]
+    fn test_rfc3339_is_normalized_to_utc() {
+        assert_eq!(
+            parse_utc("2025-03-09T02:00:00+02:00"),
+            Some(midnight(2025, 3, 9))
+        );
+        assert_eq!(
+            parse_utc("2025-03-09T00:00:00Z"),
+            Some(midnight(2025, 3, 9))
+        );
+    }
+
+    #[test]
+    fn test_missing_and_malformed_dates() {
+        let range: Range = serde_json::from_str("{}").unwrap();
+        assert_eq!(range.start_date, None);
+
+        for raw in ["2025-13-01", "09/03/2025", "yesterday"] {
+            let json = format!(r#"{{"start_date": "{raw}"}}"#);
+            let err = serde_json::from_str::<Range>(&json).unwrap_err();
+            assert!(err.to_string().contains("YYYY-MM-DD"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use chrono::{DateTime, Utc};
+use http::{
+    HeaderMap, HeaderValue,
+    header::{ETAG, IF_NONE_MATCH},
+};
+
+/// Row count and newest timestamp of a (filtered) collection; any insert
+/// or delete changes at least one of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CollectionVersion {
+    pub count: i64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Weak ETag for a list response.
+///
+/// Built from the [`CollectionVersion`] plus whatever else shapes the body
+/// (query parameters, ids of the returned rows), so two responses share a
+/// tag only when their payloads would match.
+#[derive(Debug, Clone)]
+pub struct CollectionEtag {
+    version: CollectionVersion,
+    hasher: DefaultHasher,
+}
+
+impl CollectionEtag {
+    pub fn new(version: CollectionVersion) -> Self {
+        Self {
+            version,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    /// Mix in anything else the response depends on
+    pub fn scope(mut self, value: impl Hash) -> Self {
+        value.hash(&mut self.hasher);
+        self
+    }
+
+    /// `W/"<count>-<last modified micros>-<scope hash>"`
+    pub fn value(&self) -> String {
+        format!(
+            "W/\"{}-{}-{:016x}\"",
+            self.version.count,
+            self.version
+                .last_modified
+                .map_or(0, |ts| ts.timestamp_micros()),
+            self.hasher.finish()
+        )
+    }
+
+    pub fn header_value(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.value())
+            .expect("ETag is always visible ASCII")
+    }
+
+    /// Whether `If-None-Match` names this tag (weak comparison) or is `*`
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        let own = self.value();
+        let own = opaque(&own);
+        headers
+            .get_all(IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || opaque(tag) == own)
+    }
+
+    /// Headers for a response carrying this tag
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, self.header_value());
+        headers
+    }
+}
+
+fn opaque(tag: &str) -> &str { tag.strip_prefix("W/").unwrap_or(tag) }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn version(count: i64) -> CollectionVersion {
+        CollectionVersion {
+            count,
+            last_modified: Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()),
+        }
+    }
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_tag_changes_with_version_and_scope() {
+        let base = CollectionEtag::new(version(3)).scope("limit=10");
+
+        assert_eq!(
+            base.value(),
+            CollectionEtag::new(version(3)).scope("limit=10").value()
+        );
+        assert_ne!(
+            base.value(),
+            CollectionEtag::new(version(4)).scope("limit=10").value()
+        );
+        assert_ne!(
+            base.value(),
+            CollectionEtag::new(version(3)).scope("limit=20").value()
+        );
+        assert!(base.value().starts_with("W/\"3-1700000000000000-"));
+    }
+
+    #[test]
+    fn test_if_none_match_uses_weak_comparison() {
+        let tag = CollectionEtag::new(version(3));
+        let strong = tag.value().trim_start_matches("W/").to_string();
+
+        assert!(tag.matches(&if_none_match(&tag.value())));
+        assert!(tag.matches(&if_none_match(&strong)));
+        assert!(tag.matches(&if_none_match(&format!("\"x\", {strong}"))));
+        assert!(tag.matches(&if_none_match("*")));
+        assert!(!tag.matches(&if_none_match("\"x\"")));
+        assert!(!tag.matches(&HeaderMap::new()));
+    }
+}
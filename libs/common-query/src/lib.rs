@@ -0,0 +1,230 @@
+use std::{fmt, time::Duration};
+
+use common_errors::{AppError, FieldError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+
+pub mod date;
+mod etag;
+
+pub use etag::{CollectionEtag, CollectionVersion};
+
+/// How long a collection's total count may be served from cache by
+/// default. Writes through the command handlers clear it sooner.
+pub const DEFAULT_COUNT_TTL: Duration = Duration::from_secs(30);
+
+/// Response header carrying the total size of an unfiltered collection
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+
+    pub fn reverse(&self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_sql())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SortError {
+    #[error("Cannot sort by '{column}', expected one of {allowed:?}")]
+    UnknownColumn {
+        column: String,
+        allowed: &'static [&'static str],
+    },
+}
+
+impl From<SortError> for AppError {
+    fn from(err: SortError) -> Self {
+        match &err {
+            SortError::UnknownColumn { .. } => {
+                AppError::validation(vec![FieldError::new(
+                    "sort",
+                    "unknown_column",
+                    &err.to_string(),
+                )])
+            }
+        }
+    }
+}
+
+/// A column checked against a [`SortColumns`] allowlist. It can only be
+/// built from the allowlist, so it is always safe to splice into SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortColumn {
+    name: &'static str,
+    tie_breaker: Option<&'static str>,
+}
+
+impl SortColumn {
+    pub fn name(&self) -> &'static str { self.name }
+}
+
+/// The columns a listing may be sorted by, plus an optional unique column
+/// appended to every ordering so rows with equal sort keys keep a stable
+/// order across pages.
+#[derive(Debug, Clone, Copy)]
+pub struct SortColumns {
+    columns: &'static [&'static str],
+    tie_breaker: Option<&'static str>,
+}
+
+impl SortColumns {
+    pub const fn new(columns: &'static [&'static str]) -> Self {
+        Self {
+            columns,
+            tie_breaker: None,
+        }
+    }
+
+    pub const fn with_tie_breaker(mut self, column: &'static str) -> Self {
+        self.tie_breaker = Some(column);
+        self
+    }
+
+    /// Look up a caller-supplied column name
+    pub fn column(&self, name: &str) -> Result<SortColumn, SortError> {
+        self.columns
+            .iter()
+            .find(|column| column.eq_ignore_ascii_case(name))
+            .map(|&column| {
+                SortColumn {
+                    name: column,
+                    tie_breaker: self.tie_breaker.filter(|&t| t != column),
+                }
+            })
+            .ok_or_else(|| {
+                SortError::UnknownColumn {
+                    column: name.to_string(),
+                    allowed: self.columns,
+                }
+            })
+    }
+
+    pub fn order_by(
+        &self, name: &str, direction: SortDirection,
+    ) -> Result<OrderBy, SortError> {
+        Ok(OrderBy::new(self.column(name)?, direction))
+    }
+}
+
+/// Query parameters shared by `DELETE` endpoints
+#[derive(Debug, Clone, Copy, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteParams {
+    /// Answer `204` instead of `404` when the resource is already gone, so
+    /// a retried delete succeeds
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+/// A validated `ORDER BY` clause, accepted by DAOs in place of raw SQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderBy {
+    pub column: SortColumn,
+    pub direction: SortDirection,
+}
+
+impl OrderBy {
+    pub fn new(column: SortColumn, direction: SortDirection) -> Self {
+        Self { column, direction }
+    }
+
+    /// Render as `ORDER BY <column> <dir>[, <tie breaker> <dir>]`
+    pub fn to_sql(&self) -> String {
+        let direction = self.direction.as_sql();
+        match self.column.tie_breaker {
+            Some(tie_breaker) => {
+                format!(
+                    "ORDER BY {} {direction}, {tie_breaker} {direction}",
+                    self.column.name
+                )
+            }
+            None => format!("ORDER BY {} {direction}", self.column.name),
+        }
+    }
+}
+
+impl fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_sql())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: SortColumns =
+        SortColumns::new(&["name", "created_at"]).with_tie_breaker("id");
+
+    #[test]
+    fn test_known_columns_render() {
+        let order = COLUMNS.order_by("name", SortDirection::Asc).unwrap();
+        assert_eq!(order.to_sql(), "ORDER BY name ASC, id ASC");
+
+        let order =
+            COLUMNS.order_by("CREATED_AT", SortDirection::Desc).unwrap();
+        assert_eq!(order.to_sql(), "ORDER BY created_at DESC, id DESC");
+
+        let plain = SortColumns::new(&["name"]);
+        let order = plain.order_by("name", SortDirection::Desc).unwrap();
+        assert_eq!(order.to_sql(), "ORDER BY name DESC");
+    }
+
+    #[test]
+    fn test_unknown_column_is_rejected() {
+        for column in ["password", "name; DROP TABLE users", "", "id"] {
+            let err =
+                COLUMNS.order_by(column, SortDirection::Asc).unwrap_err();
+            assert_eq!(
+                err,
+                SortError::UnknownColumn {
+                    column: column.to_string(),
+                    allowed: &["name", "created_at"],
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_direction_deserializes_lowercase() {
+        let direction: SortDirection =
+            serde_json::from_str("\"desc\"").unwrap();
+        assert_eq!(direction, SortDirection::Desc);
+        assert_eq!(direction.reverse(), SortDirection::Asc);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::time::interval;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// Longest random delay before a job's schedule starts, by default
+pub const DEFAULT_STARTUP_JITTER: Duration = Duration::from_secs(30);
+
+type JobFn =
+    Arc<dyn Fn() -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync>;
+
+/// A job can't run every zero seconds
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Background job {0} needs a non-zero interval")]
+pub struct ZeroInterval(pub String);
+
+/// How the latest run of a job ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum JobResult {
+    Success,
+    Failure { error: String },
+}
+
+/// A registered job and its latest run, if it has run yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobStatus {
+    pub name: String,
+    /// Seconds between scheduled runs
+    pub every_secs: u64,
+    /// A run is in progress right now
+    pub running: bool,
+    /// Runs skipped because the previous one had not finished
+    pub skipped_runs: u64,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_result: Option<JobResult>,
+}
+
+struct LastRun {
+    at: DateTime<Utc>,
+    duration: Duration,
+    result: JobResult,
+}
+
+struct Job {
+    name: String,
+    every: Duration,
+    run: JobFn,
+    last: Mutex<Option<LastRun>>,
+    running: AtomicBool,
+    skipped: AtomicU64,
+}
+
+/// Clears `Job::running` when a run ends, even by panicking
+struct RunningGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) { self.0.store(false, Ordering::Release); }
+}
+
+impl Job {
+    /// Run the job unless its previous run is still going, in which case
+    /// the run is skipped and the current status returned
+    async fn execute(&self) -> JobStatus {
+        if self.running.swap(true, Ordering::AcqRel) {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "Background job {} is still running, skipping this run",
+                self.name
+            );
+            return self.status();
+        }
+        let guard = RunningGuard(&self.running);
+
+        let at = Utc::now();
+        let started = Instant::now();
+        let result = match (self.run)().await {
+            Ok(()) => JobResult::Success,
+            Err(e) => {
+                error!("Background job {} failed: {}", self.name, e);
+                JobResult::Failure {
+                    error: e.to_string(),
+                }
+            }
+        };
+        let duration = started.elapsed();
+        info!("Background job {} ran in {:?}", self.name, duration);
+
+        *self.last.lock().unwrap() = Some(LastRun {
+            at,
+            duration,
+            result,
+        });
+        drop(guard);
+        self.status()
+    }
+
+    fn status(&self) -> JobStatus {
+        let last = self.last.lock().unwrap();
+        JobStatus {
+            name: self.name.clone(),
+            every_secs: self.every.as_secs(),
+            running: self.running.load(Ordering::Acquire),
+            skipped_runs: self.skipped.load(Ordering::Relaxed),
+            last_run: last.as_ref().map(|run| run.at),
+            last_duration_ms: last
+                .as_ref()
+                .map(|run| run.duration.as_millis() as u64),
+            last_result: last.as_ref().map(|run| run.result.clone()),
+        }
+    }
+}
+
+/// Runs registered jobs on their own intervals and remembers how each
+/// one's latest run went. Clones share the same jobs.
+///
+/// A job never runs twice at once: a tick arriving while the previous run
+/// is still going is skipped, as is a manual trigger. Each schedule starts
+/// after a random delay of up to the startup jitter, so replicas booted
+/// together don't all run a job at the same moment.
+#[derive(Clone)]
+pub struct BackgroundJobScheduler {
+    jobs: Arc<RwLock<Vec<Arc<Job>>>>,
+    startup_jitter: Duration,
+}
+
+impl Default for BackgroundJobScheduler {
+    fn default() -> Self { Self::new() }
+}
+
+impl BackgroundJobScheduler {
+    /// A scheduler without jobs
+    pub fn new() -> Self {
+        Self {
+            jobs: Arc::default(),
+            startup_jitter: DEFAULT_STARTUP_JITTER,
+        }
+    }
+
+    /// Delay each job's first run by a random amount up to `max`
+    pub fn with_startup_jitter(mut self, max: Duration) -> Self {
+        self.startup_jitter = max;
+        self
+    }
+
+    /// Add a job run every `every`, replacing any job of the same name.
+    /// Only jobs registered before [`start`] are scheduled; later ones can
+    /// still be run with [`trigger`].
+    ///
+    /// [`start`]: BackgroundJobScheduler::start
+    /// [`trigger`]: BackgroundJobScheduler::trigger
+    pub fn register<F, Fut>(
+        &self, name: &str, every: Duration, run: F,
+    ) -> Result<(), ZeroInterval>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        if every.is_zero() {
+            return Err(ZeroInterval(name.to_string()));
+        }
+
+        let job = Arc::new(Job {
+            name: name.to_string(),
+            every,
+            run: Arc::new(move || -> BoxFuture<'static, _> {
+                Box::pin(run())
+            }),
+            last: Mutex::new(None),
+            running: AtomicBool::new(false),
+            skipped: AtomicU64::new(0),
+        });
+
+        let mut jobs = self.jobs.write().unwrap();
+        jobs.retain(|existing| existing.name != name);
+        jobs.push(job);
+        Ok(())
+    }
+
+    /// Every registered job with its latest run
+    pub fn status(&self) -> Vec<JobStatus> {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .map(|job| job.status())
+            .collect()
+    }
+
+    /// Run the job named `name` now and return its status afterwards;
+    /// `None` when no such job is registered. When the job is already
+    /// running nothing new is started and the status says `running`.
+    pub async fn trigger(&self, name: &str) -> Option<JobStatus> {
+        let job = self
+            .jobs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|job| job.name == name)
+            .cloned()?;
+        Some(job.execute().await)
+    }
+
+    /// Start all background jobs
+    pub async fn start(&self) {
+        let jobs = self.jobs.read().unwrap().clone();
+        let mut rng = SmallRng::from_entropy();
+        for job in jobs {
+            let jitter =
+                self.startup_jitter.mul_f64(rng.gen_range(0.0..=1.0));
+            info!(
+                "Starting background job {} (every {:?}, after {:?})",
+                job.name, job.every, jitter
+            );
+            tokio::spawn(async move {
+                tokio::time::sleep(jitter).await;
+                let mut ticks = interval(job.every);
+                ticks.tick().await; // Skip first immediate tick
+
+                loop {
+                    ticks.tick().await;
+                    // Run off the ticking task so a slow run shows up as
+                    // skipped ticks instead of a drifting schedule
+                    let job = job.clone();
+                    tokio::spawn(async move { job.execute().await });
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trigger_records_failures_and_unknown_jobs() {
+        let scheduler = BackgroundJobScheduler::new();
+        scheduler
+            .register("flaky", Duration::from_secs(60), || {
+                async { Err(anyhow::anyhow!("upstream unavailable")) }
+            })
+            .unwrap();
+
+        assert!(scheduler.trigger("missing").await.is_none());
+
+        let status = scheduler.trigger("flaky").await.unwrap();
+        assert_eq!(
+            status.last_result,
+            Some(JobResult::Failure {
+                error: "upstream unavailable".to_string()
+            })
+        );
+        assert!(status.last_run.is_some());
+
+        let names: Vec<_> =
+            scheduler.status().into_iter().map(|job| job.name).collect();
+        assert_eq!(names, ["flaky"]);
+    }
+
+    #[test]
+    fn test_zero_interval_is_rejected() {
+        let scheduler = BackgroundJobScheduler::new();
+
+        let registered =
+            scheduler.register("busy", Duration::ZERO, || async { Ok(()) });
+
+        assert_eq!(registered, Err(ZeroInterval("busy".to_string())));
+        assert!(scheduler.status().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_job_skips_overlapping_ticks() {
+        let scheduler =
+            BackgroundJobScheduler::new().with_startup_jitter(Duration::ZERO);
+        tokio::time::pause();
+
+        let active = Arc::new(AtomicU64::new(0));
+        let most_active = Arc::new(AtomicU64::new(0));
+        let (now, most) = (active.clone(), most_active.clone());
+        scheduler
+            .register("slow", Duration::from_millis(100), move || {
+                let (now, most) = (now.clone(), most.clone());
+                async move {
+                    let running = now.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(running, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                    now.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+            .unwrap();
+
+        scheduler.start().await;
+        tokio::time::sleep(Duration::from_millis(1050)).await;
+
+        let slow = scheduler
+            .status()
+            .into_iter()
+            .find(|job| job.name == "slow")
+            .unwrap();
+        assert_eq!(most_active.load(Ordering::SeqCst), 1);
+        assert_eq!(slow.last_result, Some(JobResult::Success));
+        assert!(slow.skipped_runs >= 4, "{slow:?}");
+    }
+}
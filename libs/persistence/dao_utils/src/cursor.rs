@@ -0,0 +1,172 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use common_errors::{AppError, FieldError};
+use common_query::SortDirection;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Version byte written in front of every cursor. Bump it when the payload
+/// layout changes so cursors handed out by an older build are turned away
+/// instead of being misread.
+pub const CURSOR_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CursorError {
+    #[error("Malformed cursor")]
+    Malformed,
+    #[error("Cursor version {found} is not supported, expected {expected}")]
+    UnsupportedVersion { found: u8, expected: u8 },
+    #[error("Cursor does not match this listing")]
+    Mismatch,
+}
+
+impl From<CursorError> for AppError {
+    fn from(err: CursorError) -> Self {
+        AppError::bad_request("INVALID_CURSOR", &err.to_string())
+    }
+}
+
+impl From<CursorError> for FieldError {
+    fn from(err: CursorError) -> Self {
+        FieldError::new("cursor", "INVALID_CURSOR", &err.to_string())
+    }
+}
+
+/// Opaque position in a keyset-paginated listing: the sort key of the last
+/// row served and the direction it was read in. Clients only ever see the
+/// [`encode`]d form, so column names and values stay out of the URL
+/// contract and the layout can change behind [`CURSOR_VERSION`].
+///
+/// [`encode`]: Cursor::encode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub sort_values: Vec<Value>,
+    pub direction: SortDirection,
+}
+
+impl Cursor {
+    pub fn new(sort_values: Vec<Value>, direction: SortDirection) -> Self {
+        Self {
+            sort_values,
+            direction,
+        }
+    }
+
+    /// URL-safe base64 of the version byte followed by the JSON payload
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![CURSOR_VERSION];
+        serde_json::to_writer(&mut bytes, self)
+            .expect("cursor payload is always serializable");
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, CursorError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw)
+            .map_err(|_| CursorError::Malformed)?;
+        let (&version, payload) =
+            bytes.split_first().ok_or(CursorError::Malformed)?;
+        if version != CURSOR_VERSION {
+            return Err(CursorError::UnsupportedVersion {
+                found: version,
+                expected: CURSOR_VERSION,
+            });
+        }
+
+        serde_json::from_slice(payload).map_err(|_| CursorError::Malformed)
+    }
+
+    /// The sort values as the typed key a listing expects, e.g.
+    /// `(String, i64)`. A cursor read in another direction or carrying a
+    /// key of a different shape belongs to some other listing and is
+    /// rejected.
+    pub fn sort_key<K: DeserializeOwned>(
+        &self, direction: SortDirection,
+    ) -> Result<K, CursorError> {
+        if self.direction != direction {
+            return Err(CursorError::Mismatch);
+        }
+
+        K::deserialize(Value::Array(self.sort_values.clone()))
+            .map_err(|_| CursorError::Mismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sample() -> Cursor {
+        Cursor::new(vec![json!("ann_marie"), json!(42)], SortDirection::Asc)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cursor = sample();
+        let raw = cursor.encode();
+
+        assert!(
+            raw.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "{raw}"
+        );
+        assert_eq!(Cursor::decode(&raw), Ok(cursor));
+    }
+
+    #[test]
+    fn test_sort_key_is_typed() {
+        let key: (String, i64) =
+            sample().sort_key(SortDirection::Asc).unwrap();
+        assert_eq!(key, ("ann_marie".to_string(), 42));
+    }
+
+    #[test]
+    fn test_tampered_cursor_is_rejected() {
+        let raw = sample().encode();
+
+        let mut flipped = raw.clone().into_bytes();
+        let last = flipped.len() - 2;
+        flipped[last] = if flipped[last] == b'A' { b'B' } else { b'A' };
+        let flipped = String::from_utf8(flipped).unwrap();
+
+        for bad in ["", "not base64!", &raw[..raw.len() - 3], &flipped] {
+            assert_eq!(Cursor::decode(bad), Err(CursorError::Malformed));
+        }
+    }
+
+    #[test]
+    fn test_cursor_for_another_listing_is_rejected() {
+        let cursor = sample();
+        assert_eq!(
+            cursor.sort_key::<(String, i64)>(SortDirection::Desc),
+            Err(CursorError::Mismatch)
+        );
+        assert_eq!(
+            cursor.sort_key::<(i64, i64)>(SortDirection::Asc),
+            Err(CursorError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn test_version_mismatch_is_rejected() {
+        let mut bytes = URL_SAFE_NO_PAD.decode(sample().encode()).unwrap();
+        bytes[0] = CURSOR_VERSION + 1;
+        let raw = URL_SAFE_NO_PAD.encode(bytes);
+
+        assert_eq!(
+            Cursor::decode(&raw),
+            Err(CursorError::UnsupportedVersion {
+                found: CURSOR_VERSION + 1,
+                expected: CURSOR_VERSION,
+            })
+        );
+
+        let err: AppError = Cursor::decode(&raw).unwrap_err().into();
+        assert!(
+            err.to_string().contains("version 2 is not supported"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use tokio_postgres::types::ToSql;
+
+use crate::{params::QueryParams, query_helpers::PgParam};
+
+/// Comparison operator of a single filter condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "<>",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+}
+
+/// Accumulates `column <op> $n` conditions together with their values in
+/// [`QueryParams`].
+///
+/// Columns are `&'static str` so only names written in code, never request
+/// input, end up in the SQL.
+#[derive(Default)]
+pub struct FilterBuilder {
+    conditions: Vec<String>,
+    params: QueryParams,
+}
+
+impl FilterBuilder {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add `column <op> value`
+    pub fn cmp<T>(
+        &mut self, column: &'static str, op: Op, value: T,
+    ) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        let placeholder = self.bind(value);
+        self.conditions
+            .push(format!("{column} {} {placeholder}", op.as_sql()));
+        self
+    }
+
+    /// Add `column <op> value` when `value` is present
+    pub fn cmp_opt<T>(
+        &mut self, column: &'static str, op: Op, value: Option<T>,
+    ) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        if let Some(value) = value {
+            self.cmp(column, op, value);
+        }
+        self
+    }
+
+    pub fn eq<T>(&mut self, column: &'static str, value: T) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.cmp(column, Op::Eq, value)
+    }
+
+    pub fn eq_opt<T>(
+        &mut self, column: &'static str, value: Option<T>,
+    ) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.cmp_opt(column, Op::Eq, value)
+    }
+
+    /// Add `(first, second) <op> (a, b)`, the row comparison a keyset
+    /// page seeks past the previous one with
+    pub fn cmp_pair<A, B>(
+        &mut self, columns: (&'static str, &'static str), op: Op,
+        values: (A, B),
+    ) -> &mut Self
+    where
+        A: ToSql + Sync + Send + 'static,
+        B: ToSql + Sync + Send + 'static,
+    {
+        let first = self.bind(values.0);
+        let second = self.bind(values.1);
+        self.conditions.push(format!(
+            "({}, {}) {} ({first}, {second})",
+            columns.0,
+            columns.1,
+            op.as_sql()
+        ));
+        self
+    }
+
+    /// Push a param used outside the WHERE clause, such as `LIMIT`, and
+    /// return its placeholder
+    pub fn bind<T>(&mut self, value: T) -> String
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.params.bind(value)
+    }
+
+    pub fn is_empty(&self) -> bool { self.conditions.is_empty() }
+
+    /// ` WHERE a AND b`, or an empty string without conditions
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            String::new()
+        }
+        else {
+            format!(" WHERE {}", self.conditions.join(" AND "))
+        }
+    }
+
+    /// Params in placeholder order, ready for `Client::query`
+    pub fn params(&self) -> Vec<&PgParam> { self.params.as_refs() }
+
+    pub fn param_count(&self) -> usize { self.params.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_conditions() {
+        let filter = FilterBuilder::new();
+        assert!(filter.is_empty());
+        assert_eq!(filter.where_clause(), "");
+        assert_eq!(filter.param_count(), 0);
+    }
+
+    #[test]
+    fn test_one_condition() {
+        let mut filter = FilterBuilder::new();
+        filter.eq("user_id", 7i64);
+
+        assert_eq!(filter.where_clause(), " WHERE user_id = $1");
+        assert_eq!(filter.params().len(), 1);
+    }
+
+    #[test]
+    fn test_multiple_conditions_skip_missing_values() {
+        let mut filter = FilterBuilder::new();
+        filter
+            .eq_opt("user_id", Some(7i64))
+            .eq_opt("event_type_id", None::<i32>)
+            .cmp("timestamp", Op::Ge, 0i64)
+            .cmp_opt("id", Op::Lt, Some(100i64));
+        let limit = filter.bind(10i64);
+
+        assert_eq!(
+            filter.where_clause(),
+            " WHERE user_id = $1 AND timestamp >= $2 AND id < $3"
+        );
+        assert_eq!(limit, "$4");
+        assert_eq!(filter.param_count(), 4);
+    }
+
+    #[test]
+    fn test_pair_condition() {
+        let mut filter = FilterBuilder::new();
+        filter.eq("user_id", 7i64).cmp_pair(
+            ("timestamp", "id"),
+            Op::Lt,
+            (0i64, 42i64),
+        );
+
+        assert_eq!(
+            filter.where_clause(),
+            " WHERE user_id = $1 AND (timestamp, id) < ($2, $3)"
+        );
+        assert_eq!(filter.param_count(), 3);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod cursor;
 pub mod error_handling;
+pub mod filter;
 pub mod pagination;
+pub mod params;
 pub mod query_helpers;
+pub mod update;
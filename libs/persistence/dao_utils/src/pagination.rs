@@ -1,6 +1,30 @@
+use common_errors::AppError;
+use thiserror::Error;
 use tokio_postgres::types::ToSql;
 
-#[derive(Debug, Clone)]
+/// Page size of a listing whose request names no `limit`
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// Largest page size a listing serves
+pub const MAX_PAGE_SIZE: u64 = 1000;
+
+/// Deepest `offset` a listing accepts; anything past it is either a typo or
+/// a scan better served by a cursor
+pub const MAX_OFFSET: u64 = 10_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PaginationError {
+    #[error("Offset {offset} is too large, expected at most {max}")]
+    OffsetTooLarge { offset: u64, max: u64 },
+}
+
+impl From<PaginationError> for AppError {
+    fn from(err: PaginationError) -> Self {
+        AppError::bad_request("INVALID_PAGINATION", &err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PaginationParams {
     pub limit: Option<u64>,
     pub offset: Option<u64>,
@@ -11,6 +35,25 @@ impl PaginationParams {
         Self { limit, offset }
     }
 
+    /// Pagination taken from a request: `limit` is clamped to
+    /// `1..=max_limit` and an `offset` beyond [`MAX_OFFSET`] is rejected.
+    /// A missing `limit` stays unset so the caller's default applies.
+    pub fn validated(
+        limit: Option<u64>, offset: Option<u64>, max_limit: u64,
+    ) -> Result<Self, PaginationError> {
+        if let Some(offset) = offset.filter(|&offset| offset > MAX_OFFSET) {
+            return Err(PaginationError::OffsetTooLarge {
+                offset,
+                max: MAX_OFFSET,
+            });
+        }
+
+        Ok(Self {
+            limit: limit.map(|limit| limit.clamp(1, max_limit.max(1))),
+            offset,
+        })
+    }
+
     pub fn build_query_parts(
         &self, base_query: &str, order_by: &str,
     ) -> (String, Vec<i64>) {
@@ -86,7 +129,7 @@ impl<T> CursorPagination<T> {
     pub fn new(cursor: Option<T>, limit: u64) -> Self {
         Self {
             cursor,
-            limit: limit.min(1000), // Cap at 1000
+            limit: limit.min(MAX_PAGE_SIZE),
         }
     }
 
@@ -98,3 +141,50 @@ pub fn create_param_refs<T: ToSql + Sync>(
 ) -> Vec<&(dyn ToSql + Sync)> {
     params.iter().map(|p| p as &(dyn ToSql + Sync)).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validated_clamps_limit() {
+        let params =
+            PaginationParams::validated(Some(99_999_999), None, 1000)
+                .unwrap();
+        assert_eq!(params.limit, Some(1000));
+
+        let params =
+            PaginationParams::validated(Some(0), Some(20), 1000).unwrap();
+        assert_eq!(params, PaginationParams::new(Some(1), Some(20)));
+    }
+
+    #[test]
+    fn test_validated_keeps_defaults_unset() {
+        let params = PaginationParams::validated(None, None, 1000).unwrap();
+        assert_eq!(params, PaginationParams::new(None, None));
+
+        let params =
+            PaginationParams::validated(Some(50), Some(MAX_OFFSET), 1000)
+                .unwrap();
+        assert_eq!(params, PaginationParams::new(Some(50), Some(MAX_OFFSET)));
+    }
+
+    #[test]
+    fn test_validated_rejects_absurd_offsets() {
+        for offset in [MAX_OFFSET + 1, i64::MAX as u64 + 1, u64::MAX] {
+            assert_eq!(
+                PaginationParams::validated(Some(10), Some(offset), 1000),
+                Err(PaginationError::OffsetTooLarge {
+                    offset,
+                    max: MAX_OFFSET,
+                })
+            );
+        }
+
+        let err: AppError =
+            PaginationParams::validated(None, Some(u64::MAX), 1000)
+                .unwrap_err()
+                .into();
+        assert!(matches!(err, AppError::BadRequest { .. }));
+    }
+}
@@ -0,0 +1,50 @@
+use tokio_postgres::types::ToSql;
+
+use crate::query_helpers::{PgParam, PgParamVec};
+
+/// The values of a statement being built, numbering each placeholder from
+/// the params pushed so far so the SQL and the param vec cannot drift
+/// apart. Shared by [`FilterBuilder`] and [`UpdateBuilder`].
+///
+/// [`FilterBuilder`]: crate::filter::FilterBuilder
+/// [`UpdateBuilder`]: crate::update::UpdateBuilder
+#[derive(Default)]
+pub struct QueryParams(PgParamVec);
+
+impl QueryParams {
+    pub fn new() -> Self { Self::default() }
+
+    /// Push a value and return its placeholder
+    pub fn bind<T>(&mut self, value: T) -> String
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.0.push(Box::new(value));
+        format!("${}", self.0.len())
+    }
+
+    /// Params in placeholder order, ready for `Client::query`
+    pub fn as_refs(&self) -> Vec<&PgParam> {
+        self.0.iter().map(|p| p.as_ref() as &PgParam).collect()
+    }
+
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_follow_push_order() {
+        let mut params = QueryParams::new();
+        assert!(params.is_empty());
+
+        assert_eq!(params.bind(7i64), "$1");
+        assert_eq!(params.bind("alice".to_string()), "$2");
+        assert_eq!(params.len(), 2);
+        assert_eq!(params.as_refs().len(), 2);
+    }
+}
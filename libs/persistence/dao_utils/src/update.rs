@@ -0,0 +1,108 @@
+use tokio_postgres::types::ToSql;
+
+use crate::{params::QueryParams, query_helpers::PgParam};
+
+/// Accumulates the `column = $n` assignments of a partial `UPDATE`
+/// together with their values, so only the fields a caller provided are
+/// written. Columns are `&'static str`, as in
+/// [`FilterBuilder`](crate::filter::FilterBuilder).
+#[derive(Default)]
+pub struct UpdateBuilder {
+    assignments: Vec<String>,
+    params: QueryParams,
+}
+
+impl UpdateBuilder {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add `column = value`
+    pub fn set<T>(&mut self, column: &'static str, value: T) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        let placeholder = self.bind(value);
+        self.assignments.push(format!("{column} = {placeholder}"));
+        self
+    }
+
+    /// Add `column = value` when `value` is present
+    pub fn set_opt<T>(
+        &mut self, column: &'static str, value: Option<T>,
+    ) -> &mut Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        if let Some(value) = value {
+            self.set(column, value);
+        }
+        self
+    }
+
+    /// Push a param used outside the SET clause, such as the id in
+    /// `WHERE`, and return its placeholder
+    pub fn bind<T>(&mut self, value: T) -> String
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.params.bind(value)
+    }
+
+    /// Whether no column is being set, in which case there is no statement
+    /// to run
+    pub fn is_empty(&self) -> bool { self.assignments.is_empty() }
+
+    /// ` SET a = $1, b = $2`; empty when nothing is set
+    pub fn set_clause(&self) -> String {
+        if self.assignments.is_empty() {
+            String::new()
+        }
+        else {
+            format!(" SET {}", self.assignments.join(", "))
+        }
+    }
+
+    /// Params in placeholder order, ready for `Client::query`
+    pub fn params(&self) -> Vec<&PgParam> { self.params.as_refs() }
+
+    pub fn param_count(&self) -> usize { self.params.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nothing_to_set() {
+        let mut update = UpdateBuilder::new();
+        update.set_opt::<String>("name", None);
+
+        assert!(update.is_empty());
+        assert_eq!(update.set_clause(), "");
+        assert_eq!(update.param_count(), 0);
+    }
+
+    #[test]
+    fn test_single_field() {
+        let mut update = UpdateBuilder::new();
+        update.set_opt("name", Some("alice".to_string()));
+        let id = update.bind(7_i64);
+
+        assert_eq!(update.set_clause(), " SET name = $1");
+        assert_eq!(id, "$2");
+        assert_eq!(update.params().len(), 2);
+    }
+
+    #[test]
+    fn test_multiple_fields_skip_absent_ones() {
+        let mut update = UpdateBuilder::new();
+        update
+            .set_opt("name", Some("bob".to_string()))
+            .set_opt::<String>("email", None)
+            .set("active", true);
+        let id = update.bind(1_i64);
+
+        assert_eq!(update.set_clause(), " SET name = $1, active = $2");
+        assert_eq!(id, "$3");
+        assert_eq!(update.param_count(), 3);
+    }
+}
@@ -1,8 +1,12 @@
-use std::{borrow::Cow, marker::PhantomData, time::Duration};
+use std::{
+    borrow::Cow,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use moka::future::Cache;
+use moka::{Expiry, future::Cache};
 use serde::{Deserialize, Serialize};
 
 use super::r#trait::{CacheError, CacheResult, CacheTrait};
@@ -14,8 +18,63 @@ use crate::{
     },
 };
 
+/// In-process cache layer keyed by the full cache key
+pub type MemoryCache = Cache<String, MemoryEntry>;
+
+/// An encoded value held by a [`MemoryCache`]. It expires after its own
+/// TTL or the cache's configured one, whichever comes first.
+#[derive(Debug, Clone)]
+pub struct MemoryEntry {
+    pub bytes: Bytes,
+    pub ttl: Option<Duration>,
+}
+
+impl MemoryEntry {
+    /// An entry living as long as the cache's configured TTL allows
+    pub fn new(bytes: impl Into<Bytes>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            ttl: None,
+        }
+    }
+
+    /// An entry expiring after `ttl` at the latest
+    pub fn with_ttl(bytes: impl Into<Bytes>, ttl: Duration) -> Self {
+        Self {
+            bytes: bytes.into(),
+            ttl: Some(ttl),
+        }
+    }
+}
+
+struct EntryExpiry;
+
+impl Expiry<String, MemoryEntry> for EntryExpiry {
+    fn expire_after_create(
+        &self, _key: &String, entry: &MemoryEntry, _created_at: Instant,
+    ) -> Option<Duration> {
+        entry.ttl
+    }
+
+    fn expire_after_update(
+        &self, _key: &String, entry: &MemoryEntry, _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        entry.ttl
+    }
+}
+
+/// Build a [`MemoryCache`] bounded by `config`, honoring each entry's TTL
+pub fn memory_cache(config: &MemoryConfig) -> MemoryCache {
+    Cache::builder()
+        .max_capacity(config.capacity)
+        .time_to_live(config.ttl())
+        .expire_after(EntryExpiry)
+        .build()
+}
+
 pub struct Memory<'cache, T> {
-    memory: Cache<String, Bytes>,
+    memory: MemoryCache,
     #[allow(dead_code)]
     key: Cow<'static, str>,
     config: MemoryConfig,
@@ -51,10 +110,7 @@ where
     T: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'cache,
 {
     pub fn with_config(mut self, config: MemoryConfig) -> Self {
-        self.memory = Cache::builder()
-            .max_capacity(config.capacity)
-            .time_to_live(config.ttl())
-            .build();
+        self.memory = memory_cache(&config);
         self.config = config;
         self
     }
@@ -72,8 +128,8 @@ where
     }
 
     async fn get(&mut self, key: &str) -> CacheResult<Self::Value> {
-        if let Some(bytes) = self.memory.get(key).await {
-            let json = Json::<T>::from_bytes(&bytes).map_err(|e| {
+        if let Some(entry) = self.memory.get(key).await {
+            let json = Json::<T>::from_bytes(&entry.bytes).map_err(|e| {
                 CacheError::DeserializationError(e.to_string())
             })?;
             Ok(json.inner())
@@ -86,8 +142,8 @@ where
     async fn try_get(
         &mut self, key: &str,
     ) -> CacheResult<Option<Self::Value>> {
-        if let Some(bytes) = self.memory.get(key).await {
-            let json = Json::<T>::from_bytes(&bytes).map_err(|e| {
+        if let Some(entry) = self.memory.get(key).await {
+            let json = Json::<T>::from_bytes(&entry.bytes).map_err(|e| {
                 CacheError::DeserializationError(e.to_string())
             })?;
             Ok(Some(json.inner()))
@@ -105,21 +161,20 @@ where
             .to_bytes()
             .map_err(|e| CacheError::SerializationError(e.to_string()))?;
         self.memory
-            .insert(key.to_string(), Bytes::from(bytes))
+            .insert(key.to_string(), MemoryEntry::new(bytes))
             .await;
         Ok(())
     }
 
     async fn set_with_ttl(
-        &mut self, key: &str, value: &Self::Value, _ttl: Duration,
+        &mut self, key: &str, value: &Self::Value, ttl: Duration,
     ) -> CacheResult<()> {
-        // Note: Moka cache uses global TTL, not per-key TTL
         let json = Json(value.clone());
         let bytes = json
             .to_bytes()
             .map_err(|e| CacheError::SerializationError(e.to_string()))?;
         self.memory
-            .insert(key.to_string(), Bytes::from(bytes))
+            .insert(key.to_string(), MemoryEntry::with_ttl(bytes, ttl))
             .await;
         Ok(())
     }
@@ -133,7 +188,7 @@ where
                 .to_bytes()
                 .map_err(|e| CacheError::SerializationError(e.to_string()))?;
             self.memory
-                .insert(key.to_string(), Bytes::from(bytes))
+                .insert(key.to_string(), MemoryEntry::new(bytes))
                 .await;
             Ok(true)
         }
@@ -1,10 +1,28 @@
-use std::sync::{Arc, OnceLock};
+use std::{
+    any::Any,
+    borrow::Cow,
+    future::Future,
+    sync::{Arc, LazyLock, OnceLock, RwLock},
+    time::Duration,
+};
 
-use crate::core::backend::CacheBackend;
+use crate::{
+    core::backend::CacheBackend, metrics::CacheMetrics,
+    single_flight::SingleFlight, ttl::jittered, types::normal::Normal,
+};
 
 // Store Arc<CacheBackend> for efficient cloning
 static CACHE_BACKEND: OnceLock<Arc<CacheBackend<'static>>> = OnceLock::new();
 
+// Namespace in front of every key built by `cache_key!`; empty means none
+static KEY_PREFIX: RwLock<String> = RwLock::new(String::new());
+
+// Fetches in progress in `get_or_set`, by cache key. A value is shared
+// with the callers that waited for it, or `None` when the fetch failed.
+type SharedFetch = Option<Arc<dyn Any + Send + Sync>>;
+static IN_FLIGHT: LazyLock<SingleFlight<String, SharedFetch>> =
+    LazyLock::new(SingleFlight::new);
+
 pub struct CacheProvider;
 
 impl CacheProvider {
@@ -16,14 +34,47 @@ impl CacheProvider {
 
     /// Initialize the global cache backend with a memory cache (for testing)
     pub fn init_memory_static(config: crate::config::MemoryConfig) {
-        let cache = moka::future::Cache::builder()
-            .max_capacity(config.capacity)
-            .time_to_live(config.ttl())
-            .build();
+        let cache = crate::cache::memory::memory_cache(&config);
         let backend = Arc::new(CacheBackend::Memory { cache, config });
         CACHE_BACKEND.set(backend).ok();
     }
 
+    /// Initialize the global cache backend with an in-process L1 in front
+    /// of Redis, so previously cached reads survive a Redis outage
+    pub fn init_tiered_static(
+        pool: deadpool_redis::Pool, l1: crate::config::MemoryConfig,
+    ) {
+        let backend = Arc::new(Self::l1_redis_backend(pool, l1));
+        CACHE_BACKEND.set(backend).ok();
+    }
+
+    /// Prefix every key built by `cache_key!` bindings with `prefix`, e.g.
+    /// `prod:`, so deployments sharing one Redis don't read each other's
+    /// entries. Set it once at startup, before any key is built; an empty
+    /// prefix turns namespacing off.
+    pub fn set_key_prefix(prefix: &str) {
+        let mut current =
+            KEY_PREFIX.write().unwrap_or_else(|e| e.into_inner());
+        *current = prefix.to_string();
+    }
+
+    /// The prefix set with [`set_key_prefix`](Self::set_key_prefix)
+    pub fn key_prefix() -> String {
+        KEY_PREFIX.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// `key` in the configured namespace. Keys built by `cache_key!`
+    /// already are; use this for hand-built keys and patterns.
+    pub fn namespaced(key: Cow<'static, str>) -> Cow<'static, str> {
+        let prefix = KEY_PREFIX.read().unwrap_or_else(|e| e.into_inner());
+        if prefix.is_empty() {
+            key
+        }
+        else {
+            format!("{prefix}{key}").into()
+        }
+    }
+
     /// Get a clone of the global cache backend (cheap Arc clone)
     pub fn get_backend() -> Arc<CacheBackend<'static>> {
         CACHE_BACKEND
@@ -35,6 +86,126 @@ impl CacheProvider {
             .clone()
     }
 
+    /// Get the global cache backend if one has been initialized
+    pub fn try_get_backend() -> Option<Arc<CacheBackend<'static>>> {
+        CACHE_BACKEND.get().cloned()
+    }
+
+    /// Remove every key matching `pattern` in the configured namespace
+    /// from the global backend and return how many were removed. Failures
+    /// are counted in [`CacheMetrics`].
+    pub async fn invalidate_pattern(
+        pattern: &str,
+    ) -> crate::cache::r#trait::CacheResult<u64> {
+        let pattern = Self::namespaced(pattern.to_string().into());
+        let result = Self::get_backend().invalidate_pattern(&pattern).await;
+        if result.is_err() {
+            CacheMetrics::record_error();
+        }
+        result
+    }
+
+    /// Look up many keys with a single round trip, keeping `keys` order.
+    /// Errors and a missing backend count as misses, so callers can fall
+    /// back to the database for every `None`; errors are still counted in
+    /// [`CacheMetrics`].
+    pub async fn multi_get<T>(keys: &[String]) -> Vec<Option<T>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        let Some(backend) = Self::try_get_backend()
+        else {
+            return keys.iter().map(|_| None).collect();
+        };
+        backend.multi_get(keys).await.unwrap_or_else(|_| {
+            CacheMetrics::record_error();
+            keys.iter().map(|_| None).collect()
+        })
+    }
+
+    /// Store many values with a single round trip, each for its own TTL.
+    /// Fails when no backend has been initialized.
+    pub async fn multi_set<T>(
+        entries: &[(String, T, Duration)],
+    ) -> crate::cache::r#trait::CacheResult<()>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        let Some(backend) = Self::try_get_backend()
+        else {
+            return Err(crate::cache::r#trait::CacheError::Other(
+                "Cache backend not initialized".to_string(),
+            ));
+        };
+        backend.multi_set(entries).await
+    }
+
+    /// Cache-aside read of `entry`: return the cached value, or run `fetch`
+    /// on a miss and cache what it returns for a jittered `ttl`.
+    ///
+    /// Cache errors count as misses and failed writes are ignored, so the
+    /// cache never turns a successful fetch into an error. Errors from
+    /// `fetch` are returned as is and nothing is cached for them.
+    ///
+    /// Concurrent misses on the same key share one fetch. When that fetch
+    /// fails, only its caller gets the error; the others then fetch for
+    /// themselves.
+    pub async fn get_or_set<T, F, Fut, E>(
+        entry: &mut Normal<T>, ttl: Duration, fetch: F,
+    ) -> Result<T, E>
+    where
+        T: serde::Serialize
+            + serde::de::DeserializeOwned
+            + Send
+            + Sync
+            + Clone
+            + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Ok(Some(value)) = entry.try_get().await {
+            tracing::debug!("Cache hit for {}", entry.key());
+            return Ok(value);
+        }
+
+        tracing::debug!("Cache miss for {}, fetching", entry.key());
+        let mut fetch = Some(fetch);
+        let mut led = None;
+        let shared = IN_FLIGHT
+            .run(entry.key().to_string(), || {
+                let (fetch, led) = (fetch.take(), &mut led);
+                async move {
+                    let result = fetch?().await;
+                    let shared = result.as_ref().ok().map(|value| {
+                        Arc::new(value.clone()) as Arc<dyn Any + Send + Sync>
+                    });
+                    *led = Some(result);
+                    shared
+                }
+            })
+            .await;
+
+        let value = match led {
+            // This call ran the fetch
+            Some(result) => result?,
+            None => {
+                if let Some(value) = shared
+                    .and_then(|value| value.downcast_ref::<T>().cloned())
+                {
+                    return Ok(value);
+                }
+                // The fetch we waited for failed, so try our own
+                let fetch = fetch.take().expect("fetch runs at most once");
+                fetch().await?
+            }
+        };
+        let _ = entry
+            .set_with_expire::<()>(value.clone(), jittered(ttl))
+            .await;
+
+        Ok(value)
+    }
+
     /// Create a Redis-based cache backend from a pool
     pub fn redis_backend(
         pool: deadpool_redis::Pool,
@@ -44,7 +215,7 @@ impl CacheProvider {
 
     /// Create a memory-based cache backend
     pub fn memory_backend(
-        cache: moka::future::Cache<String, bytes::Bytes>,
+        cache: crate::cache::memory::MemoryCache,
         config: crate::config::MemoryConfig,
     ) -> CacheBackend<'static> {
         CacheBackend::Memory { cache, config }
@@ -53,13 +224,27 @@ impl CacheProvider {
     /// Create a memory-based cache backend with default configuration
     pub fn default_memory_backend() -> CacheBackend<'static> {
         let config = crate::config::MemoryConfig::default();
-        let cache = moka::future::Cache::builder()
-            .max_capacity(config.capacity)
-            .time_to_live(config.ttl())
-            .build();
+        let cache = crate::cache::memory::memory_cache(&config);
         CacheBackend::Memory { cache, config }
     }
 
+    /// Create a write-through tiered backend with a bounded memory L1 in
+    /// front of Redis
+    pub fn l1_redis_backend(
+        pool: deadpool_redis::Pool, l1: crate::config::MemoryConfig,
+    ) -> CacheBackend<'static> {
+        let cache = crate::cache::memory::memory_cache(&l1);
+        let config = crate::config::TieredConfig {
+            write_strategy: crate::config::WriteStrategy::WriteThrough,
+            ..Default::default()
+        };
+        Self::tiered_builder(config)
+            .add_memory(cache, l1)
+            .and_then(|builder| builder.add_redis(pool))
+            .and_then(|builder| builder.build())
+            .expect("memory + redis fits the default tiered layer limits")
+    }
+
     #[cfg(feature = "file-cache")]
     /// Create a file-based cache backend
     pub fn file_backend(
@@ -92,10 +277,222 @@ impl CacheProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        cache::memory::{MemoryCache, MemoryEntry},
+        core::CacheTypeBind,
+    };
 
     #[test]
     fn test_memory_backend_creation() {
         let backend = CacheProvider::default_memory_backend();
         assert!(!backend.is_redis());
     }
+
+    #[tokio::test]
+    async fn test_memory_backend_invalidate_pattern() {
+        let backend = CacheProvider::default_memory_backend();
+        let CacheBackend::Memory { cache, .. } = &backend
+        else {
+            unreachable!()
+        };
+        for key in ["user:1", "user:2", "users:list", "event:1"] {
+            cache.insert(key.to_string(), MemoryEntry::new("")).await;
+        }
+
+        assert_eq!(backend.invalidate_pattern("user:*").await.unwrap(), 2);
+        assert!(cache.get("user:1").await.is_none());
+        assert!(cache.get("users:list").await.is_some());
+        assert!(cache.get("event:1").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_multi_get_keeps_key_order() {
+        let backend = CacheProvider::default_memory_backend();
+        let CacheBackend::Memory { cache, .. } = &backend
+        else {
+            unreachable!()
+        };
+        for id in [1, 3, 5] {
+            cache
+                .insert(
+                    format!("user:{id}"),
+                    MemoryEntry::new(id.to_string()),
+                )
+                .await;
+        }
+        cache
+            .insert("user:4".to_string(), MemoryEntry::new("not json"))
+            .await;
+        let keys: Vec<String> =
+            (1..=5).map(|id| format!("user:{id}")).collect();
+
+        let values = backend.multi_get::<i64>(&keys).await.unwrap();
+
+        assert_eq!(values, vec![Some(1), None, Some(3), None, Some(5)]);
+        assert!(backend.multi_get::<i64>(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_multi_set_round_trips_through_multi_get() {
+        let backend = CacheProvider::default_memory_backend();
+        let ttl = Duration::from_secs(60);
+        let entries: Vec<(String, i64, Duration)> = [1, 2]
+            .map(|id| (format!("user:{id}"), id * 10, ttl))
+            .to_vec();
+
+        backend.multi_set(&entries).await.unwrap();
+
+        let keys = ["user:1", "user:2", "user:3"].map(String::from);
+        assert_eq!(
+            backend.multi_get::<i64>(&keys).await.unwrap(),
+            vec![Some(10), Some(20), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_honors_per_entry_ttl() {
+        let backend = CacheProvider::default_memory_backend();
+        let keys = ["user:1", "user:2"].map(String::from);
+
+        backend
+            .multi_set(&[
+                (keys[0].clone(), 1_i64, Duration::from_millis(50)),
+                (keys[1].clone(), 2, Duration::from_secs(60)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.multi_get::<i64>(&keys).await.unwrap(),
+            [Some(1), Some(2)]
+        );
+
+        // Well within the backend's own TTL, but past the first entry's
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            backend.multi_get::<i64>(&keys).await.unwrap(),
+            [None, Some(2)]
+        );
+    }
+
+    fn memory_layer(backend: &CacheBackend<'static>) -> MemoryCache {
+        let CacheBackend::Memory { cache, .. } = backend
+        else {
+            unreachable!()
+        };
+        cache.clone()
+    }
+
+    #[tokio::test]
+    async fn test_tiered_multi_get_copies_deeper_hits_up() {
+        let (l1, l2) = (
+            CacheProvider::default_memory_backend(),
+            CacheProvider::default_memory_backend(),
+        );
+        l2.multi_set(&[(
+            "user:1".to_string(),
+            1_i64,
+            Duration::from_secs(5),
+        )])
+        .await
+        .unwrap();
+        let tiered =
+            CacheBackend::tiered(vec![l1.clone(), l2.clone()]).unwrap();
+        let keys = ["user:1", "user:2"].map(String::from);
+
+        assert_eq!(
+            tiered.multi_get::<i64>(&keys).await.unwrap(),
+            [Some(1), None]
+        );
+
+        // L1 now holds the copy, for no longer than L2 keeps it
+        let copy = memory_layer(&l1).get("user:1").await.unwrap();
+        assert_eq!(copy.ttl, Some(Duration::from_secs(5)));
+        assert!(memory_layer(&l1).get("user:2").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tiered_multi_get_keeps_hits_past_a_failing_layer() {
+        let healthy = CacheProvider::default_memory_backend();
+        healthy
+            .multi_set(&[(
+                "user:1".to_string(),
+                1_i64,
+                Duration::from_secs(60),
+            )])
+            .await
+            .unwrap();
+        // A nested tiered layer fails every call
+        let failing = CacheBackend::tiered(vec![
+            CacheProvider::default_memory_backend(),
+            CacheProvider::default_memory_backend(),
+        ])
+        .unwrap();
+        let keys = ["user:1", "user:2"].map(String::from);
+
+        let tiered =
+            CacheBackend::tiered(vec![failing.clone(), healthy]).unwrap();
+        assert_eq!(
+            tiered.multi_get::<i64>(&keys).await.unwrap(),
+            [Some(1), None]
+        );
+
+        let broken =
+            CacheBackend::tiered(vec![failing.clone(), failing]).unwrap();
+        assert!(broken.multi_get::<i64>(&keys).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_fetch_for_themselves_when_the_shared_fetch_fails() {
+        crate::cache_key!(FlightKey::<i64> => "flight:{}"[id: i64]);
+        let backend = CacheProvider::default_memory_backend();
+        let (started, release) =
+            (tokio::sync::Notify::new(), tokio::sync::Notify::new());
+
+        let mut leader = FlightKey.bind_with(backend.clone(), &1);
+        let leading = CacheProvider::get_or_set(
+            &mut leader,
+            Duration::from_secs(60),
+            || {
+                async {
+                    started.notify_one();
+                    release.notified().await;
+                    Err::<i64, _>("database down")
+                }
+            },
+        );
+        let mut follower = FlightKey.bind_with(backend.clone(), &1);
+        let following = async {
+            started.notified().await;
+            let lookup = CacheProvider::get_or_set(
+                &mut follower,
+                Duration::from_secs(60),
+                || async { Ok::<_, &str>(5) },
+            );
+            tokio::pin!(lookup);
+            // Let the follower join the flight before the leader fails
+            std::future::poll_fn(|cx| {
+                assert!(lookup.as_mut().poll(cx).is_pending());
+                std::task::Poll::Ready(())
+            })
+            .await;
+            release.notify_one();
+            lookup.await
+        };
+
+        let (led, followed) = tokio::join!(leading, following);
+        assert_eq!(led, Err("database down"));
+        assert_eq!(followed, Ok(5));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        use crate::core::backend::glob_matches;
+
+        assert!(glob_matches("user:*", "user:42"));
+        assert!(glob_matches("user:*:missing", "user:42:missing"));
+        assert!(glob_matches("event?:*", "events:list:abc"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("user:*", "users:list"));
+        assert!(!glob_matches("user:?", "user:42"));
+    }
 }
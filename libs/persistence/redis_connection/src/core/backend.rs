@@ -1,5 +1,4 @@
-use bytes::Bytes;
-use moka::future::Cache;
+use crate::cache::memory::{MemoryCache, MemoryEntry};
 
 /// A runtime-configurable bounded vector for cache backends
 /// This provides the configurability we need while maintaining efficiency
@@ -89,11 +88,11 @@ impl<'a> From<std::sync::Arc<CacheBackend<'a>>> for CacheBackend<'a> {
     }
 }
 
-impl<'a> From<(Cache<String, Bytes>, crate::config::MemoryConfig)>
+impl<'a> From<(MemoryCache, crate::config::MemoryConfig)>
     for CacheBackend<'a>
 {
     fn from(
-        (cache, config): (Cache<String, Bytes>, crate::config::MemoryConfig),
+        (cache, config): (MemoryCache, crate::config::MemoryConfig),
     ) -> Self {
         CacheBackend::Memory { cache, config }
     }
@@ -124,7 +123,7 @@ pub enum CacheBackend<'a> {
 
     /// In-memory cache backend
     Memory {
-        cache: Cache<String, Bytes>,
+        cache: MemoryCache,
         config: crate::config::MemoryConfig,
     },
 
@@ -152,6 +151,38 @@ impl<'a> CacheBackend<'a> {
     /// Check if this is a Redis backend
     pub fn is_redis(&self) -> bool { matches!(self, CacheBackend::Redis(_)) }
 
+    /// The Redis pool behind this backend: its own, or the first Redis
+    /// layer of a tiered backend
+    pub fn redis_pool(&self) -> Option<&deadpool_redis::Pool> {
+        match self {
+            CacheBackend::Redis(pool) => Some(pool),
+            CacheBackend::Tiered { backends, .. } => {
+                backends.iter().find_map(|layer| {
+                    match layer {
+                        CacheBackend::Redis(pool) => Some(pool),
+                        _ => None,
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The first in-memory layer of a tiered backend
+    pub fn memory_layer(&self) -> Option<&MemoryCache> {
+        match self {
+            CacheBackend::Tiered { backends, .. } => {
+                backends.iter().find_map(|layer| {
+                    match layer {
+                        CacheBackend::Memory { cache, .. } => Some(cache),
+                        _ => None,
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+
     /// Create a tiered cache with the given backends
     /// Uses default configuration which determines the maximum capacity
     pub fn tiered(backends: Vec<CacheBackend<'a>>) -> Result<Self, String> {
@@ -201,6 +232,423 @@ impl<'a> CacheBackend<'a> {
         }
     }
 
+    /// Remove every key matching the Redis-style glob `pattern` (`*` and
+    /// `?` wildcards) and return how many keys were removed. For tiered
+    /// backends each layer is purged and the largest per-layer count is
+    /// reported, since layers hold overlapping copies of the same keys.
+    pub async fn invalidate_pattern(
+        &self, pattern: &str,
+    ) -> crate::cache::r#trait::CacheResult<u64> {
+        match self {
+            CacheBackend::Tiered { backends, .. } => {
+                let mut removed = 0;
+                for backend in backends.iter() {
+                    removed = removed.max(
+                        backend.invalidate_layer_pattern(pattern).await?,
+                    );
+                }
+                Ok(removed)
+            }
+            _ => self.invalidate_layer_pattern(pattern).await,
+        }
+    }
+
+    async fn invalidate_layer_pattern(
+        &self, pattern: &str,
+    ) -> crate::cache::r#trait::CacheResult<u64> {
+        use crate::cache::r#trait::CacheError;
+
+        match self {
+            CacheBackend::Redis(pool) => {
+                let mut conn = pool
+                    .get()
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                let mut cursor: u64 = 0;
+                let mut removed = 0;
+                loop {
+                    let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                        .arg(cursor)
+                        .arg("MATCH")
+                        .arg(pattern)
+                        .arg("COUNT")
+                        .arg(1000)
+                        .query_async(&mut conn)
+                        .await
+                        .map_err(|e| CacheError::Other(e.to_string()))?;
+                    if !keys.is_empty() {
+                        let deleted: u64 = redis::cmd("UNLINK")
+                            .arg(&keys)
+                            .query_async(&mut conn)
+                            .await
+                            .map_err(|e| CacheError::Other(e.to_string()))?;
+                        removed += deleted;
+                    }
+                    if next == 0 {
+                        break;
+                    }
+                    cursor = next;
+                }
+                Ok(removed)
+            }
+            CacheBackend::Memory { cache, .. } => {
+                let keys: Vec<String> = cache
+                    .iter()
+                    .filter(|(key, _)| glob_matches(pattern, key))
+                    .map(|(key, _)| key.as_ref().clone())
+                    .collect();
+                for key in &keys {
+                    cache.invalidate(key).await;
+                }
+                Ok(keys.len() as u64)
+            }
+            #[cfg(feature = "file-cache")]
+            CacheBackend::File { .. } => {
+                Err(CacheError::Unsupported(
+                    "Pattern invalidation is not supported by the file cache"
+                        .to_string(),
+                ))
+            }
+            CacheBackend::Tiered { .. } => {
+                Err(CacheError::Unsupported(
+                    "Nested tiered caches not supported".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Fetch many keys in one round trip, one slot per key in `keys` order.
+    /// Missing or undecodable values come back as `None`; tiered caches
+    /// only ask the next layer for the keys the previous ones missed, and
+    /// copy what a deeper layer found into the layers above it. A failing
+    /// layer counts as all misses, so only an error from every layer is
+    /// returned.
+    pub async fn multi_get<T>(
+        &self, keys: &[String],
+    ) -> crate::cache::r#trait::CacheResult<Vec<Option<T>>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        match self {
+            CacheBackend::Tiered { backends, .. } => {
+                let mut values: Vec<Option<T>> =
+                    keys.iter().map(|_| None).collect();
+                let mut failure = None;
+                let mut answered = false;
+                for (depth, backend) in backends.iter().enumerate() {
+                    let missing: Vec<usize> = values
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, value)| value.is_none())
+                        .map(|(i, _)| i)
+                        .collect();
+                    if missing.is_empty() {
+                        break;
+                    }
+                    let missing_keys: Vec<String> =
+                        missing.iter().map(|&i| keys[i].clone()).collect();
+                    let found = match backend
+                        .multi_get_layer::<T>(&missing_keys)
+                        .await
+                    {
+                        Ok(found) => found,
+                        Err(e) => {
+                            tracing::warn!(
+                                "Cache layer {} failed a multi-get: {}",
+                                depth,
+                                e
+                            );
+                            failure = Some(e);
+                            continue;
+                        }
+                    };
+                    answered = true;
+
+                    let mut hits = Vec::new();
+                    for (i, value) in missing.into_iter().zip(found) {
+                        if value.is_some() {
+                            hits.push(i);
+                        }
+                        values[i] = value;
+                    }
+                    if depth > 0 && !hits.is_empty() {
+                        let hit_keys: Vec<String> =
+                            hits.iter().map(|&i| keys[i].clone()).collect();
+                        let hit_values: Vec<&T> = hits
+                            .iter()
+                            .filter_map(|&i| values[i].as_ref())
+                            .collect();
+                        backend
+                            .backfill(
+                                backends.iter().take(depth),
+                                &hit_keys,
+                                &hit_values,
+                            )
+                            .await;
+                    }
+                }
+                match failure {
+                    Some(e) if !answered => Err(e),
+                    _ => Ok(values),
+                }
+            }
+            _ => self.multi_get_layer(keys).await,
+        }
+    }
+
+    /// Copy values this layer holds into `upper` layers, for no longer than
+    /// they have left here. Failures are logged: the copies only save
+    /// later round trips.
+    async fn backfill<'b, T>(
+        &self, upper: impl Iterator<Item = &'b CacheBackend<'a>>,
+        keys: &[String], values: &[&T],
+    ) where
+        'a: 'b,
+        T: serde::Serialize,
+    {
+        let ttls = match self.remaining_ttls_layer(keys).await {
+            Ok(ttls) => ttls,
+            Err(e) => {
+                tracing::warn!("Skipping cache backfill: {}", e);
+                return;
+            }
+        };
+        let encoded: Vec<_> = keys
+            .iter()
+            .zip(values)
+            .zip(ttls)
+            .filter_map(|((key, value), ttl)| {
+                serde_json::to_vec(value)
+                    .ok()
+                    .map(|bytes| (key, bytes, ttl))
+            })
+            .collect();
+        for layer in upper {
+            if let Err(e) = layer.put_encoded_layer(&encoded).await {
+                tracing::warn!("Failed to backfill a cache layer: {}", e);
+            }
+        }
+    }
+
+    /// How long each of `keys` has left in this layer; `None` when it
+    /// doesn't expire or is gone
+    async fn remaining_ttls_layer(
+        &self, keys: &[String],
+    ) -> crate::cache::r#trait::CacheResult<Vec<Option<std::time::Duration>>>
+    {
+        use crate::cache::r#trait::CacheError;
+
+        match self {
+            CacheBackend::Redis(pool) => {
+                let mut conn = pool
+                    .get()
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                let mut pipe = redis::pipe();
+                for key in keys {
+                    pipe.pttl(key);
+                }
+                let ttls: Vec<i64> = pipe
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                Ok(ttls
+                    .into_iter()
+                    .map(|ms| {
+                        u64::try_from(ms)
+                            .ok()
+                            .map(std::time::Duration::from_millis)
+                    })
+                    .collect())
+            }
+            CacheBackend::Memory { cache, .. } => {
+                let mut ttls = Vec::with_capacity(keys.len());
+                for key in keys {
+                    ttls.push(
+                        cache.get(key).await.and_then(|entry| entry.ttl),
+                    );
+                }
+                Ok(ttls)
+            }
+            #[cfg(feature = "file-cache")]
+            CacheBackend::File { .. } => {
+                Err(CacheError::Unsupported(
+                    "Expiry lookups are not supported by the file cache"
+                        .to_string(),
+                ))
+            }
+            CacheBackend::Tiered { .. } => {
+                Err(CacheError::Unsupported(
+                    "Nested tiered caches not supported".to_string(),
+                ))
+            }
+        }
+    }
+
+    async fn multi_get_layer<T>(
+        &self, keys: &[String],
+    ) -> crate::cache::r#trait::CacheResult<Vec<Option<T>>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        use crate::{
+            cache::r#trait::CacheError,
+            core::value::{CacheValue, Json},
+        };
+
+        fn decode<T>(bytes: &[u8]) -> Option<T>
+        where
+            T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+        {
+            Json::<T>::from_bytes(bytes).ok().map(Json::inner)
+        }
+
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        match self {
+            CacheBackend::Redis(pool) => {
+                let mut conn = pool
+                    .get()
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                let raw: Vec<Option<Vec<u8>>> = redis::cmd("MGET")
+                    .arg(keys)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                Ok(raw
+                    .into_iter()
+                    .map(|bytes| bytes.and_then(|b| decode(&b)))
+                    .collect())
+            }
+            CacheBackend::Memory { cache, .. } => {
+                let mut values = Vec::with_capacity(keys.len());
+                for key in keys {
+                    values.push(
+                        cache
+                            .get(key)
+                            .await
+                            .and_then(|entry| decode(&entry.bytes)),
+                    );
+                }
+                Ok(values)
+            }
+            #[cfg(feature = "file-cache")]
+            CacheBackend::File { .. } => {
+                Err(CacheError::Unsupported(
+                    "Multi-get is not supported by the file cache"
+                        .to_string(),
+                ))
+            }
+            CacheBackend::Tiered { .. } => {
+                Err(CacheError::Unsupported(
+                    "Nested tiered caches not supported".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Store many values in one round trip, each expiring after its own
+    /// TTL. Tiered caches write every layer.
+    pub async fn multi_set<T>(
+        &self, entries: &[(String, T, std::time::Duration)],
+    ) -> crate::cache::r#trait::CacheResult<()>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        match self {
+            CacheBackend::Tiered { backends, .. } => {
+                for backend in backends.iter() {
+                    backend.multi_set_layer(entries).await?;
+                }
+                Ok(())
+            }
+            _ => self.multi_set_layer(entries).await,
+        }
+    }
+
+    async fn multi_set_layer<T>(
+        &self, entries: &[(String, T, std::time::Duration)],
+    ) -> crate::cache::r#trait::CacheResult<()>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        use crate::cache::r#trait::CacheError;
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let encoded = entries
+            .iter()
+            .map(|(key, value, ttl)| {
+                serde_json::to_vec(value)
+                    .map(|bytes| (key, bytes, Some(*ttl)))
+                    .map_err(|e| {
+                        CacheError::SerializationError(e.to_string())
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.put_encoded_layer(&encoded).await
+    }
+
+    /// Store encoded values in this layer, each until its TTL runs out or,
+    /// without one, for as long as the layer keeps entries
+    async fn put_encoded_layer(
+        &self, encoded: &[(&String, Vec<u8>, Option<std::time::Duration>)],
+    ) -> crate::cache::r#trait::CacheResult<()> {
+        use crate::cache::r#trait::CacheError;
+
+        match self {
+            CacheBackend::Redis(pool) => {
+                let mut conn = pool
+                    .get()
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))?;
+                let mut pipe = redis::pipe();
+                for (key, bytes, ttl) in encoded {
+                    match ttl {
+                        Some(ttl) => {
+                            pipe.set_ex(*key, bytes, ttl.as_secs().max(1))
+                                .ignore();
+                        }
+                        None => {
+                            pipe.set(*key, bytes).ignore();
+                        }
+                    }
+                }
+                pipe.query_async::<()>(&mut conn)
+                    .await
+                    .map_err(|e| CacheError::Other(e.to_string()))
+            }
+            CacheBackend::Memory { cache, .. } => {
+                for (key, bytes, ttl) in encoded {
+                    let entry = match ttl {
+                        Some(ttl) => {
+                            MemoryEntry::with_ttl(bytes.clone(), *ttl)
+                        }
+                        None => MemoryEntry::new(bytes.clone()),
+                    };
+                    cache.insert(key.to_string(), entry).await;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "file-cache")]
+            CacheBackend::File { .. } => {
+                Err(CacheError::Unsupported(
+                    "Multi-set is not supported by the file cache"
+                        .to_string(),
+                ))
+            }
+            CacheBackend::Tiered { .. } => {
+                Err(CacheError::Unsupported(
+                    "Nested tiered caches not supported".to_string(),
+                ))
+            }
+        }
+    }
+
     /// Check if this backend can handle the given number of layers
     pub fn can_handle_layers(&self, count: usize) -> bool {
         match self {
@@ -212,6 +660,35 @@ impl<'a> CacheBackend<'a> {
     }
 }
 
+/// Match `key` against a Redis-style glob supporting `*` and `?`
+pub fn glob_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+    let (mut p, mut k) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == key[k]) {
+            p += 1;
+            k += 1;
+        }
+        else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, k));
+            p += 1;
+        }
+        else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            k = matched + 1;
+            backtrack = Some((star, matched + 1));
+        }
+        else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 /// Builder for creating tiered caches with validation
 /// Provides a fluent API for constructing tiered caches layer by layer
 pub struct TieredCacheBuilder<'a> {
@@ -239,8 +716,7 @@ impl<'a> TieredCacheBuilder<'a> {
 
     /// Add a memory cache layer
     pub fn add_memory(
-        self, cache: Cache<String, Bytes>,
-        config: crate::config::MemoryConfig,
+        self, cache: MemoryCache, config: crate::config::MemoryConfig,
     ) -> Result<Self, String> {
         let backend = CacheBackend::Memory { cache, config };
         self.add_layer(backend)
@@ -1,3 +1,4 @@
+use common_errors::redact;
 use deadpool_redis::{Config, CreatePoolError, Pool, Runtime};
 pub use deadpool_redis::{PoolError, redis::FromRedisValue};
 pub use redis::{AsyncCommands, RedisError};
@@ -9,6 +10,9 @@ pub mod cache_provider;
 pub mod config;
 pub mod connection;
 pub mod macros;
+pub mod metrics;
+pub mod single_flight;
+pub mod ttl;
 
 // Organized submodules
 pub mod cache;
@@ -20,15 +24,8 @@ pub async fn connect_redis_db<C>(config: &C) -> Result<Pool, CreatePoolError>
 where
     C: config::DbConnectConfig,
 {
-    let mut url = Url::parse("redis://").unwrap();
-
-    url.set_host(Some(config.host())).unwrap();
-    url.set_port(config.port().into()).unwrap();
-    url.path_segments_mut()
-        .unwrap()
-        .extend(&[config.db().to_string()]);
-
-    info!(redis.url = %url, redis.connect = true);
+    let url = redis_url(config);
+    log_connect(&url);
 
     let cfg = Config {
         url: Some(url.to_string()),
@@ -40,8 +37,31 @@ where
     Ok(pool)
 }
 
+/// `redis://host:port/db` for `config`
+fn redis_url<C>(config: &C) -> Url
+where
+    C: config::DbConnectConfig,
+{
+    let mut url = Url::parse("redis://").unwrap();
+
+    url.set_host(Some(config.host())).unwrap();
+    url.set_port(config.port().into()).unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .extend(&[config.db().to_string()]);
+
+    url
+}
+
+/// Log the URL being connected to with any credentials masked
+fn log_connect(url: &Url) {
+    info!(redis.url = %redact(url.as_str()), redis.connect = true);
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
+
     use super::*;
 
     #[test]
@@ -90,4 +110,69 @@ mod tests {
         assert_eq!(config.port, 6379);
         assert_eq!(config.db, 0);
     }
+
+    /// Everything logged while `f` runs
+    fn logged(f: impl FnOnce()) -> String {
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+        }
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || Captured(writer.clone()))
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+
+        String::from_utf8(output.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_connect_log_masks_password() {
+        let url = Url::parse("redis://:hunter2@cache:6379/2").unwrap();
+
+        let output = logged(|| log_connect(&url));
+
+        assert!(
+            output.contains("redis.url=redis://:***@cache:6379/2"),
+            "{output}"
+        );
+        assert!(!output.contains("hunter2"), "{output}");
+    }
+
+    #[test]
+    fn test_connect_leaves_the_password_out_of_the_url() {
+        #[derive(serde::Deserialize)]
+        struct SecretConfig;
+
+        impl config::DbConnectConfig for SecretConfig {
+            fn password(&self) -> Option<&str> { Some("hunter2") }
+
+            fn host(&self) -> &str { "cache" }
+
+            fn port(&self) -> u16 { 6379 }
+
+            fn db(&self) -> u8 { 2 }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let output = logged(|| {
+            runtime.block_on(connect_redis_db(&SecretConfig)).unwrap();
+        });
+
+        assert!(
+            output.contains("redis.url=redis://cache:6379/2"),
+            "{output}"
+        );
+        assert!(!output.contains("hunter2"), "{output}");
+    }
 }
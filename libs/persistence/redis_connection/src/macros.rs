@@ -10,7 +10,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -26,7 +28,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
@@ -44,7 +46,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -60,7 +64,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
@@ -79,7 +83,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -95,7 +101,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
@@ -114,7 +120,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -130,7 +138,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
@@ -149,7 +157,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -165,7 +175,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
@@ -184,7 +194,9 @@ macro_rules! cache_key {
             fn get_key_with_args(&self, args: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
                 let ($($arg,)*) = args;
 
-                (format!($format_key, $($arg),*)).into()
+                $crate::cache_provider::CacheProvider::namespaced(
+                    format!($format_key, $($arg),*).into(),
+                )
             }
         }
 
@@ -200,7 +212,7 @@ macro_rules! cache_key {
             type Args<'r> = ();
 
             fn get_key_with_args(&self, _: Self::Args<'_>) -> std::borrow::Cow<'static, str> {
-                ($key).into()
+                $crate::cache_provider::CacheProvider::namespaced(($key).into())
             }
         }
 
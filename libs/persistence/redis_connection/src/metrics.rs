@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Process-wide, like the cache backend itself
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Health counters for every cache binding in the process. Callers treat a
+/// failed read as a miss and ignore failed writes, so without these a
+/// broken Redis only shows up as slower responses.
+pub struct CacheMetrics;
+
+/// Counters since startup
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CacheMetricsSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+    /// Reads, writes and invalidations that failed, e.g. because Redis
+    /// was unreachable
+    pub errors: u64,
+    /// `hits / (hits + misses)`, 0 before the first lookup
+    pub hit_ratio: f64,
+}
+
+impl CacheMetrics {
+    pub fn snapshot() -> CacheMetricsSnapshot {
+        let hits = HITS.load(Ordering::Relaxed);
+        let misses = MISSES.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        CacheMetricsSnapshot {
+            hits,
+            misses,
+            errors: ERRORS.load(Ordering::Relaxed),
+            hit_ratio: if lookups == 0 {
+                0.0
+            }
+            else {
+                hits as f64 / lookups as f64
+            },
+        }
+    }
+
+    /// Count a lookup as a hit, a miss or an error
+    pub(crate) fn record_lookup<T>(result: &RedisResult<Option<T>>) {
+        let counter = match result {
+            Ok(Some(_)) => &HITS,
+            Ok(None) => &MISSES,
+            Err(_) => &ERRORS,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a failed write; successful ones are not tracked
+    pub(crate) fn record_write<T>(result: &RedisResult<T>) {
+        if result.is_err() {
+            Self::record_error();
+        }
+    }
+
+    /// Count a failed cache operation of any kind
+    pub(crate) fn record_error() { ERRORS.fetch_add(1, Ordering::Relaxed); }
+}
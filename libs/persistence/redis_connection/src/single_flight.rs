@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::OnceCell;
+
+/// Collapses concurrent loads of the same key into one.
+///
+/// The first caller for a key runs the loader; callers arriving while it is
+/// in flight wait for and share its result. Once the load completes the key
+/// is forgotten, so the next miss loads again. Clones share the same set of
+/// in-flight calls.
+pub struct SingleFlight<K, V> {
+    calls: Arc<Mutex<HashMap<K, Arc<OnceCell<V>>>>>,
+}
+
+impl<K, V> Clone for SingleFlight<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            calls: Arc::clone(&self.calls),
+        }
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            calls: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self { Self::default() }
+
+    /// Run `load` for `key` unless a load for it is already in flight, in
+    /// which case wait for that one instead
+    pub async fn run<F, Fut>(&self, key: K, load: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = {
+            let mut calls =
+                self.calls.lock().expect("single flight poisoned");
+            calls.entry(key.clone()).or_default().clone()
+        };
+
+        let value = cell.get_or_init(load).await.clone();
+
+        let mut calls = self.calls.lock().expect("single flight poisoned");
+        if calls
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            calls.remove(&key);
+        }
+
+        value
+    }
+
+    /// Number of keys with a load currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.calls.lock().expect("single flight poisoned").len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_load() {
+        let flight = SingleFlight::<i64, i64>::new();
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let flight = flight.clone();
+                let loads = loads.clone();
+                tokio::spawn(async move {
+                    flight
+                        .run(1, || {
+                            async {
+                                loads.fetch_add(1, Ordering::SeqCst);
+                                tokio::time::sleep(Duration::from_millis(50))
+                                    .await;
+                                42
+                            }
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 42);
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(flight.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_completed_key_loads_again() {
+        let flight = SingleFlight::<&str, usize>::new();
+        let loads = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            flight
+                .run("key", || {
+                    async { loads.fetch_add(1, Ordering::SeqCst) + 1 }
+                })
+                .await;
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+/// Maximum relative deviation applied by [`jittered`]
+pub const JITTER_RATIO: f64 = 0.1;
+
+/// Spread `base` by up to ±10% so entries written together don't all expire
+/// at the same instant and stampede the database.
+///
+/// The RNG is seeded from OS entropy on every call, so concurrent callers
+/// never share a sequence and no global RNG state is needed.
+pub fn jittered(base: Duration) -> Duration {
+    let mut rng = SmallRng::from_entropy();
+    base.mul_f64(1.0 + rng.gen_range(-JITTER_RATIO..=JITTER_RATIO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jittered_stays_within_band() {
+        let base = Duration::from_secs(300);
+        let min = base.mul_f64(1.0 - JITTER_RATIO);
+        let max = base.mul_f64(1.0 + JITTER_RATIO);
+
+        for _ in 0..1000 {
+            let ttl = jittered(base);
+            assert!(ttl >= min && ttl <= max, "{ttl:?} outside band");
+        }
+    }
+
+    #[test]
+    fn test_jittered_spreads_values() {
+        let base = Duration::from_secs(300);
+        let first = jittered(base);
+        assert!((0..100).any(|_| jittered(base) != first));
+    }
+
+    #[test]
+    fn test_jittered_zero_stays_zero() {
+        assert_eq!(jittered(Duration::ZERO), Duration::ZERO);
+    }
+}
@@ -8,6 +8,7 @@ use deadpool_redis::redis::{
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
+use super::connection;
 use crate::core::{
     backend::CacheBackend,
     type_bind::CacheTypeTrait,
@@ -15,7 +16,7 @@ use crate::core::{
 };
 
 pub struct Hash<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
@@ -24,13 +25,8 @@ impl<T> CacheTypeTrait<'_> for Hash<T> {
     fn from_cache_and_key(
         backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            CacheBackend::Redis(pool) => pool,
-            _ => panic!("Hash type can only be created from Redis backend"),
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -46,13 +42,7 @@ where
         F: ToRedisArgs + Send + Sync + 'arg,
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.hexists(&*self.key, field).await
     }
 
@@ -63,13 +53,7 @@ where
         F: ToRedisArgs + Send + Sync + 'arg,
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.hset(&*self.key, field, value.into()).await
     }
 
@@ -77,13 +61,7 @@ where
     where
         F: ToRedisArgs + Send + Sync + 'arg,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json: Json<T> = conn.hget(&*self.key, field).await?;
         Ok(json.inner())
     }
@@ -92,13 +70,7 @@ where
     where
         K: FromRedisValue + Eq + std::hash::Hash,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let map: HashMap<K, Json<T>> = conn.hgetall(&*self.key).await?;
         Ok(map.into_iter().map(|(k, v)| (k, v.inner())).collect())
     }
@@ -122,13 +94,7 @@ where
         F: ToRedisArgs + Send + Sync + 'arg,
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.hdel(&*self.key, field).await
     }
 }
@@ -8,6 +8,7 @@ use deadpool_redis::redis::{
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
+use super::connection;
 use crate::core::{
     backend::CacheBackend,
     type_bind::CacheTypeTrait,
@@ -15,7 +16,7 @@ use crate::core::{
 };
 
 pub struct List<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
@@ -24,13 +25,8 @@ impl<T> CacheTypeTrait<'_> for List<T> {
     fn from_cache_and_key(
         backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            CacheBackend::Redis(pool) => pool,
-            _ => panic!("List type can only be created from Redis backend"),
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -48,13 +44,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.lpush(&*self.key, value.into()).await
     }
 
@@ -66,13 +56,7 @@ where
         RV: FromRedisValue,
     {
         // Redis doesn't have lpush_multiple, so we use multiple lpush calls
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut result: i32 = 0;
         for value in values {
             result = conn.lpush(&*self.key, Json(value)).await?;
@@ -87,13 +71,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.rpush(&*self.key, value.into()).await
     }
 
@@ -105,13 +83,7 @@ where
         RV: FromRedisValue,
     {
         // Redis doesn't have rpush_multiple, so we use multiple rpush calls
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut result: i32 = 0;
         for value in values {
             result = conn.rpush(&*self.key, Json(value)).await?;
@@ -121,26 +93,14 @@ where
 
     /// Pop element from the left (beginning) of the list
     pub async fn pop_left(&mut self) -> RedisResult<Option<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json: Option<Json<T>> = conn.lpop(&*self.key, None).await?;
         Ok(json.map(|j| j.inner()))
     }
 
     /// Pop element from the right (end) of the list
     pub async fn pop_right(&mut self) -> RedisResult<Option<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json: Option<Json<T>> = conn.rpop(&*self.key, None).await?;
         Ok(json.map(|j| j.inner()))
     }
@@ -150,13 +110,7 @@ where
         &mut self, count: usize,
     ) -> RedisResult<Vec<T>> {
         if let Some(non_zero_count) = std::num::NonZero::new(count) {
-            let mut conn = self.pool.get().await.map_err(|e| {
-                redis::RedisError::from((
-                    redis::ErrorKind::IoError,
-                    "Pool connection error",
-                    e.to_string(),
-                ))
-            })?;
+            let mut conn = connection(self.pool.as_ref()).await?;
             let jsons: Vec<Json<T>> =
                 conn.lpop(&*self.key, Some(non_zero_count)).await?;
             Ok(jsons.into_iter().map(|j| j.inner()).collect())
@@ -171,13 +125,7 @@ where
         &mut self, count: usize,
     ) -> RedisResult<Vec<T>> {
         if let Some(non_zero_count) = std::num::NonZero::new(count) {
-            let mut conn = self.pool.get().await.map_err(|e| {
-                redis::RedisError::from((
-                    redis::ErrorKind::IoError,
-                    "Pool connection error",
-                    e.to_string(),
-                ))
-            })?;
+            let mut conn = connection(self.pool.as_ref()).await?;
             let jsons: Vec<Json<T>> =
                 conn.rpop(&*self.key, Some(non_zero_count)).await?;
             Ok(jsons.into_iter().map(|j| j.inner()).collect())
@@ -191,13 +139,7 @@ where
     pub async fn blocking_pop_left(
         &mut self, timeout: Duration,
     ) -> RedisResult<Option<(String, T)>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let result: Option<(String, Json<T>)> =
             conn.blpop(&*self.key, timeout.as_secs() as f64).await?;
         Ok(result.map(|(k, v)| (k, v.inner())))
@@ -207,13 +149,7 @@ where
     pub async fn blocking_pop_right(
         &mut self, timeout: Duration,
     ) -> RedisResult<Option<(String, T)>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let result: Option<(String, Json<T>)> =
             conn.brpop(&*self.key, timeout.as_secs() as f64).await?;
         Ok(result.map(|(k, v)| (k, v.inner())))
@@ -221,13 +157,7 @@ where
 
     /// Get element at index
     pub async fn get(&mut self, index: isize) -> RedisResult<Option<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json: Option<Json<T>> = conn.lindex(&*self.key, index).await?;
         Ok(json.map(|j| j.inner()))
     }
@@ -239,13 +169,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.lset(&*self.key, index, value.into()).await
     }
 
@@ -253,13 +177,7 @@ where
     pub async fn range(
         &mut self, start: isize, stop: isize,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let jsons: Vec<Json<T>> =
             conn.lrange(&*self.key, start, stop).await?;
         Ok(jsons.into_iter().map(|j| j.inner()).collect())
@@ -275,13 +193,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.llen(&*self.key).await
     }
 
@@ -293,13 +205,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let pivot_json = pivot.into();
         let value_json = value.into();
         if before {
@@ -318,13 +224,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.lrem(&*self.key, count, value.into()).await
     }
 
@@ -335,13 +235,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.ltrim(&*self.key, start, stop).await
     }
 
@@ -350,13 +244,7 @@ where
         &mut self, dest_key: &str, from_left: bool, to_left: bool,
     ) -> RedisResult<Option<T>> {
         // Use the simpler RPOPLPUSH or equivalent operations
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         if from_left {
             let value: Option<T> = self.pop_left().await?;
             if let Some(val) = value.clone() {
@@ -397,13 +285,7 @@ where
         RV: FromRedisValue,
     {
         // Check if list exists first, then push
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let exists: bool = conn.exists(&*self.key).await?;
         if exists {
             conn.lpush(&*self.key, value.into()).await
@@ -421,13 +303,7 @@ where
         RV: FromRedisValue,
     {
         // Check if list exists first, then push
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let exists: bool = conn.exists(&*self.key).await?;
         if exists {
             conn.rpush(&*self.key, value.into()).await
@@ -12,3 +12,24 @@ pub use normal::Normal;
 pub use set::Set;
 pub use stream::Stream;
 pub use zset::SortedSet;
+
+/// Check out a connection for a binding. Bindings created from a backend
+/// without a Redis layer have no pool and fail here instead of panicking
+/// when bound.
+pub(crate) async fn connection(
+    pool: Option<&deadpool_redis::Pool>,
+) -> redis::RedisResult<deadpool_redis::Connection> {
+    let pool = pool.ok_or_else(|| {
+        redis::RedisError::from((
+            redis::ErrorKind::ClientError,
+            "Cache backend has no Redis layer",
+        ))
+    })?;
+    pool.get().await.map_err(|e| {
+        redis::RedisError::from((
+            redis::ErrorKind::IoError,
+            "Pool connection error",
+            e.to_string(),
+        ))
+    })
+}
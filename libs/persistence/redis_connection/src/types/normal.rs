@@ -1,34 +1,41 @@
 #![allow(unused)]
 use std::{borrow::Cow, marker::PhantomData, time::Duration};
 
-use bytes::Bytes;
 use deadpool_redis::redis::{AsyncCommands, FromRedisValue, RedisResult};
-use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
-use crate::core::{
-    type_bind::CacheTypeTrait,
-    value::{CacheValue, Json},
+use super::connection;
+use crate::{
+    cache::memory::{MemoryCache, MemoryEntry},
+    core::{
+        backend::CacheBackend,
+        type_bind::CacheTypeTrait,
+        value::{CacheValue, Json},
+    },
+    metrics::CacheMetrics,
 };
 
+/// Plain key/value binding backed by Redis.
+///
+/// When bound to a tiered backend holding a memory layer in front of Redis,
+/// the memory layer acts as an L1: writes go to both layers, reads are
+/// served from L1 first and Redis hits are copied back into it. Reads that
+/// hit L1 never touch Redis, so previously cached values stay available
+/// while Redis is down. An L1 copy never outlives the Redis key's expiry.
 pub struct Normal<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
+    l1: Option<MemoryCache>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
 
 impl<T> CacheTypeTrait<'_> for Normal<T> {
     fn from_cache_and_key(
-        backend: super::super::core::backend::CacheBackend<'_>,
-        key: Cow<'static, str>,
+        backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            super::super::core::backend::CacheBackend::Redis(pool) => pool,
-            _ => panic!("Normal type can only be created from Redis backend"),
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
+            l1: backend.memory_layer().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -39,17 +46,14 @@ impl<T> Normal<T>
 where
     T: Serialize + for<'de> Deserialize<'de> + Send + Sync,
 {
+    /// Full Redis key this binding reads and writes
+    pub fn key(&self) -> &str { &self.key }
+
     pub async fn exists<RV>(&mut self) -> RedisResult<RV>
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.exists(&*self.key).await
     }
 
@@ -59,14 +63,16 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
-        conn.set(&*self.key, value.into()).await
+        let value = value.into();
+        self.l1_set(&value, None).await;
+
+        let result: RedisResult<RV> = async {
+            let mut conn = connection(self.pool.as_ref()).await?;
+            conn.set(&*self.key, value).await
+        }
+        .await;
+        CacheMetrics::record_write(&result);
+        result
     }
 
     pub async fn set_if_not_exist<RV>(
@@ -75,13 +81,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.set_nx(&*self.key, value.into()).await
     }
 
@@ -91,30 +91,54 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
-        conn.set_ex(&*self.key, value.into(), duration.as_secs() as _)
-            .await
+        let value = value.into();
+        self.l1_set(&value, Some(duration)).await;
+
+        let result: RedisResult<RV> = async {
+            let mut conn = connection(self.pool.as_ref()).await?;
+            conn.set_ex(&*self.key, value, duration.as_secs() as _)
+                .await
+        }
+        .await;
+        CacheMetrics::record_write(&result);
+        result
     }
 
     pub async fn get(&mut self) -> RedisResult<T> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
-        let json: Json<T> = conn.get(&*self.key).await?;
+        if let Some(value) = self.l1_get().await {
+            return Ok(value);
+        }
+
+        let mut conn = connection(self.pool.as_ref()).await?;
+        if self.l1.is_none() {
+            let json: Json<T> = conn.get(&*self.key).await?;
+            return Ok(json.inner());
+        }
+
+        // Copy into L1 for no longer than the key has left in Redis
+        let (json, ttl_ms): (Json<T>, i64) = redis::pipe()
+            .get(&*self.key)
+            .pttl(&*self.key)
+            .query_async(&mut conn)
+            .await?;
+        let ttl = u64::try_from(ttl_ms).ok().map(Duration::from_millis);
+        self.l1_set(&json, ttl).await;
         Ok(json.inner())
     }
 
+    /// Read the value if the key exists. Every call counts as a hit, a
+    /// miss or an error in [`CacheMetrics`].
     pub async fn try_get(&mut self) -> RedisResult<Option<T>> {
+        let result = self.lookup().await;
+        CacheMetrics::record_lookup(&result);
+        result
+    }
+
+    async fn lookup(&mut self) -> RedisResult<Option<T>> {
+        if let Some(value) = self.l1_get().await {
+            return Ok(Some(value));
+        }
+
         Ok(if self.exists().await? {
             Some(self.get().await?)
         }
@@ -127,13 +151,37 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
-        conn.del(&*self.key).await
+        if let Some(l1) = &self.l1 {
+            l1.invalidate(&*self.key).await;
+        }
+
+        let result = async {
+            let mut conn = connection(self.pool.as_ref()).await?;
+            conn.del(&*self.key).await
+        }
+        .await;
+        CacheMetrics::record_write(&result);
+        result
+    }
+
+    async fn l1_get(&self) -> Option<T> {
+        let entry = self.l1.as_ref()?.get(&*self.key).await?;
+        Json::<T>::from_bytes(&entry.bytes).ok().map(Json::inner)
+    }
+
+    /// Copy `value` into L1 for at most `ttl`, or the L1's own TTL when the
+    /// Redis key doesn't expire
+    async fn l1_set(&self, value: &Json<T>, ttl: Option<Duration>) {
+        let Some(l1) = &self.l1
+        else {
+            return;
+        };
+        if let Ok(bytes) = value.to_bytes() {
+            let entry = match ttl {
+                Some(ttl) => MemoryEntry::with_ttl(bytes, ttl),
+                None => MemoryEntry::new(bytes),
+            };
+            l1.insert(self.key.to_string(), entry).await;
+        }
     }
 }
@@ -8,6 +8,7 @@ use deadpool_redis::redis::{
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
+use super::connection;
 use crate::core::{
     backend::CacheBackend,
     type_bind::CacheTypeTrait,
@@ -15,7 +16,7 @@ use crate::core::{
 };
 
 pub struct Set<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
@@ -24,13 +25,8 @@ impl<T> CacheTypeTrait<'_> for Set<T> {
     fn from_cache_and_key(
         backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            CacheBackend::Redis(pool) => pool,
-            _ => panic!("Set type can only be created from Redis backend"),
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -53,13 +49,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.sadd(&*self.key, value.into()).await
     }
 
@@ -74,13 +64,7 @@ where
         let json_values: Vec<Json<T>> =
             values.into_iter().map(|v| v.into()).collect();
         // Redis can handle multiple values in a single sadd call
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.sadd(&*self.key, json_values).await
     }
 
@@ -91,13 +75,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.srem(&*self.key, value.into()).await
     }
 
@@ -108,25 +86,13 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.sismember(&*self.key, value.into()).await
     }
 
     /// Get all members of the set
     pub async fn members(&mut self) -> RedisResult<HashSet<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_set: HashSet<Json<T>> = conn.smembers(&*self.key).await?;
         Ok(json_set.into_iter().map(|json| json.inner()).collect())
     }
@@ -136,25 +102,13 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.scard(&*self.key).await
     }
 
     /// Remove and return a random member from the set
     pub async fn pop(&mut self) -> RedisResult<Option<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let result: Option<Json<T>> = conn.spop(&*self.key).await?;
         Ok(result.map(|json| json.inner()))
     }
@@ -163,13 +117,7 @@ where
     pub async fn random_members(
         &mut self, count: usize,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<Json<T>> =
             conn.srandmember_multiple(&*self.key, count).await?;
         Ok(json_vec.into_iter().map(|json| json.inner()).collect())
@@ -179,13 +127,7 @@ where
     pub async fn union(
         &mut self, other_keys: &[&str],
     ) -> RedisResult<HashSet<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut keys = vec![&*self.key];
         keys.extend(other_keys);
         let json_set: HashSet<Json<T>> = conn.sunion(&keys).await?;
@@ -196,13 +138,7 @@ where
     pub async fn intersect(
         &mut self, other_keys: &[&str],
     ) -> RedisResult<HashSet<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut keys = vec![&*self.key];
         keys.extend(other_keys);
         let json_set: HashSet<Json<T>> = conn.sinter(&keys).await?;
@@ -213,13 +149,7 @@ where
     pub async fn diff(
         &mut self, other_keys: &[&str],
     ) -> RedisResult<HashSet<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut keys = vec![&*self.key];
         keys.extend(other_keys);
         let json_set: HashSet<Json<T>> = conn.sdiff(&keys).await?;
@@ -233,13 +163,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.smove(&*self.key, dest_key, value.into()).await
     }
 }
@@ -13,6 +13,7 @@ use deadpool_redis::redis::{
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
+use super::connection;
 use crate::core::{
     backend::CacheBackend,
     type_bind::CacheTypeTrait,
@@ -20,7 +21,7 @@ use crate::core::{
 };
 
 pub struct Stream<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
@@ -29,13 +30,8 @@ impl<T> CacheTypeTrait<'_> for Stream<T> {
     fn from_cache_and_key(
         backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            CacheBackend::Redis(pool) => pool,
-            _ => panic!("Stream type can only be created from Redis backend"),
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -53,13 +49,7 @@ where
     where
         F: Into<Json<T>> + Clone,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_fields: Vec<(&str, Json<T>)> =
             fields.iter().map(|(k, v)| (*k, v.clone().into())).collect();
         conn.xadd(&*self.key, "*", &json_fields).await
@@ -72,13 +62,7 @@ where
     where
         F: Into<Json<T>> + Clone,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_fields: Vec<(&str, Json<T>)> =
             fields.iter().map(|(k, v)| (*k, v.clone().into())).collect();
         conn.xadd(&*self.key, id, &json_fields).await
@@ -91,13 +75,7 @@ where
     where
         F: Into<Json<T>> + Clone,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_fields: Vec<(&str, Json<T>)> =
             fields.iter().map(|(k, v)| (*k, v.clone().into())).collect();
         conn.xadd_maxlen(
@@ -114,13 +92,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xlen(&*self.key).await
     }
 
@@ -128,13 +100,7 @@ where
     pub async fn range(
         &mut self, start: &str, end: &str,
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xrange(&*self.key, start, end).await
     }
 
@@ -142,13 +108,7 @@ where
     pub async fn range_count(
         &mut self, start: &str, end: &str, count: usize,
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xrange_count(&*self.key, start, end, count).await
     }
 
@@ -156,13 +116,7 @@ where
     pub async fn reverse_range(
         &mut self, end: &str, start: &str,
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xrevrange(&*self.key, end, start).await
     }
 
@@ -170,25 +124,13 @@ where
     pub async fn reverse_range_count(
         &mut self, end: &str, start: &str, count: usize,
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xrevrange_count(&*self.key, end, start, count).await
     }
 
     /// Read new entries from stream (blocking)
     pub async fn read(&mut self, id: &str) -> RedisResult<StreamReadReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let opts = StreamReadOptions::default().count(10);
         conn.xread_options(&[&*self.key], &[id], &opts).await
     }
@@ -197,13 +139,7 @@ where
     pub async fn read_blocking(
         &mut self, id: &str, timeout: Duration,
     ) -> RedisResult<StreamReadReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let opts = StreamReadOptions::default()
             .count(10)
             .block(timeout.as_millis() as usize);
@@ -215,13 +151,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xdel(&*self.key, ids).await
     }
 
@@ -230,13 +160,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xtrim(&*self.key, redis::streams::StreamMaxlen::Equals(maxlen))
             .await
     }
@@ -246,13 +170,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xtrim(&*self.key, redis::streams::StreamMaxlen::Approx(maxlen))
             .await
     }
@@ -264,13 +182,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xgroup_create(&*self.key, group, id).await
     }
 
@@ -281,13 +193,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xgroup_create_mkstream(&*self.key, group, id).await
     }
 
@@ -296,13 +202,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xgroup_destroy(&*self.key, group).await
     }
 
@@ -310,13 +210,7 @@ where
     pub async fn read_group(
         &mut self, group: &str, consumer: &str, id: &str,
     ) -> RedisResult<StreamReadReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let opts = StreamReadOptions::default()
             .count(10)
             .group(group, consumer);
@@ -327,13 +221,7 @@ where
     pub async fn read_group_blocking(
         &mut self, group: &str, consumer: &str, id: &str, timeout: Duration,
     ) -> RedisResult<StreamReadReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let opts = StreamReadOptions::default()
             .count(10)
             .block(timeout.as_millis() as usize)
@@ -348,13 +236,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xack(&*self.key, group, ids).await
     }
 
@@ -362,13 +244,7 @@ where
     pub async fn pending(
         &mut self, group: &str,
     ) -> RedisResult<redis::streams::StreamPendingReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xpending(&*self.key, group).await
     }
 
@@ -376,13 +252,7 @@ where
     pub async fn pending_count(
         &mut self, group: &str, start: &str, end: &str, count: usize,
     ) -> RedisResult<redis::streams::StreamPendingCountReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xpending_count(&*self.key, group, start, end, count)
             .await
     }
@@ -392,13 +262,7 @@ where
         &mut self, group: &str, consumer: &str, min_idle_time: usize,
         ids: &[&str],
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xclaim(&*self.key, group, consumer, min_idle_time, ids)
             .await
     }
@@ -408,13 +272,7 @@ where
         &mut self, group: &str, consumer: &str, min_idle_time: usize,
         ids: &[&str],
     ) -> RedisResult<StreamRangeReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xclaim(&*self.key, group, consumer, min_idle_time, ids)
             .await
     }
@@ -423,13 +281,7 @@ where
     pub async fn info(
         &mut self,
     ) -> RedisResult<redis::streams::StreamInfoStreamReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xinfo_stream(&*self.key).await
     }
 
@@ -437,13 +289,7 @@ where
     pub async fn info_groups(
         &mut self,
     ) -> RedisResult<redis::streams::StreamInfoGroupsReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xinfo_groups(&*self.key).await
     }
 
@@ -451,13 +297,7 @@ where
     pub async fn info_consumers(
         &mut self, group: &str,
     ) -> RedisResult<redis::streams::StreamInfoConsumersReply> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.xinfo_consumers(&*self.key, group).await
     }
 }
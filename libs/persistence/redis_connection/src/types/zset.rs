@@ -8,6 +8,7 @@ use deadpool_redis::redis::{
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 
+use super::connection;
 use crate::core::{
     backend::CacheBackend,
     type_bind::CacheTypeTrait,
@@ -15,7 +16,7 @@ use crate::core::{
 };
 
 pub struct SortedSet<T> {
-    pool: deadpool_redis::Pool,
+    pool: Option<deadpool_redis::Pool>,
     key: Cow<'static, str>,
     __phantom: PhantomData<T>,
 }
@@ -24,17 +25,8 @@ impl<T> CacheTypeTrait<'_> for SortedSet<T> {
     fn from_cache_and_key(
         backend: CacheBackend<'_>, key: Cow<'static, str>,
     ) -> Self {
-        let pool = match backend {
-            CacheBackend::Redis(pool) => pool,
-            _ => {
-                panic!(
-                    "SortedSet type can only be created from Redis backend"
-                )
-            }
-        };
-
         Self {
-            pool,
+            pool: backend.redis_pool().cloned(),
             key,
             __phantom: PhantomData,
         }
@@ -52,13 +44,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zadd(&*self.key, value.into(), score).await
     }
 
@@ -74,13 +60,7 @@ where
             .into_iter()
             .map(|(score, value)| (score, value.into()))
             .collect();
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zadd_multiple(&*self.key, &json_items).await
     }
 
@@ -91,13 +71,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zrem(&*self.key, value.into()).await
     }
 
@@ -105,13 +79,7 @@ where
     pub async fn score(
         &mut self, value: impl Into<Json<T>>,
     ) -> RedisResult<Option<f64>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zscore(&*self.key, value.into()).await
     }
 
@@ -119,13 +87,7 @@ where
     pub async fn rank(
         &mut self, value: impl Into<Json<T>>,
     ) -> RedisResult<Option<usize>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zrank(&*self.key, value.into()).await
     }
 
@@ -133,13 +95,7 @@ where
     pub async fn reverse_rank(
         &mut self, value: impl Into<Json<T>>,
     ) -> RedisResult<Option<usize>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zrevrank(&*self.key, value.into()).await
     }
 
@@ -148,13 +104,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zcard(&*self.key).await
     }
 
@@ -162,13 +112,7 @@ where
     pub async fn range(
         &mut self, start: isize, stop: isize,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<Json<T>> =
             conn.zrange(&*self.key, start, stop).await?;
         Ok(json_vec.into_iter().map(|json| json.inner()).collect())
@@ -178,13 +122,7 @@ where
     pub async fn range_with_scores(
         &mut self, start: isize, stop: isize,
     ) -> RedisResult<Vec<(T, f64)>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<(Json<T>, f64)> =
             conn.zrange_withscores(&*self.key, start, stop).await?;
         Ok(json_vec
@@ -197,13 +135,7 @@ where
     pub async fn reverse_range(
         &mut self, start: isize, stop: isize,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<Json<T>> =
             conn.zrevrange(&*self.key, start, stop).await?;
         Ok(json_vec.into_iter().map(|json| json.inner()).collect())
@@ -213,13 +145,7 @@ where
     pub async fn reverse_range_with_scores(
         &mut self, start: isize, stop: isize,
     ) -> RedisResult<Vec<(T, f64)>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<(Json<T>, f64)> =
             conn.zrevrange_withscores(&*self.key, start, stop).await?;
         Ok(json_vec
@@ -232,13 +158,7 @@ where
     pub async fn range_by_score(
         &mut self, min: f64, max: f64,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<Json<T>> =
             conn.zrangebyscore(&*self.key, min, max).await?;
         Ok(json_vec.into_iter().map(|json| json.inner()).collect())
@@ -248,13 +168,7 @@ where
     pub async fn range_by_score_with_scores(
         &mut self, min: f64, max: f64,
     ) -> RedisResult<Vec<(T, f64)>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<(Json<T>, f64)> =
             conn.zrangebyscore_withscores(&*self.key, min, max).await?;
         Ok(json_vec
@@ -267,13 +181,7 @@ where
     pub async fn range_by_score_limit(
         &mut self, min: f64, max: f64, offset: isize, count: isize,
     ) -> RedisResult<Vec<T>> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let json_vec: Vec<Json<T>> = conn
             .zrangebyscore_limit(&*self.key, min, max, offset, count)
             .await?;
@@ -287,13 +195,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zcount(&*self.key, min, max).await
     }
 
@@ -301,13 +203,7 @@ where
     pub async fn increment_score(
         &mut self, value: impl Into<Json<T>>, increment: f64,
     ) -> RedisResult<f64> {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zincr(&*self.key, value.into(), increment).await
     }
 
@@ -318,13 +214,7 @@ where
     where
         RV: FromRedisValue,
     {
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         conn.zremrangebyrank(&*self.key, start, stop).await
     }
 
@@ -337,13 +227,7 @@ where
     {
         // Get members in range and remove them individually
         let members: Vec<T> = self.range_by_score(min, max).await?;
-        let mut conn = self.pool.get().await.map_err(|e| {
-            redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Pool connection error",
-                e.to_string(),
-            ))
-        })?;
+        let mut conn = connection(self.pool.as_ref()).await?;
         let mut removed_count = 0u32;
         for member in members {
             let count: u32 = conn.zrem(&*self.key, Json(member)).await?;
@@ -0,0 +1,102 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use redis_connection::{
+    cache_key, cache_provider::CacheProvider, core::CacheTypeBind,
+};
+use test_utils::TestRedisContainer;
+use tokio::task::JoinSet;
+
+cache_key!(CountKey::<i64> => "get_or_set:{}"[id: i64]);
+
+#[tokio::test]
+async fn test_fetch_runs_only_on_a_miss() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+    let fetches = AtomicUsize::new(0);
+    let fetch = || {
+        async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>(42)
+        }
+    };
+
+    for _ in 0..3 {
+        let mut entry = CountKey.bind_with(container.pool.clone(), &1);
+        let value = CacheProvider::get_or_set(
+            &mut entry,
+            Duration::from_secs(60),
+            fetch,
+        )
+        .await
+        .unwrap();
+        assert_eq!(value, 42);
+    }
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+    let mut entry = CountKey.bind_with(container.pool.clone(), &1);
+    assert_eq!(entry.try_get().await.unwrap(), Some(42));
+}
+
+#[tokio::test]
+async fn test_fetch_errors_are_not_cached() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+    let mut entry = CountKey.bind_with(container.pool.clone(), &2);
+
+    let err = CacheProvider::get_or_set(
+        &mut entry,
+        Duration::from_secs(60),
+        || async { Err::<i64, _>("database down") },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err, "database down");
+    assert_eq!(entry.try_get().await.unwrap(), None);
+
+    let value = CacheProvider::get_or_set(
+        &mut entry,
+        Duration::from_secs(60),
+        || async { Ok::<_, &str>(7) },
+    )
+    .await
+    .unwrap();
+    assert_eq!(value, 7);
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_share_one_fetch() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+    let fetches = Arc::new(AtomicUsize::new(0));
+
+    let mut lookups = JoinSet::new();
+    for _ in 0..8 {
+        let mut entry = CountKey.bind_with(container.pool.clone(), &3);
+        let fetches = fetches.clone();
+        lookups.spawn(async move {
+            CacheProvider::get_or_set(
+                &mut entry,
+                Duration::from_secs(60),
+                || {
+                    async move {
+                        fetches.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Ok::<_, String>(11)
+                    }
+                },
+            )
+            .await
+        });
+    }
+
+    while let Some(value) = lookups.join_next().await {
+        assert_eq!(value.unwrap(), Ok(11));
+    }
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,41 @@
+// Lives in its own test binary because the key prefix is process-wide and
+// this test switches it between two deployments sharing one Redis.
+
+use redis_connection::{
+    cache_key, cache_provider::CacheProvider, core::CacheTypeBind,
+};
+use test_utils::TestRedisContainer;
+
+cache_key!(NameKey::<String> => "key_prefix:{}"[id: i64]);
+
+#[tokio::test]
+async fn test_prefixed_deployments_do_not_share_entries() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+
+    CacheProvider::set_key_prefix("prod:");
+    let mut prod = NameKey.bind_with(container.pool.clone(), &1);
+    assert_eq!(prod.key(), "prod:key_prefix:1");
+    prod.set::<()>("from prod".to_string()).await.unwrap();
+
+    CacheProvider::set_key_prefix("staging:");
+    let mut staging = NameKey.bind_with(container.pool.clone(), &1);
+    assert_eq!(staging.key(), "staging:key_prefix:1");
+    assert_eq!(staging.try_get().await.unwrap(), None);
+    staging.set::<()>("from staging".to_string()).await.unwrap();
+
+    CacheProvider::set_key_prefix("prod:");
+    let mut prod = NameKey.bind_with(container.pool.clone(), &1);
+    assert_eq!(prod.try_get().await.unwrap().as_deref(), Some("from prod"));
+
+    // A pattern purge stays inside the current namespace
+    assert_eq!(
+        CacheProvider::namespaced("key_prefix:*".into()),
+        "prod:key_prefix:*"
+    );
+    CacheProvider::set_key_prefix("");
+    assert_eq!(
+        NameKey.bind_with(container.pool.clone(), &1).key(),
+        "key_prefix:1"
+    );
+}
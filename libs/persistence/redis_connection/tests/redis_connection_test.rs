@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use deadpool_redis::redis::AsyncCommands;
 use redis_connection::{
+    cache_provider::CacheProvider,
     config::{DbConnectConfig, RedisDbConfig},
     connection::RedisConnectionManager,
     core::command::{IntoRedisCommands, RedisCommands, RedisCommandsExt},
@@ -534,3 +535,33 @@ async fn test_redis_commands_key_operations() {
     let ttl = commands.ttl("new_key").await.unwrap();
     assert!(ttl > 0 && ttl <= 60);
 }
+
+#[tokio::test]
+async fn test_multi_get_aligns_hits_and_misses_by_position() {
+    let (container, manager) = setup_test_redis().await.unwrap();
+    let conn = manager.get_connection().await.unwrap();
+    let mut commands = conn.cmd();
+
+    let keys: Vec<String> = (1..=5)
+        .map(|id| test_key(&container, &format!("multi:user:{id}")))
+        .collect();
+    for (key, name) in
+        [(&keys[0], "alice"), (&keys[2], "carol"), (&keys[4], "eve")]
+    {
+        commands.set(key, format!("\"{name}\"")).await.unwrap();
+    }
+
+    let backend = CacheProvider::redis_backend(container.pool.clone());
+    let values = backend.multi_get::<String>(&keys).await.unwrap();
+
+    assert_eq!(
+        values,
+        vec![
+            Some("alice".to_string()),
+            None,
+            Some("carol".to_string()),
+            None,
+            Some("eve".to_string()),
+        ]
+    );
+}
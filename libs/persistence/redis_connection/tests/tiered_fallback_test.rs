@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use redis_connection::{
+    cache_key, cache_provider::CacheProvider, config::MemoryConfig,
+    core::CacheTypeBind,
+};
+use serde::{Deserialize, Serialize};
+use test_utils::TestRedisContainer;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedUser {
+    pub id: i64,
+    pub name: String,
+}
+
+cache_key!(CachedUserKey::<CachedUser> => "fallback:user:{}"[id: i64]);
+
+fn l1_config() -> MemoryConfig {
+    MemoryConfig {
+        capacity: 100,
+        ttl_secs: 60,
+    }
+}
+
+#[tokio::test]
+async fn test_l1_serves_cached_value_when_redis_is_down() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+
+    let backend =
+        CacheProvider::l1_redis_backend(container.pool.clone(), l1_config());
+    let user = CachedUser {
+        id: 1,
+        name: "Alice".to_string(),
+    };
+
+    let mut cache = CachedUserKey.bind_with(backend.clone(), &1);
+    cache
+        .set_with_expire::<()>(user.clone(), Duration::from_secs(300))
+        .await
+        .unwrap();
+
+    // Stopping the container takes Redis away from under the backend
+    drop(container);
+
+    let mut cache = CachedUserKey.bind_with(backend.clone(), &1);
+    assert_eq!(cache.try_get().await.unwrap(), Some(user));
+
+    // Keys that never made it into L1 still surface the Redis failure so
+    // callers fall back to the database
+    let mut uncached = CachedUserKey.bind_with(backend, &2);
+    assert!(uncached.try_get().await.is_err());
+}
+
+#[tokio::test]
+async fn test_l1_is_populated_from_redis_hits() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+
+    let user = CachedUser {
+        id: 3,
+        name: "Bob".to_string(),
+    };
+    let mut redis_only = CachedUserKey.bind_with(container.pool.clone(), &3);
+    redis_only
+        .set_with_expire::<()>(user.clone(), Duration::from_secs(300))
+        .await
+        .unwrap();
+
+    let backend =
+        CacheProvider::l1_redis_backend(container.pool.clone(), l1_config());
+    let mut cache = CachedUserKey.bind_with(backend.clone(), &3);
+    assert_eq!(cache.try_get().await.unwrap(), Some(user.clone()));
+
+    drop(container);
+
+    let mut cache = CachedUserKey.bind_with(backend, &3);
+    assert_eq!(cache.try_get().await.unwrap(), Some(user));
+}
+
+#[tokio::test]
+async fn test_l1_copy_expires_with_the_redis_key() {
+    let container = TestRedisContainer::new().await.unwrap();
+    container.flush_db().await.unwrap();
+
+    let backend =
+        CacheProvider::l1_redis_backend(container.pool.clone(), l1_config());
+    let user = CachedUser {
+        id: 4,
+        name: "Carol".to_string(),
+    };
+
+    let mut cache = CachedUserKey.bind_with(backend.clone(), &4);
+    cache
+        .set_with_expire::<()>(user.clone(), Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert_eq!(cache.try_get().await.unwrap(), Some(user));
+
+    // The L1 keeps entries for a minute, but not past the one second the
+    // Redis key was written with
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let mut cache = CachedUserKey.bind_with(backend, &4);
+    assert_eq!(cache.try_get().await.unwrap(), None);
+}
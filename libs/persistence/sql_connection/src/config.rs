@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub trait DbConnectConfig: serde::de::DeserializeOwned {
     fn scheme(&self) -> &str;
     fn username(&self) -> &str;
@@ -13,6 +15,41 @@ pub trait DbOptionsConfig {
     fn max_conn(&self) -> Option<u32> { None }
     fn min_conn(&self) -> Option<u32> { None }
     fn sql_logger(&self) -> bool { false }
+    fn statement_timeouts(&self) -> StatementTimeouts {
+        StatementTimeouts::default()
+    }
+}
+
+/// `statement_timeout` applied when a client is checked out, per kind of
+/// client. `None` leaves that kind unbounded. When every kind is `None`
+/// nothing is sent on checkout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct StatementTimeouts {
+    pub write: Option<Duration>,
+    pub read: Option<Duration>,
+    pub analytics: Option<Duration>,
+}
+
+impl StatementTimeouts {
+    /// `DB_WRITE_STATEMENT_TIMEOUT_MS`, `DB_READ_STATEMENT_TIMEOUT_MS` and
+    /// `DB_ANALYTICS_STATEMENT_TIMEOUT_MS`; unset or 0 means unbounded
+    pub fn from_env() -> Self {
+        fn millis(var: &str) -> Option<Duration> {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|&ms| ms > 0)
+                .map(Duration::from_millis)
+        }
+
+        Self {
+            write: millis("DB_WRITE_STATEMENT_TIMEOUT_MS"),
+            read: millis("DB_READ_STATEMENT_TIMEOUT_MS"),
+            analytics: millis("DB_ANALYTICS_STATEMENT_TIMEOUT_MS"),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool { *self != Self::default() }
 }
 
 // ReadReplicaConfig trait removed for BRRRRR mode - all connections on
@@ -24,6 +61,8 @@ pub struct PostgresDbConfig {
     pub min_conn: Option<u32>,
     #[serde(default = "logger_default")]
     pub logger: bool,
+    #[serde(default)]
+    pub statement_timeouts: StatementTimeouts,
     // Read replica fields removed for BRRRRR mode
 }
 
@@ -49,6 +88,10 @@ impl DbOptionsConfig for PostgresDbConfig {
     fn min_conn(&self) -> Option<u32> { self.min_conn }
 
     fn sql_logger(&self) -> bool { self.logger }
+
+    fn statement_timeouts(&self) -> StatementTimeouts {
+        self.statement_timeouts
+    }
 }
 
 // ReadReplicaConfig implementation removed for BRRRRR mode
@@ -1,31 +1,93 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, future::Future, pin::Pin, time::Duration};
 
 use database_traits::connection::{FromRequestParts, Parts};
-use deadpool_postgres::{Object, Pool};
+use deadpool_postgres::{Object, Pool, PoolError, Transaction};
 use tracing::{instrument, warn};
 
-use crate::static_vars::get_sql_pool;
+use crate::{
+    config::StatementTimeouts,
+    pooled_client::PooledClient,
+    static_vars::{get_sql_pool, get_statement_timeouts},
+};
+
+/// Future returned by the closure passed to [`SqlConnect::with_transaction`]
+pub type TxFuture<'t, T, E> =
+    Pin<Box<dyn Future<Output = Result<T, E>> + Send + 't>>;
 
 #[derive(Debug, Clone)]
 pub struct SqlConnect {
     pool: Pool, /* Single pool for BRRRRR mode - all 1000 connections on
                  * primary */
+    timeouts: StatementTimeouts,
 }
 
 impl SqlConnect {
-    pub fn new(pool: Pool) -> Self { Self { pool } }
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+            timeouts: StatementTimeouts::default(),
+        }
+    }
 
     pub fn from_global() -> Self {
         Self {
             pool: get_sql_pool().clone(),
+            timeouts: get_statement_timeouts(),
         }
     }
 
+    /// Bound how long statements on each kind of client may run
+    pub fn with_statement_timeouts(
+        mut self, timeouts: StatementTimeouts,
+    ) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn statement_timeouts(&self) -> StatementTimeouts { self.timeouts }
+
     /// Get connection for write operations (always uses primary database)
     #[instrument(skip(self), fields(pool_type = "primary"))]
-    pub async fn get_client(
-        &self,
-    ) -> Result<Object, deadpool_postgres::PoolError> {
+    pub async fn get_client(&self) -> Result<PooledClient, PoolError> {
+        let conn = self.checkout().await?;
+        self.apply_statement_timeout(conn, self.timeouts.write)
+            .await
+    }
+
+    /// Run `f` in a transaction on a primary connection. The transaction
+    /// commits when `f` returns `Ok` and rolls back when it returns `Err`,
+    /// so writes made through DAOs bound with their `in_transaction`
+    /// constructors land together or not at all.
+    ///
+    /// The closure returns a boxed future, e.g.
+    /// `|tx| Box::pin(async move { ... })`, and has to own what it moves
+    /// into it.
+    pub async fn with_transaction<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: for<'t> FnOnce(&'t Transaction<'t>) -> TxFuture<'t, T, E>,
+        E: From<PoolError> + From<tokio_postgres::Error>,
+    {
+        let mut client = self.get_client().await?;
+        let tx = client.transaction().await?;
+
+        match f(&tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    warn!(
+                        "Failed to roll back transaction: {}",
+                        rollback_err
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn checkout(&self) -> Result<Object, PoolError> {
         let status = self.pool.status();
         if status.available == 0 {
             warn!(
@@ -55,20 +117,36 @@ impl SqlConnect {
     /// Get connection for read operations (uses same pool as writes in BRRRRR
     /// mode)
     #[instrument(skip(self), fields(pool_type = "primary"))]
-    pub async fn get_read_client(
-        &self,
-    ) -> Result<Object, deadpool_postgres::PoolError> {
+    pub async fn get_read_client(&self) -> Result<PooledClient, PoolError> {
         // In BRRRRR mode, all reads and writes use the same 1000-connection
         // pool
-        self.get_client().await
+        let conn = self.checkout().await?;
+        self.apply_statement_timeout(conn, self.timeouts.read).await
     }
 
     /// Get connection optimized for heavy analytics queries
     pub async fn get_analytics_client(
         &self,
-    ) -> Result<Object, deadpool_postgres::PoolError> {
+    ) -> Result<PooledClient, PoolError> {
         // In BRRRRR mode, analytics use the same pool as everything else
-        self.get_client().await
+        let conn = self.checkout().await?;
+        self.apply_statement_timeout(conn, self.timeouts.analytics)
+            .await
+    }
+
+    /// Pooled connections keep session settings, so once any timeout is
+    /// configured every checkout sets its own (0 disables it) instead of
+    /// inheriting whatever the previous borrower left behind
+    async fn apply_statement_timeout(
+        &self, conn: Object, timeout: Option<Duration>,
+    ) -> Result<PooledClient, PoolError> {
+        if self.timeouts.is_configured() {
+            let millis = timeout.map_or(0, |t| t.as_millis());
+            conn.batch_execute(&format!("SET statement_timeout = {millis}"))
+                .await
+                .map_err(PoolError::Backend)?;
+        }
+        Ok(PooledClient::new(conn))
     }
 
     /// Check if read-write splitting is enabled (always false in BRRRRR mode)
@@ -1,10 +1,25 @@
-pub use config::{DbConnectConfig, DbOptionsConfig, PostgresDbConfig}; /* ReadReplicaConfig removed for BRRRRR mode */
+pub use config::{
+    DbConnectConfig, DbOptionsConfig, PostgresDbConfig, StatementTimeouts,
+}; // ReadReplicaConfig removed for BRRRRR mode
 pub use database_traits;
-pub use deadpool_postgres::PoolError;
-pub use impl_get_connect::SqlConnect;
+pub use deadpool_postgres::{GenericClient, PoolError, Transaction};
+pub use impl_get_connect::{SqlConnect, TxFuture};
+pub use migrator::SqlMigrator;
+pub use pool_error::{
+    POOL_EXHAUSTED_RETRY_AFTER, pg_error_to_app_error,
+    pool_error_to_app_error,
+};
+pub use pooled_client::PooledClient;
+pub use query_count::QueryCounter;
 pub use tokio_postgres::Error as PgError;
 pub mod config;
 mod impl_get_connect;
+mod migrator;
+mod pool_error;
+mod pooled_client;
+mod query_count;
 mod static_vars;
 
-pub use static_vars::{connect_postgres_db, get_sql_pool};
+pub use static_vars::{
+    connect_postgres_db, get_sql_pool, get_statement_timeouts,
+};
@@ -1,5 +1,9 @@
-use deadpool_postgres::Pool;
+use deadpool_postgres::{Client, Pool};
 use tokio_postgres::Transaction;
+use tracing::{debug, info, warn};
+
+/// Key of the session advisory lock held while migrations run
+const MIGRATION_LOCK_KEY: i64 = 0x636f_6c6c_6964_6572; // "collider"
 
 pub struct SqlMigrator {
     pool: Pool,
@@ -8,52 +12,99 @@ pub struct SqlMigrator {
 impl SqlMigrator {
     pub fn new(pool: Pool) -> Self { Self { pool } }
 
+    /// Apply every pending migration. Holds an advisory lock for the whole
+    /// run, so when several instances boot at once one applies the
+    /// migrations while the others wait and then find nothing to do.
     pub async fn run_all_migrations(&self) -> anyhow::Result<()> {
-        self.create_migration_table().await?;
+        let mut client = self.pool.get().await?;
+        client
+            .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_KEY])
+            .await?;
+
+        let applied = self.apply_pending(&mut client).await;
+        // Release before reporting a failure; the pooled connection would
+        // otherwise keep the lock
+        let unlocked = client
+            .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_KEY])
+            .await;
+        applied?;
+        unlocked?;
+        Ok(())
+    }
+
+    async fn apply_pending(&self, client: &mut Client) -> anyhow::Result<()> {
+        self.create_migration_table(client).await?;
 
         let migrations = vec![
             (
                 "001_create_users",
                 include_str!(
-                    "../../../domains/users/migrations/sql/001_create_users.\
-                     sql"
+                    "../../../../domains/users/migrations/sql/\
+                     001_create_users.sql"
                 ),
             ),
             (
                 "002_create_event_types",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      002_create_event_types.sql"
                 ),
             ),
             (
                 "003_create_events",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      003_create_events.sql"
                 ),
             ),
             (
                 "004_create_stats_materialized_view",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      004_create_stats_materialized_view.sql"
                 ),
             ),
             (
                 "005_add_indexes",
                 include_str!(
-                    "../../../domains/events/migrations/sql/005_add_indexes.\
-                     sql"
+                    "../../../../domains/events/migrations/sql/\
+                     005_add_indexes.sql"
+                ),
+            ),
+            (
+                "006_create_hourly_summary",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     006_create_hourly_summary.sql"
+                ),
+            ),
+            (
+                "007_unique_user_names",
+                include_str!(
+                    "../../../../domains/users/migrations/sql/\
+                     007_unique_user_names.sql"
+                ),
+            ),
+            (
+                "008_extract_metadata_columns",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     008_extract_metadata_columns.sql"
+                ),
+            ),
+            (
+                "009_refresh_state_previous_event_id",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     009_refresh_state_previous_event_id.sql"
                 ),
             ),
         ];
 
         for (migration_name, migration_sql) in migrations {
-            if !self.is_migration_applied(migration_name).await? {
-                println!("Running migration: {migration_name}");
+            if !self.is_migration_applied(client, migration_name).await? {
+                info!("Running migration: {migration_name}");
 
-                let mut client = self.pool.get().await?;
                 let tx = client.transaction().await?;
 
                 self.execute_migration_sql(&tx, migration_sql)
@@ -74,10 +125,10 @@ impl SqlMigrator {
                 .await?;
 
                 tx.commit().await?;
-                println!("Migration {migration_name} completed successfully");
+                info!("Migration {migration_name} completed successfully");
             }
             else {
-                println!(
+                debug!(
                     "Migration {migration_name} already applied, skipping"
                 );
             }
@@ -86,8 +137,9 @@ impl SqlMigrator {
         Ok(())
     }
 
-    async fn create_migration_table(&self) -> anyhow::Result<()> {
-        let client = self.pool.get().await?;
+    async fn create_migration_table(
+        &self, client: &Client,
+    ) -> anyhow::Result<()> {
         client
             .execute(
                 r#"
@@ -104,9 +156,8 @@ impl SqlMigrator {
     }
 
     async fn is_migration_applied(
-        &self, migration_name: &str,
+        &self, client: &Client, migration_name: &str,
     ) -> anyhow::Result<bool> {
-        let client = self.pool.get().await?;
         let row = client
             .query_one(
                 "SELECT COUNT(*) FROM _migrations WHERE name = $1",
@@ -121,10 +172,10 @@ impl SqlMigrator {
     pub async fn run_migration(
         &self, migration_name: &str, migration_sql: &str,
     ) -> anyhow::Result<()> {
-        self.create_migration_table().await?;
+        let mut client = self.pool.get().await?;
+        self.create_migration_table(&client).await?;
 
-        if !self.is_migration_applied(migration_name).await? {
-            let mut client = self.pool.get().await?;
+        if !self.is_migration_applied(&client, migration_name).await? {
             let tx = client.transaction().await?;
 
             self.execute_migration_sql(&tx, migration_sql)
@@ -153,9 +204,9 @@ impl SqlMigrator {
     pub async fn list_applied_migrations(
         &self,
     ) -> anyhow::Result<Vec<String>> {
-        self.create_migration_table().await?;
-
         let client = self.pool.get().await?;
+        self.create_migration_table(&client).await?;
+
         let rows = client
             .query("SELECT name FROM _migrations ORDER BY applied_at", &[])
             .await?;
@@ -168,18 +219,12 @@ impl SqlMigrator {
     ) -> anyhow::Result<()> {
         let statements = self.split_sql_statements(migration_sql);
 
-        println!("DEBUG: Found {} statements", statements.len());
-        for (i, statement) in statements.iter().enumerate() {
-            println!("DEBUG: Statement {}: {}", i, statement.trim());
-        }
-
         for statement in statements {
             let trimmed = statement.trim();
             if !trimmed.is_empty()
                 && !trimmed.starts_with("--")
                 && !trimmed.starts_with("/*")
             {
-                println!("Executing SQL: {trimmed}");
                 tx.execute(trimmed, &[]).await.map_err(|e| {
                     anyhow::anyhow!(
                         "Failed to execute SQL statement '{}': {}",
@@ -241,46 +286,74 @@ impl SqlMigrator {
         &self, migrations_to_rollback: &[&str],
     ) -> anyhow::Result<()> {
         let down_migrations = vec![
+            (
+                "009_refresh_state_previous_event_id",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     009_refresh_state_previous_event_id.down.sql"
+                ),
+            ),
+            (
+                "008_extract_metadata_columns",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     008_extract_metadata_columns.down.sql"
+                ),
+            ),
+            (
+                "007_unique_user_names",
+                include_str!(
+                    "../../../../domains/users/migrations/sql/\
+                     007_unique_user_names.down.sql"
+                ),
+            ),
+            (
+                "006_create_hourly_summary",
+                include_str!(
+                    "../../../../domains/events/migrations/sql/\
+                     006_create_hourly_summary.down.sql"
+                ),
+            ),
             (
                 "005_add_indexes",
                 include_str!(
-                    "../../../domains/events/migrations/sql/005_add_indexes.\
-                     down.sql"
+                    "../../../../domains/events/migrations/sql/\
+                     005_add_indexes.down.sql"
                 ),
             ),
             (
                 "004_create_stats_materialized_view",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      004_create_stats_materialized_view.down.sql"
                 ),
             ),
             (
                 "003_create_events",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      003_create_events.down.sql"
                 ),
             ),
             (
                 "002_create_event_types",
                 include_str!(
-                    "../../../domains/events/migrations/sql/\
+                    "../../../../domains/events/migrations/sql/\
                      002_create_event_types.down.sql"
                 ),
             ),
             (
                 "001_create_users",
                 include_str!(
-                    "../../../domains/users/migrations/sql/001_create_users.\
-                     down.sql"
+                    "../../../../domains/users/migrations/sql/\
+                     001_create_users.down.sql"
                 ),
             ),
         ];
 
         for (migration_name, down_sql) in down_migrations {
             if migrations_to_rollback.contains(&migration_name) {
-                println!("Rolling back migration: {migration_name}");
+                info!("Rolling back migration: {migration_name}");
 
                 let mut client = self.pool.get().await?;
                 let tx = client.transaction().await?;
@@ -302,9 +375,7 @@ impl SqlMigrator {
                 .await?;
 
                 tx.commit().await?;
-                println!(
-                    "Migration {migration_name} rolled back successfully"
-                );
+                info!("Migration {migration_name} rolled back successfully");
             }
         }
 
@@ -312,9 +383,7 @@ impl SqlMigrator {
     }
 
     pub async fn reset_all(&self) -> anyhow::Result<()> {
-        println!(
-            "WARNING: Resetting all migrations - this will delete ALL data!"
-        );
+        warn!("Resetting all migrations - this will delete ALL data!");
 
         let applied_migrations = self.list_applied_migrations().await?;
         let migrations_to_rollback: Vec<&str> =
@@ -327,7 +396,7 @@ impl SqlMigrator {
             .execute("DROP TABLE IF EXISTS _migrations CASCADE", &[])
             .await?;
 
-        println!("All migrations reset successfully");
+        info!("All migrations reset successfully");
         Ok(())
     }
 }
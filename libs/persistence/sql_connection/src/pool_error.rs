@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use common_errors::{AppError, redact};
+use deadpool_postgres::PoolError;
+use tokio_postgres::error::SqlState;
+
+/// Retry hint sent when no database connection could be acquired in time
+pub const POOL_EXHAUSTED_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Map a failure to acquire a connection to an API error.
+///
+/// A timeout means every connection stayed busy for the whole wait (or new
+/// ones could not be opened fast enough). That is temporary, so it becomes a
+/// 503 with a retry hint rather than a 500.
+pub fn pool_error_to_app_error(err: &PoolError) -> AppError {
+    match err {
+        PoolError::Timeout(_) => {
+            AppError::service_unavailable(
+                "DATABASE_BUSY",
+                "No database connection available, try again shortly",
+                Some(POOL_EXHAUSTED_RETRY_AFTER),
+            )
+        }
+        _ => {
+            AppError::internal_server_error(&format!(
+                "Database connection error: {}",
+                redact(&err.to_string())
+            ))
+        }
+    }
+}
+
+/// Map a failed statement to an API error.
+///
+/// A statement cancelled by `statement_timeout` becomes a 504 so clients can
+/// tell a query that took too long from a broken one; anything else is a 500.
+pub fn pg_error_to_app_error(err: &tokio_postgres::Error) -> AppError {
+    if err.code() == Some(&SqlState::QUERY_CANCELED) {
+        AppError::GatewayTimeout {
+            code: "DATABASE_TIMEOUT".to_string(),
+            message: "Database query exceeded its statement timeout"
+                .to_string(),
+            details: None,
+        }
+    }
+    else {
+        AppError::internal_server_error(&format!(
+            "Database error: {}",
+            redact(&err.to_string())
+        ))
+    }
+}
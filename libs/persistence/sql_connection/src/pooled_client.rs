@@ -0,0 +1,102 @@
+use std::ops::{Deref, DerefMut};
+
+use deadpool_postgres::Object;
+use tokio_postgres::{
+    Error, Row, RowStream, SimpleQueryMessage, ToStatement,
+    types::{BorrowToSql, ToSql},
+};
+
+use crate::query_count::record_query;
+
+/// A connection checked out of the pool by [`SqlConnect`]. Running a
+/// statement on it counts towards the enclosing [`QueryCounter`] scope;
+/// everything else, like `prepare_cached` or `transaction`, goes straight
+/// to the pooled client.
+///
+/// Statements run inside a transaction, or through a helper taking
+/// `&impl GenericClient`, reach the pooled client directly and are not
+/// counted.
+///
+/// [`SqlConnect`]: crate::SqlConnect
+/// [`QueryCounter`]: crate::QueryCounter
+#[derive(Debug)]
+pub struct PooledClient(Object);
+
+impl PooledClient {
+    pub(crate) fn new(client: Object) -> Self { Self(client) }
+
+    pub async fn query<T>(
+        &self, statement: &T, params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        record_query();
+        self.0.query(statement, params).await
+    }
+
+    pub async fn query_one<T>(
+        &self, statement: &T, params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        record_query();
+        self.0.query_one(statement, params).await
+    }
+
+    pub async fn query_opt<T>(
+        &self, statement: &T, params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        record_query();
+        self.0.query_opt(statement, params).await
+    }
+
+    pub async fn query_raw<T, P, I>(
+        &self, statement: &T, params: I,
+    ) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        record_query();
+        self.0.query_raw(statement, params).await
+    }
+
+    pub async fn execute<T>(
+        &self, statement: &T, params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        record_query();
+        self.0.execute(statement, params).await
+    }
+
+    pub async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        record_query();
+        self.0.batch_execute(query).await
+    }
+
+    pub async fn simple_query(
+        &self, query: &str,
+    ) -> Result<Vec<SimpleQueryMessage>, Error> {
+        record_query();
+        self.0.simple_query(query).await
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = Object;
+
+    fn deref(&self) -> &Object { &self.0 }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Object { &mut self.0 }
+}
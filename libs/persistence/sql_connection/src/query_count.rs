@@ -0,0 +1,43 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+tokio::task_local! {
+    static QUERIES: Arc<AtomicUsize>;
+}
+
+/// Counts the statements run on clients from [`SqlConnect`] while a future
+/// runs, i.e. the database round trips a request made — enough to spot an
+/// N+1 loop, even one reusing a single client. Preparing a statement is
+/// not counted, only running it.
+///
+/// Only statements on the scoped task are seen; work moved onto a spawned
+/// task or a background flusher is not counted. See [`PooledClient`] for
+/// what else goes uncounted.
+///
+/// [`SqlConnect`]: crate::SqlConnect
+/// [`PooledClient`]: crate::PooledClient
+#[derive(Debug, Clone, Default)]
+pub struct QueryCounter(Arc<AtomicUsize>);
+
+impl QueryCounter {
+    pub fn new() -> Self { Self::default() }
+
+    /// Run `fut`, counting every statement it runs
+    pub async fn scope<F: Future>(&self, fut: F) -> F::Output {
+        QUERIES.scope(self.0.clone(), fut).await
+    }
+
+    /// Statements counted so far, shared across clones
+    pub fn count(&self) -> usize { self.0.load(Ordering::Relaxed) }
+}
+
+/// Called for every statement; a no-op outside [`QueryCounter::scope`]
+pub(crate) fn record_query() {
+    let _ =
+        QUERIES.try_with(|queries| queries.fetch_add(1, Ordering::Relaxed));
+}
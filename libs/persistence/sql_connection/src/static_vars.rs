@@ -4,9 +4,10 @@ use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
 use tokio_postgres::NoTls;
 use tracing::{debug, info, instrument};
 
-use crate::config::{DbConnectConfig, DbOptionsConfig};
+use crate::config::{DbConnectConfig, DbOptionsConfig, StatementTimeouts};
 
 static SQL_DATABASE_POOL: OnceLock<Pool> = OnceLock::new();
+static SQL_STATEMENT_TIMEOUTS: OnceLock<StatementTimeouts> = OnceLock::new();
 
 /// Pre-warms a connection pool by creating connections up front
 async fn prewarm_pool(pool: &Pool, count: u32) {
@@ -58,7 +59,8 @@ where
         postgres.url = db_url,
         postgres.max_conn = ?config.max_conn(),
         postgres.min_conn = ?config.min_conn(),
-        postgres.sql_logger = config.sql_logger()
+        postgres.sql_logger = config.sql_logger(),
+        postgres.statement_timeouts = ?config.statement_timeouts()
     );
 
     let pg_config = db_url.parse::<tokio_postgres::Config>()?;
@@ -86,6 +88,7 @@ where
     if SQL_DATABASE_POOL.set(pool.clone()).is_err() {
         panic!("SQL database pool already established")
     }
+    let _ = SQL_STATEMENT_TIMEOUTS.set(config.statement_timeouts());
 
     // Pre-warm the connection pool
     if let Some(min_conn) = config.min_conn() {
@@ -103,3 +106,8 @@ pub fn get_sql_pool() -> &'static Pool {
         .get()
         .expect("SQL database pool not established")
 }
+
+/// Statement timeouts from the config passed to [`connect_postgres_db`]
+pub fn get_statement_timeouts() -> StatementTimeouts {
+    SQL_STATEMENT_TIMEOUTS.get().copied().unwrap_or_default()
+}
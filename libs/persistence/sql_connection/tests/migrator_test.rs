@@ -0,0 +1,80 @@
+use sql_connection::SqlMigrator;
+use test_utils::TestPostgresContainer;
+
+#[tokio::test]
+async fn test_concurrent_runs_apply_each_migration_once() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let migrator = SqlMigrator::new(container.pool.clone());
+    migrator.reset_all().await.unwrap();
+
+    let other = SqlMigrator::new(container.pool.clone());
+    let (first, second) = tokio::join!(
+        migrator.run_all_migrations(),
+        other.run_all_migrations()
+    );
+    first.unwrap();
+    second.unwrap();
+
+    // Without the lock the loser fails re-running a migration the winner
+    // has already applied
+    let applied = migrator.list_applied_migrations().await.unwrap();
+    assert!(applied.contains(&"008_extract_metadata_columns".to_string()));
+
+    // Both runs released the lock
+    let client = container.pool.get().await.unwrap();
+    let row = client
+        .query_one(
+            "SELECT pg_try_advisory_lock(x'636f6c6c69646572'::bigint)",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(row.get::<_, bool>(0));
+}
+
+#[tokio::test]
+async fn test_unique_names_migration_renames_existing_duplicates() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let migrator = SqlMigrator::new(container.pool.clone());
+    migrator
+        .run_down_migrations(&["007_unique_user_names"])
+        .await
+        .unwrap();
+
+    let client = container.pool.get().await.unwrap();
+    let long = "x".repeat(100);
+    let mut ids = Vec::new();
+    for name in ["dup", "solo", "dup", long.as_str(), long.as_str()] {
+        let row = client
+            .query_one(
+                "INSERT INTO users (name) VALUES ($1) RETURNING id",
+                &[&name],
+            )
+            .await
+            .unwrap();
+        ids.push(row.get::<_, i64>(0));
+    }
+
+    migrator.run_all_migrations().await.unwrap();
+
+    let rows = client
+        .query(
+            "SELECT name FROM users WHERE id = ANY($1) ORDER BY id",
+            &[&ids],
+        )
+        .await
+        .unwrap();
+    let names: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+    let long_dup =
+        format!("{}#{}", &long[..100 - 1 - ids[4].to_string().len()], ids[4]);
+    assert_eq!(
+        names,
+        [
+            "dup".to_string(),
+            "solo".to_string(),
+            format!("dup#{}", ids[2]),
+            long.clone(),
+            long_dup,
+        ]
+    );
+}
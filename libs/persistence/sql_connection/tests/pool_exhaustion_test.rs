@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use common_errors::AppError;
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use sql_connection::{
+    POOL_EXHAUSTED_RETRY_AFTER, SqlConnect, pool_error_to_app_error,
+};
+use test_utils::TestPostgresContainer;
+use tokio_postgres::NoTls;
+
+#[tokio::test]
+async fn test_starved_pool_maps_to_service_unavailable() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let mgr = Manager::from_config(
+        container.connection_string.parse().unwrap(),
+        NoTls,
+        ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        },
+    );
+    let pool = Pool::builder(mgr)
+        .runtime(deadpool_postgres::Runtime::Tokio1)
+        .max_size(1)
+        .wait_timeout(Some(Duration::from_millis(100)))
+        .build()
+        .unwrap();
+    let sql_connect = SqlConnect::new(pool);
+
+    let _held = sql_connect.get_client().await.unwrap();
+    let err = sql_connect.get_client().await.unwrap_err();
+
+    assert!(matches!(
+        pool_error_to_app_error(&err),
+        AppError::ServiceUnavailable {
+            retry_after: Some(retry_after),
+            ..
+        } if retry_after == POOL_EXHAUSTED_RETRY_AFTER
+    ));
+}
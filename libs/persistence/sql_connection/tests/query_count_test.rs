@@ -0,0 +1,29 @@
+use sql_connection::{QueryCounter, SqlConnect};
+use test_utils::TestPostgresContainer;
+
+#[tokio::test]
+async fn test_counter_sees_only_statements_in_its_scope() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let sql_connect = SqlConnect::new(container.pool.clone());
+
+    let counter = QueryCounter::new();
+    counter
+        .scope(async {
+            // Several statements on one client each count
+            let client = sql_connect.get_read_client().await.unwrap();
+            let stmt = client.prepare_cached("SELECT $1::int").await.unwrap();
+            for n in 0..3 {
+                client.query_one(&stmt, &[&n]).await.unwrap();
+            }
+            drop(client);
+
+            // A checkout without statements counts nothing
+            let _idle = sql_connect.get_client().await.unwrap();
+        })
+        .await;
+
+    // Outside the scope nothing is recorded
+    let client = sql_connect.get_read_client().await.unwrap();
+    client.query_one("SELECT 1", &[]).await.unwrap();
+    assert_eq!(counter.count(), 3);
+}
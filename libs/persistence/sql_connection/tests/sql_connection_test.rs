@@ -104,3 +104,48 @@ async fn test_concurrent_operations() {
         assert_eq!(result, i as i32);
     }
 }
+
+#[tokio::test]
+async fn test_with_transaction_commits_or_rolls_back() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let sql_connect = SqlConnect::new(container.pool.clone());
+    let count_users = || {
+        async {
+            let client = sql_connect.get_client().await.unwrap();
+            let row = client
+                .query_one("SELECT COUNT(*) FROM users", &[])
+                .await
+                .unwrap();
+            row.get::<_, i64>(0)
+        }
+    };
+
+    let result = sql_connect
+        .with_transaction(|tx| {
+            Box::pin(async move {
+                tx.execute(
+                    "INSERT INTO users (name) VALUES ('rolled_back')",
+                    &[],
+                )
+                .await?;
+                Err::<(), _>(anyhow::anyhow!("fail after the insert"))
+            })
+        })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(count_users().await, 0);
+
+    let inserted = sql_connect
+        .with_transaction(|tx| {
+            Box::pin(async move {
+                let inserted = tx
+                    .execute("INSERT INTO users (name) VALUES ('kept')", &[])
+                    .await?;
+                Ok::<_, anyhow::Error>(inserted)
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(inserted, 1);
+    assert_eq!(count_users().await, 1);
+}
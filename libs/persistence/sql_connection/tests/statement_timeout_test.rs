@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use common_errors::AppError;
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use sql_connection::{SqlConnect, StatementTimeouts, pg_error_to_app_error};
+use test_utils::TestPostgresContainer;
+use tokio_postgres::{NoTls, error::SqlState};
+
+fn single_connection_pool(connection_string: &str) -> Pool {
+    let mgr = Manager::from_config(
+        connection_string.parse().unwrap(),
+        NoTls,
+        ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        },
+    );
+    Pool::builder(mgr)
+        .runtime(deadpool_postgres::Runtime::Tokio1)
+        .max_size(1)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_read_client_statement_timeout_fails_fast() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let sql_connect =
+        SqlConnect::new(single_connection_pool(&container.connection_string))
+            .with_statement_timeouts(StatementTimeouts {
+                read: Some(Duration::from_millis(100)),
+                ..Default::default()
+            });
+
+    let client = sql_connect.get_read_client().await.unwrap();
+    let started = Instant::now();
+    let err = client.simple_query("SELECT pg_sleep(5)").await.unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(err.code(), Some(&SqlState::QUERY_CANCELED));
+    assert!(matches!(
+        pg_error_to_app_error(&err),
+        AppError::GatewayTimeout { ref code, .. } if code == "DATABASE_TIMEOUT"
+    ));
+}
+
+#[tokio::test]
+async fn test_write_client_does_not_inherit_read_timeout() {
+    let container = TestPostgresContainer::new().await.unwrap();
+    let sql_connect =
+        SqlConnect::new(single_connection_pool(&container.connection_string))
+            .with_statement_timeouts(StatementTimeouts {
+                read: Some(Duration::from_millis(100)),
+                ..Default::default()
+            });
+
+    // Same physical connection both times: the pool holds one
+    drop(sql_connect.get_read_client().await.unwrap());
+    let client = sql_connect.get_client().await.unwrap();
+    let row = client
+        .query_one("SHOW statement_timeout", &[])
+        .await
+        .unwrap();
+
+    assert_eq!(row.get::<_, String>(0), "0");
+}
@@ -0,0 +1,102 @@
+use std::fmt::Debug;
+
+use database_traits::dao::GenericDao;
+
+/// Standard CRUD battery for a [`GenericDao`]: create, find, list, update,
+/// delete, then check the row is gone. Each phase checks `count`, and the
+/// entity is deleted before the last phase, so the table is back where it
+/// started when the suite ends.
+pub struct GenericDaoSuite<D: GenericDao> {
+    /// Request creating the sample entity
+    pub create: D::CreateRequest,
+    /// Request applied to the created entity
+    pub update: D::UpdateRequest,
+    /// Id of an entity in a response
+    pub id_of: fn(&D::Response) -> D::ID,
+    /// Panics unless the response reflects `update`
+    pub assert_updated: fn(&D::Response),
+    /// Whether an error means "no such row"
+    pub is_not_found: fn(&D::Error) -> bool,
+}
+
+impl<D> GenericDaoSuite<D>
+where
+    D: GenericDao,
+    D::ID: Clone + PartialEq + Debug,
+    D::Error: Debug,
+{
+    pub async fn run(self, dao: &D) {
+        let baseline = dao.count().await.unwrap();
+
+        // Create
+        let created = dao.create(self.create).await.unwrap();
+        let id = (self.id_of)(&created);
+        assert_eq!(dao.count().await.unwrap(), baseline + 1);
+
+        // Find
+        let found = dao.find_by_id(id.clone()).await.unwrap();
+        assert_eq!((self.id_of)(&found), id);
+        assert!(
+            dao.all()
+                .await
+                .unwrap()
+                .iter()
+                .any(|entity| (self.id_of)(entity) == id)
+        );
+
+        // Update
+        let updated = dao.update(id.clone(), self.update).await.unwrap();
+        assert_eq!((self.id_of)(&updated), id);
+        (self.assert_updated)(&updated);
+        (self.assert_updated)(&dao.find_by_id(id.clone()).await.unwrap());
+        assert_eq!(dao.count().await.unwrap(), baseline + 1);
+
+        // Delete
+        dao.delete(id.clone()).await.unwrap();
+        assert_eq!(dao.count().await.unwrap(), baseline);
+
+        // Not found
+        match dao.find_by_id(id.clone()).await {
+            Err(err) => {
+                assert!(
+                    (self.is_not_found)(&err),
+                    "unexpected error: {err:?}"
+                )
+            }
+            Ok(_) => panic!("entity {id:?} still found after delete"),
+        }
+        match dao.delete(id.clone()).await {
+            Err(err) => {
+                assert!(
+                    (self.is_not_found)(&err),
+                    "unexpected error: {err:?}"
+                )
+            }
+            Ok(()) => panic!("entity {id:?} deleted twice"),
+        }
+    }
+}
+
+/// Define a `#[tokio::test]` running a [`GenericDaoSuite`] against a fresh
+/// database.
+///
+/// ```ignore
+/// test_generic_dao!(
+///     test_user_dao_crud,
+///     |container| UserDao::new(create_sql_connect(container)),
+///     GenericDaoSuite::<UserDao> { .. }
+/// );
+/// ```
+#[macro_export]
+macro_rules! test_generic_dao {
+    ($name:ident, $dao:expr, $suite:expr $(,)?) => {
+        #[tokio::test]
+        async fn $name() {
+            let container =
+                $crate::TestPostgresContainer::new().await.unwrap();
+            let make_dao: fn(&$crate::TestPostgresContainer) -> _ = $dao;
+            let dao = make_dao(&container);
+            $crate::dao_harness::GenericDaoSuite::run($suite, &dao).await;
+        }
+    };
+}
@@ -1,4 +1,4 @@
-pub mod sql_migrator;
+pub mod dao_harness;
 pub mod test_helpers;
 
 use std::time::Duration;
@@ -9,6 +9,7 @@ use deadpool_postgres::{
 };
 use deadpool_redis::{Config as RedisConfig, Pool as RedisPool, Runtime};
 use redis_connection::connection::RedisConnectionManager;
+pub use sql_connection::SqlMigrator;
 pub use test_helpers::*;
 use testcontainers_modules::{
     postgres::Postgres,
@@ -17,8 +18,6 @@ use testcontainers_modules::{
 };
 use tokio_postgres::NoTls;
 
-pub use crate::sql_migrator::SqlMigrator;
-
 /// Modern PostgreSQL test container using testcontainers-rs
 pub struct TestPostgresContainer {
     pub pool: PostgresPool,
@@ -0,0 +1,372 @@
+use std::time::Duration;
+
+use axum::{
+    BoxError, Router,
+    error_handling::HandleErrorLayer,
+    http::{Method, Uri},
+    middleware,
+};
+use common_errors::AppError;
+use tower::{ServiceBuilder, timeout::TimeoutLayer};
+use tower_http::trace::TraceLayer;
+use tracing::info;
+
+use crate::{
+    correlation::propagate_correlation_id,
+    cors::CorsConfig,
+    debug_queries::count_queries,
+    sampling::{SpanSampler, sample_traces},
+};
+
+/// Used when `REQUEST_TIMEOUT_SECS` is unset or invalid
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wraps the assembled routes with the server-wide layers
+pub struct AppBuilder {
+    routes: Router,
+    request_timeout: Duration,
+    cors: CorsConfig,
+    debug_queries: bool,
+    trace_sampler: SpanSampler,
+}
+
+impl AppBuilder {
+    pub fn new(routes: Router) -> Self {
+        Self {
+            routes,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            cors: CorsConfig::default(),
+            debug_queries: cfg!(debug_assertions),
+            trace_sampler: SpanSampler::default(),
+        }
+    }
+
+    /// Requests running longer than `timeout` are abandoned with a 504
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Read the request timeout from `REQUEST_TIMEOUT_SECS`
+    pub fn request_timeout_from_env(self) -> Self {
+        let timeout = std::env::var("REQUEST_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        self.request_timeout(timeout)
+    }
+
+    /// Cross-origin policy; permissive unless configured
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Read the cross-origin policy from the `CORS_*` variables
+    pub fn cors_from_env(self) -> Self { self.cors(CorsConfig::from_env()) }
+
+    /// Let callers ask for the request's query count with
+    /// `X-Debug-Queries: 1`; on by default in debug builds only
+    pub fn debug_queries(mut self, enabled: bool) -> Self {
+        self.debug_queries = enabled;
+        self
+    }
+
+    /// Read the query-count switch from `DEBUG_QUERIES`
+    pub fn debug_queries_from_env(self) -> Self {
+        let enabled = std::env::var("DEBUG_QUERIES")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(cfg!(debug_assertions));
+        self.debug_queries(enabled)
+    }
+
+    /// Trace only one in every `rate` `POST /event` requests; 0 or 1
+    /// traces them all
+    pub fn trace_sampling(mut self, rate: u64) -> Self {
+        self.trace_sampler = SpanSampler::new(rate);
+        self
+    }
+
+    /// Read the event trace sampling rate from `EVENT_TRACE_SAMPLE_RATE`
+    pub fn trace_sampling_from_env(self) -> Self {
+        let rate = std::env::var("EVENT_TRACE_SAMPLE_RATE")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1);
+        self.trace_sampling(rate)
+    }
+
+    pub fn build(self) -> Router {
+        let mut routes = self.routes;
+        if self.debug_queries {
+            routes = routes.layer(middleware::from_fn(count_queries));
+        }
+
+        let routes = routes
+            .method_not_allowed_fallback(method_not_allowed)
+            .layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_timeout))
+                    .layer(TimeoutLayer::new(self.request_timeout)),
+            )
+            .layer(self.cors.layer())
+            .layer(middleware::from_fn(propagate_correlation_id))
+            .layer(TraceLayer::new_for_http());
+
+        if self.trace_sampler.rate() > 1 {
+            info!(
+                "Tracing 1 in {} event creations",
+                self.trace_sampler.rate()
+            );
+            return routes.layer(middleware::from_fn_with_state(
+                self.trace_sampler,
+                sample_traces,
+            ));
+        }
+        routes
+    }
+}
+
+/// Body of a 405. Axum adds the `Allow` header listing the methods the
+/// path does support.
+async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::method_not_allowed(&format!(
+        "{method} is not supported on {}",
+        uri.path()
+    ))
+}
+
+async fn handle_timeout(err: BoxError) -> AppError {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        AppError::gateway_timeout("Request took too long to complete")
+    }
+    else {
+        AppError::internal_server_error(&format!(
+            "Unhandled internal error: {err}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{Body, to_bytes},
+        http::{
+            HeaderValue, Method, Request, StatusCode,
+            header::{
+                ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+                ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+                ACCESS_CONTROL_REQUEST_HEADERS,
+                ACCESS_CONTROL_REQUEST_METHOD, ALLOW, ORIGIN,
+            },
+        },
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app() -> Router {
+        let routes =
+            Router::new().route("/fast", get(|| async { "ok" })).route(
+                "/slow",
+                get(|| {
+                    async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        "too late"
+                    }
+                }),
+            );
+
+        AppBuilder::new(routes)
+            .request_timeout(Duration::from_millis(50))
+            .build()
+    }
+
+    fn request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out_with_json_504() {
+        let response = app().oneshot(request("/slow")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "REQUEST_TIMEOUT");
+        assert_eq!(
+            json["error"]["message"],
+            "Request took too long to complete"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fast_handler_is_unaffected() {
+        let response = app().oneshot(request("/fast")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_method_is_a_json_405_with_allow() {
+        let routes = Router::new()
+            .route("/users", get(|| async { "list" }))
+            .route("/users", post(|| async { "created" }));
+        let request = Request::builder()
+            .method(Method::PATCH)
+            .uri("/users")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = AppBuilder::new(routes)
+            .build()
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let allow = response.headers()[ALLOW].to_str().unwrap().to_string();
+        assert!(allow.contains("GET") && allow.contains("POST"), "{allow}");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "METHOD_NOT_ALLOWED");
+        assert_eq!(
+            json["error"]["message"],
+            "PATCH is not supported on /users"
+        );
+    }
+
+    fn cors_app(cors: CorsConfig) -> Router {
+        AppBuilder::new(Router::new().route("/fast", get(|| async { "ok" })))
+            .cors(cors)
+            .build()
+    }
+
+    fn cors_request(method: Method, origin: &str) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method(method.clone())
+            .uri("/fast")
+            .header(ORIGIN, origin);
+        if method == Method::OPTIONS {
+            builder = builder.header(ACCESS_CONTROL_REQUEST_METHOD, "POST");
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_origin_gets_allow_origin_header() {
+        let app =
+            cors_app(CorsConfig::allowlist(["https://app.example.com"]));
+
+        let response = app
+            .oneshot(cors_request(Method::GET, "https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_origin_gets_no_allow_origin_header() {
+        let app =
+            cors_app(CorsConfig::allowlist(["https://app.example.com"]));
+
+        let response = app
+            .clone()
+            .oneshot(cors_request(Method::GET, "https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+
+        let preflight = app
+            .oneshot(cors_request(
+                Method::OPTIONS,
+                "https://evil.example.com",
+            ))
+            .await
+            .unwrap();
+        assert!(
+            preflight
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preflight_returns_allowed_methods() {
+        let app =
+            cors_app(CorsConfig::allowlist(["https://app.example.com"]));
+
+        let response = app
+            .oneshot(cors_request(Method::OPTIONS, "https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        let methods = response.headers()[ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("POST"));
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_lets_browsers_use_api_headers() {
+        let app =
+            cors_app(CorsConfig::allowlist(["https://app.example.com"]));
+        let mut preflight =
+            cors_request(Method::OPTIONS, "https://app.example.com");
+        preflight.headers_mut().insert(
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_static(
+                "if-none-match,x-correlation-id,x-debug-queries",
+            ),
+        );
+
+        let response = app.clone().oneshot(preflight).await.unwrap();
+        let allowed = response.headers()[ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap();
+        for header in ["if-none-match", "x-correlation-id", "x-debug-queries"]
+        {
+            assert!(allowed.contains(header), "{allowed}");
+        }
+
+        let response = app
+            .oneshot(cors_request(Method::GET, "https://app.example.com"))
+            .await
+            .unwrap();
+        let exposed = response.headers()[ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap();
+        for header in ["etag", "x-next-cursor", "x-query-count"] {
+            assert!(exposed.contains(header), "{exposed}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permissive_default_allows_any_origin() {
+        let response = app()
+            .oneshot(cors_request(Method::GET, "https://anywhere.example"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            HeaderValue::from_static("*")
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::{fmt, future::Future, time::Duration};
+
+use redis_connection::{
+    config::RedisDbConfig,
+    connect_redis_db,
+    connection::RedisConnectionManager,
+    core::command::{IntoRedisCommands, RedisCommands},
+};
+use sql_connection::{
+    SqlMigrator, config::PostgresDbConfig, connect_postgres_db, get_sql_pool,
+};
+use tracing::{error, info, warn};
+
+/// Dependency checked during startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootstrapStep {
+    Database,
+    Migrations,
+    Redis,
+}
+
+impl fmt::Display for BootstrapStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Database => "database",
+            Self::Migrations => "migrations",
+            Self::Redis => "redis",
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "bootstrap step `{step}` failed after {attempts} attempt(s): {source}"
+)]
+pub struct BootstrapError {
+    pub step: BootstrapStep,
+    pub attempts: u32,
+    #[source]
+    pub source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+/// How often a dependency is probed before startup gives up
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// `BOOTSTRAP_MAX_ATTEMPTS` (default 10) and
+    /// `BOOTSTRAP_RETRY_DELAY_MS` (default 1000)
+    pub fn from_env() -> Self {
+        Self {
+            max_attempts: std::env::var("BOOTSTRAP_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(10),
+            delay: Duration::from_millis(
+                std::env::var("BOOTSTRAP_RETRY_DELAY_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1000),
+            ),
+        }
+    }
+}
+
+pub struct BootstrapConfig {
+    pub database: PostgresDbConfig,
+    pub redis: RedisDbConfig,
+    pub run_migrations: bool,
+    pub retry: RetryPolicy,
+}
+
+/// Brings up every dependency the HTTP server needs, in order: Postgres,
+/// migrations, then Redis. Postgres and Redis are retried per `retry`;
+/// migrations run once, serialized across instances by an advisory lock. The
+/// listener should only be bound once this returns.
+pub async fn bootstrap(
+    config: &BootstrapConfig,
+) -> Result<RedisConnectionManager, BootstrapError> {
+    connect_postgres_db(&config.database)
+        .await
+        .map_err(|e| failed(BootstrapStep::Database, 1, e))?;
+    let pool = get_sql_pool();
+    with_retries(BootstrapStep::Database, config.retry, || {
+        async {
+            let client = pool.get().await?;
+            client.simple_query("SELECT 1").await?;
+            Ok(())
+        }
+    })
+    .await?;
+
+    if config.run_migrations {
+        info!(step = %BootstrapStep::Migrations, "Running migrations");
+        SqlMigrator::new(pool.clone())
+            .run_all_migrations()
+            .await
+            .map_err(|e| failed(BootstrapStep::Migrations, 1, e))?;
+        info!(step = %BootstrapStep::Migrations, "Migrations up to date");
+    }
+    else {
+        info!(step = %BootstrapStep::Migrations, "Skipped (RUN_MIGRATIONS)");
+    }
+
+    let redis = connect_redis_db(&config.redis)
+        .await
+        .map(RedisConnectionManager::new)
+        .map_err(|e| failed(BootstrapStep::Redis, 1, e))?;
+    with_retries(BootstrapStep::Redis, config.retry, || {
+        async {
+            redis.get_connection().await?.cmd().ping().await?;
+            Ok(())
+        }
+    })
+    .await?;
+
+    Ok(redis)
+}
+
+async fn with_retries<F, Fut>(
+    step: BootstrapStep, policy: RetryPolicy, mut probe: F,
+) -> Result<(), BootstrapError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut attempt = 1;
+    loop {
+        match probe().await {
+            Ok(()) => {
+                info!(%step, attempt, "Dependency ready");
+                return Ok(());
+            }
+            Err(e) if attempt < policy.max_attempts => {
+                warn!(
+                    %step,
+                    attempt,
+                    max_attempts = policy.max_attempts,
+                    error = %e,
+                    "Dependency not ready, retrying in {:?}",
+                    policy.delay
+                );
+                tokio::time::sleep(policy.delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(failed(step, attempt, e)),
+        }
+    }
+}
+
+fn failed(
+    step: BootstrapStep, attempts: u32, source: impl Into<anyhow::Error>,
+) -> BootstrapError {
+    let source = source.into();
+    error!(%step, attempts, error = %source, "Bootstrap failed");
+    BootstrapError {
+        step,
+        attempts,
+        source: source.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unreachable_database_fails_fast() {
+        let config = BootstrapConfig {
+            // Nothing listens on port 1
+            database: PostgresDbConfig {
+                uri: "postgresql://postgres@127.0.0.1:1/postgres".into(),
+                max_conn: Some(1),
+                min_conn: None,
+                logger: false,
+                statement_timeouts: Default::default(),
+            },
+            redis: RedisDbConfig {
+                host: "127.0.0.1".into(),
+                port: 1,
+                db: 0,
+            },
+            run_migrations: true,
+            retry: RetryPolicy {
+                max_attempts: 3,
+                delay: Duration::from_millis(10),
+            },
+        };
+
+        let started = Instant::now();
+        let Err(err) = bootstrap(&config).await
+        else {
+            panic!("bootstrap succeeded without a database");
+        };
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(err.step, BootstrapStep::Database);
+        assert_eq!(err.attempts, 3);
+        assert!(
+            err.to_string()
+                .starts_with("bootstrap step `database` failed after 3")
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract::Request, http::HeaderValue, middleware::Next, response::Response,
+};
+use tracing::{Instrument, info_span};
+use uuid::Uuid;
+
+/// Header carrying the id that ties together everything done for one
+/// logical request, across services
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Longest caller-supplied id we keep; anything longer is replaced
+const MAX_CORRELATION_ID_LEN: usize = 128;
+
+/// Correlation id of the current request, available to handlers as
+/// `Extension<CorrelationId>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(pub String);
+
+/// Reuse the caller's `X-Correlation-Id` or mint one, expose it to the
+/// handler, attach it to the request span and echo it on the response.
+pub async fn propagate_correlation_id(
+    mut request: Request, next: Next,
+) -> Response {
+    let id = request
+        .headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_CORRELATION_ID_LEN)
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::now_v7().to_string());
+
+    request.extensions_mut().insert(CorrelationId(id.clone()));
+
+    let span = info_span!("request", correlation_id = %id);
+    let mut response = next.run(request).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Extension, Router,
+        body::{Body, to_bytes},
+        middleware,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|Extension(id): Extension<CorrelationId>| {
+                    async move { id.0 }
+                }),
+            )
+            .layer(middleware::from_fn(propagate_correlation_id))
+    }
+
+    async fn call(correlation_id: Option<&str>) -> (String, String) {
+        let mut request = Request::builder().uri("/");
+        if let Some(id) = correlation_id {
+            request = request.header(CORRELATION_ID_HEADER, id);
+        }
+
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let header = response.headers()[CORRELATION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_incoming_correlation_id_reaches_handler() {
+        let (header, seen) = call(Some("checkout-42")).await;
+        assert_eq!(seen, "checkout-42");
+        assert_eq!(header, "checkout-42");
+    }
+
+    #[tokio::test]
+    async fn test_missing_correlation_id_is_generated() {
+        let (header, seen) = call(None).await;
+        assert_eq!(header, seen);
+        assert!(Uuid::parse_str(&seen).is_ok());
+    }
+}
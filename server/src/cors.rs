@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::warn;
+
+/// Methods the API serves; used unless `CORS_ALLOWED_METHODS` says otherwise
+pub const DEFAULT_METHODS: &[Method] = &[
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::OPTIONS,
+];
+
+/// Request headers browsers may send; used unless `CORS_ALLOWED_HEADERS`
+/// says otherwise
+pub const DEFAULT_HEADERS: &[&str] = &[
+    "accept",
+    "authorization",
+    "content-type",
+    "if-none-match",
+    "x-admin-token",
+    "x-correlation-id",
+    "x-debug-queries",
+];
+
+/// Response headers scripts on an allowlisted origin may read
+pub const EXPOSED_HEADERS: &[&str] = &[
+    "etag",
+    "retry-after",
+    "x-correlation-id",
+    "x-next-cursor",
+    "x-query-count",
+    "x-total-count",
+];
+
+/// How long browsers may cache a preflight response
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Which origins may call the API from a browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsOrigins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    pub origins: CorsOrigins,
+    pub methods: Vec<Method>,
+    pub headers: Vec<HeaderName>,
+    pub allow_credentials: bool,
+    pub max_age: Duration,
+}
+
+impl CorsConfig {
+    /// Any origin, method and header. Meant for local development.
+    pub fn permissive() -> Self {
+        Self {
+            origins: CorsOrigins::Any,
+            methods: DEFAULT_METHODS.to_vec(),
+            headers: parse_list(DEFAULT_HEADERS, "header"),
+            allow_credentials: false,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Only the given origins; an empty list blocks all cross-origin calls
+    pub fn allowlist<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            origins: CorsOrigins::List(parse_list(origins, "origin")),
+            ..Self::permissive()
+        }
+    }
+
+    pub fn methods(mut self, methods: Vec<Method>) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    pub fn headers(mut self, headers: Vec<HeaderName>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.allow_credentials = allow;
+        self
+    }
+
+    /// Read the policy from the environment.
+    ///
+    /// `CORS_ALLOWED_ORIGINS` is a comma separated allowlist, or `*` for any
+    /// origin. When it is unset, production (`ENVIRONMENT=production`) allows
+    /// no cross-origin calls and every other environment is permissive.
+    /// `CORS_ALLOWED_METHODS`, `CORS_ALLOWED_HEADERS` and
+    /// `CORS_ALLOW_CREDENTIALS` refine an allowlist.
+    pub fn from_env() -> Self {
+        let production = std::env::var("ENVIRONMENT")
+            .is_ok_and(|env| env.eq_ignore_ascii_case("production"));
+
+        let mut config = match env_list("CORS_ALLOWED_ORIGINS") {
+            Some(origins) if origins.iter().any(|o| o == "*") => {
+                Self::permissive()
+            }
+            Some(origins) => Self::allowlist(origins),
+            None if production => Self::allowlist(Vec::<String>::new()),
+            None => Self::permissive(),
+        };
+
+        if let Some(methods) = env_list("CORS_ALLOWED_METHODS") {
+            config = config.methods(parse_list(methods, "method"));
+        }
+        if let Some(headers) = env_list("CORS_ALLOWED_HEADERS") {
+            config = config.headers(parse_list(headers, "header"));
+        }
+        if let Ok(allow) = std::env::var("CORS_ALLOW_CREDENTIALS") {
+            config = config.allow_credentials(allow == "true");
+        }
+
+        config
+    }
+
+    /// Browsers reject credentials alongside a wildcard origin, so the
+    /// permissive policy ignores `allow_credentials` and allows any header.
+    pub fn layer(&self) -> CorsLayer {
+        let layer = CorsLayer::new()
+            .allow_methods(self.methods.clone())
+            .max_age(self.max_age);
+
+        match &self.origins {
+            CorsOrigins::Any => {
+                layer
+                    .allow_origin(Any)
+                    .allow_headers(Any)
+                    .expose_headers(Any)
+            }
+            CorsOrigins::List(origins) => {
+                layer
+                    .allow_origin(AllowOrigin::list(origins.clone()))
+                    .allow_headers(self.headers.clone())
+                    .allow_credentials(self.allow_credentials)
+                    .expose_headers(parse_list::<_, _, HeaderName>(
+                        EXPOSED_HEADERS,
+                        "header",
+                    ))
+            }
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self { Self::permissive() }
+}
+
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    let items: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect();
+    (!items.is_empty()).then_some(items)
+}
+
+fn parse_list<I, S, T>(items: I, kind: &str) -> Vec<T>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    T: std::str::FromStr,
+{
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let item = item.as_ref();
+            let parsed = item.parse().ok();
+            if parsed.is_none() {
+                warn!("Ignoring invalid CORS {}: {}", kind, item);
+            }
+            parsed
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+use axum::{
+    extract::Request, http::HeaderValue, middleware::Next, response::Response,
+};
+use sql_connection::QueryCounter;
+
+/// Send `X-Debug-Queries: 1` to have the request's queries counted
+pub const DEBUG_QUERIES_HEADER: &str = "x-debug-queries";
+
+/// Response header carrying the number of database round trips
+pub const QUERY_COUNT_HEADER: &str = "x-query-count";
+
+/// Count the database statements run while serving a request that asked
+/// for it and report the total in `X-Query-Count`. The counter is
+/// also available to handlers as `Extension<QueryCounter>`. Other requests
+/// pass straight through.
+pub async fn count_queries(mut request: Request, next: Next) -> Response {
+    let wanted = request
+        .headers()
+        .get(DEBUG_QUERIES_HEADER)
+        .is_some_and(|value| value == "1");
+    if !wanted {
+        return next.run(request).await;
+    }
+
+    let counter = QueryCounter::new();
+    request.extensions_mut().insert(counter.clone());
+    let mut response = counter.scope(next.run(request)).await;
+    response
+        .headers_mut()
+        .insert(QUERY_COUNT_HEADER, HeaderValue::from(counter.count()));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, http::StatusCode, middleware};
+    use events_http::{EventHandlers, EventServices};
+    use test_utils::*;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// `PUT /event/{id}` with new metadata only runs the update, then looks
+    /// up the event type's name
+    async fn update_event(
+        app: Router, event_id: i64, debug: Option<&str>,
+    ) -> Option<String> {
+        let mut request = Request::builder()
+            .method("PUT")
+            .uri(format!("/{event_id}"))
+            .header("content-type", "application/json");
+        if let Some(debug) = debug {
+            request = request.header(DEBUG_QUERIES_HEADER, debug);
+        }
+        let response = app
+            .oneshot(
+                request
+                    .body(Body::from(r#"{"metadata": {"page": "/home"}}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+            .headers()
+            .get(QUERY_COUNT_HEADER)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_event_update_reports_its_two_queries() {
+        let container = TestPostgresContainer::new().await.unwrap();
+        let event_type_id = create_test_event_type(&container).await.unwrap();
+        let user_id = create_test_user(&container).await.unwrap();
+        let event_id =
+            create_test_event(&container, user_id, event_type_id, None)
+                .await
+                .unwrap();
+        let app = EventHandlers::routes()
+            .with_state(EventServices::new(create_sql_connect(&container)))
+            .layer(middleware::from_fn(count_queries));
+
+        assert_eq!(
+            update_event(app.clone(), event_id, Some("1"))
+                .await
+                .as_deref(),
+            Some("2")
+        );
+        assert_eq!(update_event(app.clone(), event_id, None).await, None);
+        assert_eq!(update_event(app, event_id, Some("0")).await, None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeSet;
+
+use axum::Router;
+
+/// Gates `GET /analytics/referrers/conversions`, a session join over the
+/// whole events table
+pub const REFERRER_CONVERSIONS: &str = "referrer_conversions";
+
+/// Experimental endpoints that stay unmounted unless switched on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureFlags {
+    enabled: BTreeSet<String>,
+}
+
+impl FeatureFlags {
+    /// Enable every comma-separated name in `list` (case-insensitive)
+    pub fn parse(list: &str) -> Self {
+        Self {
+            enabled: list
+                .split(',')
+                .map(|name| name.trim().to_ascii_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Read the enabled features from `FEATURE_FLAGS`; none when unset
+    pub fn from_env() -> Self {
+        std::env::var("FEATURE_FLAGS")
+            .map(|list| Self::parse(&list))
+            .unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.enabled.contains(&feature.to_ascii_lowercase())
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.enabled.iter().map(String::as_str)
+    }
+
+    /// `routes` if `feature` is enabled, otherwise an empty router so its
+    /// paths fall through to 404
+    pub fn gate<S>(&self, feature: &str, routes: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if self.is_enabled(feature) {
+            routes
+        }
+        else {
+            Router::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app(features: &FeatureFlags) -> Router {
+        Router::new()
+            .route("/stable", get(|| async { "ok" }))
+            .merge(features.gate(
+                "funnel",
+                Router::new().route("/funnel", get(|| async { "funnel" })),
+            ))
+    }
+
+    async fn status(app: Router, uri: &str) -> StatusCode {
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_disabled_feature_route_is_not_found() {
+        let app = app(&FeatureFlags::parse("retention"));
+
+        assert_eq!(
+            status(app.clone(), "/funnel").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status(app, "/stable").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_enabled_feature_route_is_reachable() {
+        let app = app(&FeatureFlags::parse(" Funnel, retention,"));
+
+        assert_eq!(status(app, "/funnel").await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_parse_ignores_blanks_and_case() {
+        let flags = FeatureFlags::parse("Funnel,, retention ");
+
+        assert!(flags.is_enabled("funnel"));
+        assert!(flags.is_enabled("RETENTION"));
+        assert!(!flags.is_enabled("reprocess"));
+        assert_eq!(
+            flags.enabled().collect::<Vec<_>>(),
+            ["funnel", "retention"]
+        );
+        assert_eq!(FeatureFlags::parse(""), FeatureFlags::default());
+    }
+}
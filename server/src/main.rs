@@ -1,27 +1,40 @@
-use std::net::SocketAddr;
-
+mod app;
+mod bootstrap;
+mod correlation;
+mod cors;
+mod debug_queries;
+mod features;
+mod openapi;
+mod sampling;
+mod serve;
+
+use admin_http::{AdminHandlers, AdminServices};
+use app::AppBuilder;
 use axum::{
     Json, Router,
     http::StatusCode,
     response::IntoResponse,
     routing::{delete, get, post, put},
 };
+use bootstrap::{BootstrapConfig, RetryPolicy};
+use features::FeatureFlags;
 use redis_connection::{
-    cache_provider::CacheProvider, config::RedisDbConfig, connect_redis_db,
+    cache_provider::CacheProvider,
+    config::{MemoryConfig, RedisDbConfig},
     connection::RedisConnectionManager,
+    metrics::{CacheMetrics, CacheMetricsSnapshot},
 };
 use serde::Serialize;
+use serve::ServeConfig;
 use sql_connection::{
-    SqlConnect, config::PostgresDbConfig, connect_postgres_db,
+    SqlConnect, StatementTimeouts, config::PostgresDbConfig,
 };
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{
     fmt, layer::SubscriberExt, util::SubscriberInitExt,
 };
-use user_http::UserServices;
-use utoipa::OpenApi;
-use utoipa_rapidoc::RapiDoc;
+use user_http::{UserHandlers, UserServices};
+use user_query_handlers::{CacheWarmer, HotUsers};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -36,7 +49,9 @@ async fn main() -> anyhow::Result<()> {
 
     let env_filter = tracing_subscriber::EnvFilter::new(&log_level);
 
-    let registry = tracing_subscriber::registry().with(env_filter);
+    let registry = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(sampling::UnsampledSpans);
 
     if enable_file_logging {
         // File appender for clean, structured logs
@@ -86,11 +101,10 @@ async fn main() -> anyhow::Result<()> {
         max_conn: Some(1000),
         min_conn: Some(100),
         logger: false,
+        // Fail runaway queries fast instead of holding a connection
+        statement_timeouts: StatementTimeouts::from_env(),
     };
 
-    // Initialize primary database connection
-    connect_postgres_db(&db_config).await?;
-
     let redis_config = RedisDbConfig {
         host: std::env::var("REDIS_HOST")
             .unwrap_or_else(|_| "127.0.0.1".to_string()),
@@ -100,117 +114,298 @@ async fn main() -> anyhow::Result<()> {
             .unwrap_or(6379),
         db: 0,
     };
-    let redis_pool = connect_redis_db(&redis_config).await?;
+
+    // Wait for Postgres, migrations and Redis before serving anything
+    let redis = bootstrap::bootstrap(&BootstrapConfig {
+        database: db_config,
+        redis: redis_config,
+        run_migrations: std::env::var("RUN_MIGRATIONS")
+            .unwrap_or_else(|_| "true".into())
+            == "true",
+        retry: RetryPolicy::from_env(),
+    })
+    .await?;
+    let redis_pool = redis.get_pool().clone();
     RedisConnectionManager::init_static(redis_pool.clone());
-    CacheProvider::init_redis_static(redis_pool);
+    // Keeps deployments that share one Redis apart, e.g. `prod:`
+    if let Ok(prefix) = std::env::var("CACHE_KEY_PREFIX") {
+        CacheProvider::set_key_prefix(&prefix);
+        info!("Cache keys prefixed with '{}'", prefix);
+    }
+    // Optional in-process L1 in front of Redis; keeps cached reads
+    // available during a Redis outage
+    let l1_capacity = std::env::var("CACHE_L1_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    if l1_capacity > 0 {
+        let l1_config = MemoryConfig {
+            capacity: l1_capacity,
+            ttl_secs: std::env::var("CACHE_L1_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+        };
+        CacheProvider::init_tiered_static(redis_pool, l1_config);
+        info!("In-memory L1 cache enabled (capacity {})", l1_capacity);
+    }
+    else {
+        CacheProvider::init_redis_static(redis_pool);
+    }
     info!("Redis connection pool and cache backend initialized");
 
     info!("Connection pools initialized successfully");
 
     let db = SqlConnect::from_global();
-    let user_services = UserServices::new(db.clone());
-    let event_services = events_http::EventServices::new(db.clone());
+    // Page sizes of `GET /users` and `GET /events` without a `limit`
+    let page_size = |var: &str| {
+        std::env::var(var)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(dao_utils::pagination::DEFAULT_PAGE_SIZE)
+    };
+    let user_services = UserServices::new(db.clone())
+        .with_default_page_size(page_size("USERS_DEFAULT_PAGE_SIZE"));
+    let mut event_services = events_http::EventServices::new(db.clone())
+        .with_default_page_size(page_size("EVENTS_DEFAULT_PAGE_SIZE"));
+    // Per-user cap on event ingestion (0 or unset disables)
+    let event_rate_limit = std::env::var("EVENT_RATE_LIMIT")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    if event_rate_limit > 0 {
+        let window = std::env::var("EVENT_RATE_LIMIT_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        event_services = event_services.with_rate_limit(
+            RedisConnectionManager::from_static(),
+            event_rate_limit,
+            std::time::Duration::from_secs(window),
+        );
+        info!(
+            "Event rate limit: {} per user every {}s",
+            event_rate_limit, window
+        );
+    }
+
+    // Per-event-type JSON Schemas for metadata (unset skips validation)
+    if let Ok(path) = std::env::var("EVENT_METADATA_SCHEMAS") {
+        let schemas =
+            events_command_handlers::MetadataSchemas::from_file(&path)?;
+        info!(
+            "Loaded {} event metadata schema(s) from {}",
+            schemas.len(),
+            path
+        );
+        event_services = event_services.with_metadata_schemas(schemas);
+    }
+
+    // Accepted event timestamp window: future skew (default 300s) and
+    // maximum age (unset accepts any age)
+    let mut timestamp_policy =
+        events_command_handlers::TimestampPolicy::default();
+    if let Ok(secs) = std::env::var("EVENT_MAX_FUTURE_SKEW_SECS") {
+        timestamp_policy.max_future_skew = secs
+            .parse::<i64>()
+            .ok()
+            .filter(|&secs| secs >= 0)
+            .and_then(chrono::Duration::try_seconds)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "EVENT_MAX_FUTURE_SKEW_SECS must be a non-negative \
+                     number of seconds, got {secs:?}"
+                )
+            })?;
+    }
+    if let Ok(days) = std::env::var("EVENT_MAX_AGE_DAYS") {
+        let max_age = days
+            .parse::<i64>()
+            .ok()
+            .filter(|&days| days > 0)
+            .and_then(chrono::Duration::try_days)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "EVENT_MAX_AGE_DAYS must be a positive number of days, \
+                     got {days:?}"
+                )
+            })?;
+        timestamp_policy = timestamp_policy.with_max_age(max_age);
+        info!("Rejecting events stamped more than {} days ago", days);
+    }
+    event_services = event_services.with_timestamp_policy(timestamp_policy);
+
+    // Batch event inserts: each event waits up to EVENT_WRITE_BUFFER_MS
+    // for others, up to EVENT_WRITE_BUFFER_BATCH per insert (0 or unset
+    // inserts every event on its own)
+    let write_buffer_ms = std::env::var("EVENT_WRITE_BUFFER_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    if write_buffer_ms > 0 {
+        let config = events_command_handlers::WriteBufferConfig {
+            max_batch: std::env::var("EVENT_WRITE_BUFFER_BATCH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&batch| batch > 0)
+                .unwrap_or(events_command_handlers::DEFAULT_MAX_BATCH),
+            max_delay: std::time::Duration::from_millis(write_buffer_ms),
+            max_queued: std::env::var("EVENT_WRITE_BUFFER_QUEUE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&queued| queued > 0)
+                .unwrap_or(events_command_handlers::DEFAULT_MAX_QUEUED),
+        };
+        event_services = event_services.with_write_buffer(config);
+        info!(
+            "Buffering event inserts: up to {} per batch, {}ms delay",
+            config.max_batch, write_buffer_ms
+        );
+    }
+
+    // Preload the busiest users into the cache: the top
+    // CACHE_WARM_USERS by events over the last CACHE_WARM_WINDOW_HOURS
+    // (default 24), or the ids listed in CACHE_WARM_USER_IDS
+    let warm_source = match std::env::var("CACHE_WARM_USER_IDS") {
+        Ok(ids) => {
+            Some(HotUsers::Ids(
+                ids.split(',')
+                    .filter_map(|id| id.trim().parse().ok())
+                    .collect(),
+            ))
+        }
+        Err(_) => {
+            std::env::var("CACHE_WARM_USERS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|&limit| limit > 0)
+                .map(|limit| {
+                    let window = std::env::var("CACHE_WARM_WINDOW_HOURS")
+                        .ok()
+                        .and_then(|v| v.parse::<i64>().ok())
+                        .filter(|&hours| hours > 0)
+                        .and_then(chrono::Duration::try_hours)
+                        .unwrap_or_else(|| chrono::Duration::hours(24));
+                    HotUsers::MostActive { limit, window }
+                })
+        }
+    };
+    if let Some(source) = warm_source {
+        match CacheWarmer::new(db.clone(), source).warm().await {
+            Ok(warmed) => info!("Warmed the cache with {} user(s)", warmed),
+            Err(e) => warn!("Cache warming failed: {}", e),
+        }
+    }
+
+    // Experimental endpoints are mounted only when named in FEATURE_FLAGS
+    let features = FeatureFlags::from_env();
+    let enabled = features.enabled().collect::<Vec<_>>();
+    if !enabled.is_empty() {
+        info!("Enabled features: {}", enabled.join(", "));
+    }
 
     // Start background job for refreshing materialized views
     info!("Starting background job scheduler...");
-    event_services.background_jobs.start().await;
+    // Random delay before each job's schedule starts, so replicas booted
+    // together don't refresh at the same moment
+    let job_startup_jitter = std::env::var("JOB_STARTUP_JITTER_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(std::time::Duration::from_secs)
+        .unwrap_or(job_scheduler::DEFAULT_STARTUP_JITTER);
+    event_services
+        .background_jobs
+        .clone()
+        .with_startup_jitter(job_startup_jitter)
+        .start()
+        .await;
     info!("Background job scheduler started successfully");
 
+    let admin_services = AdminServices::from_env()
+        .with_jobs(event_services.background_jobs.clone());
+
     let api_routes = Router::new()
         .route("/stats", axum::routing::get(events_http::stats::get_stats))
+        .route(
+            "/stats/summary",
+            axum::routing::get(events_http::stats::get_stats_summary),
+        )
         .route(
             "/stats/refresh",
             axum::routing::post(events_http::stats::refresh_stats),
         )
+        .route(
+            "/analytics/active-users",
+            axum::routing::get(events_http::stats::get_active_users),
+        )
+        .route(
+            "/analytics/event-types/{name}/timeseries",
+            axum::routing::get(events_http::stats::get_event_type_timeseries),
+        )
+        .route(
+            "/analytics/heatmap",
+            axum::routing::get(events_http::stats::get_activity_heatmap),
+        )
+        .route(
+            "/analytics/pages",
+            axum::routing::get(events_http::stats::get_pages),
+        )
         .route("/event", post(events_http::create_event))
         .route("/event/{id}", get(events_http::get_event))
         .route("/event/{id}", put(events_http::update_event))
         .route("/event/{id}", delete(events_http::delete_event))
         .route("/events", get(events_http::list_events))
         .route("/events", delete(events_http::bulk_delete_events))
+        .route(
+            "/events/session/{session_id}",
+            get(events_http::get_session_events),
+        )
+        .route("/events/export", get(events_http::export_events))
+        .route("/events/recent", get(events_http::recent_events))
+        .merge(features.gate(
+            features::REFERRER_CONVERSIONS,
+            Router::new().route(
+                "/analytics/referrers/conversions",
+                axum::routing::get(
+                    events_http::stats::get_referrer_conversions,
+                ),
+            ),
+        ))
         .with_state(event_services)
-        .route("/user", post(user_http::create_user))
-        .route("/user/{id}", get(user_http::get_user))
-        .route("/user/{id}", put(user_http::update_user))
-        .route("/user/{id}", delete(user_http::delete_user))
-        .route("/user/{id}/events", get(user_http::get_user_events))
-        .route("/users", get(user_http::list_users))
-        .with_state(user_services.clone());
+        .merge(UserHandlers::routes().with_state(user_services))
+        .merge(AdminHandlers::routes().with_state(admin_services));
 
     let app = Router::new()
         .route("/", get(health_check))
         .route("/pool_status", get(pool_status))
+        .route("/metrics/cache", get(cache_metrics))
         .merge(api_routes);
 
-    let app = app
-        .merge(RapiDoc::new("/api-docs/openapi.json").path("/docs"))
-        .route(
-            "/api-docs/openapi.json",
-            get(|| async { axum::Json(ApiDoc::openapi()) }),
-        )
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http());
-
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8880));
-    info!("🚀 Collider server starting on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let app = app.merge(openapi::routes());
+    let app = AppBuilder::new(app)
+        .request_timeout_from_env()
+        .cors_from_env()
+        .debug_queries_from_env()
+        .trace_sampling_from_env()
+        .build();
+
+    let serve_config = ServeConfig::from_env();
+    info!("🚀 Collider server starting on {}", serve_config.addr);
+
+    let listener = tokio::net::TcpListener::bind(serve_config.addr).await?;
+    serve::serve(
+        app,
+        listener,
+        serve::shutdown_signal(),
+        serve_config.drain_timeout,
+    )
+    .await?;
+    info!("Server stopped");
 
     Ok(())
 }
 
-#[derive(OpenApi)]
-#[openapi(
-    paths(
-        health_check,
-        pool_status,
-        events_http::create_event,
-        events_http::update_event,
-        events_http::delete_event,
-        events_http::get_event,
-        events_http::list_events,
-        events_http::bulk_delete_events,
-        events_http::stats::get_stats,
-        events_http::stats::refresh_stats,
-        user_http::create_user,
-        user_http::update_user,
-        user_http::delete_user,
-        user_http::get_user,
-        user_http::list_users,
-        user_http::get_user_events
-    ),
-    components(
-        schemas(
-            PoolStatus,
-            PoolInfo,
-            events_responses::EventResponse,
-            events_http::EventsListParams,
-            events_http::EventsDeleteParams,
-            events_http::stats::StatsQuery,
-            events_http::stats::StatsResponse,
-            events_commands::CreateEventCommand,
-            events_commands::UpdateEventCommand,
-            events_responses::BulkDeleteEventsResponse,
-            user_responses::UserResponse,
-            user_commands::CreateUserCommand,
-            user_commands::UpdateUserCommand,
-        )
-    ),
-    tags(
-        (name = "health", description = "Health check endpoints"),
-        (name = "events", description = "Event management endpoints"),
-        (name = "users", description = "User management endpoints"),
-        (name = "stats", description = "Event statistics endpoints")
-    ),
-    info(
-        title = "Collider API",
-        description = "High-performance event tracking API",
-        version = "1.0.0"
-    )
-)]
-struct ApiDoc;
-
 #[utoipa::path(
     get,
     path = "/health",
@@ -282,3 +477,15 @@ async fn pool_status() -> impl IntoResponse {
 
     Json(status)
 }
+
+#[utoipa::path(
+    get,
+    path = "/metrics/cache",
+    responses(
+        (status = 200, description = "Cache hits, misses and errors since startup", body = CacheMetricsSnapshot)
+    ),
+    tag = "monitoring"
+)]
+async fn cache_metrics() -> impl IntoResponse {
+    Json(CacheMetrics::snapshot())
+}
@@ -0,0 +1,121 @@
+use admin_http::AdminApi;
+use axum::{Json, Router, routing::get};
+use events_http::{EventsApi, stats::StatsApi};
+use user_http::UsersApi;
+use utoipa::OpenApi;
+use utoipa_rapidoc::RapiDoc;
+
+/// Where the assembled spec is served
+pub const SPEC_PATH: &str = "/openapi.json";
+
+/// Earlier location of the spec, still served for existing clients
+const LEGACY_SPEC_PATH: &str = "/api-docs/openapi.json";
+
+/// Server-level entries of the spec. The per-domain documents are merged
+/// into it by [`spec`].
+#[derive(OpenApi)]
+#[openapi(
+    paths(crate::health_check, crate::pool_status, crate::cache_metrics),
+    components(schemas(
+        crate::PoolStatus,
+        crate::PoolInfo,
+        redis_connection::metrics::CacheMetricsSnapshot,
+        common_errors::ApiErrorResponse
+    )),
+    tags(
+        (name = "health", description = "Health check endpoints"),
+        (name = "events", description = "Event management endpoints"),
+        (name = "users", description = "User management endpoints"),
+        (name = "stats", description = "Event statistics endpoints"),
+        (name = "admin", description = "Operational endpoints guarded by ADMIN_TOKEN")
+    ),
+    info(
+        title = "Collider API",
+        description = "High-performance event tracking API",
+        version = "1.0.0"
+    )
+)]
+struct ApiDoc;
+
+/// The OpenAPI document for every domain. Schemas shared between domains,
+/// such as `EventResponse`, end up in the components once.
+pub fn spec() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+        .merge_from(UsersApi::openapi())
+        .merge_from(EventsApi::openapi())
+        .merge_from(StatsApi::openapi())
+        .merge_from(AdminApi::openapi())
+}
+
+/// The spec as JSON and the RapiDoc UI at `/docs`
+pub fn routes() -> Router {
+    let spec = spec();
+    let serve = move || {
+        let spec = spec.clone();
+        async move { Json(spec) }
+    };
+
+    Router::new()
+        .merge(RapiDoc::new(SPEC_PATH).path("/docs"))
+        .route(SPEC_PATH, get(serve.clone()))
+        .route(LEGACY_SPEC_PATH, get(serve))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spec_covers_every_domain() {
+        let response = routes()
+            .oneshot(
+                Request::builder()
+                    .uri(SPEC_PATH)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: Value = serde_json::from_slice(&body).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/health",
+            "/user/{id}",
+            "/users",
+            "/event/{id}",
+            "/events",
+            "/analytics/heatmap",
+            "/admin/cache/purge",
+        ] {
+            assert!(paths.contains_key(path), "{path} missing from spec");
+        }
+
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        for schema in ["ApiErrorResponse", "EventResponse", "UserResponse"] {
+            assert!(schemas.contains_key(schema), "{schema} missing");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_legacy_spec_path_and_docs_ui_are_served() {
+        for uri in [LEGACY_SPEC_PATH, "/docs"] {
+            let response = routes()
+                .oneshot(
+                    Request::builder().uri(uri).body(Body::empty()).unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+        }
+    }
+}
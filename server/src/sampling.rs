@@ -0,0 +1,179 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Metadata, Subscriber, subscriber::Interest};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Path of the high-volume route whose traces are sampled
+pub const SAMPLED_PATH: &str = "/event";
+
+tokio::task_local! {
+    /// Set while serving a request that was not picked for tracing
+    static SAMPLED_OUT: ();
+}
+
+/// Traces one in every `rate` event creations so high-volume ingest doesn't
+/// flood the tracing pipeline
+#[derive(Clone, Debug)]
+pub struct SpanSampler {
+    rate: u64,
+    calls: Arc<AtomicU64>,
+}
+
+impl SpanSampler {
+    /// A `rate` of 0 or 1 keeps every trace
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: rate.max(1),
+            calls: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn rate(&self) -> u64 { self.rate }
+
+    /// Requests seen so far, including the untraced ones
+    #[cfg(test)]
+    fn calls(&self) -> u64 { self.calls.load(Ordering::Relaxed) }
+
+    fn keep(&self) -> bool {
+        self.calls
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.rate)
+    }
+}
+
+impl Default for SpanSampler {
+    fn default() -> Self { Self::new(1) }
+}
+
+/// Decide once per `POST /event` whether it is traced. An unsampled request
+/// runs with every span disabled by [`UnsampledSpans`], the request span
+/// included, so it costs no tracing at all; its log events still go out.
+/// Must sit outside the `TraceLayer`.
+pub async fn sample_traces(
+    State(sampler): State<SpanSampler>, request: Request, next: Next,
+) -> Response {
+    let sampled_route = request.method() == Method::POST
+        && request.uri().path() == SAMPLED_PATH;
+    if !sampled_route || sampler.keep() {
+        return next.run(request).await;
+    }
+    SAMPLED_OUT.scope((), next.run(request)).await
+}
+
+/// Disables spans inside requests that [`sample_traces`] left untraced.
+/// Install it on the subscriber ahead of the formatting layers.
+pub struct UnsampledSpans;
+
+impl<S: Subscriber> Layer<S> for UnsampledSpans {
+    fn register_callsite(
+        &self, metadata: &'static Metadata<'static>,
+    ) -> Interest {
+        // Spans are decided per request, so their interest can't be cached
+        if metadata.is_span() {
+            Interest::sometimes()
+        }
+        else {
+            Interest::always()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        !metadata.is_span() || SAMPLED_OUT.try_with(|_| ()).is_err()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use axum::{
+        Router,
+        body::Body,
+        middleware,
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+    use tower_http::trace::TraceLayer;
+    use tracing::{Instrument, info_span, span};
+    use tracing_subscriber::{prelude::*, registry::LookupSpan};
+
+    use super::*;
+
+    struct CountSpans(Arc<AtomicUsize>);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CountSpans {
+        fn on_new_span(
+            &self, _: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>,
+        ) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The request span from `TraceLayer` plus one child per request
+    fn app(sampler: SpanSampler) -> Router {
+        let create =
+            || async { "created" }.instrument(info_span!("create_event"));
+        Router::new()
+            .route(SAMPLED_PATH, post(create))
+            .route(SAMPLED_PATH, get(create))
+            .layer(TraceLayer::new_for_http())
+            .layer(middleware::from_fn_with_state(sampler, sample_traces))
+    }
+
+    async fn spans_for_burst(
+        sampler: &SpanSampler, method: Method, burst: usize,
+    ) -> usize {
+        let created = Arc::new(AtomicUsize::new(0));
+        let _default = tracing_subscriber::registry()
+            .with(UnsampledSpans)
+            .with(CountSpans(created.clone()))
+            .set_default();
+
+        let app = app(sampler.clone());
+        for _ in 0..burst {
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(SAMPLED_PATH)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+        created.load(Ordering::Relaxed)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_one_in_n_requests_emit_their_span_tree() {
+        let sampler = SpanSampler::new(10);
+
+        let emitted = spans_for_burst(&sampler, Method::POST, 1000).await;
+
+        // Both the request span and its child, for 1 in 10 requests
+        assert_eq!(emitted, 200);
+        assert_eq!(sampler.calls(), 1000);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_rate_of_one_and_other_routes_keep_every_span() {
+        for rate in [0, 1] {
+            let sampler = SpanSampler::new(rate);
+            assert_eq!(
+                spans_for_burst(&sampler, Method::POST, 50).await,
+                100
+            );
+            assert_eq!(sampler.calls(), 50);
+        }
+
+        let sampler = SpanSampler::new(10);
+        assert_eq!(spans_for_burst(&sampler, Method::GET, 50).await, 100);
+        assert_eq!(sampler.calls(), 0);
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use axum::Router;
+use tokio::{net::TcpListener, sync::oneshot};
+use tracing::{info, warn};
+
+/// Where the server listens when `BIND_ADDR` is unset or invalid
+pub const DEFAULT_BIND_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8880);
+
+/// How long in-flight requests may run on after a shutdown signal
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServeConfig {
+    pub addr: SocketAddr,
+    pub drain_timeout: Duration,
+}
+
+impl ServeConfig {
+    /// `BIND_ADDR` (default `0.0.0.0:8880`) and `SHUTDOWN_DRAIN_SECS`
+    /// (default 30)
+    pub fn from_env() -> Self {
+        Self {
+            addr: std::env::var("BIND_ADDR")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_BIND_ADDR),
+            drain_timeout: std::env::var("SHUTDOWN_DRAIN_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_DRAIN_TIMEOUT),
+        }
+    }
+}
+
+/// Serve `router` until `shutdown` resolves. The listener is closed at
+/// once, so new connections are refused, while requests already running
+/// get up to `drain_timeout` to finish before being dropped.
+pub async fn serve<F>(
+    router: Router, listener: TcpListener, shutdown: F,
+    drain_timeout: Duration,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (draining_tx, draining_rx) = oneshot::channel();
+    let server =
+        axum::serve(listener, router).with_graceful_shutdown(async move {
+            shutdown.await;
+            let _ = draining_tx.send(());
+        });
+    let mut server = std::pin::pin!(server.into_future());
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = draining_rx => {
+            info!("Shutting down, draining in-flight requests");
+        }
+    }
+
+    match tokio::time::timeout(drain_timeout, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                "In-flight requests still running after {}s, dropping them",
+                drain_timeout.as_secs()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Resolves on Ctrl+C or, on Unix, SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(
+            tokio::signal::unix::SignalKind::terminate(),
+        ) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::routing::get;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::Notify,
+    };
+
+    use super::*;
+
+    /// `/slow` answers once `release` is notified, after notifying
+    /// `entered` that it started
+    fn slow_app(entered: Arc<Notify>, release: Arc<Notify>) -> Router {
+        Router::new().route(
+            "/slow",
+            get(move || {
+                async move {
+                    entered.notify_one();
+                    release.notified().await;
+                    "done"
+                }
+            }),
+        )
+    }
+
+    struct Running {
+        addr: SocketAddr,
+        stop: oneshot::Sender<()>,
+        server: tokio::task::JoinHandle<std::io::Result<()>>,
+        entered: Arc<Notify>,
+        release: Arc<Notify>,
+    }
+
+    async fn start(drain_timeout: Duration) -> Running {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop, stop_rx) = oneshot::channel::<()>();
+        let (entered, release) =
+            (Arc::new(Notify::new()), Arc::new(Notify::new()));
+        let server = tokio::spawn(serve(
+            slow_app(entered.clone(), release.clone()),
+            listener,
+            async {
+                let _ = stop_rx.await;
+            },
+            drain_timeout,
+        ));
+        Running {
+            addr,
+            stop,
+            server,
+            entered,
+            release,
+        }
+    }
+
+    /// Send `GET /slow` and return once the handler is running
+    async fn send_slow_request(running: &Running) -> TcpStream {
+        let mut stream = TcpStream::connect(running.addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /slow HTTP/1.1\r\nhost: localhost\r\nconnection: \
+                  close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        running.entered.notified().await;
+        stream
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_request_finishes_after_shutdown() {
+        let running = start(Duration::from_secs(5)).await;
+        let mut stream = send_slow_request(&running).await;
+
+        running.stop.send(()).unwrap();
+        // The listener closes once the shutdown future has been polled
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while TcpStream::connect(running.addr).await.is_ok() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("new connections should be refused while draining");
+
+        running.release.notify_one();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("done"), "{response}");
+        running.server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_after_timeout() {
+        let running = start(Duration::from_millis(100)).await;
+        let _stream = send_slow_request(&running).await;
+
+        // The request is never released, so only the timeout ends the drain
+        running.stop.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), running.server)
+            .await
+            .expect("serve should return once the drain times out")
+            .unwrap()
+            .unwrap();
+    }
+}